@@ -0,0 +1,243 @@
+// <FILE>src/archive.rs</FILE> - <DESC>Zip archives for `wsl-clip zip`</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T13:05:00Z</VERS>
+// <WCTX>Bundle files and folders into one file object.</WCTX>
+// <CLOG>Initial creation: relative entry names, followed or stored symlinks, modification times.</CLOG>
+
+use crate::debug_logger::create_logger;
+use anyhow::{Context, Result};
+use chrono::{Datelike, Timelike};
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+/// A finished archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    pub path: PathBuf,
+    /// Files, folders and symlinks stored
+    pub entries: usize,
+    pub bytes: u64,
+}
+/// Where an argument goes inside the archive: a relative path without `..`
+/// keeps its folders (`logs/app.log`), anything else its last component.
+pub fn entry_base(path: &Path) -> Result<PathBuf> {
+    let relative = path.is_relative()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    let base: PathBuf = if relative {
+        path.components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect()
+    } else {
+        // `..` and `/mnt/c/..` name a folder only once resolved
+        let name = path.file_name().map(PathBuf::from);
+        name.or_else(|| {
+            let canonical = dunce::canonicalize(path).ok()?;
+            canonical.file_name().map(PathBuf::from)
+        })
+        .unwrap_or_default()
+    };
+    if base.as_os_str().is_empty() {
+        anyhow::bail!("{} has no name to store in the archive", path.display());
+    }
+    Ok(base)
+}
+/// Zips `paths` (files or whole folders) into `dest`. With `follow`, symlinks
+/// are stored as what they point to; without it, as links. A failed run
+/// leaves no partial archive behind.
+pub fn create(paths: &[PathBuf], dest: &Path, follow: bool) -> Result<Archive> {
+    let result = write_archive(paths, dest, follow);
+    if result.is_err() {
+        let _ = std::fs::remove_file(dest);
+    }
+    result
+}
+fn write_archive(paths: &[PathBuf], dest: &Path, follow: bool) -> Result<Archive> {
+    let log = create_logger("archive");
+    if let Some(dir) = dest.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let file = File::create(dest).with_context(|| format!("Failed to create {:?}", dest))?;
+    let mut zip = ZipWriter::new(file);
+    let mut names = HashSet::new();
+    for path in paths {
+        let base = entry_base(path)?;
+        let walk = walkdir::WalkDir::new(path)
+            .follow_links(follow)
+            .follow_root_links(follow)
+            .sort_by_file_name();
+        for entry in walk {
+            let entry = entry.with_context(|| format!("Failed to read {}", path.display()))?;
+            let inner = entry.path().strip_prefix(path).unwrap_or(Path::new(""));
+            let name = entry_name(&base.join(inner));
+            if !names.insert(name.clone()) {
+                anyhow::bail!(
+                    "Two entries would be stored as {} (rename one, or zip their folders)",
+                    name
+                );
+            }
+            let meta = entry
+                .metadata()
+                .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+            let options = options_for(&meta);
+            if entry.file_type().is_symlink() {
+                let target = std::fs::read_link(entry.path())
+                    .with_context(|| format!("Failed to read link {}", entry.path().display()))?;
+                zip.add_symlink(name, target.to_string_lossy(), options)?;
+            } else if entry.file_type().is_dir() {
+                zip.add_directory(name, options)?;
+            } else {
+                zip.start_file(name, options.large_file(meta.len() >= u32::MAX as u64))?;
+                let mut source = File::open(entry.path())
+                    .with_context(|| format!("Failed to open {}", entry.path().display()))?;
+                std::io::copy(&mut source, &mut zip)
+                    .with_context(|| format!("Failed to compress {}", entry.path().display()))?;
+            }
+        }
+    }
+    let entries = names.len();
+    let file = zip.finish().context("Failed to finish the archive")?;
+    let bytes = file.metadata()?.len();
+    log.debug(&format!(
+        "Zipped {} entries into {:?} ({} bytes)",
+        entries, dest, bytes
+    ));
+    Ok(Archive {
+        path: dest.to_path_buf(),
+        entries,
+        bytes,
+    })
+}
+/// Zip entry names always use `/`.
+fn entry_name(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+/// Deflate, with the entry's permissions and local modification time.
+fn options_for(meta: &std::fs::Metadata) -> SimpleFileOptions {
+    use std::os::unix::fs::PermissionsExt;
+    let mut options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(meta.permissions().mode() & 0o7777);
+    let modified = meta
+        .modified()
+        .ok()
+        .map(chrono::DateTime::<chrono::Local>::from);
+    // Zip times are local, 1980 to 2107; outside that range the writer's default stays
+    if let Some(time) = modified.and_then(|t| {
+        zip::DateTime::from_date_and_time(
+            u16::try_from(t.year()).ok()?,
+            t.month() as u8,
+            t.day() as u8,
+            t.hour() as u8,
+            t.minute() as u8,
+            t.second() as u8,
+        )
+        .ok()
+    }) {
+        options = options.last_modified_time(time);
+    }
+    options
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    fn read_entries(path: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut archive = zip::ZipArchive::new(File::open(path).unwrap()).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut entry = archive.by_index(i).unwrap();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                (entry.name().to_string(), data)
+            })
+            .collect()
+    }
+    #[test]
+    fn test_entry_base() {
+        assert_eq!(
+            entry_base(Path::new("./logs/app.log")).unwrap(),
+            PathBuf::from("logs/app.log")
+        );
+        assert_eq!(
+            entry_base(Path::new("/var/log/syslog")).unwrap(),
+            PathBuf::from("syslog")
+        );
+        assert_eq!(
+            entry_base(Path::new("../x/report.pdf")).unwrap(),
+            PathBuf::from("report.pdf")
+        );
+    }
+    #[test]
+    fn test_folders_keep_their_structure() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let logs = dir.path().join("logs");
+        std::fs::create_dir_all(logs.join("old"))?;
+        std::fs::write(logs.join("app.log"), "started\n")?;
+        std::fs::write(logs.join("old/app.log"), "stopped\n")?;
+        std::fs::write(dir.path().join("notes.txt"), "hi\n")?;
+        let dest = dir.path().join("out/bundle.zip");
+        let archive = create(&[logs, dir.path().join("notes.txt")], &dest, true)?;
+        assert_eq!(archive.entries, 5);
+        assert_eq!(archive.bytes, std::fs::metadata(&dest)?.len());
+        let names: Vec<String> = read_entries(&dest).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(
+            names,
+            [
+                "logs/",
+                "logs/app.log",
+                "logs/old/",
+                "logs/old/app.log",
+                "notes.txt"
+            ]
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_symlinks_followed_or_stored() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let real = dir.path().join("real.txt");
+        std::fs::write(&real, "payload\n")?;
+        let link = dir.path().join("link.txt");
+        std::os::unix::fs::symlink(&real, &link)?;
+        let followed = create(std::slice::from_ref(&link), &dir.path().join("a.zip"), true)?;
+        assert_eq!(
+            read_entries(&followed.path),
+            [("link.txt".to_string(), b"payload\n".to_vec())]
+        );
+        let stored = create(&[link], &dir.path().join("b.zip"), false)?;
+        let mut archive = zip::ZipArchive::new(File::open(&stored.path)?)?;
+        let entry = archive.by_index(0)?;
+        assert!(entry.is_symlink());
+        Ok(())
+    }
+    #[test]
+    fn test_clashing_names_leave_nothing_behind() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for sub in ["a", "b"] {
+            std::fs::create_dir(dir.path().join(sub))?;
+            std::fs::write(dir.path().join(sub).join("same.txt"), sub)?;
+        }
+        let dest = dir.path().join("c.zip");
+        let err = create(
+            &[dir.path().join("a/same.txt"), dir.path().join("b/same.txt")],
+            &dest,
+            true,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Two entries would be stored as same.txt"));
+        assert!(!dest.exists());
+        Ok(())
+    }
+}
+
+// <FILE>src/archive.rs</FILE> - <DESC>Zip archives for `wsl-clip zip`</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T13:05:00Z</VERS>
@@ -0,0 +1,169 @@
+// <FILE>src/checksum.rs</FILE> - <DESC>SHA-256 manifests</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:34:02Z</VERS>
+// <WCTX>Integrity manifests for file-object copies.</WCTX>
+// <CLOG>Initial creation with sha256_reader, hash_files (bounded pool, progress), manifest_line and manifest.</CLOG>
+
+use crate::debug_logger::create_logger;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+/// File name of the manifest placed in the drop list.
+pub const MANIFEST_NAME: &str = "MANIFEST.sha256";
+/// Files hashed at once; hashing is I/O bound on drvfs, so more threads only thrash.
+pub const HASH_WORKERS: usize = 4;
+/// Batches smaller than this finish before a progress line would be useful.
+const PROGRESS_THRESHOLD: u64 = 64 * 1024 * 1024;
+const CHUNK: usize = 64 * 1024;
+/// Streams `reader` through SHA-256, reporting each chunk to `progress`.
+pub fn sha256_reader<R: Read>(mut reader: R, progress: &dyn Fn(u64)) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        progress(n as u64);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+/// Hashes every file on a bounded pool of `workers` threads. Digests come
+/// back in input order.
+pub fn hash_files(files: &[PathBuf], workers: usize) -> Result<Vec<String>> {
+    let log = create_logger("checksum");
+    let total: u64 = files
+        .iter()
+        .filter_map(|f| std::fs::metadata(f).ok())
+        .map(|m| m.len())
+        .sum();
+    let done = AtomicU64::new(0);
+    let next = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<String>>>> =
+        Mutex::new((0..files.len()).map(|_| None).collect());
+    let show_progress = total >= PROGRESS_THRESHOLD && std::io::stderr().is_terminal();
+    std::thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, files.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = files.get(i) else {
+                    break;
+                };
+                let digest = File::open(path)
+                    .and_then(|f| {
+                        sha256_reader(f, &|n| {
+                            done.fetch_add(n, Ordering::Relaxed);
+                        })
+                    })
+                    .with_context(|| format!("Failed to hash {:?}", path));
+                results.lock().unwrap()[i] = Some(digest);
+                finished.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        if show_progress {
+            while finished.load(Ordering::Relaxed) < files.len() {
+                let bytes = done.load(Ordering::Relaxed);
+                eprint!(
+                    "\rHashing {} files: {:>3}% ({} / {} MiB)",
+                    files.len(),
+                    bytes * 100 / total,
+                    bytes >> 20,
+                    total >> 20
+                );
+                let _ = std::io::stderr().flush();
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            eprintln!("\rHashing {} files: done{:30}", files.len(), "");
+        }
+    });
+    log.debug(&format!("Hashed {} files ({} bytes)", files.len(), total));
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every file is hashed"))
+        .collect()
+}
+/// One `sha256sum` line. Names are bare file names, since the files are
+/// pasted side by side with the manifest; names holding `\` or a newline use
+/// the coreutils escape form.
+pub fn manifest_line(digest: &str, name: &str) -> String {
+    if name.contains('\\') || name.contains('\n') {
+        let escaped = name.replace('\\', "\\\\").replace('\n', "\\n");
+        format!("\\{}  {}\n", digest, escaped)
+    } else {
+        format!("{}  {}\n", digest, name)
+    }
+}
+/// The `sha256sum -c` compatible manifest for `files`.
+pub fn manifest(files: &[PathBuf]) -> Result<String> {
+    let digests = hash_files(files, HASH_WORKERS)?;
+    Ok(files
+        .iter()
+        .zip(&digests)
+        .map(|(f, d)| manifest_line(d, &display_name(f)))
+        .collect())
+}
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_known_digests() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let files: Vec<PathBuf> = [("empty.bin", &b""[..]), ("abc.txt", b"abc")]
+            .iter()
+            .map(|(name, content)| {
+                let p = dir.path().join(name);
+                std::fs::write(&p, content).unwrap();
+                p
+            })
+            .collect();
+        assert_eq!(
+            manifest(&files)?,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  empty.bin\n\
+             ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  abc.txt\n"
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_pool_keeps_input_order_and_reports_errors() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let files: Vec<PathBuf> = (0..9)
+            .map(|i| {
+                let p = dir.path().join(format!("{}.bin", i));
+                std::fs::write(&p, vec![i as u8; 100_000 * i]).unwrap();
+                p
+            })
+            .collect();
+        let parallel = hash_files(&files, HASH_WORKERS)?;
+        let serial = hash_files(&files, 1)?;
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel[0], sha256_reader(&b""[..], &|_| {})?);
+        let missing = vec![files[0].clone(), dir.path().join("gone")];
+        assert!(hash_files(&missing, 2).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_escaped_names() {
+        assert_eq!(manifest_line("ab", "plain.txt"), "ab  plain.txt\n");
+        assert_eq!(manifest_line("ab", "a\\b\nc"), "\\ab  a\\\\b\\nc\n");
+    }
+}
+
+// <FILE>src/checksum.rs</FILE> - <DESC>SHA-256 manifests</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:34:02Z</VERS>
@@ -0,0 +1,263 @@
+// <FILE>src/cite.rs</FILE> - <DESC>Copy-as-citation provenance</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:44:41Z</VERS>
+// <WCTX>--cite and --lines.</WCTX>
+// <CLOG>Initial creation with LineRange, CiteConfig, git_info, Emitted, render_url and citation.</CLOG>
+
+use crate::debug_logger::create_logger;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+/// `--lines START:END`: 1-based and inclusive; either end may be left open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+impl LineRange {
+    /// Clap value parser: `10:42`, `10:` or `:42`.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let (start, end) = raw
+            .split_once(':')
+            .ok_or_else(|| format!("expected START:END (e.g. 10:42), got '{}'", raw))?;
+        let number = |s: &str| -> Result<Option<u64>, String> {
+            if s.trim().is_empty() {
+                return Ok(None);
+            }
+            match s.trim().parse::<u64>() {
+                Ok(0) | Err(_) => Err(format!("'{}' is not a line number (lines start at 1)", s)),
+                Ok(n) => Ok(Some(n)),
+            }
+        };
+        let range = LineRange {
+            start: number(start)?.unwrap_or(1),
+            end: number(end)?,
+        };
+        if range.end.is_some_and(|e| e < range.start) {
+            return Err(format!("range {} ends before it starts", raw));
+        }
+        Ok(range)
+    }
+    pub fn contains(&self, line: u64) -> bool {
+        line >= self.start && self.end.is_none_or(|e| line <= e)
+    }
+    /// True once `line` is past the end, so the rest of the file can be skipped.
+    pub fn is_past(&self, line: u64) -> bool {
+        self.end.is_some_and(|e| line > e)
+    }
+}
+/// `[cite]` section of the config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CiteConfig {
+    /// Link template, e.g. `https://github.com/org/repo/blob/{commit}/{path}#L{start}-L{end}`
+    pub url: Option<String>,
+}
+/// The git commit a cited file was read at, and its path inside the repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitInfo {
+    pub commit: String,
+    /// Relative to the repository root, `/`-separated
+    pub path: String,
+}
+/// HEAD and the repo-relative path for `file`, or None outside a git
+/// checkout (or without git installed).
+pub fn git_info(file: &Path) -> Option<GitInfo> {
+    let log = create_logger("cite");
+    let file = dunce::canonicalize(file).ok()?;
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(file.parent()?)
+        .args(["rev-parse", "HEAD", "--show-toplevel"])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        log.debug(&format!("No git metadata for {:?}", file));
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let mut lines = stdout.lines();
+    let commit = lines.next()?.trim().to_string();
+    let root = dunce::canonicalize(lines.next()?.trim()).ok()?;
+    let rel = file.strip_prefix(&root).ok()?;
+    let path = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(GitInfo { commit, path })
+}
+/// Which source lines of one file made it into the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Emitted {
+    pub first: Option<u64>,
+    pub last: Option<u64>,
+    /// Lines inside the --lines range
+    pub considered: u64,
+    /// Of those, lines that passed --grep
+    pub kept: u64,
+}
+impl Emitted {
+    pub fn record(&mut self, line: u64, kept: bool) {
+        self.considered += 1;
+        if kept {
+            self.kept += 1;
+            self.first.get_or_insert(line);
+            self.last = Some(line);
+        }
+    }
+}
+/// Fills `{commit}`, `{path}`, `{start}` and `{end}`. None when the template
+/// needs a value this copy does not have (no git, no lines emitted).
+pub fn render_url(
+    template: &str,
+    git: Option<&GitInfo>,
+    display: &str,
+    emitted: &Emitted,
+) -> Option<String> {
+    let needs = |key: &str| template.contains(key);
+    if needs("{commit}") && git.is_none() {
+        return None;
+    }
+    if (needs("{start}") || needs("{end}")) && emitted.first.is_none() {
+        return None;
+    }
+    let path = git.map_or(display.trim_start_matches("./"), |g| g.path.as_str());
+    let number = |n: Option<u64>| n.map(|n| n.to_string()).unwrap_or_default();
+    Some(
+        template
+            .replace("{commit}", git.map_or("", |g| g.commit.as_str()))
+            .replace("{path}", path)
+            .replace("{start}", &number(emitted.first))
+            .replace("{end}", &number(emitted.last)),
+    )
+}
+/// The provenance line written after a cited snippet, e.g.
+/// `from [src/foo.rs lines 10-42](https://...) at commit abc1234`. Line
+/// numbers are the ones actually emitted; when --grep dropped lines the
+/// count says so.
+pub fn citation(
+    display: &str,
+    emitted: &Emitted,
+    git: Option<&GitInfo>,
+    url_template: Option<&str>,
+) -> String {
+    let mut what = display.to_string();
+    match (emitted.first, emitted.last) {
+        (Some(a), Some(b)) if a == b => what.push_str(&format!(" line {}", a)),
+        (Some(a), Some(b)) => what.push_str(&format!(" lines {}-{}", a, b)),
+        _ if emitted.considered > 0 => what.push_str(" (no lines matching --grep)"),
+        _ => what.push_str(" (no lines)"),
+    }
+    let what = match url_template.and_then(|t| render_url(t, git, display, emitted)) {
+        Some(url) => format!("[{}]({})", what, url),
+        None => what,
+    };
+    let mut line = format!("from {}", what);
+    if emitted.kept < emitted.considered && emitted.kept > 0 {
+        line.push_str(&format!(
+            " ({} of {} lines matching --grep)",
+            emitted.kept, emitted.considered
+        ));
+    }
+    if let Some(g) = git {
+        let short = g.commit.get(..7).unwrap_or(&g.commit);
+        line.push_str(&format!(" at commit {}", short));
+    }
+    line.push('\n');
+    line
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const GITHUB: &str = "https://github.com/org/repo/blob/{commit}/{path}#L{start}-L{end}";
+    fn git() -> GitInfo {
+        GitInfo {
+            commit: "abc1234def5678".into(),
+            path: "src/foo.rs".into(),
+        }
+    }
+    fn emitted(lines: &[(u64, bool)]) -> Emitted {
+        let mut e = Emitted::default();
+        for &(n, kept) in lines {
+            e.record(n, kept);
+        }
+        e
+    }
+    #[test]
+    fn test_parse_line_range() {
+        assert_eq!(
+            LineRange::parse("10:42"),
+            Ok(LineRange {
+                start: 10,
+                end: Some(42)
+            })
+        );
+        assert_eq!(
+            LineRange::parse(":5"),
+            Ok(LineRange {
+                start: 1,
+                end: Some(5)
+            })
+        );
+        let open = LineRange::parse("7:").unwrap();
+        assert!(open.contains(1_000_000) && !open.contains(6) && !open.is_past(u64::MAX));
+        assert!(LineRange::parse("0:3").is_err());
+        assert!(LineRange::parse("9:3").unwrap_err().contains("ends before"));
+        assert!(LineRange::parse("12").is_err());
+    }
+    #[test]
+    fn test_template_rendering() {
+        let e = emitted(&[(10, true), (11, true), (42, true)]);
+        assert_eq!(
+            render_url(GITHUB, Some(&git()), "./src/foo.rs", &e).unwrap(),
+            "https://github.com/org/repo/blob/abc1234def5678/src/foo.rs#L10-L42"
+        );
+        assert_eq!(
+            citation("src/foo.rs", &e, Some(&git()), Some(GITHUB)),
+            "from [src/foo.rs lines 10-42](https://github.com/org/repo/blob/abc1234def5678/src/foo.rs#L10-L42) at commit abc1234\n"
+        );
+        // Without a template there is no link
+        assert_eq!(
+            citation("src/foo.rs", &emitted(&[(3, true)]), Some(&git()), None),
+            "from src/foo.rs line 3 at commit abc1234\n"
+        );
+    }
+    #[test]
+    fn test_no_git_fallback() {
+        let e = emitted(&[(1, true), (2, true)]);
+        // {commit} cannot be filled: plain text, no broken link
+        assert_eq!(
+            citation("notes/a.md", &e, None, Some(GITHUB)),
+            "from notes/a.md lines 1-2\n"
+        );
+        // A template without {commit} still links, using the display path
+        assert_eq!(
+            citation("./notes/a.md", &e, None, Some("file:///{path}#{start}")),
+            "from [./notes/a.md lines 1-2](file:///notes/a.md#1)\n"
+        );
+    }
+    #[test]
+    fn test_grep_shrinks_the_cited_range() {
+        // --lines 10:20 considered 11 lines; --grep kept 12 and 15
+        let mut lines: Vec<(u64, bool)> = (10..=20).map(|n| (n, false)).collect();
+        lines[2].1 = true;
+        lines[5].1 = true;
+        let e = emitted(&lines);
+        assert_eq!(
+            citation("a.rs", &e, None, None),
+            "from a.rs lines 12-15 (2 of 11 lines matching --grep)\n"
+        );
+        let none = emitted(&[(10, false)]);
+        assert_eq!(
+            citation("a.rs", &none, None, Some("{start}")),
+            "from a.rs (no lines matching --grep)\n"
+        );
+        assert_eq!(
+            citation("empty.rs", &Emitted::default(), None, None),
+            "from empty.rs (no lines)\n"
+        );
+    }
+}
+
+// <FILE>src/cite.rs</FILE> - <DESC>Copy-as-citation provenance</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:44:41Z</VERS>
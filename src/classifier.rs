@@ -1,71 +1,516 @@
 // <FILE>src/classifier.rs</FILE> - <DESC>Consolidated file classification logic</DESC>
-// <VERS>VERSION: 1.4.0 - 2025-11-25T16:55:29Z</VERS>
-// <WCTX>Moved ASSET_EXTS and override logic here. Added high-level inspection.</WCTX>
-// <CLOG>Added inspect() function; merged extension overrides.</CLOG>
+// <VERS>VERSION: 1.20.0 - 2026-10-16T14:31:33Z</VERS>
+// <WCTX>Typed classification rules</WCTX>
+// <CLOG>Classification reasons are a Rule enum; reject_binary_stdin matches Rule::NonPrintableRatio instead of a log label</CLOG>
 
 use crate::debug_logger::create_logger;
+use crate::encoding;
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 use std::io::Read;
-use std::path::Path;
-#[derive(Debug, PartialEq, Eq)]
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum ClipboardStrategy {
     Image, // Bitmaps
     File,  // File Objects (Binary, Assets, Archives)
     Text,  // Raw Text
 }
+/// The check that decided a classification. Callers match on this, never
+/// on the wording `label` gives it for logs and `wsl-clip classify`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    AssetExtension,
+    Directory,
+    TextExtension,
+    Shebang,
+    ByteOrderMark,
+    ImageMagic,
+    ElfMagic,
+    MachOMagic,
+    PeMagic,
+    BinarySignature,
+    NulRun,
+    /// Too many bytes no text would have, but no signature
+    NonPrintableRatio,
+    /// Nothing else matched: text
+    Default,
+    FileCommand,
+}
+impl Rule {
+    pub fn label(self) -> &'static str {
+        match self {
+            Rule::AssetExtension => "asset extension",
+            Rule::Directory => "directory",
+            Rule::TextExtension => "text extension",
+            Rule::Shebang => "shebang",
+            Rule::ByteOrderMark => "byte order mark",
+            Rule::ImageMagic => "image magic",
+            Rule::ElfMagic => "elf magic",
+            Rule::MachOMagic => "mach-o magic",
+            Rule::PeMagic => "pe magic",
+            Rule::BinarySignature => "binary signature",
+            Rule::NulRun => "nul run",
+            Rule::NonPrintableRatio => "non-printable ratio",
+            Rule::Default => "default",
+            Rule::FileCommand => "file command",
+        }
+    }
+}
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+impl Serialize for Rule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.label())
+    }
+}
 // Extensions that should ALWAYS be treated as File Objects (Assets), even if they are technically text.
 const ASSET_EXTS: &[&str] = &[
     "dxf", "obj", "stl", "ply", "gcode", "svg", "eps", "ai", "psd", "pdf", "zip", "7z", "tar",
     "gz", "rar", "iso", "dll", "bin", "exe", "jar", "class",
 ];
-fn is_asset_extension(p: &Path) -> bool {
-    if let Some(ext) = p.extension() {
-        if let Some(s) = ext.to_str() {
-            return ASSET_EXTS.contains(&s.to_lowercase().as_str());
+// Extensions that are always text: smart mode copies them without opening the file.
+const TEXT_EXTS: &[&str] = &[
+    "txt", "md", "rst", "adoc", "log", "csv", "tsv", "rs", "py", "pyi", "c", "h", "cc", "cpp",
+    "hpp", "cs", "go", "java", "kt", "swift", "js", "mjs", "cjs", "ts", "tsx", "jsx", "rb", "pl",
+    "php", "lua", "sh", "bash", "zsh", "fish", "ps1", "psm1", "bat", "cmd", "sql", "html", "htm",
+    "css", "scss", "xml", "json", "jsonl", "yaml", "yml", "toml", "ini", "cfg", "conf", "tex",
+    "vue", "svelte", "dart", "r", "el", "vim", "diff", "patch",
+];
+fn is_text_extension(p: &Path) -> bool {
+    p.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| TEXT_EXTS.contains(&ext.to_lowercase().as_str()))
+}
+static FAST: OnceLock<bool> = OnceLock::new();
+/// `--no-fast-classify`: sniff every file, known text extensions included.
+pub fn set_fast_classify(enabled: bool) {
+    let _ = FAST.set(enabled);
+}
+fn fast_classify() -> bool {
+    *FAST.get_or_init(|| true)
+}
+/// How much of a file (or of stdin) is sniffed unless the config says
+/// otherwise: enough for a meaningful non-text ratio, and for formats whose
+/// tell-tale bytes sit past the first few hundred.
+pub const DEFAULT_SAMPLE_LEN: usize = 8192;
+/// `[classifier] sample_size` is kept within these bounds: infer needs 262
+/// bytes (a tar's magic sits at 257), and more than a MiB only slows every copy.
+const SAMPLE_LEN_RANGE: std::ops::RangeInclusive<usize> = 512..=1024 * 1024;
+static SAMPLE: OnceLock<usize> = OnceLock::new();
+/// Fixes the sample size for this process (`[classifier] sample_size`).
+pub fn set_sample_len(size: Option<usize>) {
+    let len = size.map_or(DEFAULT_SAMPLE_LEN, |n| {
+        n.clamp(*SAMPLE_LEN_RANGE.start(), *SAMPLE_LEN_RANGE.end())
+    });
+    let len = *SAMPLE.get_or_init(|| len);
+    create_logger("classifier").debug(&format!("Sample size: {} bytes", len));
+}
+static DEEP: OnceLock<bool> = OnceLock::new();
+/// `--deep-classify` (or `[classifier] deep`): doubtful text goes to `file`.
+pub fn set_deep_classify(enabled: bool) {
+    let _ = DEEP.set(enabled);
+}
+fn deep_classify() -> bool {
+    *DEEP.get_or_init(|| false)
+}
+/// How long `file` may take before the built-in decision stands.
+const FILE_TIMEOUT: Duration = Duration::from_secs(3);
+/// `file`'s answers for this process, so a path is only asked about once.
+static FILE_MIMES: Mutex<Option<HashMap<PathBuf, Option<String>>>> = Mutex::new(None);
+/// `file --brief --mime-type` for `path`, or None if it is missing, fails
+/// or times out.
+fn file_mime(path: &Path) -> Option<String> {
+    let mut cache = FILE_MIMES.lock().unwrap_or_else(|e| e.into_inner());
+    let cache = cache.get_or_insert_with(HashMap::new);
+    if let Some(mime) = cache.get(path) {
+        return mime.clone();
+    }
+    let mime = run_file(path)
+        .map_err(|e| create_logger("classifier").debug(&format!("file: {:#}", e)))
+        .ok();
+    cache.insert(path.to_path_buf(), mime.clone());
+    mime
+}
+fn run_file(path: &Path) -> Result<String> {
+    let mut child = Command::new("file")
+        .args(["--brief", "--mime-type", "--"])
+        .arg(path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to execute file")?;
+    if crate::deadline::wait(&mut child, Some(FILE_TIMEOUT))?.is_none() {
+        anyhow::bail!("timed out after {}s", FILE_TIMEOUT.as_secs());
+    }
+    let output = child.wait_with_output()?;
+    let mime = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || !mime.contains('/') {
+        anyhow::bail!("no MIME type ({}: {:?})", output.status, mime);
+    }
+    Ok(mime)
+}
+/// What a `file` MIME type means for the clipboard; None leaves the
+/// built-in decision (`file` could not tell either). Images it knows but
+/// the magic check did not are formats Windows cannot paste as pixels.
+pub fn strategy_for_mime(mime: &str) -> Option<ClipboardStrategy> {
+    match mime {
+        "application/octet-stream" => None,
+        "inode/x-empty" => Some(ClipboardStrategy::Text),
+        m if is_text_type(m) => Some(ClipboardStrategy::Text),
+        _ => Some(ClipboardStrategy::File),
+    }
+}
+/// How many leading bytes the magic-byte checks see.
+pub fn sample_len() -> usize {
+    *SAMPLE.get_or_init(|| DEFAULT_SAMPLE_LEN)
+}
+/// Up to `sample_len()` bytes from the start of `reader`.
+fn read_sample(reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(sample_len());
+    reader.take(sample_len() as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+/// Adds to (`parquet`) or removes from (`-svg`) the asset list, comma-separated.
+pub const ASSET_EXTS_ENV: &str = "WSL_CLIP_ASSET_EXTS";
+/// `[classifier]` section of the config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClassifierConfig {
+    /// Extensions copied as file objects, on top of the built-in ones
+    pub asset_extensions: Vec<String>,
+    /// Built-in asset extensions to drop; such files go by their content
+    pub text_extensions: Vec<String>,
+    /// Bytes read from the start of a file to sniff it (default 8192)
+    pub sample_size: Option<usize>,
+    /// Ask `file` about content the built-in rules can only call text by default
+    pub deep: bool,
+}
+/// `Report.PDF` and `.pdf` both mean `pdf`.
+fn normalize_extension(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+/// The built-in list, then the config's additions and removals, then the
+/// environment's (`env`, in `ASSET_EXTS_ENV` form), each able to undo the last.
+pub fn merge_extensions(config: &ClassifierConfig, env: Option<&str>) -> BTreeSet<String> {
+    let mut exts: BTreeSet<String> = ASSET_EXTS.iter().map(|e| e.to_string()).collect();
+    exts.extend(
+        config
+            .asset_extensions
+            .iter()
+            .map(|e| normalize_extension(e)),
+    );
+    for ext in &config.text_extensions {
+        exts.remove(&normalize_extension(ext));
+    }
+    for item in env.unwrap_or("").split(',') {
+        match item.trim().strip_prefix('-') {
+            Some(removed) => {
+                exts.remove(&normalize_extension(removed));
+            }
+            None => {
+                exts.insert(normalize_extension(item));
+            }
         }
     }
-    false
+    exts.remove("");
+    exts
+}
+static ASSETS: OnceLock<BTreeSet<String>> = OnceLock::new();
+/// Fixes the asset extensions for this process from the config and
+/// `WSL_CLIP_ASSET_EXTS`; `--debug` logs the result.
+pub fn set_extensions(config: &ClassifierConfig) {
+    let exts = ASSETS
+        .get_or_init(|| merge_extensions(config, std::env::var(ASSET_EXTS_ENV).ok().as_deref()));
+    create_logger("classifier").debug(&format!(
+        "Asset extensions: {}",
+        exts.iter().cloned().collect::<Vec<_>>().join(", ")
+    ));
+}
+fn asset_extensions() -> &'static BTreeSet<String> {
+    ASSETS.get_or_init(|| merge_extensions(&ClassifierConfig::default(), None))
+}
+fn is_asset_extension(p: &Path) -> bool {
+    let exts = asset_extensions();
+    p.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| exts.contains(&ext.to_lowercase()))
 }
 /// Determines the best clipboard strategy for a given file.
-/// Checks extension overrides first (fast), then falls back to magic bytes (robust).
+/// Checks extension overrides first (fast), then known text extensions
+/// (unless --no-fast-classify), then falls back to magic bytes (robust).
+/// Directories are File Objects: a drop list carries the whole tree.
 pub fn inspect(path: &Path) -> Result<ClipboardStrategy> {
+    Ok(inspect_with_reason(path)?.0)
+}
+/// `inspect`, plus which check decided.
+pub fn inspect_with_reason(path: &Path) -> Result<(ClipboardStrategy, Rule)> {
     let log = create_logger("classifier");
-    // 1. Extension Override (Fast Path)
+    let started = Instant::now();
+    let (strategy, reason) = decide(path)?;
+    log.debug(&format!(
+        "Classified as {:?} ({}) in {:?}: {:?}",
+        strategy,
+        reason,
+        started.elapsed(),
+        path
+    ));
+    Ok((strategy, reason))
+}
+/// What `wsl-clip classify` reports for one path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Classification {
+    pub strategy: ClipboardStrategy,
+    /// The check that decided (`asset extension`, `image magic`, ...)
+    pub rule: Rule,
+    /// What infer recognizes in the first bytes, if anything
+    pub mime: Option<&'static str>,
+}
+/// `inspect_with_reason`, plus the detected MIME type. The sample is read
+/// for the MIME even when an extension decided without it.
+pub fn explain(path: &Path) -> Result<Classification> {
+    let (strategy, rule) = inspect_with_reason(path)?;
+    let mime = File::open(path)
+        .and_then(read_sample)
+        .ok()
+        .and_then(|buffer| infer::get(&buffer).map(|kind| kind.mime_type()));
+    Ok(Classification {
+        strategy,
+        rule,
+        mime,
+    })
+}
+fn decide(path: &Path) -> Result<(ClipboardStrategy, Rule)> {
+    // 0. Extension Override (Fast Path)
     if is_asset_extension(path) {
-        log.debug(&format!(
-            "Extension override detected (Asset/Binary): {:?}",
-            path
-        ));
-        return Ok(ClipboardStrategy::File);
+        return Ok((ClipboardStrategy::File, Rule::AssetExtension));
     }
-    // 2. Open file for Magic Byte detection
-    let mut file = File::open(path)
+    // 1. Directories have no bytes to sniff; a missing file fails here, before the text fast path
+    let meta = std::fs::metadata(path)
         .with_context(|| format!("Failed to open file for classification: {:?}", path))?;
+    if meta.is_dir() {
+        return Ok((ClipboardStrategy::File, Rule::Directory));
+    }
+    // 2. Known text extension: no need to open or read the file
+    if fast_classify() && is_text_extension(path) {
+        return Ok((ClipboardStrategy::Text, Rule::TextExtension));
+    }
+    // 3. Open file for Magic Byte detection
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open file for classification: {:?}", path))?;
+    let buffer = read_sample(file).unwrap_or_default();
+    let decided = inspect_bytes_with_reason(&buffer);
+    // 11. Deep mode: text only by default, and doubtful, goes to `file`
+    if decided == (ClipboardStrategy::Text, Rule::Default) && deep_classify() && doubtful(&buffer) {
+        if let Some(mime) = file_mime(path) {
+            create_logger("classifier").debug(&format!("file says {} for {:?}", mime, path));
+            if let Some(strategy) = strategy_for_mime(&mime) {
+                return Ok((strategy, Rule::FileCommand));
+            }
+        }
+    }
+    Ok(decided)
+}
+/// The magic-byte half of `inspect`, for the first bytes of any content.
+pub fn inspect_bytes(buffer: &[u8]) -> ClipboardStrategy {
+    inspect_bytes_with_reason(buffer).0
+}
+/// `inspect_bytes`, plus which check decided (for the debug log).
+pub fn inspect_bytes_with_reason(buffer: &[u8]) -> (ClipboardStrategy, Rule) {
+    // 4. Shebang: a script, even with a stray high or NUL byte
+    if buffer.starts_with(b"#!") {
+        return (ClipboardStrategy::Text, Rule::Shebang);
+    }
+    // 5. Byte order mark (UTF-8, UTF-16LE/BE): text, whatever follows
+    if encoding_rs::Encoding::for_bom(buffer).is_some() {
+        return (ClipboardStrategy::Text, Rule::ByteOrderMark);
+    }
+    // 6. Check Image
+    if infer::is_image(buffer) {
+        return (ClipboardStrategy::Image, Rule::ImageMagic);
+    }
+    // 7. Executables, whatever they are named and however printable their header
+    if let Some(magic) = executable_magic(buffer) {
+        return (ClipboardStrategy::File, magic);
+    }
+    // 8. Check Binary Signatures (infer takes any "MZ" for an exe; is_pe decided those)
+    let app = infer::is_app(buffer) && !buffer.starts_with(b"MZ");
+    if infer::is_archive(buffer) || app || infer::doc::is_doc(buffer) {
+        return (ClipboardStrategy::File, Rule::BinarySignature);
+    }
+    // 9. Heuristics: NUL padding anywhere in the sample, or too many bytes
+    // no text would have (BOM-less UTF-16's NULs excepted)
+    if encoding::sniff_utf16(buffer).is_none() {
+        if has_nul_run(buffer) {
+            return (ClipboardStrategy::File, Rule::NulRun);
+        }
+        if looks_binary(buffer) {
+            return (ClipboardStrategy::File, Rule::NonPrintableRatio);
+        }
+    }
+    // 10. Default
+    (ClipboardStrategy::Text, Rule::Default)
+}
+/// Share of the sample, in percent, that may be non-text before it counts as binary.
+const BINARY_PERCENT: usize = 10;
+/// A NUL counts this many times over: text almost never has one, but a
+/// single one (a truncated write) should not turn a text file into a file object.
+const NUL_WEIGHT: usize = 16;
+/// Consecutive NULs that mark padding or a binary record.
+const NUL_RUN: usize = 4;
+/// Binary data pads with NULs; text never has a run of them, so one
+/// anywhere in the sample wins over a long text preamble.
+fn has_nul_run(buffer: &[u8]) -> bool {
+    buffer.windows(NUL_RUN).any(|w| w.iter().all(|&b| b == 0))
+}
+/// Counts control characters other than common whitespace and ESC (colored
+/// logs are text), and bytes that are not valid UTF-8, so Latin-1 text
+/// (a few high bytes) stays text. A character cut off by the end of the
+/// sample is not held against it.
+fn looks_binary(buffer: &[u8]) -> bool {
+    non_text_score(buffer) * 100 > buffer.len() * BINARY_PERCENT
+}
+/// Text by default, but not convincingly: within half the threshold of
+/// binary, or starting with bytes no text would (an unknown magic number).
+fn doubtful(buffer: &[u8]) -> bool {
+    let head = &buffer[..buffer.len().min(4)];
+    non_text_score(buffer) * 200 > buffer.len() * BINARY_PERCENT || non_text_score(head) > 0
+}
+/// The weighted count `looks_binary` compares against the sample length.
+fn non_text_score(buffer: &[u8]) -> usize {
+    let mut suspicious = 0;
+    let mut rest = buffer;
+    while !rest.is_empty() {
+        let (valid, invalid) = match std::str::from_utf8(rest) {
+            Ok(text) => (text, 0),
+            Err(e) => {
+                let valid = std::str::from_utf8(&rest[..e.valid_up_to()]).unwrap_or_default();
+                (valid, e.error_len().unwrap_or(0))
+            }
+        };
+        suspicious += valid
+            .chars()
+            .filter(|&c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0C' | '\x1B'))
+            .map(|c| if c == '\0' { NUL_WEIGHT } else { 1 })
+            .sum::<usize>();
+        if invalid == 0 {
+            break;
+        }
+        suspicious += invalid;
+        rest = &rest[valid.len() + invalid..];
+    }
+    suspicious
+}
+/// ELF, Mach-O (thin, either byte order, and fat) and PE headers.
+fn executable_magic(buffer: &[u8]) -> Option<Rule> {
+    const MACH_O: [[u8; 4]; 5] = [
+        [0xFE, 0xED, 0xFA, 0xCE],
+        [0xFE, 0xED, 0xFA, 0xCF],
+        [0xCE, 0xFA, 0xED, 0xFE],
+        [0xCF, 0xFA, 0xED, 0xFE],
+        [0xCA, 0xFE, 0xBA, 0xBE],
+    ];
+    if buffer.starts_with(b"\x7FELF") {
+        return Some(Rule::ElfMagic);
+    }
+    if MACH_O.iter().any(|m| buffer.starts_with(m)) {
+        return Some(Rule::MachOMagic);
+    }
+    is_pe(buffer).then_some(Rule::PeMagic)
+}
+/// `MZ`, then `PE\0\0` where the DOS header's e_lfanew points. Text that
+/// merely starts with "MZ" has no such signature; when the offset is past
+/// the sampled bytes, the DOS header's own NULs are taken as enough.
+fn is_pe(buffer: &[u8]) -> bool {
+    if !buffer.starts_with(b"MZ") || buffer.len() < 0x40 {
+        return false;
+    }
+    let offset =
+        u32::from_le_bytes([buffer[0x3C], buffer[0x3D], buffer[0x3E], buffer[0x3F]]) as usize;
+    match buffer.get(offset..offset.saturating_add(4)) {
+        Some(signature) => signature == b"PE\0\0",
+        None => offset >= buffer.len() && buffer[..0x40].contains(&0),
+    }
+}
+/// `inspect` for a download: the name's extension and the magic bytes as
+/// for a file, then the Content-Type, so bytes that merely look like text
+/// (`application/octet-stream`, a PDF served without its signature) still
+/// go as a file object.
+pub fn classify_download(
+    name: Option<&str>,
+    content_type: Option<&str>,
+    bytes: &[u8],
+) -> ClipboardStrategy {
+    if name.is_some_and(|n| is_asset_extension(Path::new(n))) {
+        return ClipboardStrategy::File;
+    }
+    let head = &bytes[..bytes.len().min(sample_len())];
+    match inspect_bytes(head) {
+        ClipboardStrategy::Text if content_type.is_some_and(|t| !is_text_type(t)) => {
+            ClipboardStrategy::File
+        }
+        strategy => strategy,
+    }
+}
+/// Media types whose bodies are text to paste.
+fn is_text_type(media: &str) -> bool {
+    media.starts_with("text/")
+        || media.ends_with("+json")
+        || media.ends_with("+xml")
+        || matches!(
+            media,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-sh"
+                | "application/yaml"
+                | "application/toml"
+        )
+}
+/// True for `.svg` / `.svgz` files, which `img` renders instead of decoding.
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("svg") || e.eq_ignore_ascii_case("svgz"))
+}
+/// Checks that `path` holds pixels an Image copy can use (or an SVG to
+/// render), before any path conversion or PowerShell run. Errors name the
+/// reason: missing, unreadable, empty, or the type that was detected instead.
+pub fn expect_image(path: &Path) -> Result<()> {
+    let meta = std::fs::metadata(path).with_context(|| format!("Cannot read {:?}", path))?;
+    if meta.is_dir() {
+        anyhow::bail!("{:?} is a directory, not an image", path);
+    }
+    if meta.len() == 0 {
+        anyhow::bail!("{:?} is empty (0 bytes), not an image", path);
+    }
+    if is_svg(path) {
+        // Parsed when rendered; its errors are the parser's
+        return Ok(());
+    }
+    let mut file = File::open(path).with_context(|| format!("Cannot read {:?}", path))?;
     let mut buffer = [0u8; 262];
-    let n = file.read(&mut buffer).unwrap_or(0);
+    let n = file
+        .read(&mut buffer)
+        .with_context(|| format!("Cannot read {:?}", path))?;
     let buffer = &buffer[..n];
-    // 3. Check Image
     if infer::is_image(buffer) {
-        log.debug(&format!("Detected IMAGE signature: {:?}", path));
-        return Ok(ClipboardStrategy::Image);
-    }
-    // 4. Check Binary Signatures
-    if infer::is_archive(buffer) || infer::is_app(buffer) || infer::doc::is_doc(buffer) {
-        log.debug(&format!("Detected BINARY signature: {:?}", path));
-        return Ok(ClipboardStrategy::File);
-    }
-    // 5. Heuristic: Null bytes
-    if buffer.contains(&0) {
-        log.debug(&format!(
-            "Detected NULL bytes (Binary heuristic): {:?}",
-            path
-        ));
-        return Ok(ClipboardStrategy::File);
+        return Ok(());
     }
-    // 6. Default
-    log.debug(&format!("Classified as TEXT: {:?}", path));
-    Ok(ClipboardStrategy::Text)
+    let detected = match infer::get(buffer) {
+        Some(kind) => kind.mime_type(),
+        None if buffer.contains(&0) => "application/octet-stream",
+        None => "text/plain",
+    };
+    anyhow::bail!("{:?} is not an image: detected {}", path, detected)
 }
 #[cfg(test)]
 mod tests {
@@ -89,9 +534,294 @@ mod tests {
         let mut file = NamedTempFile::new()?;
         write!(file, "Hello World")?;
         assert_eq!(inspect(file.path())?, ClipboardStrategy::Text);
+        // Empty files are text; text mode decides whether to include them
+        let empty = NamedTempFile::new()?;
+        assert_eq!(
+            decide(empty.path())?,
+            (ClipboardStrategy::Text, Rule::Default)
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_expect_image() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let write = |name: &str, bytes: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, bytes).unwrap();
+            path
+        };
+        let reason = |path: &Path| expect_image(path).unwrap_err().to_string();
+        expect_image(&write("shot.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"))?;
+        // Content decides, not the extension
+        expect_image(&write("shot.dat", b"GIF89a\x01\0\x01\0"))?;
+        assert!(reason(&write("notes.png", b"hello\n"))
+            .ends_with("is not an image: detected text/plain"));
+        assert!(reason(&write("doc.png", b"%PDF-1.7\n")).ends_with("detected application/pdf"));
+        assert!(reason(&write("blob.png", &[1, 0, 2, 0]))
+            .ends_with("detected application/octet-stream"));
+        assert!(reason(&write("empty.png", b"")).contains("is empty (0 bytes)"));
+        expect_image(&write("logo.SVG", b"<svg/>"))?;
+        assert!(reason(dir.path()).contains("is a directory"));
+        let missing = reason(&dir.path().join("missing.png"));
+        assert!(missing.starts_with("Cannot read") && missing.contains("missing.png"));
+        Ok(())
+    }
+    #[test]
+    fn test_directories_are_file_objects() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert_eq!(inspect(dir.path())?, ClipboardStrategy::File);
+        // The name does not matter either
+        let odd = dir.path().join("photos.png");
+        std::fs::create_dir(&odd)?;
+        assert_eq!(inspect(&odd)?, ClipboardStrategy::File);
+        Ok(())
+    }
+    #[test]
+    fn test_classify_download() {
+        use ClipboardStrategy::*;
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        assert_eq!(classify_download(None, Some("text/plain"), &png), Image);
+        assert_eq!(
+            classify_download(None, Some("text/html"), b"<p>hi</p>"),
+            Text
+        );
+        assert_eq!(classify_download(None, None, b"plain words"), Text);
+        assert_eq!(
+            classify_download(None, Some("application/octet-stream"), b"looks textual"),
+            File
+        );
+        assert_eq!(
+            classify_download(Some("model.stl"), Some("text/plain"), b"solid cube"),
+            File
+        );
+        assert_eq!(
+            classify_download(None, Some("application/vnd.api+json"), b"{}"),
+            Text
+        );
+    }
+    #[test]
+    fn test_classify_unicode_text() {
+        let utf16 = |text: &str, be: bool| -> Vec<u8> {
+            text.encode_utf16()
+                .flat_map(|u| if be { u.to_be_bytes() } else { u.to_le_bytes() })
+                .collect()
+        };
+        let mut le = vec![0xFF, 0xFE];
+        le.extend(utf16(
+            "**********************\r\nWindows PowerShell transcript start",
+            false,
+        ));
+        let mut be = vec![0xFE, 0xFF];
+        be.extend(utf16("Event log\r\n", true));
+        let bom_utf8 = b"\xEF\xBB\xBFname,value\n".to_vec();
+        // Notepad's "Unicode" without its BOM
+        let bare = utf16("Saved by a tool that skips the BOM\r\n", false);
+        for bytes in [le, be, bom_utf8, bare] {
+            assert_eq!(
+                inspect_bytes(&bytes),
+                ClipboardStrategy::Text,
+                "{:?}",
+                bytes
+            );
+        }
+        // Scattered NULs are still binary
+        assert_eq!(
+            inspect_bytes(&[b'a', 0, 0x01, 0, 0x02, 0]),
+            ClipboardStrategy::File
+        );
+    }
+    #[test]
+    fn test_text_extensions_skip_the_read() -> Result<()> {
+        assert!(is_text_extension(Path::new("src/main.RS")));
+        assert!(!is_text_extension(Path::new("shot.png")));
+        assert!(!is_text_extension(Path::new("Makefile")));
+        // Not opened, so the bytes do not matter; the asset list still wins
+        let dir = tempfile::tempdir()?;
+        let odd = dir.path().join("notes.md");
+        std::fs::write(&odd, [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0])?;
+        assert_eq!(
+            decide(&odd)?,
+            (ClipboardStrategy::Text, Rule::TextExtension)
+        );
+        assert!(decide(&dir.path().join("gone.md")).is_err());
+        assert!(TEXT_EXTS.iter().all(|e| !ASSET_EXTS.contains(e)));
+        Ok(())
+    }
+    #[test]
+    fn test_explain() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let shot = dir.path().join("shot.dat");
+        std::fs::write(&shot, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")?;
+        assert_eq!(
+            explain(&shot)?,
+            Classification {
+                strategy: ClipboardStrategy::Image,
+                rule: Rule::ImageMagic,
+                mime: Some("image/png"),
+            }
+        );
+        // Decided by the name, but the MIME still comes from the bytes
+        let odd = dir.path().join("notes.md");
+        std::fs::write(&odd, b"%PDF-1.7\n")?;
+        let odd = explain(&odd)?;
+        assert_eq!(
+            (odd.rule, odd.mime),
+            (Rule::TextExtension, Some("application/pdf"))
+        );
+        assert_eq!(explain(dir.path())?.mime, None);
+        assert!(explain(&dir.path().join("gone.bin")).is_ok());
+        assert!(explain(&dir.path().join("gone.dat")).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_merge_extensions() {
+        let config = ClassifierConfig {
+            asset_extensions: vec!["parquet".into(), ".SQLite".into(), "onnx".into()],
+            text_extensions: vec!["SVG".into()],
+            ..Default::default()
+        };
+        let exts = merge_extensions(&config, None);
+        assert!(exts.contains("parquet") && exts.contains("sqlite") && exts.contains("onnx"));
+        assert!(!exts.contains("svg"));
+        assert!(exts.contains("pdf"));
+        // The environment comes last: it can remove an addition and restore a removal
+        let exts = merge_extensions(&config, Some(" -parquet, svg,-PDF ,,"));
+        assert!(!exts.contains("parquet") && exts.contains("svg") && !exts.contains("pdf"));
+        assert!(exts.contains("sqlite"));
+        assert!(!exts.contains(""));
+        assert_eq!(
+            merge_extensions(&ClassifierConfig::default(), None).len(),
+            ASSET_EXTS.len()
+        );
+    }
+    #[test]
+    fn test_classify_shebang_scripts() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        // A Latin-1 byte and a NUL would otherwise make it a file object
+        file.write_all(b"#!/bin/sh\necho caf\xE9\nprintf '\0'\x00\n")?;
+        assert_eq!(inspect(file.path())?, ClipboardStrategy::Text);
+        assert_eq!(
+            inspect_bytes_with_reason(b"#!/usr/bin/env python3\n"),
+            (ClipboardStrategy::Text, Rule::Shebang)
+        );
         Ok(())
     }
     #[test]
+    fn test_classify_executables() {
+        // Headers followed by printable bytes, which nothing else would catch
+        let padded = |header: &[u8]| {
+            let mut bytes = header.to_vec();
+            bytes.resize(262, b'A');
+            bytes
+        };
+        let elf = padded(b"\x7FELF\x02\x01\x01");
+        assert_eq!(
+            inspect_bytes_with_reason(&elf),
+            (ClipboardStrategy::File, Rule::ElfMagic)
+        );
+        let macho = padded(&[0xCF, 0xFA, 0xED, 0xFE]);
+        assert_eq!(
+            inspect_bytes_with_reason(&macho),
+            (ClipboardStrategy::File, Rule::MachOMagic)
+        );
+        let mut pe = padded(b"MZ");
+        pe[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        assert_eq!(
+            inspect_bytes_with_reason(&pe),
+            (ClipboardStrategy::File, Rule::PeMagic)
+        );
+        // e_lfanew past the sample: the DOS header's NULs decide
+        let mut far = padded(b"MZ");
+        far[0x3C..0x40].copy_from_slice(&0x400u32.to_le_bytes());
+        assert_eq!(inspect_bytes(&far), ClipboardStrategy::File);
+        // Text that happens to start with MZ stays text
+        let notes = padded(b"MZ notes: the mezzanine floor plan\n");
+        assert_eq!(
+            inspect_bytes_with_reason(&notes),
+            (ClipboardStrategy::Text, Rule::Default)
+        );
+    }
+    #[test]
+    fn test_non_printable_ratio() {
+        use ClipboardStrategy::*;
+        // Latin-1: high bytes are invalid UTF-8, but few
+        let latin1 = b"Le caf\xE9 est ouvert tous les jours de 8h \xE0 18h.\n\
+            Les commandes pass\xE9es avant midi sont livr\xE9es le lendemain.\n";
+        assert_eq!(inspect_bytes(latin1), Text);
+        // ...even with one NUL left by a truncated write
+        let mut truncated = latin1.repeat(3);
+        truncated.push(0);
+        assert_eq!(inspect_bytes(&truncated), Text);
+        // Minified JS with a stray control character, and colored output
+        let js = b"var a=function(){return\x0b'x'};console.log('\x1b[31mred\x1b[0m');/* \xC3\xA9t\xC3\xA9 */\n";
+        assert_eq!(inspect_bytes(js), Text);
+        // A multibyte character cut off by the end of the sample
+        assert_eq!(
+            inspect_bytes("tr\u{e8}s \u{1F980}".as_bytes().split_last().unwrap().1),
+            Text
+        );
+        // Genuinely binary: a compressed-looking run, and a NUL-padded record
+        let noise: Vec<u8> = (0u32..512)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        assert_eq!(
+            inspect_bytes_with_reason(&noise),
+            (File, Rule::NonPrintableRatio)
+        );
+        assert_eq!(inspect_bytes(b"REC1\0\0\0\x04data\0\0\0\0"), File);
+    }
+    #[test]
+    fn test_text_preamble_before_nuls() -> Result<()> {
+        // A readable header, then a NUL-padded binary blob at 1 KB
+        let mut data = b"HEADER v2: exported by the tool, do not edit by hand\n".repeat(20);
+        data.truncate(1024);
+        assert!(data[..300]
+            .iter()
+            .all(|b| b.is_ascii_graphic() || b" \n".contains(b)));
+        data.extend_from_slice(b"\0\0\0\0\x10\0\0\0payload");
+        data.extend(b"more text after the record\n".repeat(10));
+        let mut file = NamedTempFile::new()?;
+        file.write_all(&data)?;
+        assert_eq!(
+            decide(file.path())?,
+            (ClipboardStrategy::File, Rule::NulRun)
+        );
+        // The first 262 bytes alone look like text
+        assert_eq!(inspect_bytes(&data[..262]), ClipboardStrategy::Text);
+        // A lone NUL from a truncated write is still not enough
+        data[1024..1028].copy_from_slice(b"\0abc");
+        file.as_file().set_len(0)?;
+        std::fs::write(file.path(), &data)?;
+        assert_eq!(inspect(file.path())?, ClipboardStrategy::Text);
+        Ok(())
+    }
+    #[test]
+    fn test_doubtful_text() {
+        assert!(!doubtful(b"plain words and a newline\n"));
+        // An unknown binary header in front of text
+        let mut lotus = b"\0\0\x02\0\x06\x04".to_vec();
+        lotus.extend(b"sheet one, row one\n".repeat(60));
+        assert_eq!(
+            inspect_bytes_with_reason(&lotus),
+            (ClipboardStrategy::Text, Rule::Default)
+        );
+        assert!(doubtful(&lotus));
+        // Control characters at 6% of the sample: text, but close to the line
+        let noisy = b"0123456789abcdef\x01\n".repeat(10);
+        assert!(!looks_binary(&noisy) && doubtful(&noisy));
+    }
+    #[test]
+    fn test_strategy_for_mime() {
+        use ClipboardStrategy::*;
+        assert_eq!(strategy_for_mime("text/x-c"), Some(Text));
+        assert_eq!(strategy_for_mime("application/json"), Some(Text));
+        assert_eq!(strategy_for_mime("inode/x-empty"), Some(Text));
+        assert_eq!(strategy_for_mime("application/vnd.lotus-1-2-3"), Some(File));
+        assert_eq!(strategy_for_mime("image/x-portable-pixmap"), Some(File));
+        assert_eq!(strategy_for_mime("application/octet-stream"), None);
+    }
+    #[test]
     fn test_classify_binary_nulls() -> Result<()> {
         let mut file = NamedTempFile::new()?;
         file.write_all(&[0x00, 0x01, 0x02])?;
@@ -101,4 +831,4 @@ mod tests {
 }
 
 // <FILE>src/classifier.rs</FILE> - <DESC>Consolidated file classification logic</DESC>
-// <VERS>END OF VERSION: 1.4.0 - 2025-11-25T16:55:29Z</VERS>
+// <VERS>END OF VERSION: 1.20.0 - 2026-10-16T14:31:33Z</VERS>
@@ -0,0 +1,88 @@
+// <FILE>src/clear.rs</FILE> - <DESC>Delayed clipboard clearing</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:53:38Z</VERS>
+// <WCTX>wsl-clip clear.</WCTX>
+// <CLOG>Initial creation with parse_delay, detached_args and schedule.</CLOG>
+
+use crate::debug_logger::create_logger;
+use anyhow::{Context, Result};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+/// Parses `--after`: seconds, optionally suffixed `s`, `m` or `h` (`30`, `90s`, `2m`).
+pub fn parse_delay(raw: &str) -> Result<Duration, String> {
+    let t = raw.trim().to_ascii_lowercase();
+    let (number, scale) = match t.chars().last() {
+        Some('s') => (&t[..t.len() - 1], 1),
+        Some('m') => (&t[..t.len() - 1], 60),
+        Some('h') => (&t[..t.len() - 1], 3600),
+        _ => (t.as_str(), 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(scale))
+        .map(Duration::from_secs)
+        .ok_or_else(|| {
+            format!(
+                "invalid delay '{}' (expected seconds, e.g. 30, 90s or 2m)",
+                raw
+            )
+        })
+}
+/// The arguments the detached clearer runs with.
+pub fn detached_args(delay: Duration) -> Vec<String> {
+    vec![
+        "clear".to_string(),
+        "--after".to_string(),
+        delay.as_secs().to_string(),
+        "--detached".to_string(),
+    ]
+}
+/// Starts a second wsl-clip that sleeps `delay` and then clears, and returns
+/// its pid without waiting. It gets its own process group and no stdio, so
+/// the calling shell can exit (or be interrupted) without cancelling it.
+pub fn schedule(delay: Duration) -> Result<u32> {
+    let log = create_logger("clipboard");
+    let exe = std::env::current_exe().context("Cannot locate the wsl-clip executable")?;
+    let child = Command::new(&exe)
+        .args(detached_args(delay))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .with_context(|| format!("Failed to start {:?} for the delayed clear", exe))?;
+    log.debug(&format!(
+        "Delayed clear in {}s: pid {}",
+        delay.as_secs(),
+        child.id()
+    ));
+    // Deliberately not waited on: it outlives this process and is reaped by init
+    Ok(child.id())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_parse_delay() {
+        assert_eq!(parse_delay("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_delay("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_delay(" 2M "), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_delay("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_delay("0"), Ok(Duration::ZERO));
+        assert!(parse_delay("-5").is_err());
+        assert!(parse_delay("1.5").is_err());
+        assert!(parse_delay("s").is_err());
+        assert!(parse_delay("10d").unwrap_err().contains("invalid delay"));
+    }
+    #[test]
+    fn test_detached_args_round_trip() {
+        let args = detached_args(Duration::from_secs(120));
+        assert_eq!(args, ["clear", "--after", "120", "--detached"]);
+        assert_eq!(parse_delay(&args[2]), Ok(Duration::from_secs(120)));
+    }
+}
+
+// <FILE>src/clear.rs</FILE> - <DESC>Delayed clipboard clearing</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:53:38Z</VERS>
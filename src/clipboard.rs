@@ -1,93 +1,1942 @@
-// <FILE>src/clipboard.rs</FILE> - <DESC>Fixed PowerShell argument passing logic</DESC>
-// <VERS>VERSION: 1.6.0 - 2025-11-25T17:32:57Z</VERS>
-// <WCTX>Wrapped script body in "& { ... }" to correctly capture CLI arguments into $args.</WCTX>
-// <CLOG>Fixed PS injection by using call operator block; removed args_placeholder.</CLOG>
+// <FILE>src/clipboard.rs</FILE> - <DESC>Clipboard read path</DESC>
+// <VERS>VERSION: 1.41.0 - 2026-10-16T14:50:15Z</VERS>
+// <WCTX>Daemon clients never block each other.</WCTX>
+// <CLOG>Connect at wait(); a thread per connection; buffer at most daemon::TEXT_LIMIT.</CLOG>
 
+use crate::daemon;
+use crate::deadline;
 use crate::debug_logger::create_logger;
+use crate::encoding::{EncodingWriter, TextEncoding, Windows1252Decoder};
+use crate::environment;
+use crate::interop;
+use crate::paths;
+use crate::powershell;
+use crate::preflight::{self, Preflight};
+use crate::retry;
+use crate::staging;
+use crate::transcode;
 use anyhow::{Context, Result};
-use std::process::{Child, ChildStdin, Command, Stdio};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
 pub enum ClipboardMode {
     Image,
+    /// Image mode's formats plus a drop list naming the file, so Paint
+    /// pastes the pixels and Explorer the file
+    ImageAndFile,
     File,
+    /// A drop list plus a plain-text alternative (CF_UNICODETEXT), so a paste
+    /// into a text field yields the text instead of nothing
+    FileWithText(String),
+    /// A drop list marked as cut (`--move`): Explorer removes the source
+    /// once it has pasted it. Carries FileWithText's text when set.
+    FileMove(Option<String>),
+    /// Several text formats set together, each payload a Base64 line on
+    /// stdin (documents can outgrow the Windows command line, and stdin
+    /// leaves no temp files to clean up)
+    Multi(Vec<(Format, String)>),
 }
+/// What a paste of file objects does to the source files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropEffect {
+    #[default]
+    Copy,
+    /// `--move`: the paste target removes the source (shown as cut)
+    Move,
+}
+/// A text format on the Windows clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// CF_UNICODETEXT: what terminals and Notepad paste
+    Text,
+    /// A CF_HTML payload (see `html::cf_html`)
+    Html,
+    /// An RTF document (see `rtf::document`)
+    Rtf,
+}
+impl Format {
+    /// The `System.Windows.Forms.TextDataFormat` member
+    fn data_format(&self) -> &'static str {
+        match self {
+            Format::Text => "UnicodeText",
+            Format::Html => "Html",
+            Format::Rtf => "Rtf",
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Html => "HTML",
+            Format::Rtf => "RTF",
+        }
+    }
+}
+/// "HTML+RTF": the formats beyond plain text, or "text" when there are none.
+pub fn formats_label(formats: &[Format]) -> String {
+    let rich: Vec<&str> = formats
+        .iter()
+        .filter(|f| **f != Format::Text)
+        .map(Format::label)
+        .collect();
+    if rich.is_empty() {
+        Format::Text.label().to_string()
+    } else {
+        rich.join("+")
+    }
+}
+fn multi_label(formats: &[(Format, String)]) -> String {
+    formats_label(&formats.iter().map(|(f, _)| *f).collect::<Vec<_>>())
+}
+/// Image mode: one DataObject, three formats, from $args[0]. SetImage gives
+/// CF_BITMAP, opaque, for Paint and older programs. A PNG file's own bytes go
+/// under "PNG" (Office, Figma and browsers prefer it), and every image goes as
+/// CF_DIBV5 with alpha: 32-bit BGRA rows, bottom-up, behind a 124-byte
+/// BITMAPV5HEADER with an alpha mask. Format 17 has no DataFormats name until
+/// GetFormat(17) registers one. Leaves `$data` for the caller to finish.
+const IMAGE_DATA: &str = "$img = [System.Drawing.Image]::FromFile($args[0]); \
+    $data = New-Object System.Windows.Forms.DataObject; $data.SetImage($img); \
+    if ($img.RawFormat.Equals([System.Drawing.Imaging.ImageFormat]::Png)) { \
+    $data.SetData('PNG', (New-Object System.IO.MemoryStream(,[System.IO.File]::ReadAllBytes($args[0])))) }; \
+    $bmp = New-Object System.Drawing.Bitmap($img); $w = $bmp.Width; $h = $bmp.Height; \
+    $bits = $bmp.LockBits((New-Object System.Drawing.Rectangle(0, 0, $w, $h)), \
+    [System.Drawing.Imaging.ImageLockMode]::ReadOnly, [System.Drawing.Imaging.PixelFormat]::Format32bppArgb); \
+    $pixels = New-Object byte[] ($w * 4 * $h); \
+    for ($y = 0; $y -lt $h; $y++) { [System.Runtime.InteropServices.Marshal]::Copy(\
+    [IntPtr]($bits.Scan0.ToInt64() + ($h - 1 - $y) * $bits.Stride), $pixels, $y * $w * 4, $w * 4) }; \
+    $bmp.UnlockBits($bits); \
+    $dib = New-Object System.IO.MemoryStream; $bw = New-Object System.IO.BinaryWriter($dib); \
+    @(124, $w, $h) | ForEach-Object { $bw.Write([int]$_) }; $bw.Write([int16]1); $bw.Write([int16]32); \
+    @(3, $pixels.Length, 2835, 2835, 0, 0, 0x00FF0000, 0x0000FF00, 0x000000FF, -16777216, 0x73524742) | \
+    ForEach-Object { $bw.Write([int]$_) }; \
+    $bw.Write((New-Object byte[] 48)); $bw.Write([int]4); $bw.Write((New-Object byte[] 12)); \
+    $bw.Write($pixels); $bw.Flush(); \
+    $data.SetData([System.Windows.Forms.DataFormats]::GetFormat(17).Name, $dib);";
+/// Puts `$data` on the clipboard, kept after PowerShell exits.
+const SET_DATA: &str = "[System.Windows.Forms.Clipboard]::SetDataObject($data, $true);";
 /// Uses PowerShell for complex types (Images, File Objects)
 /// SECURITY: Paths are passed as arguments to avoid injection vulnerabilities.
-pub fn set_complex(win_paths: &[String], mode: ClipboardMode) -> Result<()> {
+fn set_complex(win_paths: &[String], mode: ClipboardMode) -> Result<()> {
     let log = create_logger("clipboard");
+    let what = match &mode {
+        ClipboardMode::Image | ClipboardMode::ImageAndFile => "Image mode".to_string(),
+        ClipboardMode::File | ClipboardMode::FileWithText(_) | ClipboardMode::FileMove(_) => {
+            "File mode".to_string()
+        }
+        ClipboardMode::Multi(formats) => format!("{} mode", multi_label(formats)),
+    };
+    require_windows(&what)?;
+    if let ClipboardMode::File = mode {
+        if let Some(mut client) = daemon::client() {
+            match client.copy_files(win_paths) {
+                Ok(()) => return Ok(()),
+                Err(e) => log.warn(&format!("Daemon copy failed, using PowerShell: {:#}", e)),
+            }
+        }
+    }
     if let ClipboardMode::Image = mode {
         if win_paths.len() != 1 {
             anyhow::bail!("Image mode currently supports exactly one file at a time.");
         }
     }
-    // Header executes in the global scope to load assemblies
-    let header =
-        "Add-Type -AssemblyName System.Windows.Forms; Add-Type -AssemblyName System.Drawing;";
-    // Body uses $args, so it must be wrapped in a ScriptBlock "& { ... }"
-    // to accept the arguments passed to powershell.exe
+    if let ClipboardMode::ImageAndFile = mode {
+        if win_paths.len() != 2 {
+            anyhow::bail!("Image-and-file mode takes a pixel source and the file to drop.");
+        }
+    }
+    // Header loads the assemblies. Errors stop the script, so the first
+    // exception is the one reported.
+    let header = "$ErrorActionPreference = 'Stop'; \
+        Add-Type -AssemblyName System.Windows.Forms; Add-Type -AssemblyName System.Drawing;";
+    // Body reads the paths from $args, which -EncodedArguments fills
     let body = match mode {
-        ClipboardMode::Image => {
-            // $args[0] is the first argument passed after the command string
-            "$img = [System.Drawing.Image]::FromFile($args[0]); [System.Windows.Forms.Clipboard]::SetImage($img);"
+        ClipboardMode::Image => format!("{} {}", IMAGE_DATA, SET_DATA),
+        ClipboardMode::ImageAndFile => {
+            // $args[0] gives the pixels (a PNG when GDI+ cannot read the
+            // original), $args[1] is the file itself
+            format!(
+                "{} $files = New-Object System.Collections.Specialized.StringCollection; \
+                 [void]$files.Add($args[1]); $data.SetFileDropList($files); {}",
+                IMAGE_DATA, SET_DATA
+            )
         }
         ClipboardMode::File => {
             // Iterate all args
-            "$files = New-Object System.Collections.Specialized.StringCollection; $args | ForEach-Object { [void]$files.Add($_) }; [System.Windows.Forms.Clipboard]::SetFileDropList($files);"
+            "$files = New-Object System.Collections.Specialized.StringCollection; $args | ForEach-Object { [void]$files.Add($_) }; [System.Windows.Forms.Clipboard]::SetFileDropList($files);".to_string()
+        }
+        ClipboardMode::FileWithText(_) => {
+            // $args[0] is the text, Base64-encoded UTF-8; the rest are the files
+            "$text = [System.Text.Encoding]::UTF8.GetString([System.Convert]::FromBase64String($args[0])); $files = New-Object System.Collections.Specialized.StringCollection; $args | Select-Object -Skip 1 | ForEach-Object { [void]$files.Add($_) }; $data = New-Object System.Windows.Forms.DataObject; $data.SetFileDropList($files); $data.SetText($text, [System.Windows.Forms.TextDataFormat]::UnicodeText); [System.Windows.Forms.Clipboard]::SetDataObject($data, $true);".to_string()
+        }
+        ClipboardMode::FileMove(ref text) => {
+            // As FileWithText, plus "Preferred DropEffect" holding
+            // DROPEFFECT_MOVE (2) as a little-endian DWORD
+            let (read_text, skip, set_text) = match text {
+                Some(_) => (
+                    "$text = [System.Text.Encoding]::UTF8.GetString([System.Convert]::FromBase64String($args[0])); ",
+                    1,
+                    "$data.SetText($text, [System.Windows.Forms.TextDataFormat]::UnicodeText); ",
+                ),
+                None => ("", 0, ""),
+            };
+            format!(
+                "{}$files = New-Object System.Collections.Specialized.StringCollection; \
+                 $args | Select-Object -Skip {} | ForEach-Object {{ [void]$files.Add($_) }}; \
+                 $data = New-Object System.Windows.Forms.DataObject; $data.SetFileDropList($files); {}\
+                 $data.SetData('Preferred DropEffect', (New-Object System.IO.MemoryStream(,[byte[]](2, 0, 0, 0)))); {}",
+                read_text, skip, set_text, SET_DATA
+            )
+        }
+        ClipboardMode::Multi(_) => {
+            // $args names each TextDataFormat; line i of stdin is payload i,
+            // Base64-encoded UTF-8. One SetDataObject call sets them all.
+            "$in = [Console]::In.ReadToEnd().Split([char]10); $data = New-Object System.Windows.Forms.DataObject; for ($i = 0; $i -lt $args.Count; $i++) { $data.SetText([System.Text.Encoding]::UTF8.GetString([System.Convert]::FromBase64String($in[$i])), [System.Windows.Forms.TextDataFormat]$args[$i]) }; [System.Windows.Forms.Clipboard]::SetDataObject($data, $true);".to_string()
         }
     };
-    // Construct command: Header; & { Body }
-    // The '&' operator executes the following block, passing trailing CLI args into it.
-    let script = format!("{} & {{ {} }}", header, body);
-    log.debug("Executing PowerShell clipboard script (Parameterized)...");
-    let status = Command::new("powershell.exe")
-        .arg("-NoProfile")
-        .arg("-Command")
-        .arg(&script)
-        // Note: In PowerShell, the first argument after the command string is $args[0].
-        // We do NOT need a placeholder like in bash -c.
-        .args(win_paths)
-        .status()
-        .with_context(|| "Failed to execute powershell.exe")?;
-    if !status.success() {
-        log.error("PowerShell exited with error status");
-        anyhow::bail!("PowerShell exited with error status");
+    let input = match &mode {
+        ClipboardMode::Multi(formats) => Some(
+            formats
+                .iter()
+                .map(|(_, payload)| BASE64.encode(payload) + "\n")
+                .collect::<String>(),
+        ),
+        _ => None,
+    };
+    let text_args: Vec<String> = match &mode {
+        ClipboardMode::FileWithText(text) | ClipboardMode::FileMove(Some(text)) => {
+            vec![BASE64.encode(text)]
+        }
+        ClipboardMode::Multi(formats) => formats
+            .iter()
+            .map(|(format, _)| format.data_format().to_string())
+            .collect(),
+        _ => Vec::new(),
+    };
+    // Script and arguments both travel base64-encoded: no path is ever
+    // quoted, split or parsed by the Windows command line
+    let script = powershell::encode_command(&format!("{} {} {}", FAILURE_TRAP, header, body));
+    let arguments: Vec<&String> = text_args.iter().chain(win_paths).collect();
+    let arguments = powershell::encode_arguments(&arguments);
+    let run = |shell: Shell| {
+        log.debug(&format!(
+            "Executing PowerShell clipboard script (Parameterized) with {}...",
+            shell.program()
+        ));
+        shell
+            .command()
+            .arg("-EncodedCommand")
+            .arg(&script)
+            .arg("-EncodedArguments")
+            .arg(&arguments)
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stderr(Stdio::piped())
+            .spawn()
+            .map(|child| (child, shell))
+    };
+    // Locked clipboards (exit 5) are retried by re-running the whole script
+    let attempt = || -> Result<()> {
+        let (mut child, ran) = match (run(shell()), shell_reason()) {
+            // pwsh was picked because it looked present; it could still be blocked
+            (Err(e), ShellReason::Detected) if shell() == Shell::Pwsh => {
+                log.debug(&format!(
+                    "pwsh.exe failed to start ({}); using powershell.exe",
+                    e
+                ));
+                run(Shell::Powershell)
+            }
+            (spawned, _) => spawned,
+        }
+        .with_context(|| {
+            format!(
+                "Failed to execute {}\n  {}",
+                shell().program(),
+                interop::hint(&[shell().program()])
+            )
+        })?;
+        // Drained on a thread so a chatty error cannot fill the pipe and stall the child
+        let stderr = child.stderr.take().map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut text = String::new();
+                let _ = pipe.read_to_string(&mut text);
+                text
+            })
+        });
+        if let (Some(input), Some(mut pipe)) = (&input, child.stdin.take()) {
+            // A script that died early closed its stdin; its exit status says why
+            if let Err(e) = pipe.write_all(input.as_bytes()) {
+                log.debug(&format!("Writing to {} failed: {}", ran.program(), e));
+            }
+        }
+        let limit = deadline::limit();
+        let status = deadline::wait(&mut child, limit)
+            .with_context(|| format!("Failed to wait for {}", ran.program()))?
+            .ok_or_else(|| deadline::TimedOut {
+                what: what.to_string(),
+                program: ran.program().to_string(),
+                after: limit.unwrap_or_default(),
+            })?;
+        let stderr = stderr
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+        if !status.success() {
+            log.error(&format!(
+                "{} exited with {}; stderr:\n{}",
+                ran.program(),
+                status,
+                stderr.trim_end()
+            ));
+            let msg = script_failure(&mode, status.code(), &stderr, win_paths);
+            if status.code() == Some(5) {
+                return Err(retry::Locked(msg).into());
+            }
+            anyhow::bail!(msg);
+        }
+        Ok(())
+    };
+    retry::again(attempt(), &what, attempt)
+}
+/// Opens the script (traps cover their whole scope): reports the innermost exception on
+/// one tagged stderr line and exits with a code naming its kind.
+const FAILURE_TRAP: &str = "trap { \
+    $e = $_.Exception; while ($e.InnerException) { $e = $e.InnerException }; \
+    [Console]::Error.WriteLine('wsl-clip: ' + $e.Message); \
+    if ($e -is [System.IO.FileNotFoundException]) { exit 3 }; \
+    if ($e -is [System.OutOfMemoryException]) { exit 4 }; \
+    if ($e -is [System.Runtime.InteropServices.ExternalException]) { exit 5 }; \
+    exit 2 };";
+/// The user-facing error for a failed Image/File script.
+fn script_failure(
+    mode: &ClipboardMode,
+    code: Option<i32>,
+    stderr: &str,
+    win_paths: &[String],
+) -> String {
+    let noun = match mode {
+        ClipboardMode::Image => "image",
+        ClipboardMode::ImageAndFile => "image and file",
+        ClipboardMode::File | ClipboardMode::FileWithText(_) | ClipboardMode::FileMove(_) => {
+            "file list"
+        }
+        ClipboardMode::Multi(formats) => &multi_label(formats),
+    };
+    let kind = match code {
+        Some(3) => "Windows cannot find the file".to_string(),
+        // GDI+ reports formats it cannot decode as out-of-memory
+        Some(4) => "Windows cannot decode this image (unsupported or corrupt format)".to_string(),
+        Some(5) => {
+            "the clipboard is locked by another program, or GDI+ failed; try again".to_string()
+        }
+        Some(2) => "PowerShell raised an error".to_string(),
+        Some(n) => format!("PowerShell exited with status {}", n),
+        None => "PowerShell was terminated by a signal".to_string(),
+    };
+    let mut msg = format!("Failed to set {}: ", noun);
+    match powershell_error_message(stderr) {
+        Some(detail) => msg.push_str(&format!("{} ({})", detail, kind)),
+        None => msg.push_str(&kind),
+    }
+    if !win_paths.is_empty() {
+        msg.push_str(&format!("\n  file: {}", win_paths.join(", ")));
+    }
+    msg
+}
+/// The exception message in PowerShell's stderr: the trap's tagged line,
+/// else the message inside a 5.1 or 7.x error record.
+fn powershell_error_message(stderr: &str) -> Option<String> {
+    let lines: Vec<&str> = stderr.lines().map(str::trim).collect();
+    if let Some(tagged) = lines.iter().find_map(|l| l.strip_prefix("wsl-clip: ")) {
+        return Some(tagged.trim().to_string());
+    }
+    let first = lines.iter().find(|l| {
+        !l.is_empty()
+            && !l.starts_with("At line:")
+            && !l.starts_with('+')
+            && !l.starts_with('|')
+            && !l.starts_with("Line |")
+    })?;
+    // 7.x prefixes the exception type: "MethodInvocationException: Exception calling ...",
+    // or puts the message on the last "|" line below a code excerpt
+    let text = match first.split_once(':') {
+        Some((kind, rest)) if !kind.contains(' ') && kind.ends_with("Exception") => {
+            match rest.trim() {
+                "" => lines
+                    .iter()
+                    .rev()
+                    .filter_map(|l| l.strip_prefix('|'))
+                    .map(str::trim)
+                    .find(|l| !l.is_empty() && !l.chars().all(|c| c == '~'))?,
+                rest => rest,
+            }
+        }
+        _ => first,
+    };
+    // Exception calling "FromFile" with "1" argument(s): "A generic error occurred in GDI+."
+    let text = match text.split_once("argument(s): ") {
+        Some((_, inner)) if text.starts_with("Exception calling") => inner.trim_matches('"'),
+        _ => text,
+    };
+    Some(text.to_string())
+}
+/// The PowerShell that runs the Image/File scripts (`--shell`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    /// pwsh.exe when installed, else powershell.exe
+    Auto,
+    /// Windows PowerShell 5.1 (powershell.exe)
+    Powershell,
+    /// PowerShell 7 (pwsh.exe): starts faster
+    Pwsh,
+}
+impl Shell {
+    fn program(&self) -> &'static str {
+        match self {
+            Shell::Pwsh => "pwsh.exe",
+            Shell::Auto | Shell::Powershell => "powershell.exe",
+        }
+    }
+    /// The shell with its startup flags. Windows Forms needs an STA thread:
+    /// powershell.exe starts in one, pwsh is asked for it explicitly.
+    /// Launched from a GUI (an editor keybinding) there is no console to
+    /// share, so the window PowerShell opens is hidden and it never prompts.
+    /// (`conhost.exe --headless` would avoid even creating it, but is
+    /// undocumented and missing on older builds.)
+    fn command(&self) -> Command {
+        let mut cmd = interop::command(self.program());
+        cmd.args(["-NoProfile", "-NonInteractive", "-WindowStyle", "Hidden"]);
+        if *self == Shell::Pwsh {
+            cmd.args(["-NoLogo", "-Sta"]);
+        }
+        cmd
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShellReason {
+    Selected,
+    Detected,
+}
+static SHELL: OnceLock<(Shell, ShellReason)> = OnceLock::new();
+/// Fixes the PowerShell for this process (`--shell`). `Auto` is resolved
+/// here, once: pwsh.exe if it can be found, else powershell.exe.
+pub fn set_shell(requested: Shell) -> Shell {
+    SHELL
+        .get_or_init(|| {
+            let chosen = match requested {
+                Shell::Auto if interop::resolve("pwsh.exe").is_some() => {
+                    (Shell::Pwsh, ShellReason::Detected)
+                }
+                Shell::Auto => (Shell::Powershell, ShellReason::Detected),
+                forced => (forced, ShellReason::Selected),
+            };
+            create_logger("clipboard").debug(&format!(
+                "Shell: {} ({})",
+                chosen.0.program(),
+                match chosen.1 {
+                    ShellReason::Selected => "selected with --shell",
+                    ShellReason::Detected => "auto",
+                }
+            ));
+            chosen
+        })
+        .0
+}
+fn shell() -> Shell {
+    set_shell(Shell::Auto)
+}
+fn shell_reason() -> ShellReason {
+    set_shell(Shell::Auto);
+    SHELL
+        .get()
+        .map_or(ShellReason::Detected, |(_, reason)| *reason)
+}
+/// Which clipboard wsl-clip writes to, and through what.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    /// clip.exe / PowerShell under WSL, wl-copy or xclip elsewhere, else OSC 52 on a terminal
+    Auto,
+    /// clip.exe and PowerShell through WSL interop
+    Windows,
+    /// Windows, but text goes through PowerShell's Set-Clipboard instead of clip.exe
+    Powershell,
+    /// An OSC 52 escape sequence to the terminal (text only; for SSH sessions)
+    Osc52,
+    /// wl-copy (native Linux, Wayland)
+    Wayland,
+    /// xclip -selection clipboard (native Linux, X11)
+    X11,
+}
+impl Backend {
+    /// clip.exe / PowerShell: files are copied from their Windows paths
+    pub fn is_windows(&self) -> bool {
+        matches!(self, Backend::Windows | Backend::Powershell)
+    }
+    /// wl-copy / xclip: files are copied from their Linux paths
+    pub fn is_native(&self) -> bool {
+        matches!(self, Backend::Wayland | Backend::X11)
+    }
+    fn name(&self) -> &'static str {
+        match self {
+            Backend::Auto => "auto",
+            Backend::Windows => "windows",
+            Backend::Powershell => "powershell",
+            Backend::Osc52 => "osc52",
+            Backend::Wayland => "wayland",
+            Backend::X11 => "x11",
+        }
+    }
+}
+/// What `--backend auto` looks at.
+#[derive(Debug, Clone, Default)]
+pub struct BackendSignals {
+    pub wsl: bool,
+    pub clip_exe: bool,
+    /// powershell.exe is on PATH (the Set-Clipboard fallback)
+    pub powershell: bool,
+    /// WAYLAND_DISPLAY is set
+    pub wayland: bool,
+    pub wl_copy: bool,
+    /// DISPLAY is set
+    pub x11: bool,
+    pub xclip: bool,
+    /// A controlling terminal can be opened
+    pub tty: bool,
+}
+impl BackendSignals {
+    pub fn probe() -> Self {
+        let set = |var: &str| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+        BackendSignals {
+            wsl: environment::current().is_wsl(),
+            clip_exe: interop::resolve("clip.exe").is_some(),
+            powershell: interop::resolve("powershell.exe").is_some(),
+            wayland: set("WAYLAND_DISPLAY"),
+            wl_copy: on_path("wl-copy"),
+            x11: set("DISPLAY"),
+            xclip: on_path("xclip"),
+            tty: std::fs::File::open(tty_path()).is_ok(),
+        }
+    }
+}
+/// Resolves `auto` and says why, for the debug log and for errors about
+/// operations the chosen backend cannot do.
+pub fn choose_backend(requested: Backend, s: &BackendSignals) -> (Backend, String) {
+    if requested != Backend::Auto {
+        return (requested, "selected with --backend".to_string());
+    }
+    if s.wsl && s.clip_exe {
+        return (Backend::Windows, "WSL with clip.exe on PATH".to_string());
+    }
+    if s.wsl && s.powershell {
+        return (
+            Backend::Windows,
+            "WSL without clip.exe; text goes through Set-Clipboard".to_string(),
+        );
+    }
+    if !s.wsl && s.wayland && s.wl_copy {
+        return (
+            Backend::Wayland,
+            "not WSL; WAYLAND_DISPLAY is set and wl-copy is installed".to_string(),
+        );
+    }
+    if !s.wsl && s.x11 && s.xclip {
+        return (
+            Backend::X11,
+            "not WSL; DISPLAY is set and xclip is installed".to_string(),
+        );
+    }
+    let missing = if s.wsl {
+        "clip.exe not found"
+    } else {
+        "not WSL and no wl-copy/xclip display found"
+    };
+    if s.tty {
+        return (
+            Backend::Osc52,
+            format!("{}, but a terminal is attached", missing),
+        );
+    }
+    (
+        Backend::Windows,
+        format!("{}, and no terminal for OSC 52", missing),
+    )
+}
+static ENCODING: OnceLock<Option<TextEncoding>> = OnceLock::new();
+/// Fixes the text encoding for this process (`--encoding`). `None` keeps
+/// each tool's own (UTF-16LE for clip.exe, UTF-8 for the rest); otherwise
+/// text copies arrive already encoded.
+pub fn set_encoding(encoding: Option<TextEncoding>) {
+    let _ = ENCODING.set(encoding);
+}
+fn encoding() -> Option<TextEncoding> {
+    ENCODING.get().copied().flatten()
+}
+static BACKEND: OnceLock<(Backend, String)> = OnceLock::new();
+/// Fixes the backend for this process (`--backend`). `Auto` is resolved here.
+pub fn set_backend(requested: Backend) -> Backend {
+    BACKEND
+        .get_or_init(|| {
+            let (backend, reason) = choose_backend(requested, &BackendSignals::probe());
+            create_logger("clipboard").debug(&format!("Backend: {} ({})", backend.name(), reason));
+            (backend, reason)
+        })
+        .0
+}
+/// The backend in use; auto-detected if `set_backend` was never called.
+pub fn backend() -> Backend {
+    set_backend(Backend::Auto)
+}
+fn backend_reason() -> &'static str {
+    set_backend(Backend::Auto);
+    BACKEND.get().map_or("", |(_, reason)| reason.as_str())
+}
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+/// The controlling terminal, or `$WSL_CLIP_TTY` so the e2e tests can capture the sequence.
+fn tty_path() -> PathBuf {
+    std::env::var_os("WSL_CLIP_TTY")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/dev/tty"))
+}
+/// Many terminals (and tmux) drop OSC 52 sequences longer than about 100 KB.
+pub const OSC52_MAX_ENCODED: usize = 100_000;
+/// The OSC 52 sequence setting the clipboard to `text`, cut at a character
+/// boundary to fit `OSC52_MAX_ENCODED`. Also returns how many bytes made it in.
+pub fn osc52_sequence(text: &[u8]) -> (String, usize) {
+    let mut keep = text.len().min(OSC52_MAX_ENCODED / 4 * 3);
+    while keep < text.len() && keep > 0 && text[keep] & 0xC0 == 0x80 {
+        keep -= 1;
+    }
+    (
+        format!("\x1b]52;c;{}\x07", BASE64.encode(&text[..keep])),
+        keep,
+    )
+}
+/// Writes `text` to the terminal as OSC 52. Terminals give no reply, so
+/// success only means the sequence was written.
+fn write_osc52(text: &[u8]) -> Result<()> {
+    let tty = tty_path();
+    let (sequence, kept) = osc52_sequence(text);
+    if kept < text.len() {
+        eprintln!(
+            "[WARN] OSC 52: {} bytes is over the ~100 KB terminal limit; copied the first {} bytes",
+            text.len(),
+            kept
+        );
+    }
+    create_logger("clipboard").debug(&format!("OSC 52: {} bytes to {:?}", kept, tty));
+    let mut terminal = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&tty)
+        .with_context(|| format!("OSC 52 needs a terminal, but {:?} cannot be opened", tty))?;
+    terminal.write_all(sequence.as_bytes())?;
+    terminal.flush()?;
+    Ok(())
+}
+/// The error for Windows-only operations under another backend, naming
+/// the backend and why it was chosen.
+pub fn require_windows(what: &str) -> Result<()> {
+    let backend = backend();
+    if !backend.is_windows() {
+        anyhow::bail!(
+            "{} needs clip.exe/PowerShell, but the {} backend is in use ({})",
+            what,
+            backend.name(),
+            backend_reason()
+        );
     }
     Ok(())
 }
+/// Where streamed text goes: clip.exe's (or wl-copy's / xclip's) stdin, or a buffer sent in one
+/// piece (to the daemon, or as an OSC 52 sequence) when the stream completes.
+pub enum TextSink {
+    Pipe(ChildStdin),
+    /// clip.exe / Set-Clipboard (see `text_input`), keeping a copy of the
+    /// text to replay if the clipboard turns out to be locked. The copy is
+    /// dropped past `retry::REPLAY_LIMIT`.
+    Replay(Box<dyn Write>, Option<Vec<u8>>),
+    Buffer(Vec<u8>),
+    /// Text for the daemon, sent once the stream completes. Past
+    /// `daemon::TEXT_LIMIT` it goes to a clip.exe stream instead.
+    Daemon(Vec<u8>, Option<Box<ClipboardStream>>),
+}
+impl Write for TextSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            TextSink::Pipe(pipe) => pipe.write(buf),
+            TextSink::Replay(pipe, copy) => {
+                let n = pipe.write(buf)?;
+                if copy
+                    .as_ref()
+                    .is_some_and(|c| c.len() + n > retry::REPLAY_LIMIT)
+                {
+                    *copy = None;
+                }
+                if let Some(copy) = copy {
+                    copy.extend_from_slice(&buf[..n]);
+                }
+                Ok(n)
+            }
+            TextSink::Buffer(buffer) => buffer.write(buf),
+            TextSink::Daemon(buffer, direct) => {
+                if direct.is_none() && buffer.len() + buf.len() > daemon::TEXT_LIMIT {
+                    create_logger("daemon").debug("Text over the daemon limit, using clip.exe");
+                    let mut stream = Box::new(spawn_clip_exe().map_err(io::Error::other)?);
+                    if let Some(pipe) = stream.stdin.as_mut() {
+                        pipe.write_all(buffer)?;
+                    }
+                    *buffer = Vec::new();
+                    *direct = Some(stream);
+                }
+                match direct.as_mut().and_then(|d| d.stdin.as_mut()) {
+                    Some(pipe) => pipe.write(buf),
+                    None => buffer.write(buf),
+                }
+            }
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            TextSink::Pipe(pipe) => pipe.flush(),
+            TextSink::Replay(pipe, _) => pipe.flush(),
+            TextSink::Buffer(_) => Ok(()),
+            TextSink::Daemon(_, direct) => match direct.as_mut().and_then(|d| d.stdin.as_mut()) {
+                Some(pipe) => pipe.flush(),
+                None => Ok(()),
+            },
+        }
+    }
+}
+enum StreamTarget {
+    /// clip.exe, powershell.exe, wl-copy or xclip, named for error messages
+    Process(Child, &'static str),
+    /// Connected only once the text is complete, so a slow producer never
+    /// holds a daemon connection open
+    Daemon,
+    Osc52,
+    #[cfg(test)]
+    Memory(std::sync::Arc<std::sync::Mutex<Vec<memory::Copied>>>),
+}
 pub struct ClipboardStream {
-    child: Child,
-    pub stdin: Option<ChildStdin>,
+    target: StreamTarget,
+    pub stdin: Option<TextSink>,
 }
 impl ClipboardStream {
     pub fn wait(mut self) -> Result<()> {
-        // Drop stdin to close the pipe so clip.exe knows input is done
-        drop(self.stdin.take());
-        let status = self.child.wait().context("Failed to wait for clip.exe")?;
-        if !status.success() {
-            anyhow::bail!("clip.exe exited with error status");
+        let stdin = self.stdin.take();
+        match &mut self.target {
+            StreamTarget::Process(child, program) => {
+                // Drop stdin to close the pipe so clip.exe knows input is done
+                let replay = match stdin {
+                    Some(TextSink::Replay(pipe, copy)) => {
+                        drop(pipe);
+                        copy
+                    }
+                    other => {
+                        drop(other);
+                        None
+                    }
+                };
+                let program = *program;
+                let first = finish_text(child, program, replay.is_some());
+                let Some(text) = replay else {
+                    return first;
+                };
+                // A fresh process per attempt: the text goes in again from the start
+                retry::again(first, program, || {
+                    let mut child = text_command(program)
+                        .stdin(Stdio::piped())
+                        .spawn()
+                        .with_context(|| format!("Failed to spawn {}", program))?;
+                    if let Some(pipe) = child.stdin.take() {
+                        text_input(program, pipe).write_all(&text)?;
+                    }
+                    finish_text(&mut child, program, true)
+                })
+            }
+            StreamTarget::Daemon => {
+                let text = match stdin {
+                    Some(TextSink::Daemon(_, Some(direct))) => return direct.wait(),
+                    Some(TextSink::Daemon(text, None)) => text,
+                    _ => return Ok(()),
+                };
+                if let Some(mut client) = daemon::client() {
+                    let Err(e) = client.copy_text(&text) else {
+                        return Ok(());
+                    };
+                    // The daemon went away: the text is all here, copy it directly
+                    create_logger("daemon")
+                        .warn(&format!("Daemon copy failed, using clip.exe: {:#}", e));
+                }
+                let mut direct = spawn_clip_exe()?;
+                if let Some(pipe) = direct.stdin.as_mut() {
+                    pipe.write_all(&text)?;
+                }
+                direct.wait()
+            }
+            StreamTarget::Osc52 => {
+                let Some(TextSink::Buffer(text)) = stdin else {
+                    return Ok(());
+                };
+                write_osc52(&text)
+            }
+            #[cfg(test)]
+            StreamTarget::Memory(copies) => {
+                if let Some(TextSink::Buffer(text)) = stdin {
+                    copies.lock().unwrap().push(memory::Copied::Text(text));
+                }
+                Ok(())
+            }
+        }
+    }
+    /// Kills clip.exe before it sees EOF, so the partial payload is never
+    /// committed and the previous clipboard content survives.
+    pub fn abort(mut self) -> Result<()> {
+        match &mut self.target {
+            StreamTarget::Process(child, program) => {
+                let _ = child.kill();
+                drop(self.stdin.take());
+                child
+                    .wait()
+                    .with_context(|| format!("Failed to reap {}", program))?;
+            }
+            StreamTarget::Daemon => {
+                if let Some(TextSink::Daemon(_, Some(direct))) = self.stdin.take() {
+                    direct.abort()?;
+                }
+            }
+            _ => {}
         }
         Ok(())
     }
 }
-/// Starts a streaming session to clip.exe
-pub fn start_text_stream() -> Result<ClipboardStream> {
+/// Waits for a text-copy process. With `retryable`, a failed exit is
+/// `retry::Locked`: clip.exe and Set-Clipboard fail that way when another
+/// program holds the clipboard open.
+fn finish_text(child: &mut Child, program: &'static str, retryable: bool) -> Result<()> {
+    let limit = deadline::limit();
+    let status = deadline::wait(child, limit)
+        .with_context(|| format!("Failed to wait for {}", program))?
+        .ok_or_else(|| deadline::TimedOut {
+            what: "Text mode".to_string(),
+            program: program.to_string(),
+            after: limit.unwrap_or_default(),
+        })?;
+    if status.success() {
+        return Ok(());
+    }
+    let msg = format!("{} exited with error status", program);
+    if retryable {
+        return Err(retry::Locked(msg).into());
+    }
+    anyhow::bail!(msg)
+}
+/// The Windows text-copy commands, by the name `StreamTarget::Process` carries.
+fn text_command(program: &str) -> Command {
+    if program == "powershell.exe" {
+        let mut cmd = interop::command("powershell.exe");
+        cmd.arg("-NoProfile")
+            .arg("-Command")
+            .arg(set_clipboard_script());
+        return cmd;
+    }
+    interop::command("clip.exe")
+}
+/// What a Windows text-copy process reads. clip.exe takes its stdin in the
+/// OEM code page unless it starts with a UTF-16LE BOM, so it always gets
+/// UTF-16LE: `--encoding` text arrives already encoded (1252's `?` and
+/// --strict-encoding applied) and is turned back into Unicode for it.
+/// Set-Clipboard's script is told the encoding and reads it as is.
+fn text_input(program: &str, pipe: ChildStdin) -> Box<dyn Write> {
+    if program != "clip.exe" {
+        return Box::new(pipe);
+    }
+    let utf16 = |pipe| EncodingWriter::new(pipe, TextEncoding::Utf16le, false);
+    match encoding() {
+        Some(TextEncoding::Utf16le) => Box::new(pipe),
+        Some(TextEncoding::Windows1252) => Box::new(Windows1252Decoder::new(utf16(pipe))),
+        Some(TextEncoding::Utf8) | None => Box::new(utf16(pipe)),
+    }
+}
+/// Starts a streaming session, through the daemon when one is running
+fn start_text_stream() -> Result<ClipboardStream> {
+    match backend() {
+        Backend::Osc52 => {
+            return Ok(ClipboardStream {
+                target: StreamTarget::Osc52,
+                stdin: Some(TextSink::Buffer(Vec::new())),
+            })
+        }
+        Backend::Wayland => {
+            return spawn_tool(
+                Command::new("wl-copy").args(["--type", "text/plain;charset=utf-8"]),
+                "wl-copy",
+            )
+        }
+        Backend::X11 => {
+            return spawn_tool(
+                Command::new("xclip").args(["-selection", "clipboard", "-in"]),
+                "xclip",
+            )
+        }
+        // Forced: no daemon, so what lands on the clipboard is Set-Clipboard's own doing
+        Backend::Powershell => return spawn_set_clipboard(),
+        Backend::Windows | Backend::Auto => {}
+    }
+    // The daemon takes UTF-8 only
+    let utf8 = encoding().is_none_or(|e| e == TextEncoding::Utf8);
+    if utf8 && daemon::socket_present() {
+        return Ok(ClipboardStream {
+            target: StreamTarget::Daemon,
+            stdin: Some(TextSink::Daemon(Vec::new(), None)),
+        });
+    }
+    spawn_clip_exe()
+}
+/// clip.exe, or PowerShell's Set-Clipboard where clip.exe cannot be started
+/// (removed, or blocked by AppLocker on locked-down images).
+fn spawn_clip_exe() -> Result<ClipboardStream> {
     let log = create_logger("clipboard");
     log.debug("Spawning clip.exe for streaming...");
-    let mut child = Command::new("clip.exe")
+    let spawn_error = match text_command("clip.exe").stdin(Stdio::piped()).spawn() {
+        Ok(mut child) => {
+            let stdin = child
+                .stdin
+                .take()
+                .map(|pipe| TextSink::Replay(text_input("clip.exe", pipe), Some(Vec::new())));
+            return Ok(ClipboardStream {
+                target: StreamTarget::Process(child, "clip.exe"),
+                stdin,
+            });
+        }
+        Err(e) => e,
+    };
+    log.debug(&format!(
+        "clip.exe failed to start ({}); falling back to Set-Clipboard",
+        spawn_error
+    ));
+    spawn_set_clipboard().map_err(|fallback| {
+        log.debug(&format!("Set-Clipboard fallback failed: {:#}", fallback));
+        anyhow::Error::new(spawn_error).context(format!(
+            "Failed to spawn clip.exe (and the powershell.exe fallback)\n  {}",
+            interop::hint(&["clip.exe", "powershell.exe"])
+        ))
+    })
+}
+/// Reads all of stdin as UTF-8 (or the `--encoding` one) and sets it as
+/// Unicode text. `$input | Set-Clipboard` would decode in the console code
+/// page and rejoin lines, dropping a final newline. An empty payload clears
+/// the clipboard, as clip.exe does.
+fn set_clipboard_script() -> String {
+    let (decoder, read) = match encoding() {
+        Some(TextEncoding::Utf16le) => (
+            "New-Object System.Text.UnicodeEncoding $false, $false",
+            "[Console]::In.ReadToEnd().TrimStart([char]0xFEFF)",
+        ),
+        Some(TextEncoding::Windows1252) => (
+            "[System.Text.Encoding]::GetEncoding(1252)",
+            "[Console]::In.ReadToEnd()",
+        ),
+        _ => (
+            "New-Object System.Text.UTF8Encoding $false",
+            "[Console]::In.ReadToEnd()",
+        ),
+    };
+    format!(
+        "[Console]::InputEncoding = {}; $text = {}; \
+         if ($text.Length) {{ Set-Clipboard -Value $text }} else {{ Set-Clipboard -Value $null }}",
+        decoder, read
+    )
+}
+fn spawn_set_clipboard() -> Result<ClipboardStream> {
+    create_logger("clipboard").debug("Spawning powershell.exe Set-Clipboard for streaming...");
+    let mut child = text_command("powershell.exe")
         .stdin(Stdio::piped())
         .spawn()
-        .with_context(|| "Failed to spawn clip.exe")?;
-    let stdin = child.stdin.take();
-    Ok(ClipboardStream { child, stdin })
+        .with_context(|| {
+            format!(
+                "Failed to spawn powershell.exe\n  {}",
+                interop::hint(&["powershell.exe"])
+            )
+        })?;
+    let stdin = child
+        .stdin
+        .take()
+        .map(|pipe| TextSink::Replay(text_input("powershell.exe", pipe), Some(Vec::new())));
+    Ok(ClipboardStream {
+        target: StreamTarget::Process(child, "powershell.exe"),
+        stdin,
+    })
 }
-/// Legacy helper for one-shot strings (retained for Path mode simplicity)
-pub fn set_text_content(content: &str) -> Result<()> {
-    use std::io::Write;
-    let mut stream = start_text_stream()?;
-    if let Some(mut stdin) = stream.stdin.take() {
-        stdin.write_all(content.as_bytes())?;
+/// wl-copy and xclip fork a process that serves the selection until it is
+/// replaced; it must not inherit our stdout, or a caller reading that pipe
+/// would wait for it forever.
+fn spawn_tool(cmd: &mut Command, program: &'static str) -> Result<ClipboardStream> {
+    create_logger("clipboard").debug(&format!("Spawning {} for streaming...", program));
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("Failed to spawn {} (is it installed?)", program))?;
+    let stdin = child.stdin.take().map(TextSink::Pipe);
+    Ok(ClipboardStream {
+        target: StreamTarget::Process(child, program),
+        stdin,
+    })
+}
+/// MIME type for an image copied through wl-copy or xclip.
+pub fn image_mime(path: &Path) -> Result<&'static str> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    Ok(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => anyhow::bail!(
+            "Cannot tell the image type of {} for the {} backend (use .png, .jpg, .gif, .bmp or .webp)",
+            path.display(),
+            backend().name()
+        ),
+    })
+}
+/// Image mode off WSL: the file's bytes, offered as its MIME type.
+fn set_image_native(path: &Path) -> Result<()> {
+    let mime = image_mime(path)?;
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut stream = match backend() {
+        Backend::Wayland => spawn_tool(Command::new("wl-copy").args(["--type", mime]), "wl-copy")?,
+        Backend::X11 => spawn_tool(
+            Command::new("xclip").args(["-selection", "clipboard", "-t", mime, "-in"]),
+            "xclip",
+        )?,
+        other => anyhow::bail!("{} is not a native Linux backend", other.name()),
+    };
+    create_logger("clipboard").debug(&format!("Copying {:?} as {}", path, mime));
+    if let Some(pipe) = stream.stdin.as_mut() {
+        io::copy(&mut file, pipe)?;
     }
     stream.wait()
 }
+/// Where copies land. `SystemClipboard` is the real one; tests dispatch
+/// against `memory::MemoryClipboard`, which records what would have been
+/// copied. Reading the clipboard (paste, peek, inspect) is not covered.
+pub trait ClipboardBackend {
+    /// Starts a text copy; `ClipboardStream::wait` commits it, `abort` drops it.
+    fn start_text_stream(&self) -> Result<ClipboardStream>;
+    /// Copies one image's pixels. `file` is a Linux path (staged, if --stage).
+    fn set_image(&self, file: &Path) -> Result<()>;
+    /// Copies one image's pixels and the file itself as a drop list, in one
+    /// operation (`--both`).
+    fn set_image_and_file(&self, file: &Path) -> Result<()>;
+    /// Copies files as a drop list, plus a plain-text alternative when `text`
+    /// is set. `effect` tells Explorer whether a paste copies or moves them.
+    fn set_file_drop(
+        &self,
+        files: &[PathBuf],
+        text: Option<String>,
+        effect: DropEffect,
+    ) -> Result<()>;
+    /// Several text formats in one operation, e.g. plain text beside a
+    /// CF_HTML payload, so each paste target picks the one it understands.
+    fn set_multi(&self, formats: &[(Format, String)]) -> Result<()>;
+    /// A one-shot string (Path mode).
+    fn set_text(&self, text: &str) -> Result<()> {
+        let mut stream = self.start_text_stream()?;
+        if let Some(stdin) = stream.stdin.as_mut() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        stream.wait()
+    }
+}
+/// clip.exe and PowerShell (through the daemon when one runs), or whichever
+/// backend `--backend` resolved to.
+pub struct SystemClipboard {
+    pub preflight: Preflight,
+    /// `--strict-length`: refuse paths past MAX_PATH instead of prefixing them
+    pub strict_length: bool,
+}
+impl SystemClipboard {
+    /// Windows paths for `files`, checked for reachability first. Paths past
+    /// MAX_PATH come back in their `\\?\` form.
+    fn windows_paths(&self, files: &[PathBuf]) -> Result<Vec<String>> {
+        require_windows("Copying files as Image/File objects")?;
+        let win_paths = paths::to_windows_paths(files)?;
+        preflight::ensure_reachable(files, &win_paths, self.preflight)?;
+        paths::fit_max_path(win_paths, self.strict_length)
+    }
+}
+/// GDI+ cannot read WebP, AVIF or HEIC: those get a PNG to copy from,
+/// removed when dropped (after the copy).
+fn gdi_source(file: &Path) -> Result<Option<staging::StagedFile>> {
+    Ok(match transcode::foreign_codec(file)? {
+        Some(codec) => {
+            let png = transcode::to_png(file, codec, staging::root())?;
+            create_logger("clipboard").debug(&format!(
+                "Transcoded {} ({}) to PNG: {:?}",
+                file.display(),
+                codec.name(),
+                png.path()
+            ));
+            Some(png)
+        }
+        None => None,
+    })
+}
+impl ClipboardBackend for SystemClipboard {
+    fn start_text_stream(&self) -> Result<ClipboardStream> {
+        start_text_stream()
+    }
+    fn set_image(&self, file: &Path) -> Result<()> {
+        if backend().is_native() {
+            return set_image_native(file);
+        }
+        let converted = gdi_source(file)?;
+        let source = converted.as_ref().map_or(file, |png| png.path());
+        let win_paths = self.windows_paths(&[source.to_path_buf()])?;
+        set_complex(&win_paths, ClipboardMode::Image)
+    }
+    fn set_image_and_file(&self, file: &Path) -> Result<()> {
+        if !backend().is_windows() {
+            anyhow::bail!(
+                "--both needs the Windows clipboard; the {} backend carries one type per copy",
+                backend().name()
+            );
+        }
+        let converted = gdi_source(file)?;
+        let source = converted.as_ref().map_or(file, |png| png.path());
+        let win_paths = self.windows_paths(&[source.to_path_buf(), file.to_path_buf()])?;
+        set_complex(&win_paths, ClipboardMode::ImageAndFile)
+    }
+    fn set_file_drop(
+        &self,
+        files: &[PathBuf],
+        text: Option<String>,
+        effect: DropEffect,
+    ) -> Result<()> {
+        let win_paths = self.windows_paths(files)?;
+        let mode = match (text, effect) {
+            (text, DropEffect::Move) => ClipboardMode::FileMove(text),
+            (Some(text), DropEffect::Copy) => ClipboardMode::FileWithText(text),
+            (None, DropEffect::Copy) => ClipboardMode::File,
+        };
+        set_complex(&win_paths, mode)
+    }
+    fn set_multi(&self, formats: &[(Format, String)]) -> Result<()> {
+        check_formats(formats)?;
+        if !backend().is_windows() {
+            anyhow::bail!(
+                "{} copies need the Windows clipboard; the {} backend only carries plain text",
+                multi_label(formats),
+                backend().name()
+            );
+        }
+        set_complex(&[], ClipboardMode::Multi(formats.to_vec()))
+    }
+}
+/// At least one format, and none twice (the later one would silently win).
+fn check_formats(formats: &[(Format, String)]) -> Result<()> {
+    if formats.is_empty() {
+        anyhow::bail!("Nothing to copy: no clipboard formats given");
+    }
+    for (i, (format, _)) in formats.iter().enumerate() {
+        if formats[..i].iter().any(|(f, _)| f == format) {
+            anyhow::bail!("Clipboard format {} given twice", format.label());
+        }
+    }
+    Ok(())
+}
+/// An in-memory clipboard for tests: every committed copy is recorded.
+#[cfg(test)]
+pub mod memory {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Copied {
+        Text(Vec<u8>),
+        Image(PathBuf),
+        ImageAndFile(PathBuf),
+        Files {
+            files: Vec<PathBuf>,
+            text: Option<String>,
+            effect: DropEffect,
+        },
+        Multi(Vec<(Format, String)>),
+    }
+    #[derive(Debug, Clone, Default)]
+    pub struct MemoryClipboard {
+        pub(super) copies: Arc<Mutex<Vec<Copied>>>,
+    }
+    impl MemoryClipboard {
+        pub fn copies(&self) -> Vec<Copied> {
+            self.copies.lock().unwrap().clone()
+        }
+        /// The last text copy, as a string.
+        pub fn text(&self) -> Option<String> {
+            self.copies().into_iter().rev().find_map(|c| match c {
+                Copied::Text(bytes) => Some(String::from_utf8(bytes).unwrap()),
+                _ => None,
+            })
+        }
+    }
+    impl ClipboardBackend for MemoryClipboard {
+        fn start_text_stream(&self) -> Result<ClipboardStream> {
+            Ok(ClipboardStream {
+                target: StreamTarget::Memory(self.copies.clone()),
+                stdin: Some(TextSink::Buffer(Vec::new())),
+            })
+        }
+        fn set_image(&self, file: &Path) -> Result<()> {
+            self.copies
+                .lock()
+                .unwrap()
+                .push(Copied::Image(file.to_path_buf()));
+            Ok(())
+        }
+        fn set_image_and_file(&self, file: &Path) -> Result<()> {
+            self.copies
+                .lock()
+                .unwrap()
+                .push(Copied::ImageAndFile(file.to_path_buf()));
+            Ok(())
+        }
+        fn set_file_drop(
+            &self,
+            files: &[PathBuf],
+            text: Option<String>,
+            effect: DropEffect,
+        ) -> Result<()> {
+            self.copies.lock().unwrap().push(Copied::Files {
+                files: files.to_vec(),
+                text,
+                effect,
+            });
+            Ok(())
+        }
+        fn set_multi(&self, formats: &[(Format, String)]) -> Result<()> {
+            check_formats(formats)?;
+            self.copies
+                .lock()
+                .unwrap()
+                .push(Copied::Multi(formats.to_vec()));
+            Ok(())
+        }
+    }
+}
+/// What the Windows clipboard currently holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardContent {
+    Text(String),
+    /// Non-text data, described by its format
+    Other(String),
+    Empty,
+}
+/// Parses the tagged output of the read script: a format line, then the payload.
+pub fn parse_read_output(raw: &str) -> ClipboardContent {
+    let (tag, rest) = raw.split_once('\n').unwrap_or((raw, ""));
+    match tag.trim() {
+        "TEXT" => ClipboardContent::Text(rest.to_string()),
+        "EMPTY" | "" => ClipboardContent::Empty,
+        other => ClipboardContent::Other(other.to_string()),
+    }
+}
+const READ_SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+    [Console]::OutputEncoding = [System.Text.Encoding]::UTF8; \
+    if ([System.Windows.Forms.Clipboard]::ContainsText()) { \
+    [Console]::Out.Write(\"TEXT`n\" + [System.Windows.Forms.Clipboard]::GetText()) } \
+    elseif ([System.Windows.Forms.Clipboard]::ContainsImage()) { [Console]::Out.Write('image (bitmap)') } \
+    elseif ([System.Windows.Forms.Clipboard]::ContainsFileDropList()) { \
+    [Console]::Out.Write('file objects (' + [System.Windows.Forms.Clipboard]::GetFileDropList().Count + ')') } \
+    else { [Console]::Out.Write('EMPTY') }";
+/// Clipboard text still coming out of PowerShell, for readers that should
+/// not hold a multi-megabyte paste in memory.
+pub struct ClipboardReader {
+    child: Child,
+    pub reader: BufReader<ChildStdout>,
+}
+impl ClipboardReader {
+    /// Waits for PowerShell once the text has been read to the end.
+    pub fn finish(mut self) -> Result<()> {
+        let status = self
+            .child
+            .wait()
+            .context("Failed to wait for powershell.exe")?;
+        if !status.success() {
+            anyhow::bail!("PowerShell exited with error status while reading the clipboard");
+        }
+        Ok(())
+    }
+    /// Stops PowerShell without reading the rest (the consumer went away).
+    pub fn abort(mut self) -> Result<()> {
+        let _ = self.child.kill();
+        self.child.wait().context("Failed to reap powershell.exe")?;
+        Ok(())
+    }
+}
+/// `ClipboardContent`, with the text left unread.
+pub enum ClipboardRead {
+    Text(ClipboardReader),
+    Other(String),
+    Empty,
+}
+/// Starts PowerShell and reads only the format line; text is left in the pipe.
+pub fn open_clipboard() -> Result<ClipboardRead> {
+    let log = create_logger("clipboard");
+    require_windows("Reading the clipboard")?;
+    log.debug("Reading clipboard via powershell.exe (System.Windows.Forms, STA)");
+    let mut child = interop::command("powershell.exe")
+        .arg("-NoProfile")
+        .arg("-STA")
+        .arg("-Command")
+        .arg(READ_SCRIPT)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| {
+            format!(
+                "Failed to execute powershell.exe\n  {}",
+                interop::hint(&["powershell.exe"])
+            )
+        })?;
+    let stdout = child.stdout.take().context("powershell.exe stdout")?;
+    let mut reader = BufReader::new(stdout);
+    let mut tag = Vec::new();
+    reader
+        .read_until(b'\n', &mut tag)
+        .context("Failed to read from powershell.exe")?;
+    let tagged = ClipboardReader { child, reader };
+    match parse_read_output(&String::from_utf8_lossy(&tag)) {
+        ClipboardContent::Text(_) => {
+            log.debug("Clipboard format: text");
+            Ok(ClipboardRead::Text(tagged))
+        }
+        ClipboardContent::Other(format) => {
+            log.debug(&format!("Clipboard format: {}", format));
+            tagged.finish()?;
+            Ok(ClipboardRead::Other(format))
+        }
+        ClipboardContent::Empty => {
+            log.debug("Clipboard format: empty");
+            tagged.finish()?;
+            Ok(ClipboardRead::Empty)
+        }
+    }
+}
+/// Reads the clipboard through PowerShell. Text is returned verbatim (UTF-8);
+/// other formats are reported by name.
+pub fn read_clipboard() -> Result<ClipboardContent> {
+    Ok(match open_clipboard()? {
+        ClipboardRead::Text(mut text) => {
+            let mut raw = Vec::new();
+            text.reader
+                .read_to_end(&mut raw)
+                .context("Failed to read from powershell.exe")?;
+            text.finish()?;
+            create_logger("clipboard").debug(&format!("Clipboard text: {} bytes", raw.len()));
+            ClipboardContent::Text(String::from_utf8_lossy(&raw).into_owned())
+        }
+        ClipboardRead::Other(format) => ClipboardContent::Other(format),
+        ClipboardRead::Empty => ClipboardContent::Empty,
+    })
+}
+/// Encodings `paste --image` can write, picked by the destination's extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+}
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("png") => Ok(ImageFormat::Png),
+            Some("jpg" | "jpeg") => Ok(ImageFormat::Jpeg),
+            _ => anyhow::bail!(
+                "Cannot tell the image format of {:?}: use a .png, .jpg or .jpeg file name",
+                path
+            ),
+        }
+    }
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+    /// The `System.Drawing.Imaging.ImageFormat` property name
+    fn dotnet_name(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "Png",
+            ImageFormat::Jpeg => "Jpeg",
+        }
+    }
+}
+/// Has PowerShell save the clipboard bitmap under the Windows %TEMP% and
+/// returns that file's Windows path. The caller moves it into WSL.
+pub fn save_image(format: ImageFormat) -> Result<String> {
+    let log = create_logger("clipboard");
+    require_windows("Saving the clipboard image")?;
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms; Add-Type -AssemblyName System.Drawing; \
+        $img = [System.Windows.Forms.Clipboard]::GetImage(); \
+        if ($img -eq $null) {{ [Console]::Out.Write('NOIMAGE'); exit 0 }}; \
+        $p = Join-Path ([System.IO.Path]::GetTempPath()) ('wsl-clip-' + [guid]::NewGuid() + '.{}'); \
+        $img.Save($p, [System.Drawing.Imaging.ImageFormat]::{}); \
+        [Console]::Out.Write(\"SAVED`n\" + $p)",
+        format.extension(),
+        format.dotnet_name()
+    );
+    log.debug(&format!(
+        "Saving clipboard image as {:?} via powershell.exe",
+        format
+    ));
+    let output = interop::command("powershell.exe")
+        .arg("-NoProfile")
+        .arg("-STA")
+        .arg("-Command")
+        .arg(script)
+        .output()
+        .with_context(|| {
+            format!(
+                "Failed to execute powershell.exe\n  {}",
+                interop::hint(&["powershell.exe"])
+            )
+        })?;
+    if !output.status.success() {
+        anyhow::bail!("PowerShell exited with error status while saving the clipboard image");
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.split_once('\n') {
+        Some(("SAVED", win_path)) if !win_path.trim().is_empty() => {
+            log.debug(&format!("Image saved to {}", win_path.trim()));
+            Ok(win_path.trim().to_string())
+        }
+        _ if stdout.trim() == "NOIMAGE" => {
+            anyhow::bail!("Clipboard has no image (copy a screenshot or picture first)")
+        }
+        _ => anyhow::bail!("Unexpected output from powershell.exe: {:?}", stdout.trim()),
+    }
+}
+/// How long `shot --region` waits for the snip.
+pub const DEFAULT_SNIP_WAIT: Duration = Duration::from_secs(60);
+/// How often `shot --region` checks the clipboard for it.
+pub const DEFAULT_SNIP_POLL: Duration = Duration::from_millis(250);
+/// What `wsl-clip shot` captures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShotArea {
+    Primary,
+    /// The virtual screen: every monitor, including ones left of or above the primary
+    AllScreens,
+    /// The Windows snipping overlay; the snip is awaited on the clipboard
+    /// for up to `wait`, checking every `poll`
+    Region {
+        wait: Duration,
+        poll: Duration,
+    },
+}
+/// A screenshot now on the clipboard, and where Windows saved a copy when asked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shot {
+    pub size: ImageSize,
+    pub saved: Option<String>,
+}
+/// The PowerShell for `screenshot`. DPI awareness makes the screen bounds
+/// physical pixels, so scaled displays are captured whole.
+fn shot_script(area: ShotArea, save: Option<ImageFormat>) -> String {
+    let mut script = String::from(
+        "Add-Type -AssemblyName System.Windows.Forms; Add-Type -AssemblyName System.Drawing; \
+        Add-Type -Namespace WslClip -Name Native -MemberDefinition '\
+        [DllImport(\"user32.dll\")] public static extern bool SetProcessDPIAware(); \
+        [DllImport(\"user32.dll\")] public static extern uint GetClipboardSequenceNumber();'; \
+        [void][WslClip.Native]::SetProcessDPIAware(); ",
+    );
+    let capture = |bounds: &str| {
+        format!(
+            "$b = {}; \
+            $img = New-Object System.Drawing.Bitmap($b.Width, $b.Height); \
+            $g = [System.Drawing.Graphics]::FromImage($img); \
+            $g.CopyFromScreen($b.Left, $b.Top, 0, 0, $img.Size); $g.Dispose(); \
+            [System.Windows.Forms.Clipboard]::SetImage($img); ",
+            bounds
+        )
+    };
+    match area {
+        ShotArea::Primary => script.push_str(&capture(
+            "[System.Windows.Forms.Screen]::PrimaryScreen.Bounds",
+        )),
+        ShotArea::AllScreens => script.push_str(&capture(
+            "[System.Windows.Forms.SystemInformation]::VirtualScreen",
+        )),
+        // The snip counts only once the clipboard changed after the overlay opened
+        ShotArea::Region { wait, poll } => script.push_str(&format!(
+            "$seq = [WslClip.Native]::GetClipboardSequenceNumber(); \
+            Start-Process 'ms-screenclip:'; \
+            $end = (Get-Date).AddMilliseconds({}); $img = $null; \
+            while ((Get-Date) -lt $end) {{ Start-Sleep -Milliseconds {}; \
+            if ([WslClip.Native]::GetClipboardSequenceNumber() -ne $seq -and \
+            [System.Windows.Forms.Clipboard]::ContainsImage()) {{ \
+            $img = [System.Windows.Forms.Clipboard]::GetImage(); break }} }}; \
+            if ($img -eq $null) {{ [Console]::Out.Write('TIMEOUT'); exit 0 }}; ",
+            wait.as_millis(),
+            poll.as_millis().max(1)
+        )),
+    }
+    script.push_str("$p = ''; ");
+    if let Some(format) = save {
+        script.push_str(&format!(
+            "$p = Join-Path ([System.IO.Path]::GetTempPath()) ('wsl-clip-' + [guid]::NewGuid() + '.{}'); \
+            $img.Save($p, [System.Drawing.Imaging.ImageFormat]::{}); ",
+            format.extension(),
+            format.dotnet_name()
+        ));
+    }
+    script.push_str(
+        "[Console]::Out.Write(\"SHOT`n\" + $img.Width + 'x' + $img.Height + \"`n\" + $p)",
+    );
+    script
+}
+fn parse_shot(stdout: &str) -> Result<Shot> {
+    let mut lines = stdout.trim().lines();
+    match (lines.next(), lines.next()) {
+        (Some("SHOT"), Some(size)) => {
+            let (w, h) = size
+                .trim()
+                .split_once('x')
+                .context("Unexpected screenshot size from powershell.exe")?;
+            let saved = lines.next().map(str::trim).filter(|p| !p.is_empty());
+            Ok(Shot {
+                size: ImageSize {
+                    width: w.parse()?,
+                    height: h.parse()?,
+                },
+                saved: saved.map(str::to_string),
+            })
+        }
+        (Some("TIMEOUT"), None) => {
+            anyhow::bail!("No snip reached the clipboard in time (raise the limit with --wait)")
+        }
+        _ => anyhow::bail!("Unexpected output from powershell.exe: {:?}", stdout.trim()),
+    }
+}
+/// Takes a screenshot onto the clipboard through PowerShell. With `save`,
+/// Windows also writes it under %TEMP%; the caller moves that file into WSL.
+pub fn screenshot(area: ShotArea, save: Option<ImageFormat>) -> Result<Shot> {
+    let log = create_logger("clipboard");
+    require_windows("Taking a screenshot")?;
+    log.debug(&format!("Screenshot of {:?} via powershell.exe", area));
+    let output = interop::command("powershell.exe")
+        .arg("-NoProfile")
+        .arg("-STA")
+        .arg("-Command")
+        .arg(shot_script(area, save))
+        .output()
+        .with_context(|| {
+            format!(
+                "Failed to execute powershell.exe\n  {}",
+                interop::hint(&["powershell.exe"])
+            )
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match powershell_error_message(&stderr) {
+            Some(reason) => anyhow::bail!("Screenshot failed: {}", reason),
+            None => {
+                anyhow::bail!("PowerShell exited with error status while taking the screenshot")
+            }
+        }
+    }
+    let shot = parse_shot(&String::from_utf8_lossy(&output.stdout))?;
+    log.debug(&format!("Screenshot: {:?}", shot));
+    Ok(shot)
+}
+/// Empties the Windows clipboard, whatever it holds.
+pub fn clear() -> Result<()> {
+    let log = create_logger("clipboard");
+    require_windows("Clearing the clipboard")?;
+    log.debug("Clearing clipboard via powershell.exe");
+    let status = interop::command("powershell.exe")
+        .arg("-NoProfile")
+        .arg("-STA")
+        .arg("-Command")
+        .arg("Add-Type -AssemblyName System.Windows.Forms; [System.Windows.Forms.Clipboard]::Clear()")
+        .status()
+        .with_context(|| {
+            format!(
+                "Failed to execute powershell.exe\n  {}",
+                interop::hint(&["powershell.exe"])
+            )
+        })?;
+    if !status.success() {
+        log.error("PowerShell exited with error status");
+        anyhow::bail!("PowerShell exited with error status");
+    }
+    Ok(())
+}
+/// One entry of `GetDataObject().GetFormats()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FormatEntry {
+    pub name: String,
+    /// What the format is for, when it is a well-known one
+    pub description: Option<&'static str>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ImageSize {
+    pub width: u32,
+    pub height: u32,
+}
+/// Everything `inspect` reports about the clipboard.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ClipboardInfo {
+    pub formats: Vec<FormatEntry>,
+    /// Length of the text, in UTF-16 code units as Windows counts them
+    pub text_length: Option<u64>,
+    pub image: Option<ImageSize>,
+    pub files: Option<u64>,
+}
+/// Friendly names for formats commonly seen on the Windows clipboard.
+pub fn describe_format(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "UnicodeText" => "Unicode text",
+        "Text" => "ANSI text",
+        "OEMText" => "OEM (console code page) text",
+        "System.String" => "Text (.NET string)",
+        "Locale" => "Locale of the text",
+        "HTML Format" => "HTML (rich paste into Word, Teams, browsers)",
+        "Rich Text Format" => "Rich text (RTF)",
+        "Csv" => "CSV (Excel)",
+        "XML Spreadsheet" => "Excel XML spreadsheet",
+        "Bitmap" | "System.Drawing.Bitmap" => "Bitmap image",
+        "DeviceIndependentBitmap" | "Format17" => "Bitmap image (DIB)",
+        "PNG" => "PNG image",
+        "FileDrop" => "File list (paste into Explorer)",
+        "FileName" | "FileNameW" => "File name (legacy)",
+        "Shell IDList Array" => "Shell items",
+        "Preferred DropEffect" => "Copy/cut marker for files",
+        "EnhancedMetafile" => "Vector image (EMF)",
+        _ => return None,
+    })
+}
+/// Parses the inspect script's output: one `KEY<TAB>value` per line. Format
+/// names can contain spaces (`HTML Format`), so only the first tab splits.
+/// Unknown keys and malformed values are skipped.
+pub fn parse_inspect_output(raw: &str) -> ClipboardInfo {
+    let mut info = ClipboardInfo::default();
+    for line in raw.lines() {
+        let Some((key, value)) = line.trim_end_matches('\r').split_once('\t') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            // Duplicates are dropped
+            "FORMAT" if !value.is_empty() && info.formats.iter().all(|f| f.name != value) => {
+                info.formats.push(FormatEntry {
+                    name: value.to_string(),
+                    description: describe_format(value),
+                });
+            }
+            "TEXT" => info.text_length = value.parse().ok(),
+            "IMAGE" => {
+                info.image = value.split_once('x').and_then(|(w, h)| {
+                    Some(ImageSize {
+                        width: w.trim().parse().ok()?,
+                        height: h.trim().parse().ok()?,
+                    })
+                })
+            }
+            "FILES" => info.files = value.parse().ok(),
+            _ => {}
+        }
+    }
+    info
+}
+impl ClipboardInfo {
+    /// The human-readable summary table.
+    pub fn render_table(&self) -> String {
+        if self.formats.is_empty() {
+            return "Clipboard is empty\n".to_string();
+        }
+        let width = self
+            .formats
+            .iter()
+            .map(|f| f.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Format".len());
+        let mut out = format!("{:<width$}  Description\n", "Format");
+        for f in &self.formats {
+            out.push_str(&format!(
+                "{:<width$}  {}\n",
+                f.name,
+                f.description.unwrap_or("-")
+            ));
+        }
+        if let Some(n) = self.text_length {
+            out.push_str(&format!("\nText:  {} characters\n", n));
+        }
+        if let Some(size) = self.image {
+            out.push_str(&format!("Image: {}x{} pixels\n", size.width, size.height));
+        }
+        if let Some(n) = self.files {
+            out.push_str(&format!("Files: {}\n", n));
+        }
+        out
+    }
+}
+/// Asks PowerShell which formats the clipboard offers, plus the text length,
+/// image size and file count where those apply.
+pub fn inspect_clipboard() -> Result<ClipboardInfo> {
+    let log = create_logger("clipboard");
+    require_windows("Inspecting the clipboard")?;
+    let script = "Add-Type -AssemblyName System.Windows.Forms; \
+        [Console]::OutputEncoding = [System.Text.Encoding]::UTF8; \
+        $d = [System.Windows.Forms.Clipboard]::GetDataObject(); \
+        if ($d) { foreach ($f in $d.GetFormats()) { [Console]::Out.Write(\"FORMAT`t$f`n\") } }; \
+        if ([System.Windows.Forms.Clipboard]::ContainsText()) { \
+        [Console]::Out.Write(\"TEXT`t\" + [System.Windows.Forms.Clipboard]::GetText().Length + \"`n\") }; \
+        if ([System.Windows.Forms.Clipboard]::ContainsImage()) { $i = [System.Windows.Forms.Clipboard]::GetImage(); \
+        [Console]::Out.Write(\"IMAGE`t\" + $i.Width + 'x' + $i.Height + \"`n\") }; \
+        if ([System.Windows.Forms.Clipboard]::ContainsFileDropList()) { \
+        [Console]::Out.Write(\"FILES`t\" + [System.Windows.Forms.Clipboard]::GetFileDropList().Count + \"`n\") }";
+    log.debug("Inspecting clipboard via powershell.exe");
+    let output = interop::command("powershell.exe")
+        .arg("-NoProfile")
+        .arg("-STA")
+        .arg("-Command")
+        .arg(script)
+        .output()
+        .with_context(|| {
+            format!(
+                "Failed to execute powershell.exe\n  {}",
+                interop::hint(&["powershell.exe"])
+            )
+        })?;
+    if !output.status.success() {
+        anyhow::bail!("PowerShell exited with error status while inspecting the clipboard");
+    }
+    let info = parse_inspect_output(&String::from_utf8_lossy(&output.stdout));
+    log.debug(&format!("Clipboard formats: {}", info.formats.len()));
+    Ok(info)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_choose_backend() {
+        let pick = |s: BackendSignals| choose_backend(Backend::Auto, &s).0;
+        let wsl = BackendSignals {
+            wsl: true,
+            clip_exe: true,
+            wayland: true,
+            wl_copy: true,
+            tty: true,
+            ..Default::default()
+        };
+        // WSLg sets WAYLAND_DISPLAY too; clip.exe still wins under WSL
+        assert_eq!(pick(wsl.clone()), Backend::Windows);
+        assert_eq!(
+            pick(BackendSignals {
+                clip_exe: false,
+                ..wsl.clone()
+            }),
+            Backend::Osc52
+        );
+        // Without clip.exe, PowerShell's Set-Clipboard still beats OSC 52
+        let (backend, reason) = choose_backend(
+            Backend::Auto,
+            &BackendSignals {
+                clip_exe: false,
+                powershell: true,
+                ..wsl.clone()
+            },
+        );
+        assert_eq!(backend, Backend::Windows);
+        assert!(reason.contains("Set-Clipboard"));
+        let laptop = BackendSignals {
+            wayland: true,
+            wl_copy: true,
+            x11: true,
+            xclip: true,
+            ..Default::default()
+        };
+        assert_eq!(pick(laptop.clone()), Backend::Wayland);
+        // A display without its tool does not count
+        assert_eq!(
+            pick(BackendSignals {
+                wl_copy: false,
+                ..laptop.clone()
+            }),
+            Backend::X11
+        );
+        let (backend, reason) = choose_backend(Backend::Auto, &BackendSignals::default());
+        assert_eq!(backend, Backend::Windows);
+        assert!(reason.contains("no wl-copy/xclip"));
+        assert_eq!(
+            choose_backend(Backend::X11, &wsl),
+            (Backend::X11, "selected with --backend".to_string())
+        );
+    }
+    #[test]
+    fn test_osc52_sequence() {
+        let (seq, kept) = osc52_sequence("hi ✓\n".as_bytes());
+        assert_eq!(seq, "\x1b]52;c;aGkg4pyTCg==\x07");
+        assert_eq!(kept, 7);
+        // Over the limit: cut to fit, never inside a UTF-8 character
+        let limit = OSC52_MAX_ENCODED / 4 * 3;
+        let mut big = vec![b'a'; limit - 1];
+        big.extend_from_slice("✓✓".as_bytes());
+        let (seq, kept) = osc52_sequence(&big);
+        assert_eq!(kept, limit - 1);
+        assert!(seq.len() <= OSC52_MAX_ENCODED + "\x1b]52;c;\x07".len());
+        assert!(std::str::from_utf8(&big[..kept]).is_ok());
+        let (_, kept) = osc52_sequence(&vec![b'x'; limit]);
+        assert_eq!(kept, limit);
+    }
+    #[test]
+    fn test_parse_inspect_output() {
+        let raw = "FORMAT\tHTML Format\r\nFORMAT\tUnicodeText\r\nFORMAT\tSome App Private Format\r\n\
+            FORMAT\tUnicodeText\r\nTEXT\t42\r\nnoise without a tab\r\nIMAGE\t1920x1080\r\nFILES\tlots\r\n";
+        let info = parse_inspect_output(raw);
+        let names: Vec<&str> = info.formats.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["HTML Format", "UnicodeText", "Some App Private Format"]
+        );
+        assert_eq!(info.formats[0].description, describe_format("HTML Format"));
+        assert_eq!(info.formats[2].description, None);
+        assert_eq!(info.text_length, Some(42));
+        assert_eq!(
+            info.image,
+            Some(ImageSize {
+                width: 1920,
+                height: 1080
+            })
+        );
+        assert_eq!(info.files, None);
+        assert_eq!(parse_inspect_output(""), ClipboardInfo::default());
+    }
+    #[test]
+    fn test_render_table() {
+        let info = parse_inspect_output("FORMAT\tFileDrop\nFORMAT\tShell IDList Array\nFILES\t3\n");
+        assert_eq!(
+            info.render_table(),
+            "Format              Description\n\
+             FileDrop            File list (paste into Explorer)\n\
+             Shell IDList Array  Shell items\n\
+             Files: 3\n"
+        );
+        assert_eq!(
+            ClipboardInfo::default().render_table(),
+            "Clipboard is empty\n"
+        );
+    }
+    #[test]
+    fn test_powershell_error_message() {
+        // Windows PowerShell 5.1 error record
+        let ps51 = "Exception calling \"FromFile\" with \"1\" argument(s): \"A generic error occurred in GDI+.\"\r\n\
+            At line:1 char:130\r\n\
+            + ... ; $img = [System.Drawing.Image]::FromFile($args[0]); ...\r\n\
+            + CategoryInfo          : NotSpecified: (:) [], MethodInvocationException\r\n";
+        assert_eq!(
+            powershell_error_message(ps51).as_deref(),
+            Some("A generic error occurred in GDI+.")
+        );
+        // PowerShell 7 concise view
+        let ps7 = "MethodInvocationException: \nLine |\n   1 |  ... $img = [System.Drawing.Image]::FromFile($args[0]); ...\n     |  ~~~~~~~~~~~~\n     | Exception calling \"FromFile\" with \"1\" argument(s): \"C:\\missing.png\"\n";
+        assert_eq!(
+            powershell_error_message(ps7).as_deref(),
+            Some("C:\\missing.png")
+        );
+        let ps7 = "MethodInvocationException: Exception calling \"SetImage\" with \"1\" argument(s): \"Requested Clipboard operation did not succeed.\"";
+        assert_eq!(
+            powershell_error_message(ps7).as_deref(),
+            Some("Requested Clipboard operation did not succeed.")
+        );
+        // The trap's tagged line wins over anything else
+        let trapped = "noise\nwsl-clip: Out of memory.\n";
+        assert_eq!(
+            powershell_error_message(trapped).as_deref(),
+            Some("Out of memory.")
+        );
+        assert_eq!(powershell_error_message("\n  \n"), None);
+    }
+    #[test]
+    fn test_script_failure_messages() {
+        let paths = vec!["C:\\shots\\a.png".to_string()];
+        let msg = script_failure(
+            &ClipboardMode::Image,
+            Some(4),
+            "wsl-clip: Out of memory.\n",
+            &paths,
+        );
+        assert_eq!(
+            msg,
+            "Failed to set image: Out of memory. (Windows cannot decode this image (unsupported or corrupt format))\n  file: C:\\shots\\a.png"
+        );
+        let msg = script_failure(&ClipboardMode::File, Some(1), "", &paths);
+        assert!(msg.starts_with("Failed to set file list: PowerShell exited with status 1\n"));
+        assert!(script_failure(&ClipboardMode::Image, Some(5), "", &paths).contains("locked"));
+    }
+    #[test]
+    fn test_shot_scripts() -> Result<()> {
+        let all = shot_script(ShotArea::AllScreens, None);
+        assert!(all.contains("SystemInformation]::VirtualScreen"));
+        assert!(all.contains("SetProcessDPIAware"));
+        assert!(!all.contains(".Save("));
+        let region = ShotArea::Region {
+            wait: Duration::from_secs(5),
+            poll: Duration::from_millis(100),
+        };
+        let snip = shot_script(region, Some(ImageFormat::Jpeg));
+        assert!(snip.contains("ms-screenclip:"));
+        assert!(snip.contains("AddMilliseconds(5000)"));
+        assert!(snip.contains("Start-Sleep -Milliseconds 100"));
+        assert!(snip.contains("ImageFormat]::Jpeg"));
+        assert_eq!(
+            parse_shot("SHOT\n3840x1080\n")?,
+            Shot {
+                size: ImageSize {
+                    width: 3840,
+                    height: 1080
+                },
+                saved: None
+            }
+        );
+        let saved = parse_shot("SHOT\r\n800x600\r\nC:\\Temp\\wsl-clip-1.png")?;
+        assert_eq!(saved.saved.as_deref(), Some("C:\\Temp\\wsl-clip-1.png"));
+        assert!(parse_shot("TIMEOUT")
+            .unwrap_err()
+            .to_string()
+            .contains("--wait"));
+        Ok(())
+    }
+    #[test]
+    fn test_multi_formats() {
+        let text = (Format::Text, "a".to_string());
+        let html = (Format::Html, "<b>a</b>".to_string());
+        assert!(check_formats(&[text.clone(), html.clone()]).is_ok());
+        assert!(check_formats(&[]).is_err());
+        let err = check_formats(&[text, html.clone(), html]).unwrap_err();
+        assert_eq!(err.to_string(), "Clipboard format HTML given twice");
+        assert_eq!(formats_label(&[Format::Html, Format::Rtf]), "HTML+RTF");
+    }
+}
 
-// <FILE>src/clipboard.rs</FILE> - <DESC>Fixed PowerShell argument passing logic</DESC>
-// <VERS>END OF VERSION: 1.6.0 - 2025-11-25T17:32:57Z</VERS>
+// <FILE>src/clipboard.rs</FILE> - <DESC>Clipboard read path</DESC>
+// <VERS>END OF VERSION: 1.41.0 - 2026-10-16T14:50:15Z</VERS>
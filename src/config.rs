@@ -0,0 +1,217 @@
+// <FILE>src/config.rs</FILE> - <DESC>User config file loading</DESC>
+// <VERS>VERSION: 1.7.0 - 2026-10-16T13:23:17Z</VERS>
+// <WCTX>Configurable asset extensions</WCTX>
+// <CLOG>[classifier] and WSL_CLIP_ASSET_EXTS add to or remove from the asset list</CLOG>
+
+use crate::cite::CiteConfig;
+use crate::classifier::ClassifierConfig;
+use crate::debug_logger::create_logger;
+use crate::dirs;
+use crate::history::HistoryConfig;
+use crate::metrics::MetricsConfig;
+use crate::options::OptionLayer;
+use crate::paths::PathsConfig;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+/// User configuration loaded from `config.toml`. Every section is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub metrics: MetricsConfig,
+    pub history: HistoryConfig,
+    /// `[cite]`: link template for --cite
+    pub cite: CiteConfig,
+    /// `[paths]`: defaults for Windows path conversion
+    pub paths: PathsConfig,
+    /// `[classifier]`: extensions added to or removed from the asset list
+    pub classifier: ClassifierConfig,
+    /// `[defaults]`: text options applied before env vars and CLI flags
+    pub defaults: OptionLayer,
+    /// `[profile.<name>]`: option bundles selected with --profile
+    pub profile: BTreeMap<String, toml::Table>,
+}
+/// Resolves the config file location: `$WSL_CLIP_CONFIG`, then
+/// `config.toml` in the config root (see `dirs`). None when that root is the
+/// temp fallback: a shared temp dir is no place to trust a config from.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(p) = std::env::var_os("WSL_CLIP_CONFIG") {
+        return Some(PathBuf::from(p));
+    }
+    let root = &dirs::current().config;
+    (!root.is_ephemeral()).then(|| root.path.join("config.toml"))
+}
+impl Config {
+    /// Loads the user config. A missing file yields the defaults.
+    pub fn load() -> Result<Self> {
+        let log = create_logger("config");
+        let Some(path) = config_path() else {
+            log.debug("No HOME or XDG config directory; using defaults");
+            return Ok(Config::default());
+        };
+        if !path.exists() {
+            log.debug(&format!("No config file at {:?}; using defaults", path));
+            return Ok(Config::default());
+        }
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+        let config =
+            Self::parse(&raw).with_context(|| format!("Invalid config file: {:?}", path))?;
+        log.debug(&format!("Loaded config from {:?}", path));
+        Ok(config)
+    }
+    pub fn parse(raw: &str) -> Result<Self> {
+        Ok(toml::from_str(raw)?)
+    }
+    /// Resolves a profile and its `extends` chain into one layer. Parents are
+    /// applied first so the child wins. Unknown keys are dropped and returned
+    /// as warnings; keys with the wrong type are errors.
+    pub fn resolve_profile(&self, name: &str) -> Result<(OptionLayer, Vec<String>)> {
+        let mut chain: Vec<&str> = Vec::new();
+        let mut next = Some(name);
+        while let Some(current) = next {
+            if chain.contains(&current) {
+                chain.push(current);
+                anyhow::bail!("Profile cycle: {}", chain.join(" -> "));
+            }
+            let table = self.profile_table(current)?;
+            chain.push(current);
+            next = match table.get("extends") {
+                None => None,
+                Some(toml::Value::String(parent)) => Some(parent.as_str()),
+                Some(_) => anyhow::bail!("Profile '{}': extends must be a string", current),
+            };
+        }
+        let mut merged = toml::Table::new();
+        let mut warnings = Vec::new();
+        for current in chain.iter().rev() {
+            for (key, value) in self.profile_table(current)? {
+                if key == "extends" {
+                    continue;
+                }
+                let mut single = toml::Table::new();
+                single.insert(key.clone(), value.clone());
+                if let Err(e) = single.try_into::<OptionLayer>() {
+                    if e.to_string().contains("unknown field") {
+                        warnings.push(format!(
+                            "Profile '{}': unknown key '{}' ignored",
+                            current, key
+                        ));
+                        continue;
+                    }
+                    return Err(anyhow::anyhow!(e)).with_context(|| {
+                        format!("Profile '{}': invalid value for '{}'", current, key)
+                    });
+                }
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+        Ok((merged.try_into()?, warnings))
+    }
+    fn profile_table(&self, name: &str) -> Result<&toml::Table> {
+        self.profile.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profile.keys().map(String::as_str).collect();
+            if known.is_empty() {
+                anyhow::anyhow!(
+                    "Unknown profile '{}' (no [profile.*] sections in config)",
+                    name
+                )
+            } else {
+                anyhow::anyhow!(
+                    "Unknown profile '{}' (available: {})",
+                    name,
+                    known.join(", ")
+                )
+            }
+        })
+    }
+    /// `--profile help`: every profile with its own keys.
+    pub fn describe_profiles(&self) -> String {
+        if self.profile.is_empty() {
+            return "No profiles defined. Add a [profile.<name>] section to the config file.\n"
+                .to_string();
+        }
+        let mut out = String::new();
+        for (name, table) in &self.profile {
+            out.push_str(&format!("[profile.{}]\n", name));
+            for (key, value) in table {
+                out.push_str(&format!("  {} = {}\n", key, value));
+            }
+        }
+        out
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_parse_metrics_section() -> Result<()> {
+        let config = Config::parse("[metrics]\nenabled = true\nstatsd = \"127.0.0.1:8125\"\n")?;
+        assert!(config.metrics.enabled);
+        assert_eq!(config.metrics.statsd.as_deref(), Some("127.0.0.1:8125"));
+        assert!(!Config::parse("")?.metrics.enabled);
+        assert!(Config::parse("[metrics]\nenabled = \"yes\"").is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_parse_paths_section() -> Result<()> {
+        use crate::paths::UncStyle;
+        assert_eq!(
+            Config::parse("[paths]\nunc = \"wsl$\"\n")?.paths.unc,
+            Some(UncStyle::Wsl)
+        );
+        assert_eq!(Config::parse("")?.paths.unc, None);
+        assert!(Config::parse("[paths]\nunc = \"wsl\"").is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_parse_defaults_section() -> Result<()> {
+        let config = Config::parse("[defaults]\ncrlf = true\ntarget_shell = \"bash\"\n")?;
+        assert_eq!(config.defaults.crlf, Some(true));
+        assert_eq!(
+            config.defaults.target_shell,
+            Some(crate::shell_paste::TargetShell::Bash)
+        );
+        assert!(config.defaults.code.is_none());
+        // Typos are reported instead of silently ignored
+        assert!(Config::parse("[defaults]\ncrfl = true").is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_profile_inheritance() -> Result<()> {
+        let config = Config::parse(
+            "[profile.base]\ncrlf = true\ncode = true\n\
+             [profile.review]\nextends = \"base\"\ncode = false\nsnapshot = true\ncolour = 1\n",
+        )?;
+        let (layer, warnings) = config.resolve_profile("review")?;
+        assert_eq!(
+            (layer.crlf, layer.code, layer.snapshot),
+            (Some(true), Some(false), Some(true))
+        );
+        assert_eq!(
+            warnings,
+            vec!["Profile 'review': unknown key 'colour' ignored".to_string()]
+        );
+        assert!(config
+            .describe_profiles()
+            .contains("[profile.review]\n  code = false"));
+        Ok(())
+    }
+    #[test]
+    fn test_profile_errors() -> Result<()> {
+        let config = Config::parse(
+            "[profile.a]\nextends = \"b\"\n[profile.b]\nextends = \"a\"\n\
+             [profile.bad]\ncrlf = \"yes\"\n",
+        )?;
+        let err = config.resolve_profile("a").unwrap_err().to_string();
+        assert_eq!(err, "Profile cycle: a -> b -> a");
+        let err = config.resolve_profile("nope").unwrap_err().to_string();
+        assert!(err.contains("available: a, b, bad"));
+        assert!(config.resolve_profile("bad").is_err());
+        Ok(())
+    }
+}
+
+// <FILE>src/config.rs</FILE> - <DESC>User config file loading</DESC>
+// <VERS>END OF VERSION: 1.7.0 - 2026-10-16T13:23:17Z</VERS>
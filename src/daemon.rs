@@ -0,0 +1,617 @@
+// <FILE>src/daemon.rs</FILE> - <DESC>Daemon socket, protocol and warm PowerShell</DESC>
+// <VERS>VERSION: 1.3.0 - 2026-10-16T14:50:15Z</VERS>
+// <WCTX>Daemon clients never block each other.</WCTX>
+// <CLOG>Connect at wait(); a thread per connection; buffer at most daemon::TEXT_LIMIT.</CLOG>
+
+use crate::clipboard::ClipboardContent;
+use crate::debug_logger::create_logger;
+use crate::dirs;
+use crate::interop;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+/// Bumped on any incompatible change to `Request` / `Response` or framing.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Frames larger than this are refused rather than allocated.
+const MAX_FRAME: u32 = 512 * 1024 * 1024;
+/// How long a client waits for the daemon before copying directly, and how
+/// long the daemon waits on an idle connection before dropping it.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Text a client holds in memory for the daemon. Larger copies stream to
+/// clip.exe instead of being buffered whole.
+pub const TEXT_LIMIT: usize = 4 * 1024 * 1024;
+/// `$XDG_RUNTIME_DIR/wsl-clip.sock`, else a per-user directory under /tmp.
+pub fn socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("wsl-clip.sock"),
+        None => dirs::temp_base().join("wsl-clip.sock"),
+    }
+}
+/// Refuses a socket directory another user could control. The /tmp
+/// fallback has a predictable name, so someone else may have created it
+/// first to receive every copy.
+fn check_private_dir(dir: &Path) -> Result<()> {
+    let meta = std::fs::symlink_metadata(dir)
+        .with_context(|| format!("Cannot inspect the socket directory {:?}", dir))?;
+    if !meta.is_dir() {
+        anyhow::bail!("The socket directory {:?} is not a directory", dir);
+    }
+    if Some(meta.uid()) != dirs::uid() {
+        anyhow::bail!(
+            "The socket directory {:?} belongs to another user (uid {})",
+            dir,
+            meta.uid()
+        );
+    }
+    if meta.mode() & 0o077 != 0 {
+        anyhow::bail!(
+            "The socket directory {:?} is open to other users (mode {:o}); `chmod 700` it",
+            dir,
+            meta.mode() & 0o777
+        );
+    }
+    Ok(())
+}
+/// The socket must be ours, in a directory only we can write to.
+fn check_socket(path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        check_private_dir(dir)?;
+    }
+    let meta = std::fs::symlink_metadata(path)
+        .with_context(|| format!("Cannot inspect the daemon socket {:?}", path))?;
+    if Some(meta.uid()) != dirs::uid() {
+        anyhow::bail!(
+            "The daemon socket {:?} belongs to another user (uid {})",
+            path,
+            meta.uid()
+        );
+    }
+    Ok(())
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Op {
+    Ping,
+    /// Followed by one frame holding the UTF-8 text
+    CopyText,
+    CopyFiles,
+    Paste,
+    Stop,
+}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Request {
+    pub version: u32,
+    pub op: Op,
+    /// Windows paths, for `copy-files`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    pub version: u32,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// For `paste`: "text" (a payload frame follows), "empty", or another format's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+impl Response {
+    fn ok() -> Self {
+        Response {
+            version: PROTOCOL_VERSION,
+            ok: true,
+            ..Default::default()
+        }
+    }
+    fn error(message: String) -> Self {
+        Response {
+            version: PROTOCOL_VERSION,
+            ok: false,
+            error: Some(message),
+            ..Default::default()
+        }
+    }
+}
+/// Writes one frame: a big-endian u32 length, then the bytes.
+pub fn write_frame<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|&n| n <= MAX_FRAME)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "frame too large"))?;
+    w.write_all(&len.to_be_bytes())?;
+    w.write_all(bytes)?;
+    w.flush()
+}
+/// Reads one frame; None on a clean end of stream between frames.
+pub fn read_frame<R: Read>(r: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match r.read_exact(&mut len) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        other => other?,
+    }
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes refused", len),
+        ));
+    }
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+    Ok(Some(buf))
+}
+fn send<W: Write, T: Serialize>(w: &mut W, message: &T) -> io::Result<()> {
+    write_frame(w, &serde_json::to_vec(message)?)
+}
+/// Whether a daemon may be running: its socket exists. Cheap, and no
+/// connection is held, so text can be read before the daemon is contacted.
+pub fn socket_present() -> bool {
+    socket_path().exists()
+}
+/// A connection to a running daemon that passed the version check.
+pub struct Client {
+    stream: UnixStream,
+}
+/// Connects to the daemon if one is listening. A missing or dead socket
+/// means "copy directly" and is only logged; a daemon speaking another
+/// protocol version is worth a warning, since restarting it fixes that.
+pub fn client() -> Option<Client> {
+    let log = create_logger("daemon");
+    let path = socket_path();
+    let stream = match UnixStream::connect(&path) {
+        Ok(stream) => stream,
+        Err(e) => {
+            if path.exists() {
+                log.debug(&format!("Daemon socket {:?} is dead: {}", path, e));
+            }
+            return None;
+        }
+    };
+    // Nothing is sent before the socket is known to be our own daemon's
+    if let Err(e) = check_socket(&path) {
+        eprintln!("[WARN] Not using the wsl-clip daemon: {:#}", e);
+        return None;
+    }
+    let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+    let mut client = Client { stream };
+    match client.call(Op::Ping, &[], None) {
+        Ok(_) => {
+            log.debug(&format!("Routing through the daemon at {:?}", path));
+            Some(client)
+        }
+        Err(e) => {
+            eprintln!("[WARN] Not using the wsl-clip daemon: {:#}", e);
+            None
+        }
+    }
+}
+impl Client {
+    fn call(&mut self, op: Op, files: &[String], payload: Option<&[u8]>) -> Result<Response> {
+        let request = Request {
+            version: PROTOCOL_VERSION,
+            op,
+            files: files.to_vec(),
+        };
+        send(&mut self.stream, &request).context("Failed to reach the daemon")?;
+        if let Some(bytes) = payload {
+            write_frame(&mut self.stream, bytes).context("Failed to send text to the daemon")?;
+        }
+        let frame = read_frame(&mut self.stream)
+            .context("No answer from the daemon")?
+            .context("The daemon closed the connection")?;
+        let response: Response = serde_json::from_slice(&frame).map_err(|_| {
+            anyhow::anyhow!("The daemon sent a reply this version cannot read; restart it")
+        })?;
+        if response.version != PROTOCOL_VERSION {
+            anyhow::bail!(
+                "daemon speaks protocol {}, this wsl-clip speaks {}; restart it with `wsl-clip daemon stop && wsl-clip daemon`",
+                response.version,
+                PROTOCOL_VERSION
+            );
+        }
+        if !response.ok {
+            anyhow::bail!(
+                "daemon: {}",
+                response.error.as_deref().unwrap_or("request failed")
+            );
+        }
+        Ok(response)
+    }
+    pub fn copy_text(&mut self, text: &[u8]) -> Result<()> {
+        self.call(Op::CopyText, &[], Some(text)).map(|_| ())
+    }
+    pub fn copy_files(&mut self, win_paths: &[String]) -> Result<()> {
+        self.call(Op::CopyFiles, win_paths, None).map(|_| ())
+    }
+    pub fn paste(&mut self) -> Result<ClipboardContent> {
+        let response = self.call(Op::Paste, &[], None)?;
+        Ok(match response.content.as_deref() {
+            Some("text") => {
+                let text = read_frame(&mut self.stream)?.context("The daemon sent no text")?;
+                ClipboardContent::Text(String::from_utf8_lossy(&text).into_owned())
+            }
+            Some("empty") | None => ClipboardContent::Empty,
+            Some(other) => ClipboardContent::Other(other.to_string()),
+        })
+    }
+}
+/// `wsl-clip daemon stop`. Stop is honoured whatever the protocol version,
+/// so an old daemon can always be replaced. False when none was listening.
+pub fn stop(path: &Path) -> Result<bool> {
+    let Ok(mut stream) = UnixStream::connect(path) else {
+        return Ok(false);
+    };
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    let request = Request {
+        version: PROTOCOL_VERSION,
+        op: Op::Stop,
+        files: Vec::new(),
+    };
+    send(&mut stream, &request).context("Failed to reach the daemon")?;
+    let frame = read_frame(&mut stream)
+        .context("No answer from the daemon")?
+        .context("The daemon closed the connection")?;
+    let response: Response =
+        serde_json::from_slice(&frame).context("The daemon sent an unreadable reply")?;
+    if !response.ok {
+        anyhow::bail!(
+            "daemon: {}",
+            response.error.as_deref().unwrap_or("stop refused")
+        );
+    }
+    Ok(true)
+}
+/// One long-lived PowerShell that sets and reads the clipboard on request,
+/// so a copy costs a pipe round trip instead of a powershell.exe start.
+/// Requests and replies are single lines; payloads travel as Base64.
+struct WarmShell {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+const WARM_SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+    [Console]::OutputEncoding = [System.Text.Encoding]::UTF8; \
+    $utf8 = [System.Text.Encoding]::UTF8; \
+    while ($null -ne ($line = [Console]::In.ReadLine())) { \
+    $parts = $line.Split(' '); \
+    try { switch ($parts[0]) { \
+    'text' { $t = $utf8.GetString([System.Convert]::FromBase64String($parts[1])); \
+    if ($t.Length -eq 0) { [System.Windows.Forms.Clipboard]::Clear() } else { [System.Windows.Forms.Clipboard]::SetText($t) }; 'OK' } \
+    'files' { $c = New-Object System.Collections.Specialized.StringCollection; \
+    $parts | Select-Object -Skip 1 | ForEach-Object { [void]$c.Add($utf8.GetString([System.Convert]::FromBase64String($_))) }; \
+    [System.Windows.Forms.Clipboard]::SetFileDropList($c); 'OK' } \
+    'paste' { if ([System.Windows.Forms.Clipboard]::ContainsText()) { 'TEXT ' + [System.Convert]::ToBase64String($utf8.GetBytes([System.Windows.Forms.Clipboard]::GetText())) } \
+    elseif ([System.Windows.Forms.Clipboard]::ContainsImage()) { 'OTHER image (bitmap)' } \
+    elseif ([System.Windows.Forms.Clipboard]::ContainsFileDropList()) { 'OTHER file objects' } else { 'EMPTY' } } \
+    default { 'ERR unknown request' } } } catch { 'ERR ' + $_.Exception.Message } }";
+impl WarmShell {
+    fn start() -> Result<Self> {
+        create_logger("daemon").debug("Starting the warm powershell.exe");
+        let mut child = interop::command("powershell.exe")
+            .arg("-NoProfile")
+            .arg("-STA")
+            .arg("-Command")
+            .arg(WARM_SCRIPT)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to execute powershell.exe\n  {}",
+                    interop::hint(&["powershell.exe"])
+                )
+            })?;
+        let stdin = child.stdin.take().context("powershell.exe stdin")?;
+        let stdout = BufReader::new(child.stdout.take().context("powershell.exe stdout")?);
+        Ok(WarmShell {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+    /// Sends one request line and returns the reply line.
+    fn call(&mut self, line: &str) -> Result<String> {
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()?;
+        let mut reply = String::new();
+        if self.stdout.read_line(&mut reply)? == 0 {
+            anyhow::bail!("powershell.exe exited");
+        }
+        Ok(reply.trim_end().to_string())
+    }
+}
+impl Drop for WarmShell {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+/// The shell's answer to a set request: Ok, or its error message.
+fn expect_ok(reply: &str) -> Result<()> {
+    match reply.strip_prefix("ERR ") {
+        Some(message) => anyhow::bail!("PowerShell: {}", message),
+        None if reply == "OK" => Ok(()),
+        None => anyhow::bail!("Unexpected reply from powershell.exe: {:?}", reply),
+    }
+}
+/// Keeps the warm shell alive across requests, restarting it once when it
+/// has died in between. Connections share it and take turns.
+struct Windows {
+    shell: Mutex<Option<WarmShell>>,
+}
+impl Windows {
+    fn call(&self, line: &str) -> Result<String> {
+        let mut shell = self.shell.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(warm) = shell.as_mut() {
+            match warm.call(line) {
+                Ok(reply) => return Ok(reply),
+                Err(e) => create_logger("daemon").warn(&format!("Warm shell lost: {:#}", e)),
+            }
+        }
+        *shell = None;
+        let mut warm = WarmShell::start()?;
+        let reply = warm.call(line)?;
+        *shell = Some(warm);
+        Ok(reply)
+    }
+}
+/// What the server loop should do after a request.
+enum Next {
+    Continue,
+    Stop,
+}
+fn handle<S: Read + Write>(conn: &mut S, windows: &Windows) -> Result<Next> {
+    let log = create_logger("daemon");
+    while let Some(frame) = read_frame(conn)? {
+        let request: Request = match serde_json::from_slice(&frame) {
+            Ok(request) => request,
+            Err(e) => {
+                // Most likely a newer client: answer with our version and hang up
+                send(conn, &Response::error(format!("malformed request: {}", e)))?;
+                return Ok(Next::Continue);
+            }
+        };
+        if request.version != PROTOCOL_VERSION && request.op != Op::Stop {
+            send(
+                conn,
+                &Response::error(format!(
+                    "protocol version {} is not supported (this daemon speaks {})",
+                    request.version, PROTOCOL_VERSION
+                )),
+            )?;
+            return Ok(Next::Continue);
+        }
+        log.debug(&format!("Request: {:?}", request.op));
+        let result = match request.op {
+            Op::Ping => Ok(Response::ok()),
+            Op::Stop => {
+                send(conn, &Response::ok())?;
+                return Ok(Next::Stop);
+            }
+            Op::CopyText => {
+                let text = read_frame(conn)?.context("copy-text without a payload")?;
+                windows
+                    .call(&format!("text {}", BASE64.encode(&text)))
+                    .and_then(|reply| expect_ok(&reply))
+                    .map(|_| Response::ok())
+            }
+            Op::CopyFiles => {
+                let mut line = "files".to_string();
+                for path in &request.files {
+                    line.push(' ');
+                    line.push_str(&BASE64.encode(path));
+                }
+                windows
+                    .call(&line)
+                    .and_then(|reply| expect_ok(&reply))
+                    .map(|_| Response::ok())
+            }
+            Op::Paste => match windows.call("paste") {
+                Ok(reply) => {
+                    if let Some(b64) = reply.strip_prefix("TEXT ") {
+                        let text = BASE64.decode(b64.trim()).unwrap_or_default();
+                        let response = Response {
+                            content: Some("text".to_string()),
+                            ..Response::ok()
+                        };
+                        send(conn, &response)?;
+                        write_frame(conn, &text)?;
+                        continue;
+                    }
+                    let content = match reply.strip_prefix("OTHER ") {
+                        Some(format) => Ok(format.to_string()),
+                        None if reply == "EMPTY" => Ok("empty".to_string()),
+                        None => Err(anyhow::anyhow!("{}", reply)),
+                    };
+                    content.map(|content| Response {
+                        content: Some(content),
+                        ..Response::ok()
+                    })
+                }
+                Err(e) => Err(e),
+            },
+        };
+        let response = result.unwrap_or_else(|e| Response::error(format!("{:#}", e)));
+        send(conn, &response)?;
+    }
+    Ok(Next::Continue)
+}
+/// Removes the socket when the server stops, however it stops.
+struct SocketFile(PathBuf);
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+/// `wsl-clip daemon`: serves each connection on its own thread until
+/// `daemon stop` or Ctrl-C, so a client still reading its input never holds
+/// up another.
+pub fn serve(path: &Path) -> Result<()> {
+    let log = create_logger("daemon");
+    if UnixStream::connect(path).is_ok() {
+        anyhow::bail!("A daemon is already listening on {:?}", path);
+    }
+    if path.exists() {
+        log.debug(&format!("Removing stale socket {:?}", path));
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale socket {:?}", path))?;
+    }
+    if let Some(dir) = path.parent() {
+        if !dir.exists() {
+            std::fs::create_dir_all(dir)?;
+            std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+        }
+        check_private_dir(dir)?;
+    }
+    let listener =
+        UnixListener::bind(path).with_context(|| format!("Failed to listen on {:?}", path))?;
+    let socket = SocketFile(path.to_path_buf());
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    let cleanup = path.to_path_buf();
+    ctrlc::set_handler(move || {
+        let _ = std::fs::remove_file(&cleanup);
+        std::process::exit(0);
+    })
+    .context("Failed to install the Ctrl-C handler")?;
+    let windows = Arc::new(Windows {
+        shell: Mutex::new(Some(WarmShell::start()?)),
+    });
+    let stopping = Arc::new(AtomicBool::new(false));
+    println!("[OK] Daemon listening on {}", path.display());
+    for conn in listener.incoming() {
+        if stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let mut conn = match conn {
+            Ok(conn) => conn,
+            Err(e) => {
+                log.warn(&format!("accept failed: {}", e));
+                continue;
+            }
+        };
+        // A client that goes quiet is dropped rather than keeping its thread
+        let _ = conn.set_read_timeout(Some(CLIENT_TIMEOUT));
+        let windows = Arc::clone(&windows);
+        let stopping = Arc::clone(&stopping);
+        let wake = path.to_path_buf();
+        std::thread::spawn(move || match handle(&mut conn, &windows) {
+            Ok(Next::Stop) => {
+                stopping.store(true, Ordering::SeqCst);
+                // The accept loop only sees the flag on its next connection
+                let _ = UnixStream::connect(&wake);
+            }
+            Ok(Next::Continue) => {}
+            Err(e) => create_logger("daemon").debug(&format!("Connection ended: {:#}", e)),
+        });
+    }
+    drop(socket);
+    println!("[OK] Daemon stopped");
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_frames_round_trip() -> Result<()> {
+        let mut wire = Vec::new();
+        write_frame(&mut wire, b"hello")?;
+        write_frame(&mut wire, b"")?;
+        let mut r = &wire[..];
+        assert_eq!(read_frame(&mut r)?, Some(b"hello".to_vec()));
+        assert_eq!(read_frame(&mut r)?, Some(Vec::new()));
+        assert_eq!(read_frame(&mut r)?, None);
+        // A length past the limit is refused before allocating
+        let mut huge = &(MAX_FRAME + 1).to_be_bytes()[..];
+        assert!(read_frame(&mut huge).is_err());
+        // A frame cut short is an error, not a clean end
+        let mut cut = &wire[..6];
+        assert!(read_frame(&mut cut).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_request_wire_format() -> Result<()> {
+        let request = Request {
+            version: PROTOCOL_VERSION,
+            op: Op::CopyFiles,
+            files: vec!["C:\\a.txt".into()],
+        };
+        let json = serde_json::to_string(&request)?;
+        assert_eq!(
+            json,
+            r#"{"version":1,"op":"copy-files","files":["C:\\a.txt"]}"#
+        );
+        assert_eq!(serde_json::from_str::<Request>(&json)?, request);
+        assert!(serde_json::from_str::<Request>(r#"{"version":2,"op":"teleport"}"#).is_err());
+        Ok(())
+    }
+    /// A connection where the client's bytes are scripted up front.
+    struct Scripted {
+        input: io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+    impl Read for Scripted {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+    impl Write for Scripted {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    fn replies(frames: &[&[u8]]) -> Vec<Response> {
+        let mut input = Vec::new();
+        for f in frames {
+            write_frame(&mut input, f).unwrap();
+        }
+        let mut conn = Scripted {
+            input: io::Cursor::new(input),
+            output: Vec::new(),
+        };
+        let windows = Windows {
+            shell: Mutex::new(None),
+        };
+        handle(&mut conn, &windows).unwrap();
+        let mut out = &conn.output[..];
+        std::iter::from_fn(|| read_frame(&mut out).unwrap())
+            .map(|f| serde_json::from_slice(&f).unwrap())
+            .collect()
+    }
+    #[test]
+    fn test_old_and_new_clients_are_refused_gracefully() {
+        let old = replies(&[br#"{"version":0,"op":"ping"}"#]);
+        assert_eq!(old.len(), 1);
+        assert!(!old[0].ok && old[0].version == PROTOCOL_VERSION);
+        assert!(old[0].error.as_deref().unwrap().contains("version 0"));
+        // An op this daemon does not know ends the connection with an error
+        let new = replies(&[
+            br#"{"version":1,"op":"teleport"}"#,
+            br#"{"version":1,"op":"ping"}"#,
+        ]);
+        assert_eq!(new.len(), 1);
+        assert!(new[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("malformed request"));
+        let ping = replies(&[br#"{"version":1,"op":"ping"}"#]);
+        assert_eq!(ping, vec![Response::ok()]);
+    }
+}
+
+// <FILE>src/daemon.rs</FILE> - <DESC>Daemon socket, protocol and warm PowerShell</DESC>
+// <VERS>END OF VERSION: 1.3.0 - 2026-10-16T14:50:15Z</VERS>
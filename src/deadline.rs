@@ -0,0 +1,112 @@
+// <FILE>src/deadline.rs</FILE> - <DESC>Timeouts for Windows clipboard processes</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T12:05:00Z</VERS>
+// <WCTX>Timeout for hung PowerShell clipboard operations.</WCTX>
+// <CLOG>Initial creation with set, limit, wait and TimedOut.</CLOG>
+
+use crate::clear;
+use crate::debug_logger::create_logger;
+use std::fmt;
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+/// How long clip.exe or PowerShell may take to set the clipboard. A Remote
+/// Desktop session holding the clipboard open can block them indefinitely.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Overrides the default (`--timeout` wins). Same syntax: `20`, `90s`, `2m`; `0` disables.
+pub const TIMEOUT_ENV: &str = "WSL_CLIP_TIMEOUT";
+static LIMIT: OnceLock<Option<Duration>> = OnceLock::new();
+/// Fixes the limit for this process: `--timeout`, else `WSL_CLIP_TIMEOUT`,
+/// else the default. None means no limit.
+pub fn set(cli: Option<Duration>) -> Option<Duration> {
+    *LIMIT.get_or_init(|| {
+        let log = create_logger("clipboard");
+        let chosen = cli.or_else(|| {
+            let raw = std::env::var(TIMEOUT_ENV).ok()?;
+            clear::parse_delay(&raw)
+                .map_err(|e| log.warn(&format!("Ignoring {}: {}", TIMEOUT_ENV, e)))
+                .ok()
+        });
+        let limit = match chosen {
+            Some(d) if d.is_zero() => None,
+            Some(d) => Some(d),
+            None => Some(DEFAULT_TIMEOUT),
+        };
+        log.debug(&format!("Clipboard timeout: {:?}", limit));
+        limit
+    })
+}
+/// The limit in force; the env/default resolution if `set` was never called.
+pub fn limit() -> Option<Duration> {
+    set(None)
+}
+/// Waits for `child` until `limit` runs out, then kills it. The child is
+/// always reaped, so no zombie is left behind. Returns None on expiry.
+pub fn wait(child: &mut Child, limit: Option<Duration>) -> io::Result<Option<ExitStatus>> {
+    let Some(limit) = limit else {
+        return child.wait().map(Some);
+    };
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if started.elapsed() >= limit {
+            // Already exited between the checks: kill fails, wait still reaps
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+/// A clipboard process that ran out of time and was killed.
+#[derive(Debug)]
+pub struct TimedOut {
+    /// What was being set, e.g. "Image mode"
+    pub what: String,
+    pub program: String,
+    pub after: Duration,
+}
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Timed out after {}s setting the clipboard ({}); {} was killed. \
+             Another program (often a Remote Desktop session) may be holding the clipboard; \
+             raise the limit with --timeout or {}",
+            self.after.as_secs_f32(),
+            self.what,
+            self.program,
+            TIMEOUT_ENV
+        )
+    }
+}
+impl std::error::Error for TimedOut {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    #[test]
+    fn test_slow_command_is_killed_and_reaped() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id();
+        let started = Instant::now();
+        let status = wait(&mut child, Some(Duration::from_millis(100))).unwrap();
+        assert!(status.is_none());
+        assert!(started.elapsed() < Duration::from_secs(2));
+        // Reaped: no zombie left in the process table
+        assert!(!std::path::Path::new(&format!("/proc/{}", pid)).exists());
+    }
+    #[test]
+    fn test_fast_command_finishes() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let status = wait(&mut child, Some(Duration::from_secs(5))).unwrap();
+        assert!(status.is_some_and(|s| s.success()));
+        let mut child = Command::new("false").spawn().unwrap();
+        assert!(!wait(&mut child, None).unwrap().unwrap().success());
+    }
+}
+
+// <FILE>src/deadline.rs</FILE> - <DESC>Timeouts for Windows clipboard processes</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T12:05:00Z</VERS>
@@ -1,7 +1,7 @@
 // <FILE>src/debug_config.rs</FILE> - <DESC>Module registry configuration</DESC>
-// <VERS>VERSION: 1.2.0 - 2025-11-25T16:34:29Z</VERS>
-// <WCTX>Registered classifier module.</WCTX>
-// <CLOG>Added classifier entry.</CLOG>
+// <VERS>VERSION: 1.17.0 - 2026-10-16T11:23:56Z</VERS>
+// <WCTX>Interop binaries without appendWindowsPath.</WCTX>
+// <CLOG>interop logger.</CLOG>
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -60,8 +60,136 @@ pub fn module_registry() -> HashMap<String, ModuleConfig> {
             description: "Magic-byte based file type detection".to_string(),
         },
     );
+    // Snapshot Reads
+    registry.insert(
+        "snapshot".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "Point-in-time file snapshots and change detection".to_string(),
+        },
+    );
+    // User Config
+    registry.insert(
+        "config".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "Config file discovery and parsing".to_string(),
+        },
+    );
+    // Usage Metrics
+    registry.insert(
+        "metrics".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "Opt-in textfile/statsd usage metrics".to_string(),
+        },
+    );
+    // Clipboard History
+    registry.insert(
+        "history".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "History capture tee and index".to_string(),
+        },
+    );
+    // Completion Notifications
+    registry.insert(
+        "notify".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "Toast / notify-send completion notices".to_string(),
+        },
+    );
+    // Reachability Pre-flight
+    registry.insert(
+        "preflight".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "File/Image reachability checks before copying".to_string(),
+        },
+    );
+    // Staged Files
+    registry.insert(
+        "staging".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "Windows-visible temp files and staging manifest".to_string(),
+        },
+    );
+    // Checksums
+    registry.insert(
+        "checksum".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "SHA-256 manifests for file-object copies".to_string(),
+        },
+    );
+    // Stdin Detection
+    registry.insert(
+        "input".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "What stdin is connected to (pipe, file, /dev/null, terminal)".to_string(),
+        },
+    );
+    // Citations
+    registry.insert(
+        "cite".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "--cite provenance and git lookup".to_string(),
+        },
+    );
+    // State Bundles
+    registry.insert(
+        "state".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "export-state / import-state bundles".to_string(),
+        },
+    );
+    // Registers
+    registry.insert(
+        "registers".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "Named registers (save / load)".to_string(),
+        },
+    );
+    // Watch Mode
+    registry.insert(
+        "watch".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "--watch file events and debouncing".to_string(),
+        },
+    );
+    // Daemon
+    registry.insert(
+        "daemon".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "Daemon socket, routing and the warm PowerShell".to_string(),
+        },
+    );
+    // Editor Provider
+    registry.insert(
+        "provider".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "--provider copy/paste for editor clipboards".to_string(),
+        },
+    );
+    // Interop Lookup
+    registry.insert(
+        "interop".to_string(),
+        ModuleConfig {
+            level: LogLevel::Off,
+            description: "Finding clip.exe / powershell.exe / cmd.exe off PATH".to_string(),
+        },
+    );
     registry
 }
 
 // <FILE>src/debug_config.rs</FILE> - <DESC>Module registry configuration</DESC>
-// <VERS>END OF VERSION: 1.2.0 - 2025-11-25T16:34:29Z</VERS>
+// <VERS>END OF VERSION: 1.17.0 - 2026-10-16T11:23:56Z</VERS>
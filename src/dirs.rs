@@ -0,0 +1,251 @@
+// <FILE>src/dirs.rs</FILE> - <DESC>Per-user directory resolution</DESC>
+// <VERS>VERSION: 1.2.0 - 2026-10-16T14:49:53Z</VERS>
+// <WCTX>Daemon socket ownership.</WCTX>
+// <CLOG>uid() for the daemon socket ownership checks.</CLOG>
+
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+/// The four per-user roots wsl-clip may write under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Config,
+    State,
+    Cache,
+    Data,
+}
+impl Kind {
+    pub const ALL: [Kind; 4] = [Kind::Config, Kind::State, Kind::Cache, Kind::Data];
+    fn override_var(&self) -> &'static str {
+        match self {
+            Kind::Config => "WSL_CLIP_CONFIG_DIR",
+            Kind::State => "WSL_CLIP_STATE_DIR",
+            Kind::Cache => "WSL_CLIP_CACHE_DIR",
+            Kind::Data => "WSL_CLIP_DATA_DIR",
+        }
+    }
+    fn xdg_var(&self) -> &'static str {
+        match self {
+            Kind::Config => "XDG_CONFIG_HOME",
+            Kind::State => "XDG_STATE_HOME",
+            Kind::Cache => "XDG_CACHE_HOME",
+            Kind::Data => "XDG_DATA_HOME",
+        }
+    }
+    fn home_suffix(&self) -> &'static str {
+        match self {
+            Kind::Config => ".config",
+            Kind::State => ".local/state",
+            Kind::Cache => ".cache",
+            Kind::Data => ".local/share",
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Config => "config",
+            Kind::State => "state",
+            Kind::Cache => "cache",
+            Kind::Data => "data",
+        }
+    }
+}
+/// Which fallback step produced a root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Override(&'static str),
+    Xdg(&'static str),
+    Home,
+    /// Nothing usable was set: a per-user directory under the system temp dir
+    Temp,
+}
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Override(var) | Origin::Xdg(var) => f.write_str(var),
+            Origin::Home => f.write_str("HOME"),
+            Origin::Temp => f.write_str("temp fallback"),
+        }
+    }
+}
+#[derive(Debug)]
+pub struct Root {
+    /// Already includes the `wsl-clip` component
+    pub path: PathBuf,
+    pub origin: Origin,
+    writable: OnceLock<bool>,
+}
+impl Root {
+    fn new(path: PathBuf, origin: Origin) -> Self {
+        Root {
+            path,
+            origin,
+            writable: OnceLock::new(),
+        }
+    }
+    /// Temp-based roots do not survive a reboot; persistent features skip them.
+    pub fn is_ephemeral(&self) -> bool {
+        self.origin == Origin::Temp
+    }
+    /// Whether files can be created here. Probed once, without creating the
+    /// root itself: the nearest existing ancestor must accept a temp file.
+    pub fn is_writable(&self) -> bool {
+        *self.writable.get_or_init(|| probe_writable(&self.path))
+    }
+    /// The root, if a persistent feature (history, metrics) may use it;
+    /// otherwise the reason it should be skipped.
+    pub fn persistent(&self) -> Result<&Path, String> {
+        if self.is_ephemeral() {
+            return Err("no HOME or XDG directory to keep it in".to_string());
+        }
+        if !self.is_writable() {
+            return Err(format!("{:?} is not writable", self.path));
+        }
+        Ok(&self.path)
+    }
+}
+fn probe_writable(path: &Path) -> bool {
+    path.ancestors()
+        .find(|p| p.exists())
+        .is_some_and(|p| p.is_dir() && tempfile::tempfile_in(p).is_ok())
+}
+#[derive(Debug)]
+pub struct Dirs {
+    pub config: Root,
+    pub state: Root,
+    pub cache: Root,
+    pub data: Root,
+}
+impl Dirs {
+    /// Resolves every root: `WSL_CLIP_<KIND>_DIR`, then the XDG variable, then
+    /// HOME, then the temp fallback. Relative values are ignored, as the XDG
+    /// spec requires.
+    pub fn resolve_with(env: impl Fn(&str) -> Option<OsString>) -> Self {
+        let absolute = |var: &str| env(var).map(PathBuf::from).filter(|p| p.is_absolute());
+        let home = absolute("HOME").filter(|h| h != Path::new("/"));
+        let root = |kind: Kind| {
+            if let Some(p) = absolute(kind.override_var()) {
+                return Root::new(p, Origin::Override(kind.override_var()));
+            }
+            if let Some(p) = absolute(kind.xdg_var()) {
+                return Root::new(p.join("wsl-clip"), Origin::Xdg(kind.xdg_var()));
+            }
+            if let Some(h) = &home {
+                return Root::new(h.join(kind.home_suffix()).join("wsl-clip"), Origin::Home);
+            }
+            Root::new(temp_base().join(kind.name()), Origin::Temp)
+        };
+        Dirs {
+            config: root(Kind::Config),
+            state: root(Kind::State),
+            cache: root(Kind::Cache),
+            data: root(Kind::Data),
+        }
+    }
+    pub fn get(&self, kind: Kind) -> &Root {
+        match kind {
+            Kind::Config => &self.config,
+            Kind::State => &self.state,
+            Kind::Cache => &self.cache,
+            Kind::Data => &self.data,
+        }
+    }
+    /// `--paths` output: one line per root.
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        for kind in Kind::ALL {
+            let root = self.get(kind);
+            out.push_str(&format!(
+                "{:<7} {} ({}, {})\n",
+                kind.name(),
+                root.path.display(),
+                root.origin,
+                if root.is_writable() {
+                    "writable"
+                } else {
+                    "NOT writable"
+                }
+            ));
+        }
+        out
+    }
+}
+/// This process's uid, read from the owner of /proc/self.
+pub fn uid() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata("/proc/self").map(|m| m.uid()).ok()
+}
+/// `$TMPDIR/wsl-clip-<uid>`, so users sharing a machine do not collide.
+pub fn temp_base() -> PathBuf {
+    let uid = uid().map_or_else(|| "user".to_string(), |uid| uid.to_string());
+    std::env::temp_dir().join(format!("wsl-clip-{}", uid))
+}
+/// The roots for this process, resolved once.
+pub fn current() -> &'static Dirs {
+    static DIRS: OnceLock<Dirs> = OnceLock::new();
+    DIRS.get_or_init(|| Dirs::resolve_with(|var| std::env::var_os(var).filter(|v| !v.is_empty())))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    fn resolve(vars: &[(&str, &str)]) -> Dirs {
+        let map: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), OsString::from(v)))
+            .collect();
+        Dirs::resolve_with(|var| map.get(var).cloned())
+    }
+    #[test]
+    fn test_fallback_order() {
+        let dirs = resolve(&[
+            ("HOME", "/home/u"),
+            ("XDG_CACHE_HOME", "/xdg/cache"),
+            ("XDG_DATA_HOME", "relative/data"),
+            ("WSL_CLIP_STATE_DIR", "/override/state"),
+        ]);
+        assert_eq!(dirs.config.path, PathBuf::from("/home/u/.config/wsl-clip"));
+        assert_eq!(dirs.config.origin, Origin::Home);
+        assert_eq!(dirs.state.path, PathBuf::from("/override/state"));
+        assert_eq!(dirs.cache.path, PathBuf::from("/xdg/cache/wsl-clip"));
+        // Relative XDG values are ignored
+        assert_eq!(
+            dirs.data.path,
+            PathBuf::from("/home/u/.local/share/wsl-clip")
+        );
+    }
+    #[test]
+    fn test_home_unset_uses_ephemeral_temp_root() {
+        for dirs in [resolve(&[]), resolve(&[("HOME", "/")])] {
+            assert_eq!(dirs.data.origin, Origin::Temp);
+            assert!(dirs.data.path.starts_with(std::env::temp_dir()));
+            assert!(dirs.data.persistent().is_err());
+            assert!(dirs.describe().contains("(temp fallback, "));
+        }
+    }
+    #[test]
+    fn test_unwritable_root_is_detected() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let blocker = tmp.path().join("not-a-dir");
+        std::fs::write(&blocker, b"")?;
+        let dirs = resolve(&[
+            (
+                "WSL_CLIP_DATA_DIR",
+                blocker.join("history").to_str().unwrap(),
+            ),
+            (
+                "WSL_CLIP_STATE_DIR",
+                tmp.path().join("a/b").to_str().unwrap(),
+            ),
+        ]);
+        assert!(!dirs.data.is_writable());
+        assert!(dirs.data.persistent().is_err());
+        // Missing but creatable roots count as writable, and are not created
+        assert!(dirs.state.is_writable());
+        assert!(!tmp.path().join("a").exists());
+        Ok(())
+    }
+}
+
+// <FILE>src/dirs.rs</FILE> - <DESC>Per-user directory resolution</DESC>
+// <VERS>END OF VERSION: 1.2.0 - 2026-10-16T14:49:53Z</VERS>
@@ -0,0 +1,504 @@
+// <FILE>src/encoding.rs</FILE> - <DESC>Streaming text encodings for the clipboard tools</DESC>
+// <VERS>VERSION: 1.3.0 - 2026-10-16T14:25:44Z</VERS>
+// <WCTX>clip.exe and --encoding</WCTX>
+// <CLOG>clip.exe always gets UTF-16LE; --encoding windows-1252 is mapped first, then decoded back</CLOG>
+
+//! clip.exe reads its stdin in the console's OEM code page, so UTF-8 text
+//! arrives as mojibake. Input that starts with a UTF-16LE byte order mark is
+//! taken as Unicode instead; `EncodingWriter` produces that (or, with
+//! `--encoding`, another encoding) a chunk at a time, so a streamed copy is
+//! never buffered whole. On the way in, `DecodingReader` turns UTF-16 and
+//! BOM-prefixed files (Notepad's "Unicode", PowerShell transcripts) into UTF-8.
+use encoding_rs::{Decoder, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+const BOM: [u8; 2] = [0xFF, 0xFE];
+/// `--encoding`: the bytes a text copy hands to the clipboard tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TextEncoding {
+    /// UTF-8, as read
+    #[value(name = "utf8")]
+    Utf8,
+    /// UTF-16LE behind a byte order mark
+    #[value(name = "utf16le")]
+    Utf16le,
+    /// Windows-1252 (Western European "ANSI")
+    #[value(name = "windows-1252")]
+    Windows1252,
+}
+impl TextEncoding {
+    pub fn label(self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16le => "UTF-16LE",
+            TextEncoding::Windows1252 => "Windows-1252",
+        }
+    }
+}
+/// A character `--strict-encoding` refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unmappable {
+    pub c: char,
+    pub encoding: TextEncoding,
+}
+impl fmt::Display for Unmappable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' (U+{:04X}) has no {} encoding (without --strict-encoding it becomes '?')",
+            self.c,
+            self.c as u32,
+            self.encoding.label()
+        )
+    }
+}
+impl std::error::Error for Unmappable {}
+/// Finds a refused character anywhere in an error chain.
+pub fn unmappable(err: &anyhow::Error) -> Option<Unmappable> {
+    err.chain().find_map(|e| {
+        e.downcast_ref::<io::Error>()
+            .and_then(|io| io.get_ref())
+            .and_then(|inner| inner.downcast_ref::<Unmappable>())
+            .copied()
+    })
+}
+/// Transcodes the UTF-8 written to it. Invalid UTF-8 becomes U+FFFD.
+/// Characters Windows-1252 lacks become `?`, or, with `strict`, fail the
+/// write. Nothing at all is written for empty input, so an empty copy still
+/// clears the clipboard.
+pub struct EncodingWriter<W: Write> {
+    inner: W,
+    encoding: TextEncoding,
+    strict: bool,
+    /// The start of a character split across writes (at most 3 bytes)
+    pending: Vec<u8>,
+    started: bool,
+}
+impl<W: Write> EncodingWriter<W> {
+    pub fn new(inner: W, encoding: TextEncoding, strict: bool) -> Self {
+        Self {
+            inner,
+            encoding,
+            strict,
+            pending: Vec::new(),
+            started: false,
+        }
+    }
+    fn emit(&mut self, text: &str) -> io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let mut out = Vec::with_capacity(text.len() * 2 + BOM.len());
+        match self.encoding {
+            TextEncoding::Utf8 => out.extend_from_slice(text.as_bytes()),
+            TextEncoding::Utf16le => {
+                if !self.started {
+                    out.extend_from_slice(&BOM);
+                }
+                out.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            }
+            TextEncoding::Windows1252 => {
+                for c in text.chars() {
+                    out.push(windows_1252(c, self.strict)?);
+                }
+            }
+        }
+        self.started = true;
+        self.inner.write_all(&out)
+    }
+    /// Writes a character left incomplete at the end of the input, as
+    /// U+FFFD. Dropping the writer does the same, ignoring errors.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.pending.clear();
+        self.emit("\u{FFFD}")
+    }
+}
+/// One character in Windows-1252: `?` (or, `strict`, an error) if it has none.
+fn windows_1252(c: char, strict: bool) -> io::Result<u8> {
+    if c.is_ascii() {
+        return Ok(c as u8);
+    }
+    let mut buf = [0; 4];
+    let (bytes, _, unmappable) = encoding_rs::WINDOWS_1252.encode(c.encode_utf8(&mut buf));
+    match (unmappable, bytes.first()) {
+        (false, Some(&b)) => Ok(b),
+        _ if strict => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            Unmappable {
+                c,
+                encoding: TextEncoding::Windows1252,
+            },
+        )),
+        _ => Ok(b'?'),
+    }
+}
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(buf);
+        let mut rest = &bytes[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    self.emit(text)?;
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    self.emit(std::str::from_utf8(valid).unwrap_or_default())?;
+                    match e.error_len() {
+                        Some(bad) => {
+                            self.emit("\u{FFFD}")?;
+                            rest = &after[bad..];
+                        }
+                        None => {
+                            // Cut off mid-character: wait for the rest
+                            self.pending = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+/// Windows-1252 bytes back to UTF-8 for `inner`. clip.exe reads only its
+/// OEM code page or UTF-16LE, so `--encoding windows-1252` text, already
+/// narrowed to what 1252 holds, reaches it as Unicode through this and an
+/// `EncodingWriter`. Every byte is one character, so chunks need no care.
+pub struct Windows1252Decoder<W: Write> {
+    inner: W,
+}
+impl<W: Write> Windows1252Decoder<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+}
+impl<W: Write> Write for Windows1252Decoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let (text, _) = encoding_rs::WINDOWS_1252.decode_without_bom_handling(buf);
+        self.inner.write_all(text.as_bytes())?;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+/// BOM-less UTF-16: mostly-ASCII text leaves a NUL in every other byte, on
+/// the high side of each code unit. Every unit must also be a printable
+/// character or whitespace, so binary data with scattered NULs stays binary.
+pub fn sniff_utf16(head: &[u8]) -> Option<&'static Encoding> {
+    let units = head.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let pairs = head.chunks_exact(2);
+    let even = pairs.clone().filter(|p| p[0] == 0).count();
+    let odd = pairs.clone().filter(|p| p[1] == 0).count();
+    let encoding = if odd * 2 >= units && even * 8 < units {
+        UTF_16LE
+    } else if even * 2 >= units && odd * 8 < units {
+        UTF_16BE
+    } else {
+        return None;
+    };
+    let text = pairs
+        .map(|p| {
+            if encoding == UTF_16LE {
+                u16::from_le_bytes([p[0], p[1]])
+            } else {
+                u16::from_be_bytes([p[0], p[1]])
+            }
+        })
+        .all(|u| u >= 0x20 || matches!(u, 0x09 | 0x0A | 0x0C | 0x0D));
+    text.then_some(encoding)
+}
+/// How much of the input `DecodingReader` looks at to pick the encoding.
+const SNIFF_LEN: usize = 256;
+/// Reads text as UTF-8 whatever it was stored as: the encoding a byte order
+/// mark names, BOM-less UTF-16 (`sniff_utf16`), else UTF-8. A BOM is
+/// dropped; malformed sequences become U+FFFD and set `replaced`.
+pub struct DecodingReader<R: BufRead> {
+    inner: R,
+    decoder: Decoder,
+    /// The bytes read ahead to pick the encoding, not yet decoded
+    head: Vec<u8>,
+    out: Vec<u8>,
+    pos: usize,
+    done: bool,
+    /// Some input could not be decoded
+    pub replaced: bool,
+}
+impl<R: BufRead> DecodingReader<R> {
+    /// Reads ahead up to `SNIFF_LEN` bytes to pick the encoding.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        while head.len() < SNIFF_LEN {
+            let buf = inner.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let n = buf.len().min(SNIFF_LEN - head.len());
+            head.extend_from_slice(&buf[..n]);
+            inner.consume(n);
+        }
+        // BOM sniffing is the decoder's own; BOM-less UTF-16 has to be asked for
+        let bom = Encoding::for_bom(&head).is_some();
+        let decoder = match sniff_utf16(&head).filter(|_| !bom) {
+            Some(encoding) => encoding.new_decoder_without_bom_handling(),
+            None => UTF_8.new_decoder(),
+        };
+        Ok(Self {
+            inner,
+            decoder,
+            head,
+            out: Vec::new(),
+            pos: 0,
+            done: false,
+            replaced: false,
+        })
+    }
+    /// The encoding being decoded (settled once the BOM, if any, was read).
+    pub fn encoding(&self) -> &'static Encoding {
+        self.decoder.encoding()
+    }
+}
+impl<R: BufRead> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.out.len() {
+            if self.done {
+                return Ok(0);
+            }
+            let head = !self.head.is_empty();
+            let src = if head {
+                &self.head[..]
+            } else {
+                self.inner.fill_buf()?
+            };
+            let last = src.is_empty();
+            let capacity = self
+                .decoder
+                .max_utf8_buffer_length(src.len())
+                .ok_or_else(|| io::Error::other("input chunk too large to decode"))?;
+            self.out.resize(capacity, 0);
+            let (_, read, written, replaced) =
+                self.decoder.decode_to_utf8(src, &mut self.out, last);
+            if head {
+                self.head.drain(..read);
+            } else {
+                self.inner.consume(read);
+            }
+            self.out.truncate(written);
+            self.pos = 0;
+            self.replaced |= replaced;
+            self.done = last;
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+/// The warning for input that needed U+FFFD replacements.
+pub fn replaced_warning(source: &str, encoding: &'static Encoding) -> String {
+    format!(
+        "{} is not entirely valid {}: undecodable bytes were replaced with U+FFFD",
+        source,
+        encoding.name()
+    )
+}
+impl<W: Write> Drop for EncodingWriter<W> {
+    /// Streams end by dropping their writer; a dangling partial character
+    /// still shows up as U+FFFD rather than vanishing.
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn encode_as(encoding: TextEncoding, strict: bool, chunks: &[&[u8]]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut writer = EncodingWriter::new(&mut out, encoding, strict);
+        for chunk in chunks {
+            writer.write_all(chunk)?;
+        }
+        writer.finish()?;
+        drop(writer);
+        Ok(out)
+    }
+    fn encode(chunks: &[&[u8]]) -> Vec<u8> {
+        encode_as(TextEncoding::Utf16le, false, chunks).unwrap()
+    }
+    #[test]
+    fn test_exact_bytes() {
+        assert_eq!(encode(&[b"hi\n"]), [0xFF, 0xFE, b'h', 0, b'i', 0, b'\n', 0]);
+        // U+1F980 (crab) is a surrogate pair
+        assert_eq!(
+            encode(&["\u{1F980}".as_bytes()]),
+            [0xFF, 0xFE, 0x3E, 0xD8, 0x80, 0xDD]
+        );
+        // CJK: U+4E2D U+6587
+        assert_eq!(
+            encode(&["中文".as_bytes()]),
+            [0xFF, 0xFE, 0x2D, 0x4E, 0x87, 0x65]
+        );
+        assert_eq!(
+            encode(&["café".as_bytes()]),
+            [0xFF, 0xFE, b'c', 0, b'a', 0, b'f', 0, 0xE9, 0]
+        );
+        // No input, no BOM
+        assert_eq!(encode(&[]), Vec::<u8>::new());
+        assert_eq!(encode(&[b""]), Vec::<u8>::new());
+    }
+    #[test]
+    fn test_characters_split_across_writes() {
+        let whole = encode(&["a\u{1F980}中".as_bytes()]);
+        let bytes = "a\u{1F980}中".as_bytes();
+        for cut in 0..bytes.len() {
+            assert_eq!(
+                encode(&[&bytes[..cut], &bytes[cut..]]),
+                whole,
+                "cut at {}",
+                cut
+            );
+        }
+        // One byte at a time
+        let singles: Vec<&[u8]> = bytes.chunks(1).collect();
+        assert_eq!(encode(&singles), whole);
+    }
+    #[test]
+    fn test_invalid_utf8() {
+        assert_eq!(
+            encode(&[b"a\xFFb"]),
+            [0xFF, 0xFE, b'a', 0, 0xFD, 0xFF, b'b', 0]
+        );
+        // Truncated at the very end
+        assert_eq!(encode(&[b"a\xE4\xB8"]), [0xFF, 0xFE, b'a', 0, 0xFD, 0xFF]);
+        // Or dropped without finish, as a stream's pipe is
+        let mut out = Vec::new();
+        let mut writer = EncodingWriter::new(&mut out, TextEncoding::Utf16le, false);
+        writer.write_all(b"\xE4").unwrap();
+        drop(writer);
+        assert_eq!(out, [0xFF, 0xFE, 0xFD, 0xFF]);
+    }
+    fn utf16(text: &str, be: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|u| if be { u.to_be_bytes() } else { u.to_le_bytes() })
+            .collect()
+    }
+    /// Decodes `bytes` read `chunk` bytes at a time.
+    fn decode(bytes: &[u8], chunk: usize) -> (String, &'static Encoding, bool) {
+        let mut reader = DecodingReader::new(io::BufReader::with_capacity(chunk, bytes)).unwrap();
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        (out, reader.encoding(), reader.replaced)
+    }
+    #[test]
+    fn test_sniff_utf16() {
+        assert_eq!(
+            sniff_utf16(&utf16("PS C:\\> dir\r\n", false)),
+            Some(UTF_16LE)
+        );
+        assert_eq!(sniff_utf16(&utf16("Transcript\n", true)), Some(UTF_16BE));
+        // Some non-ASCII is fine as long as most units are ASCII
+        assert_eq!(sniff_utf16(&utf16("naïve café", false)), Some(UTF_16LE));
+        for binary in [
+            &b"plain ascii"[..],
+            &[0x00, 0x01, 0x02],
+            &[b'a', 0, 0x01, 0, b'b', 0],
+            &[0x4D, 0x5A, 0x90, 0x00, 0x03, 0x00, 0x00, 0x00],
+        ] {
+            assert_eq!(sniff_utf16(binary), None, "{:?}", binary);
+        }
+    }
+    #[test]
+    fn test_decoding_reader() {
+        let text = "héllo\r\nwörld \u{1F980}\n";
+        let mut le = vec![0xFF, 0xFE];
+        le.extend(utf16(text, false));
+        let mut be = vec![0xFE, 0xFF];
+        be.extend(utf16(text, true));
+        let mut utf8 = vec![0xEF, 0xBB, 0xBF];
+        utf8.extend(text.as_bytes());
+        for (bytes, encoding) in [
+            (le, UTF_16LE),
+            (be, UTF_16BE),
+            (utf16(text, false), UTF_16LE),
+            (utf8, UTF_8),
+            (text.as_bytes().to_vec(), UTF_8),
+        ] {
+            // Odd chunk sizes split BOMs, units and surrogate pairs
+            for chunk in [3, 8192] {
+                assert_eq!(decode(&bytes, chunk), (text.to_string(), encoding, false));
+            }
+        }
+    }
+    #[test]
+    fn test_decoding_is_lossy() {
+        assert_eq!(
+            decode(b"ok \xFF then \xE4\xB8", 4),
+            ("ok \u{FFFD} then \u{FFFD}".to_string(), UTF_8, true)
+        );
+        // A lone surrogate, and an odd byte at the end
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(utf16("a", false));
+        bytes.extend([0x00, 0xD8, b'b', 0, b'c']);
+        assert_eq!(
+            decode(&bytes, 8192),
+            ("a\u{FFFD}b\u{FFFD}".to_string(), UTF_16LE, true)
+        );
+        assert_eq!(decode(b"", 8192), (String::new(), UTF_8, false));
+    }
+    #[test]
+    fn test_windows_1252() {
+        let cp1252 =
+            |text: &str, strict| encode_as(TextEncoding::Windows1252, strict, &[text.as_bytes()]);
+        // é is 0xE9; € and “” sit in the 0x80-0x9F block Latin-1 lacks
+        assert_eq!(
+            cp1252("café €5 “q”\n", true).unwrap(),
+            b"caf\xE9 \x805 \x93q\x94\n"
+        );
+        assert_eq!(cp1252("naïve 🦀 中", false).unwrap(), b"na\xEFve ? ?");
+        let err = cp1252("ok ✓", true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err
+            .to_string()
+            .starts_with("'✓' (U+2713) has no Windows-1252 encoding"));
+        let err = anyhow::Error::new(err).context("Failed to copy");
+        assert_eq!(
+            unmappable(&err),
+            Some(Unmappable {
+                c: '✓',
+                encoding: TextEncoding::Windows1252
+            })
+        );
+        assert_eq!(unmappable(&anyhow::anyhow!("other")), None);
+        // Split characters still come out whole
+        assert_eq!(
+            encode_as(TextEncoding::Windows1252, true, &[b"caf\xC3", b"\xA9"]).unwrap(),
+            b"caf\xE9"
+        );
+        // ...and decode back to the same text
+        let mut out = Vec::new();
+        Windows1252Decoder::new(&mut out)
+            .write_all(b"caf\xE9 \x805 ?")
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "café €5 ?");
+        // UTF-8 passes through, repairing only invalid bytes
+        assert_eq!(
+            encode_as(TextEncoding::Utf8, true, &[b"\xF0\x9F", b"\xA6\x80\xFF"]).unwrap(),
+            "\u{1F980}\u{FFFD}".as_bytes()
+        );
+    }
+}
+
+// <FILE>src/encoding.rs</FILE> - <DESC>Streaming text encodings for the clipboard tools</DESC>
+// <VERS>END OF VERSION: 1.3.0 - 2026-10-16T14:25:44Z</VERS>
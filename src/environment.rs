@@ -0,0 +1,264 @@
+// <FILE>src/environment.rs</FILE> - <DESC>Runtime environment probes</DESC>
+// <VERS>VERSION: 1.5.0 - 2026-10-16T13:07:01Z</VERS>
+// <WCTX>Convert drive paths without spawning wslpath</WCTX>
+// <CLOG>Pure-Rust conversion for paths under the automount root</CLOG>
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+/// Where wsl-clip is running, as far as Windows interop is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    /// WSL1: interop works, but Linux-filesystem files have no Windows (UNC) path
+    Wsl1,
+    /// WSL2: Linux files are reachable from Windows as \\wsl.localhost\...
+    Wsl2,
+    /// Plain Linux or a container: only native desktop tools are available
+    Linux,
+}
+impl Platform {
+    pub fn is_wsl(&self) -> bool {
+        matches!(self, Platform::Wsl1 | Platform::Wsl2)
+    }
+}
+/// Raw probe results, separated from the probing so tests can inject them.
+#[derive(Debug, Clone, Default)]
+pub struct Signals {
+    /// Contents of /proc/sys/kernel/osrelease
+    pub osrelease: String,
+    /// WSL_DISTRO_NAME or WSL_INTEROP is set
+    pub wsl_env: bool,
+    /// The WSLInterop binfmt handler is registered
+    pub interop: bool,
+}
+/// Interop evidence is required: a WSL2 kernel alone also runs Docker
+/// containers, which cannot reach clip.exe.
+pub fn classify(signals: &Signals) -> Platform {
+    if !(signals.wsl_env || signals.interop) {
+        return Platform::Linux;
+    }
+    let release = signals.osrelease.to_ascii_lowercase();
+    // WSL1 reports the Windows build, e.g. "4.4.0-19041-Microsoft"
+    if release.contains("microsoft")
+        && !release.contains("microsoft-standard")
+        && !release.contains("wsl2")
+    {
+        Platform::Wsl1
+    } else {
+        Platform::Wsl2
+    }
+}
+/// `/proc`, or `$WSL_CLIP_PROC_ROOT` so the end-to-end tests can fake a WSL kernel.
+fn proc_root() -> PathBuf {
+    std::env::var_os("WSL_CLIP_PROC_ROOT")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/proc"))
+}
+/// Mount points of Windows drives, from /proc/self/mounts: `drvfs` (WSL1)
+/// or 9p with `aname=drvfs` (WSL2). None when the table cannot be read.
+pub fn drvfs_mounts() -> Option<Vec<PathBuf>> {
+    let table = std::fs::read_to_string(proc_root().join("self/mounts")).ok()?;
+    Some(parse_drvfs_mounts(&table))
+}
+pub fn parse_drvfs_mounts(table: &str) -> Vec<PathBuf> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let (_, point, fstype, options) = (
+                fields.next()?,
+                fields.next()?,
+                fields.next()?,
+                fields.next()?,
+            );
+            let drvfs = fstype == "drvfs"
+                || (fstype == "9p" && options.split([',', ';']).any(|o| o == "aname=drvfs"));
+            drvfs.then(|| PathBuf::from(unescape_mount_field(point)))
+        })
+        .collect()
+}
+/// The kernel writes space, tab, newline and backslash in mount fields as
+/// `\040`, `\011`, `\012` and `\134`.
+fn unescape_mount_field(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let code = rest
+            .get(i + 1..i + 4)
+            .and_then(|o| u8::from_str_radix(o, 8).ok());
+        match code {
+            Some(byte) => {
+                out.push(byte as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+pub fn probe() -> Signals {
+    let proc = proc_root();
+    let binfmt = proc.join("sys/fs/binfmt_misc");
+    Signals {
+        osrelease: std::fs::read_to_string(proc.join("sys/kernel/osrelease")).unwrap_or_default(),
+        wsl_env: std::env::var_os("WSL_DISTRO_NAME").is_some()
+            || std::env::var_os("WSL_INTEROP").is_some(),
+        interop: binfmt.join("WSLInterop").exists() || binfmt.join("WSLInterop-late").exists(),
+    }
+}
+pub fn detect() -> Platform {
+    classify(&probe())
+}
+/// The platform, detected once per process.
+pub fn current() -> Platform {
+    static PLATFORM: OnceLock<Platform> = OnceLock::new();
+    *PLATFORM.get_or_init(detect)
+}
+/// Overrides the drive mount root (otherwise `[automount] root` in
+/// /etc/wsl.conf, else `/mnt/`).
+pub const AUTOMOUNT_ROOT_ENV: &str = "WSL_CLIP_AUTOMOUNT_ROOT";
+/// Where Windows drives are mounted (`/mnt` holds `c`, `d`, ...), resolved once per process.
+pub fn automount_root() -> &'static Path {
+    static ROOT: OnceLock<PathBuf> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        std::env::var_os(AUTOMOUNT_ROOT_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                let conf = std::fs::read_to_string("/etc/wsl.conf").ok()?;
+                parse_automount_root(&conf)
+            })
+            .unwrap_or_else(|| PathBuf::from("/mnt"))
+    })
+}
+/// `root` under `[automount]` in a wsl.conf (`root = /windows/`).
+pub fn parse_automount_root(conf: &str) -> Option<PathBuf> {
+    let mut in_automount = false;
+    let mut root = None;
+    for line in conf.lines() {
+        let line = line.split(['#', ';']).next().unwrap_or("").trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_automount = section.trim().eq_ignore_ascii_case("automount");
+        } else if let Some((key, value)) = line.split_once('=') {
+            if in_automount && key.trim().eq_ignore_ascii_case("root") {
+                let value = value.trim().trim_matches('"').trim();
+                if value.starts_with('/') {
+                    root = Some(PathBuf::from(value));
+                }
+            }
+        }
+    }
+    root
+}
+/// Remediation for a failed Windows executable launch.
+pub fn interop_hint(platform: Platform) -> &'static str {
+    match platform {
+        Platform::Linux => {
+            "Windows interop is unavailable: plain Linux and containers have no clip.exe (install wl-clipboard or xclip, or pick one with --backend)"
+        }
+        Platform::Wsl1 | Platform::Wsl2 => {
+            "Windows interop looks disabled: check /proc/sys/fs/binfmt_misc/WSLInterop and [interop] in /etc/wsl.conf"
+        }
+    }
+}
+/// True for files on a Windows drive mount (/mnt/c/...), which have a
+/// native Windows path on every WSL version.
+pub fn is_windows_drive_path(path: &Path) -> bool {
+    let mut parts = path.components().map(|c| c.as_os_str().to_string_lossy());
+    matches!(
+        (parts.next().as_deref(), parts.next().as_deref(), parts.next()),
+        (Some("/"), Some("mnt"), Some(drive)) if drive.len() == 1 && drive.chars().all(|c| c.is_ascii_alphabetic())
+    )
+}
+/// Early error for Linux-filesystem file objects on WSL1, where Windows
+/// has no path to them.
+pub fn object_path_error(platform: Platform, path: &Path) -> Option<String> {
+    if platform != Platform::Wsl1 || is_windows_drive_path(path) {
+        return None;
+    }
+    Some(format!(
+        "{} is on the Linux filesystem, which WSL1 does not expose to Windows (no \\\\wsl$ path exists).\n  \
+         Copy it from a /mnt/<drive> location, or use --stage to copy it into the Windows temp folder first.",
+        path.display()
+    ))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn signals(osrelease: &str, wsl_env: bool, interop: bool) -> Signals {
+        Signals {
+            osrelease: osrelease.to_string(),
+            wsl_env,
+            interop,
+        }
+    }
+    #[test]
+    fn test_classify() {
+        let wsl2 = "5.15.153.1-microsoft-standard-WSL2\n";
+        assert_eq!(classify(&signals(wsl2, true, true)), Platform::Wsl2);
+        assert_eq!(
+            classify(&signals("4.4.0-19041-Microsoft\n", true, true)),
+            Platform::Wsl1
+        );
+        // Custom WSL2 kernels may drop the suffix; interop still identifies WSL
+        assert_eq!(
+            classify(&signals("6.6.0-custom", false, true)),
+            Platform::Wsl2
+        );
+        // Containers on a WSL2 kernel have neither the env nor interop
+        assert_eq!(classify(&signals(wsl2, false, false)), Platform::Linux);
+        assert_eq!(
+            classify(&signals("6.8.0-45-generic", false, false)),
+            Platform::Linux
+        );
+    }
+    #[test]
+    fn test_wsl1_object_paths() {
+        let linux_fs = Path::new("/home/me/report.pdf");
+        let drive = Path::new("/mnt/c/Users/me/report.pdf");
+        let err = object_path_error(Platform::Wsl1, linux_fs).unwrap();
+        assert!(err.contains("WSL1") && err.contains("--stage"));
+        assert!(object_path_error(Platform::Wsl1, drive).is_none());
+        assert!(object_path_error(Platform::Wsl2, linux_fs).is_none());
+        assert!(!is_windows_drive_path(Path::new("/mnt/wsl/shared")));
+        assert!(interop_hint(Platform::Linux).contains("containers"));
+        assert!(interop_hint(Platform::Wsl1).contains("WSLInterop"));
+    }
+    #[test]
+    fn test_automount_root() {
+        let conf =
+            "[boot]\nsystemd=true\n\n[automount]\nenabled = true\nroot = \"/windows/\" # drives\n";
+        assert_eq!(parse_automount_root(conf), Some(PathBuf::from("/windows/")));
+        assert_eq!(parse_automount_root("[network]\nroot = /x/\n"), None);
+        assert_eq!(
+            parse_automount_root("[automount]\noptions = metadata\n"),
+            None
+        );
+    }
+    #[test]
+    fn test_drvfs_mounts() {
+        let table = "/dev/sdc / ext4 rw,relatime 0 0\n\
+            C:\\134 /mnt/c 9p rw,noatime,aname=drvfs;path=C:\\;uid=1000;symlinkroot=/mnt/ 0 0\n\
+            drvfs /mnt/d drvfs rw,noatime 0 0\n\
+            none /mnt/wsl tmpfs rw,relatime 0 0\n\
+            E:\\134 /media/my\\040drive 9p rw,aname=drvfs;path=E:\\ 0 0\n\
+            tools /opt/tools 9p rw,aname=tools 0 0\n";
+        assert_eq!(
+            parse_drvfs_mounts(table),
+            [
+                PathBuf::from("/mnt/c"),
+                PathBuf::from("/mnt/d"),
+                PathBuf::from("/media/my drive")
+            ]
+        );
+        assert_eq!(unescape_mount_field(r"a\134b\x"), r"a\b\x");
+    }
+}
+
+// <FILE>src/environment.rs</FILE> - <DESC>Runtime environment probes</DESC>
+// <VERS>END OF VERSION: 1.5.0 - 2026-10-16T13:07:01Z</VERS>
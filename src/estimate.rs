@@ -0,0 +1,331 @@
+// <FILE>src/estimate.rs</FILE> - <DESC>Shared input/output size estimation</DESC>
+// <VERS>VERSION: 1.7.0 - 2026-10-16T14:08:16Z</VERS>
+// <WCTX>FIFOs and process substitution as text inputs.</WCTX>
+// <CLOG>Pipes and character devices are streamed unsniffed; sockets and directories are skipped with a warning.</CLOG>
+
+use crate::sanitizer::ControlSet;
+use crate::snapshot::FileState;
+use crate::text_processor::{self, SentFile, SkippedFile, TextOptions};
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+/// How far an estimate can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confidence {
+    /// Byte-for-byte what the pipeline will emit
+    Exact,
+    /// Content transforms (ANSI stripping, line joining) make the size data-dependent
+    Approximate,
+    /// Stdin/FIFO: nothing is known until the stream is consumed
+    Unknown,
+}
+/// Input and output size prediction shared by every size-reporting feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    /// Raw bytes on disk
+    pub input_bytes: u64,
+    /// Bytes the pipeline emits at minimum (structure + line terminators)
+    pub lower: u64,
+    /// Best prediction of emitted bytes
+    pub expected: u64,
+    pub confidence: Confidence,
+}
+/// Byte-level census of a file, gathered in one read pass without allocation.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Scan {
+    size: u64,
+    newlines: u64,
+    crlf_pairs: u64,
+    /// Control bytes the sanitizer removes one-for-one (excludes \t, \n, CR of CRLF, ESC)
+    controls: u64,
+    escapes: u64,
+    last: Option<u8>,
+}
+impl Scan {
+    fn lines(&self) -> u64 {
+        match self.last {
+            None => 0,
+            Some(b'\n') => self.newlines,
+            Some(_) => self.newlines + 1,
+        }
+    }
+}
+fn scan(path: &Path) -> Result<Scan> {
+    let mut file = File::open(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+    let mut buf = [0u8; 64 * 1024];
+    let mut s = Scan::default();
+    let mut pending_cr = false;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for &b in &buf[..n] {
+            if pending_cr {
+                pending_cr = false;
+                if b == b'\n' {
+                    s.crlf_pairs += 1;
+                } else {
+                    s.controls += 1;
+                }
+            }
+            match b {
+                b'\n' => s.newlines += 1,
+                b'\r' => pending_cr = true,
+                b'\t' => {}
+                0x1b => s.escapes += 1,
+                0x00..=0x1f | 0x7f => s.controls += 1,
+                _ => {}
+            }
+        }
+        s.size += n as u64;
+        s.last = Some(buf[n - 1]);
+    }
+    if pending_cr {
+        // lines() only strips "\r\n", so a final bare CR is content
+        s.controls += 1;
+    }
+    Ok(s)
+}
+/// Predicts what `process_input` will emit for these inputs and options.
+pub fn estimate(files: Option<&[PathBuf]>, opts: &TextOptions) -> Result<Estimate> {
+    let Some(files) = files.filter(|f| !f.is_empty()) else {
+        return Ok(Estimate {
+            input_bytes: 0,
+            lower: 0,
+            expected: 0,
+            confidence: Confidence::Unknown,
+        });
+    };
+    let eol = if opts.use_crlf { 2 } else { 1 };
+    // Structural text is emitted LF-first and converted, one extra byte per line under CRLF
+    let structural = |text: &str| -> u64 {
+        let lf = text.matches('\n').count() as u64;
+        text.len() as u64 + if opts.use_crlf { lf } else { 0 }
+    };
+    let timestamp = opts.timestamp.render();
+    let mut ordered = files.to_vec();
+    text_processor::order_files(&mut ordered, opts);
+    let mut confidence = Confidence::Exact;
+    let mut input_bytes = 0;
+    let mut lower = 0;
+    let mut expected = 0;
+    let mut sent = Vec::new();
+    let mut skipped = Vec::new();
+    let mut joined_lines = 0;
+    let mut streamed = false;
+    for path in &ordered {
+        if text_processor::is_stdin(path) || text_processor::is_stream(path) {
+            // Read once, by the pipeline itself
+            streamed = true;
+            sent.push(SentFile {
+                display: path.to_string_lossy().to_string(),
+                lines: 0,
+                bytes: 0,
+            });
+            continue;
+        }
+        if let Some(reason) = text_processor::skip_reason(path, opts) {
+            skipped.push(SkippedFile {
+                display: path.to_string_lossy().to_string(),
+                reason: reason.to_string(),
+            });
+            continue;
+        }
+        let s = scan(path)?;
+        input_bytes += s.size;
+        let lines = s.lines();
+        // lines() drops "\n" / "\r\n"; write_line re-adds one terminator per line
+        let mut content = s.size - s.newlines - s.crlf_pairs;
+        let mut content_lower = content;
+        if opts.strip_ansi {
+            content -= s.controls + s.escapes;
+            content_lower = content;
+            if s.escapes > 0 {
+                // Regex-dependent: an ANSI sequence may swallow any amount of the line
+                content_lower = 0;
+                confidence = Confidence::Approximate;
+            }
+        }
+        // --grep and --lines drop lines and --jsonl-pretty adds them; none is knowable from a
+        // byte census. A custom control set keeps some of the counted controls.
+        let reshaped = opts.grep.is_some()
+            || opts.jsonl_pretty
+            || opts.lines.is_some()
+            || opts.controls != ControlSet::default();
+        // A --cite line depends on git and the emitted range; it is not counted
+        if reshaped || opts.cite.is_some() {
+            confidence = Confidence::Approximate;
+        }
+        let file_bytes = content + lines * eol;
+        let mut overhead = 0;
+        if !opts.no_header {
+            let state = FileState::of(path)?;
+            overhead += structural(&text_processor::file_header(
+                path,
+                timestamp.as_deref(),
+                text_processor::header_state(&state, opts).as_ref(),
+            ));
+            overhead += eol; // spacer
+        }
+        if opts.use_markdown {
+            overhead += structural(&text_processor::fence_open(path));
+            overhead += structural(text_processor::FENCE_CLOSE);
+        }
+        lower += overhead;
+        if !reshaped {
+            lower += content_lower + lines * eol;
+        }
+        expected += overhead + file_bytes;
+        joined_lines += lines;
+        sent.push(SentFile {
+            display: path.to_string_lossy().to_string(),
+            lines,
+            bytes: file_bytes,
+        });
+    }
+    if !opts.no_header && ordered.len() > 1 {
+        let footer = structural(&text_processor::build_footer(
+            opts.footer_style,
+            &sent,
+            &skipped,
+        ));
+        if confidence == Confidence::Exact {
+            lower += footer;
+        } else {
+            // Footer byte counts shrink with the content; zero is the shortest rendering
+            let floor: Vec<SentFile> = sent
+                .iter()
+                .map(|f| SentFile {
+                    bytes: 0,
+                    ..f.clone()
+                })
+                .collect();
+            lower += structural(&text_processor::build_footer(
+                opts.footer_style,
+                &floor,
+                &skipped,
+            ));
+        }
+        expected += footer;
+    }
+    if let Some(sep) = &opts.single_line {
+        // Headers, fences and terminators vanish; blank lines are dropped
+        let content: u64 = sent.iter().map(|f| f.bytes - f.lines * eol).sum();
+        expected = content + sep.len() as u64 * joined_lines.saturating_sub(1);
+        lower = 0;
+        if joined_lines > 0 {
+            confidence = Confidence::Approximate;
+        }
+    }
+    if streamed {
+        confidence = Confidence::Unknown;
+    }
+    Ok(Estimate {
+        input_bytes,
+        lower: lower.min(expected),
+        expected,
+        confidence,
+    })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_processor::{process_input, FooterStyle};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+    fn fixture(content: &[u8]) -> NamedTempFile {
+        let mut f = tempfile::Builder::new().suffix(".rs").tempfile().unwrap();
+        f.write_all(content).unwrap();
+        f
+    }
+    fn actual(files: &[PathBuf], opts: &TextOptions) -> u64 {
+        let mut out = Vec::new();
+        process_input(Some(files.to_vec()), opts, &mut out).unwrap();
+        out.len() as u64
+    }
+    #[test]
+    fn test_exact_for_plain_files_across_options() -> Result<()> {
+        let a = fixture(b"fn main() {}\n\tlet x = 1;\n");
+        let b = fixture(b"no trailing newline");
+        let c = fixture(b"windows\r\nline\r\nbell\x07 and bs\x08\n");
+        let empty = fixture(b"");
+        let files = vec![
+            a.path().to_path_buf(),
+            b.path().to_path_buf(),
+            c.path().to_path_buf(),
+            empty.path().to_path_buf(),
+            PathBuf::from("/nonexistent/estimate-missing.rs"),
+        ];
+        for no_header in [false, true] {
+            for use_markdown in [false, true] {
+                for use_crlf in [false, true] {
+                    for strip_ansi in [false, true] {
+                        for footer_style in [FooterStyle::Detailed, FooterStyle::Compact] {
+                            let opts = TextOptions {
+                                no_header,
+                                use_markdown,
+                                use_crlf,
+                                strip_ansi,
+                                footer_style,
+                                ..Default::default()
+                            };
+                            let est = estimate(Some(&files), &opts)?;
+                            assert_eq!(est.confidence, Confidence::Exact);
+                            assert_eq!(
+                                est.expected,
+                                actual(&files, &opts),
+                                "header={} md={} crlf={} strip={} footer={:?}",
+                                no_header,
+                                use_markdown,
+                                use_crlf,
+                                strip_ansi,
+                                footer_style
+                            );
+                            assert_eq!(est.lower, est.expected);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_ansi_is_approximate_with_valid_bounds() -> Result<()> {
+        let f = fixture(b"\x1b[31mred\x1b[0m text\n");
+        let files = vec![f.path().to_path_buf()];
+        let opts = TextOptions {
+            strip_ansi: true,
+            ..Default::default()
+        };
+        let est = estimate(Some(&files), &opts)?;
+        let real = actual(&files, &opts);
+        assert_eq!(est.confidence, Confidence::Approximate);
+        assert!(est.lower <= real && real <= est.expected);
+        // Raw mode keeps escapes, so the prediction is exact again
+        let raw = TextOptions::default();
+        let est = estimate(Some(&files), &raw)?;
+        assert_eq!(est.confidence, Confidence::Exact);
+        assert_eq!(est.expected, actual(&files, &raw));
+        Ok(())
+    }
+    #[test]
+    fn test_single_line_and_stdin() -> Result<()> {
+        let f = fixture(b"cd /tmp\nls -la\n");
+        let files = vec![f.path().to_path_buf()];
+        let opts = TextOptions {
+            single_line: Some(" && ".to_string()),
+            ..Default::default()
+        };
+        let est = estimate(Some(&files), &opts)?;
+        assert_eq!(est.confidence, Confidence::Approximate);
+        assert_eq!(est.expected, actual(&files, &opts));
+        let est = estimate(None, &TextOptions::default())?;
+        assert_eq!(est.confidence, Confidence::Unknown);
+        Ok(())
+    }
+}
+
+// <FILE>src/estimate.rs</FILE> - <DESC>Shared input/output size estimation</DESC>
+// <VERS>END OF VERSION: 1.7.0 - 2026-10-16T14:08:16Z</VERS>
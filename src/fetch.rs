@@ -0,0 +1,171 @@
+// <FILE>src/fetch.rs</FILE> - <DESC>HTTP downloads for `wsl-clip url`</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T13:40:00Z</VERS>
+// <WCTX>Copy a downloaded resource as text, image or file object.</WCTX>
+// <CLOG>Initial creation: size cap, timeout, redirects, name from the final URL.</CLOG>
+
+use anyhow::Result;
+use std::time::Duration;
+/// Largest download `url` accepts when --max-size is not given.
+pub const DEFAULT_URL_MAX_SIZE: u64 = 8 * 1024 * 1024;
+/// How long a download may take (connect plus body) unless --fetch-timeout says otherwise.
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+/// Redirects followed before giving up.
+pub const MAX_REDIRECTS: usize = 10;
+/// A downloaded body, fully in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Download {
+    pub bytes: Vec<u8>,
+    /// Content-Type without parameters, lowercased
+    pub content_type: Option<String>,
+    /// Last path segment of the final URL, if it makes a usable file name
+    pub name: Option<String>,
+}
+/// The bare media type of a Content-Type value (`text/html; charset=utf-8` -> `text/html`).
+pub fn media_type(raw: &str) -> Option<String> {
+    let media = raw.split(';').next()?.trim().to_ascii_lowercase();
+    (!media.is_empty()).then_some(media)
+}
+/// A file name from a URL path's last segment: percent-decoded, and only if
+/// it is a plain name (`/a/b/report.pdf` -> `report.pdf`; `/a/` -> None).
+pub fn name_from_path(path: &str) -> Option<String> {
+    let segment = path.rsplit('/').next()?;
+    let decoded = percent_decode(segment)?;
+    let plain = !decoded.is_empty()
+        && decoded != "."
+        && decoded != ".."
+        && !decoded.contains(['/', '\\', '\0']);
+    plain.then_some(decoded)
+}
+fn percent_decode(raw: &str) -> Option<String> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut bytes = raw.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let hex = [bytes.next()?, bytes.next()?];
+            out.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            out.push(b);
+        }
+    }
+    String::from_utf8(out).ok()
+}
+/// Downloads `url`, following redirects. Refuses bodies over `max_size`
+/// bytes (by Content-Length up front, else while reading) and reports
+/// 4xx/5xx answers with their status.
+#[cfg(feature = "url")]
+pub fn download(url: &str, max_size: u64, timeout: Duration) -> Result<Download> {
+    use crate::debug_logger::create_logger;
+    use anyhow::Context;
+    use std::io::Read;
+    let log = create_logger("fetch");
+    let client = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        .user_agent(concat!("wsl-clip/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("Failed to start the HTTP client")?;
+    let response = client
+        .get(url)
+        .send()
+        .map_err(|e| anyhow::anyhow!("Could not download {}: {}", url, describe(&e)))?;
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        anyhow::bail!(
+            "{} answered HTTP {} {}",
+            url,
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("")
+        );
+    }
+    if let Some(length) = response.content_length().filter(|&l| l > max_size) {
+        anyhow::bail!(
+            "{} is {} bytes, over the {} byte limit (raise it with --max-size)",
+            url,
+            length,
+            max_size
+        );
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(media_type);
+    let name = name_from_path(response.url().path());
+    log.debug(&format!(
+        "{} -> {} ({:?}, {:?})",
+        url,
+        response.url(),
+        content_type,
+        name
+    ));
+    let mut bytes = Vec::new();
+    response
+        .take(max_size + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| anyhow::anyhow!("Could not download {}: {}", url, e))?;
+    if bytes.len() as u64 > max_size {
+        anyhow::bail!(
+            "{} is over the {} byte limit (raise it with --max-size)",
+            url,
+            max_size
+        );
+    }
+    Ok(Download {
+        bytes,
+        content_type,
+        name,
+    })
+}
+/// reqwest's own message, plus what kind of failure it was.
+#[cfg(feature = "url")]
+fn describe(e: &reqwest::Error) -> String {
+    if e.is_timeout() {
+        "timed out (see --fetch-timeout)".to_string()
+    } else if e.is_redirect() {
+        format!("more than {} redirects", MAX_REDIRECTS)
+    } else if e.is_builder() {
+        format!("not a valid URL ({})", e)
+    } else {
+        // The source carries the DNS/TLS/connection detail
+        let mut message = e.to_string();
+        let mut source = std::error::Error::source(e);
+        while let Some(inner) = source {
+            message.push_str(&format!(": {}", inner));
+            source = inner.source();
+        }
+        message
+    }
+}
+#[cfg(not(feature = "url"))]
+pub fn download(_url: &str, _max_size: u64, _timeout: Duration) -> Result<Download> {
+    anyhow::bail!("this build cannot download (rebuild with the `url` feature)")
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_media_type() {
+        assert_eq!(
+            media_type("Text/HTML; charset=utf-8").as_deref(),
+            Some("text/html")
+        );
+        assert_eq!(media_type(" ; x=y"), None);
+    }
+    #[test]
+    fn test_name_from_path() {
+        assert_eq!(
+            name_from_path("/files/report.pdf").as_deref(),
+            Some("report.pdf")
+        );
+        assert_eq!(
+            name_from_path("/a/My%20Notes.txt").as_deref(),
+            Some("My Notes.txt")
+        );
+        assert_eq!(name_from_path("/files/"), None);
+        assert_eq!(name_from_path("/x/..%2Fetc"), None);
+        assert_eq!(name_from_path("/x/%zz"), None);
+    }
+}
+
+// <FILE>src/fetch.rs</FILE> - <DESC>HTTP downloads for `wsl-clip url`</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T13:40:00Z</VERS>
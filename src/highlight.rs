@@ -0,0 +1,184 @@
+// <FILE>src/highlight.rs</FILE> - <DESC>Syntax-highlighted HTML for code copies</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T14:05:00Z</VERS>
+// <WCTX>--highlight copies colored code as CF_HTML.</WCTX>
+// <CLOG>Initial creation with Highlighter and Block.</CLOG>
+
+use crate::html;
+use anyhow::Result;
+use std::io::{self, Write};
+use std::path::Path;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, Theme, ThemeSet};
+use syntect::html::{append_highlighted_html_for_styled_line, IncludeBackground};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+/// Dark text on a white background, so a paste into Word or Outlook reads
+/// like the surrounding document.
+pub const DEFAULT_THEME: &str = "InspiredGitHub";
+/// Longer lines are copied uncolored: highlighting slows down sharply on
+/// minified or generated lines.
+const MAX_LINE: usize = 16 * 1024;
+const CODE_FONT: &str = "font-family: Consolas, monospace; font-size: 10pt;";
+pub struct Highlighter {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+impl Highlighter {
+    /// Loads the bundled syntaxes and the named bundled theme.
+    pub fn new(theme: &str) -> Result<Self> {
+        let mut themes = ThemeSet::load_defaults().themes;
+        let Some(theme) = themes.remove(theme) else {
+            let names: Vec<&str> = themes.keys().map(String::as_str).collect();
+            anyhow::bail!(
+                "Unknown theme '{}' (available: {})",
+                theme,
+                names.join(", ")
+            );
+        };
+        Ok(Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            theme,
+        })
+    }
+    /// A file header line, bold in the code font.
+    pub fn heading(&self, text: &str) -> String {
+        format!(
+            "<p style=\"{} font-weight: bold;\">{}</p>\n",
+            CODE_FONT,
+            html::escape(text)
+        )
+    }
+    /// A `<pre>` block that colors what is written to it, one line at a time,
+    /// with the syntax for `path`'s extension. Without a path, or for an
+    /// extension no syntax claims, the text is plain monospace.
+    pub fn block<'a>(&'a self, path: Option<&Path>, out: &'a mut String) -> Block<'a> {
+        let syntax = path.and_then(|p| self.syntax_for(p));
+        let mut style = CODE_FONT.to_string();
+        if let Some(c) = self.theme.settings.background {
+            style.push_str(&format!(" background: {};", css(c)));
+        }
+        if let Some(c) = self.theme.settings.foreground {
+            style.push_str(&format!(" color: {};", css(c)));
+        }
+        out.push_str(&format!("<pre style=\"{}\">", style));
+        Block {
+            lines: syntax.map(|s| HighlightLines::new(s, &self.theme)),
+            syntaxes: &self.syntaxes,
+            pending: Vec::new(),
+            out,
+        }
+    }
+    /// By extension, else by file name (`Makefile`, `Dockerfile`).
+    fn syntax_for(&self, path: &Path) -> Option<&SyntaxReference> {
+        let key = path.extension().or_else(|| path.file_name())?.to_str()?;
+        self.syntaxes.find_syntax_by_extension(key)
+    }
+}
+fn css(c: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)
+}
+/// One file's `<pre>`; see [`Highlighter::block`]. Call [`Block::finish`]
+/// to close it.
+pub struct Block<'a> {
+    lines: Option<HighlightLines<'a>>,
+    syntaxes: &'a SyntaxSet,
+    pending: Vec<u8>,
+    out: &'a mut String,
+}
+impl Block<'_> {
+    fn line(&mut self, line: &str) -> io::Result<()> {
+        match &mut self.lines {
+            Some(lines) if line.len() <= MAX_LINE => {
+                let regions = lines
+                    .highlight_line(line, self.syntaxes)
+                    .map_err(io::Error::other)?;
+                append_highlighted_html_for_styled_line(&regions, IncludeBackground::No, self.out)
+                    .map_err(io::Error::other)?;
+            }
+            _ => self.out.push_str(&html::escape(line)),
+        }
+        Ok(())
+    }
+    /// Colors a last line that has no newline and closes the `<pre>`.
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let rest = std::mem::take(&mut self.pending);
+            self.line(&String::from_utf8_lossy(&rest))?;
+        }
+        self.out.push_str("</pre>\n");
+        Ok(())
+    }
+}
+impl Write for Block<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.line(&String::from_utf8_lossy(&line))?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn render(theme: &str, path: Option<&str>, text: &str) -> String {
+        let highlighter = Highlighter::new(theme).unwrap();
+        let mut out = String::new();
+        let mut block = highlighter.block(path.map(Path::new), &mut out);
+        block.write_all(text.as_bytes()).unwrap();
+        block.finish().unwrap();
+        out
+    }
+    #[test]
+    fn test_known_extension_is_colored() {
+        let out = render(DEFAULT_THEME, Some("src/main.rs"), "fn main() {}\n");
+        assert!(out.starts_with(
+            "<pre style=\"font-family: Consolas, monospace; font-size: 10pt; background: #ffffff;"
+        ));
+        assert!(out.contains("<span style=\"color:#"));
+        assert!(out.ends_with("</pre>\n"));
+        // Coloring never changes the text
+        let inside = out.strip_suffix("</pre>\n").unwrap();
+        assert_eq!(html::plain_text(inside), "fn main() {}\n");
+        // Another theme, other colors
+        assert_ne!(
+            out,
+            render("Solarized (dark)", Some("src/main.rs"), "fn main() {}\n")
+        );
+    }
+    #[test]
+    fn test_unknown_extension_is_plain() {
+        let out = render(DEFAULT_THEME, Some("notes.zzz"), "a < b\nlast");
+        assert!(!out.contains("<span"));
+        assert!(out.ends_with(">a &lt; b\nlast</pre>\n"));
+        assert!(!render(DEFAULT_THEME, None, "fn main() {}\n").contains("<span"));
+        // A file name with no extension can still pick a syntax
+        assert!(render(DEFAULT_THEME, Some("Makefile"), "all:\n\tcc x.c\n").contains("<span"));
+    }
+    #[test]
+    fn test_long_lines_are_not_colored() {
+        let line = format!("let x = \"{}\";\n", "y".repeat(MAX_LINE));
+        let out = render(
+            DEFAULT_THEME,
+            Some("a.rs"),
+            &format!("{}fn f() {{}}\n", line),
+        );
+        assert!(out.contains(&line));
+        assert!(out.contains("<span"));
+    }
+    #[test]
+    fn test_unknown_theme() {
+        let Err(err) = Highlighter::new("Neon") else {
+            panic!("theme accepted");
+        };
+        let msg = err.to_string();
+        assert!(msg.starts_with("Unknown theme 'Neon' (available: "));
+        assert!(msg.contains("InspiredGitHub"));
+    }
+}
+
+// <FILE>src/highlight.rs</FILE> - <DESC>Syntax-highlighted HTML for code copies</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T14:05:00Z</VERS>
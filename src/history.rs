@@ -0,0 +1,589 @@
+// <FILE>src/history.rs</FILE> - <DESC>Clipboard history store</DESC>
+// <VERS>VERSION: 1.3.0 - 2026-10-16T14:27:52Z</VERS>
+// <WCTX>Grapheme-safe history previews</WCTX>
+// <CLOG>preview cuts to 80 columns with text_measure::truncate_width</CLOG>
+
+use crate::debug_logger::create_logger;
+use crate::dirs;
+use crate::text_measure;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+/// Capture stops (and the entry is marked truncated) past this many bytes.
+pub const DEFAULT_MAX_CAPTURE_BYTES: u64 = 8 * 1024 * 1024;
+/// Entries kept before the oldest are evicted.
+pub const DEFAULT_MAX_ENTRIES: usize = 500;
+/// Total stored bytes kept before the oldest entries are evicted.
+pub const DEFAULT_MAX_TOTAL_BYTES: u64 = 256 * 1024 * 1024;
+/// Display columns shown per entry by `history list`.
+const PREVIEW_WIDTH: usize = 80;
+const INDEX_FILE: &str = "index.jsonl";
+/// `[history]` section of the config file. Off unless `enabled = true`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    /// Defaults to ~/.local/share/wsl-clip/history
+    pub dir: Option<PathBuf>,
+    pub max_capture_bytes: u64,
+    /// Oldest entries are evicted past this count (0: no limit)
+    pub max_entries: usize,
+    /// ...or past this many stored bytes in total (0: no limit)
+    pub max_total_bytes: u64,
+}
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            enabled: false,
+            dir: None,
+            max_capture_bytes: DEFAULT_MAX_CAPTURE_BYTES,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+        }
+    }
+}
+impl HistoryConfig {
+    /// The configured dir, else `history/` in the data root. Errors with the
+    /// reason when the data root is ephemeral or read-only.
+    pub fn resolved_dir(&self) -> Result<PathBuf> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.clone());
+        }
+        dirs::current()
+            .data
+            .persistent()
+            .map(|root| root.join("history"))
+            .map_err(|reason| anyhow::anyhow!("No usable history directory: {}", reason))
+    }
+}
+/// One line of `index.jsonl`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub id: String,
+    pub timestamp: String,
+    /// Bytes sent to the clipboard
+    pub bytes: u64,
+    /// Bytes stored in the entry file (less than `bytes` when truncated)
+    pub captured: u64,
+    /// FNV-1a over the full payload, even when the capture was truncated
+    pub hash: String,
+    pub truncated: bool,
+    /// SHA-256 over the full payload (empty for entries written before it existed)
+    #[serde(default)]
+    pub sha256: String,
+    /// The first characters, whitespace collapsed, for `history list`
+    #[serde(default)]
+    pub preview: String,
+}
+/// Incremental FNV-1a (64-bit), cheap enough to run over every chunk.
+#[derive(Debug, Clone, Copy)]
+pub struct RollingHash(u64);
+impl Default for RollingHash {
+    fn default() -> Self {
+        RollingHash(0xcbf2_9ce4_8422_2325)
+    }
+}
+impl RollingHash {
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    pub fn hex(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+/// An in-progress history entry. Every failure is contained here: a broken
+/// capture stops recording, but never surfaces as a write error.
+pub struct Capture {
+    id: String,
+    dir: PathBuf,
+    sink: Option<Box<dyn Write>>,
+    cap: u64,
+    bytes: u64,
+    captured: u64,
+    hash: RollingHash,
+    sha256: Sha256,
+    /// Leading bytes kept for the preview
+    head: Vec<u8>,
+    truncated: bool,
+    failed: Option<String>,
+    indexed: bool,
+}
+impl Capture {
+    /// Opens a new entry file in the history directory.
+    pub fn start(config: &HistoryConfig) -> Result<Self> {
+        let dir = config.resolved_dir()?;
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create history directory: {:?}", dir))?;
+        let id = new_id();
+        let path = dir.join(format!("{}.txt", id));
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create history entry: {:?}", path))?;
+        Ok(Self::with_sink(
+            id,
+            dir,
+            Box::new(BufWriter::new(file)),
+            config.max_capture_bytes,
+        ))
+    }
+    pub fn with_sink(id: String, dir: PathBuf, sink: Box<dyn Write>, cap: u64) -> Self {
+        Capture {
+            id,
+            dir,
+            sink: Some(sink),
+            cap,
+            bytes: 0,
+            captured: 0,
+            hash: RollingHash::default(),
+            sha256: Sha256::new(),
+            head: Vec::new(),
+            truncated: false,
+            failed: None,
+            indexed: false,
+        }
+    }
+    fn entry_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.txt", self.id))
+    }
+    fn record(&mut self, chunk: &[u8]) {
+        self.bytes += chunk.len() as u64;
+        self.hash.update(chunk);
+        self.sha256.update(chunk);
+        // Room for a full row of wide graphemes (a ZWJ family is 25 bytes, 2 columns)
+        let want = (PREVIEW_WIDTH * 16).saturating_sub(self.head.len());
+        self.head.extend_from_slice(&chunk[..want.min(chunk.len())]);
+        let Some(sink) = self.sink.as_mut() else {
+            return;
+        };
+        let room = self.cap.saturating_sub(self.captured);
+        let take = (chunk.len() as u64).min(room) as usize;
+        if let Err(e) = sink.write_all(&chunk[..take]) {
+            self.fail(format!("write failed: {}", e));
+            return;
+        }
+        self.captured += take as u64;
+        if take < chunk.len() {
+            self.truncated = true;
+            if let Err(e) = sink.flush() {
+                self.fail(format!("flush failed: {}", e));
+                return;
+            }
+            self.sink = None;
+        }
+    }
+    fn fail(&mut self, reason: String) {
+        create_logger("history").warn(&format!("History capture abandoned: {}", reason));
+        self.sink = None;
+        self.failed = Some(reason);
+    }
+    /// Flushes the entry and appends it to the index. Failed captures are
+    /// removed instead, so the index only ever points at complete files.
+    pub fn finish(mut self) -> Option<IndexEntry> {
+        if let Some(mut sink) = self.sink.take() {
+            if let Err(e) = sink.flush() {
+                self.fail(format!("flush failed: {}", e));
+            }
+        }
+        if self.failed.is_some() {
+            return None;
+        }
+        let entry = IndexEntry {
+            id: self.id.clone(),
+            timestamp: chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            bytes: self.bytes,
+            captured: self.captured,
+            hash: self.hash.hex(),
+            truncated: self.truncated,
+            sha256: format!("{:x}", std::mem::take(&mut self.sha256).finalize()),
+            preview: preview(&self.head),
+        };
+        match append_index(&self.dir, &entry) {
+            Ok(()) => {
+                self.indexed = true;
+                Some(entry)
+            }
+            Err(e) => {
+                create_logger("history").warn(&format!("Failed to update history index: {:#}", e));
+                None
+            }
+        }
+    }
+}
+/// A capture dropped without a successful `finish` (failed copy, index
+/// error) leaves no orphaned entry file behind.
+impl Drop for Capture {
+    fn drop(&mut self) {
+        if !self.indexed {
+            self.sink = None;
+            let _ = std::fs::remove_file(self.entry_path());
+        }
+    }
+}
+fn new_id() -> String {
+    let now = chrono::Utc::now();
+    format!(
+        "{}-{:09}-{}",
+        now.format("%Y%m%dT%H%M%S"),
+        now.timestamp_subsec_nanos(),
+        std::process::id()
+    )
+}
+/// Appends one complete line in a single write so a crash never leaves a
+/// half-written record ahead of later entries.
+fn append_index(dir: &Path, entry: &IndexEntry) -> Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let path = dir.join(INDEX_FILE);
+    let mut index = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open history index: {:?}", path))?;
+    index.write_all(line.as_bytes())?;
+    Ok(())
+}
+/// One line of text for the list view: whitespace runs become one space,
+/// other control characters are dropped. Cut to PREVIEW_WIDTH columns on a
+/// grapheme boundary, so emoji sequences and accents stay whole and wide
+/// CJK text does not overrun the row.
+pub fn preview(head: &[u8]) -> String {
+    // A character split where the head was cut off is not shown at all
+    let head = match std::str::from_utf8(head) {
+        Err(e) if e.error_len().is_none() => &head[..e.valid_up_to()],
+        _ => head,
+    };
+    let text = String::from_utf8_lossy(head);
+    let mut out = String::new();
+    for word in text.split_whitespace() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.extend(word.chars().filter(|c| !c.is_control()));
+    }
+    match text_measure::truncate_width(&out, PREVIEW_WIDTH) {
+        (cut, true) => format!("{}...", cut),
+        (_, false) => out,
+    }
+}
+/// Evicts the oldest entries until the configured count and size caps hold.
+/// The newest entry is always kept. Returns the evicted ids.
+pub fn evict(dir: &Path, config: &HistoryConfig) -> Result<Vec<String>> {
+    let entries = read_index(dir)?;
+    let mut total = 0u64;
+    let mut keep_from = entries.len();
+    for (i, entry) in entries.iter().enumerate().rev() {
+        let kept = entries.len() - 1 - i;
+        let over_count = config.max_entries != 0 && kept >= config.max_entries;
+        let over_size =
+            config.max_total_bytes != 0 && total + entry.captured > config.max_total_bytes;
+        if kept > 0 && (over_count || over_size) {
+            break;
+        }
+        total += entry.captured;
+        keep_from = i;
+    }
+    if keep_from == 0 {
+        return Ok(Vec::new());
+    }
+    let (evicted, kept) = entries.split_at(keep_from);
+    let mut index = tempfile::NamedTempFile::new_in(dir)?;
+    for entry in kept {
+        writeln!(index, "{}", serde_json::to_string(entry)?)?;
+    }
+    index
+        .persist(dir.join(INDEX_FILE))
+        .with_context(|| format!("Failed to rewrite history index in {:?}", dir))?;
+    // Files go after the index stops pointing at them
+    for entry in evicted {
+        let _ = std::fs::remove_file(dir.join(format!("{}.txt", entry.id)));
+    }
+    Ok(evicted.iter().map(|e| e.id.clone()).collect())
+}
+/// The entry whose id is `id`, or starts with it when that is unambiguous.
+pub fn find(dir: &Path, id: &str) -> Result<IndexEntry> {
+    let entries = read_index(dir)?;
+    if let Some(exact) = entries.iter().find(|e| e.id == id) {
+        return Ok(exact.clone());
+    }
+    let mut matches = entries.into_iter().filter(|e| e.id.starts_with(id));
+    match (matches.next(), matches.count()) {
+        (Some(entry), 0) if !id.is_empty() => Ok(entry),
+        (Some(_), more) if !id.is_empty() => anyhow::bail!(
+            "History id '{}' matches {} entries; give more of it",
+            id,
+            more + 1
+        ),
+        _ => anyhow::bail!("No history entry '{}' (see `wsl-clip history list`)", id),
+    }
+}
+/// Path of an entry's stored content.
+pub fn entry_file(dir: &Path, entry: &IndexEntry) -> PathBuf {
+    dir.join(format!("{}.txt", entry.id))
+}
+/// The `history list` table, newest first.
+pub fn render_list(entries: &[IndexEntry], limit: usize) -> String {
+    if entries.is_empty() {
+        return "History is empty\n".to_string();
+    }
+    let shown: Vec<&IndexEntry> = entries.iter().rev().take(limit).collect();
+    let id_width = shown.iter().map(|e| e.id.len()).max().unwrap_or(0).max(2);
+    let mut out = format!(
+        "{:<id_width$}  {:<20}  {:>9}  PREVIEW\n",
+        "ID", "WHEN", "BYTES"
+    );
+    for e in shown {
+        // A trailing * marks an entry stored truncated
+        let bytes = format!("{}{}", e.bytes, if e.truncated { "*" } else { "" });
+        out.push_str(&format!(
+            "{:<id_width$}  {:<20}  {:>9}  {}\n",
+            e.id, e.timestamp, bytes, e.preview
+        ));
+    }
+    out
+}
+/// Reads the index, skipping lines that do not parse.
+pub fn read_index(dir: &Path) -> Result<Vec<IndexEntry>> {
+    let path = dir.join(INDEX_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read history index: {:?}", path))?;
+    Ok(raw
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect())
+}
+/// Tees every chunk written to the clipboard into a history capture. Only
+/// errors from the primary writer are returned to the caller.
+pub struct FanoutWriter<'a, W: Write> {
+    primary: &'a mut W,
+    capture: Option<&'a mut Capture>,
+}
+impl<'a, W: Write> FanoutWriter<'a, W> {
+    pub fn new(primary: &'a mut W, capture: Option<&'a mut Capture>) -> Self {
+        FanoutWriter { primary, capture }
+    }
+}
+impl<W: Write> Write for FanoutWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.primary.write(buf)?;
+        if let Some(capture) = self.capture.as_mut() {
+            capture.record(&buf[..n]);
+        }
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.primary.flush()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// Accepts `budget` bytes, then fails like a full disk.
+    struct DiskFull {
+        budget: usize,
+    }
+    impl Write for DiskFull {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.budget == 0 {
+                return Err(io::Error::other("No space left on device"));
+            }
+            let n = buf.len().min(self.budget);
+            self.budget -= n;
+            Ok(n)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    fn config(dir: &Path, cap: u64) -> HistoryConfig {
+        HistoryConfig {
+            enabled: true,
+            dir: Some(dir.to_path_buf()),
+            max_capture_bytes: cap,
+            ..HistoryConfig::default()
+        }
+    }
+    fn copy_through(payload: &[u8], capture: Option<&mut Capture>) -> Vec<u8> {
+        let mut clip = Vec::new();
+        let mut fanout = FanoutWriter::new(&mut clip, capture);
+        for chunk in payload.chunks(7) {
+            fanout.write_all(chunk).unwrap();
+        }
+        clip
+    }
+    #[test]
+    fn test_clipboard_output_identical_with_capture() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let payload = b"line one\nline two\n\x00binary-ish\n";
+        let plain = copy_through(payload, None);
+        let mut capture = Capture::start(&config(dir.path(), DEFAULT_MAX_CAPTURE_BYTES))?;
+        let teed = copy_through(payload, Some(&mut capture));
+        assert_eq!(plain, teed);
+        let entry = capture.finish().unwrap();
+        assert!(!entry.truncated);
+        assert_eq!(entry.bytes, payload.len() as u64);
+        let stored = std::fs::read(dir.path().join(format!("{}.txt", entry.id)))?;
+        assert_eq!(stored, payload);
+        assert_eq!(read_index(dir.path())?, vec![entry]);
+        Ok(())
+    }
+    #[test]
+    fn test_cap_truncates_capture_not_copy() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let payload = b"0123456789abcdefghij";
+        let mut capture = Capture::start(&config(dir.path(), 12))?;
+        let clip = copy_through(payload, Some(&mut capture));
+        assert_eq!(clip, payload);
+        let entry = capture.finish().unwrap();
+        assert!(entry.truncated);
+        assert_eq!((entry.bytes, entry.captured), (20, 12));
+        let mut full = RollingHash::default();
+        full.update(payload);
+        assert_eq!(entry.hash, full.hex());
+        let stored = std::fs::read(dir.path().join(format!("{}.txt", entry.id)))?;
+        assert_eq!(stored, b"0123456789ab");
+        Ok(())
+    }
+    #[test]
+    fn test_disk_full_keeps_copy_and_index_intact() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut good = Capture::start(&config(dir.path(), DEFAULT_MAX_CAPTURE_BYTES))?;
+        copy_through(b"first\n", Some(&mut good));
+        let first = good.finish().unwrap();
+        let path = dir.path().join("broken.txt");
+        std::fs::write(&path, b"")?;
+        let mut broken = Capture::with_sink(
+            "broken".to_string(),
+            dir.path().to_path_buf(),
+            Box::new(DiskFull { budget: 10 }),
+            DEFAULT_MAX_CAPTURE_BYTES,
+        );
+        let payload = b"a payload longer than the disk budget\n";
+        assert_eq!(copy_through(payload, Some(&mut broken)), payload);
+        assert!(broken.finish().is_none());
+        assert!(!path.exists());
+        // Only the complete entry is indexed and every line still parses
+        let raw = std::fs::read_to_string(dir.path().join(INDEX_FILE))?;
+        assert_eq!(raw.lines().count(), 1);
+        assert_eq!(read_index(dir.path())?, vec![first]);
+        // A copy that fails mid-stream drops its capture unindexed
+        let abandoned = Capture::start(&config(dir.path(), DEFAULT_MAX_CAPTURE_BYTES))?;
+        let orphan = abandoned.entry_path();
+        assert!(orphan.exists());
+        drop(abandoned);
+        assert!(!orphan.exists());
+        assert_eq!(read_index(dir.path())?.len(), 1);
+        Ok(())
+    }
+    fn store(dir: &Path, payloads: &[&[u8]], config: &HistoryConfig) -> Vec<IndexEntry> {
+        payloads
+            .iter()
+            .map(|payload| {
+                let mut c = Capture::start(config).unwrap();
+                copy_through(payload, Some(&mut c));
+                let entry = c.finish().unwrap();
+                evict(dir, config).unwrap();
+                entry
+            })
+            .collect()
+    }
+    #[test]
+    fn test_entry_metadata() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut c = Capture::start(&config(dir.path(), DEFAULT_MAX_CAPTURE_BYTES))?;
+        copy_through(b"  fn main() {\n\tprintln!(\"hi\");\r\n}\n", Some(&mut c));
+        let entry = c.finish().unwrap();
+        assert_eq!(entry.preview, "fn main() { println!(\"hi\"); }");
+        assert_eq!(
+            entry.sha256,
+            crate::checksum::sha256_reader(
+                &b"  fn main() {\n\tprintln!(\"hi\");\r\n}\n"[..],
+                &|_| {}
+            )?
+        );
+        let long = preview("ä".repeat(100).as_bytes());
+        assert_eq!(long, format!("{}...", "ä".repeat(PREVIEW_WIDTH)));
+        // Lines written before the new fields still parse
+        let old =
+            r#"{"id":"x","timestamp":"t","bytes":1,"captured":1,"hash":"h","truncated":false}"#;
+        assert!(serde_json::from_str::<IndexEntry>(old).is_ok());
+        Ok(())
+    }
+    #[test]
+    fn test_preview_cuts_on_graphemes() {
+        let pad = "a".repeat(PREVIEW_WIDTH - 1);
+        // A ZWJ family is one 2-column glyph: it goes whole or not at all
+        assert_eq!(
+            preview(format!("{}\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}x", pad).as_bytes()),
+            format!("{}...", pad)
+        );
+        // A combining accent stays with its letter
+        assert_eq!(
+            preview(format!("{}e\u{301}x", pad).as_bytes()),
+            format!("{}e\u{301}...", pad)
+        );
+        // CJK counts two columns per character
+        assert_eq!(
+            preview("日".repeat(60).as_bytes()),
+            format!("{}...", "日".repeat(PREVIEW_WIDTH / 2))
+        );
+        // A head cut inside a character drops the fragment
+        assert_eq!(preview(&"ä".as_bytes()[..1]), "");
+    }
+    #[test]
+    fn test_eviction_is_oldest_first() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut cfg = config(dir.path(), DEFAULT_MAX_CAPTURE_BYTES);
+        cfg.max_entries = 2;
+        let e = store(dir.path(), &[b"one", b"two", b"three"], &cfg);
+        assert_eq!(read_index(dir.path())?, e[1..]);
+        assert!(!entry_file(dir.path(), &e[0]).exists());
+        assert!(entry_file(dir.path(), &e[1]).exists());
+        // Size cap: 10 bytes holds "three" + "four" but not "two" as well
+        cfg.max_entries = 0;
+        cfg.max_total_bytes = 10;
+        let dir = tempfile::tempdir()?;
+        cfg.dir = Some(dir.path().to_path_buf());
+        let e = store(dir.path(), &[b"two", b"three", b"four"], &cfg);
+        assert_eq!(read_index(dir.path())?, e[1..]);
+        // The newest entry survives even when it alone is over the cap
+        cfg.max_total_bytes = 1;
+        assert_eq!(evict(dir.path(), &cfg)?, [e[1].id.clone()]);
+        assert_eq!(read_index(dir.path())?.len(), 1);
+        Ok(())
+    }
+    #[test]
+    fn test_find_and_list() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cfg = config(dir.path(), 4);
+        let e = store(dir.path(), &[b"alpha", b"beta"], &cfg);
+        assert_eq!(find(dir.path(), &e[1].id)?, e[1]);
+        assert_eq!(find(dir.path(), &e[1].id[..e[1].id.len() - 1])?, e[1]);
+        assert!(find(dir.path(), "2")
+            .unwrap_err()
+            .to_string()
+            .contains("matches 2 entries"));
+        assert!(find(dir.path(), "zz")
+            .unwrap_err()
+            .to_string()
+            .contains("No history entry 'zz'"));
+        let table = render_list(&read_index(dir.path())?, 10);
+        let rows: Vec<&str> = table.lines().collect();
+        assert!(rows[0].starts_with("ID ") && rows[0].ends_with("BYTES  PREVIEW"));
+        // Newest first; truncated entries are starred
+        assert!(rows[1].starts_with(&e[1].id) && rows[1].ends_with("4  beta"));
+        assert!(rows[2].contains("5*  alph"));
+        assert_eq!(render_list(&[], 10), "History is empty\n");
+        Ok(())
+    }
+}
+
+// <FILE>src/history.rs</FILE> - <DESC>Clipboard history store</DESC>
+// <VERS>END OF VERSION: 1.3.0 - 2026-10-16T14:27:52Z</VERS>
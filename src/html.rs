@@ -0,0 +1,132 @@
+// <FILE>src/html.rs</FILE> - <DESC>CF_HTML clipboard payloads</DESC>
+// <VERS>VERSION: 1.1.0 - 2026-10-16T11:56:34Z</VERS>
+// <WCTX>--highlight copies colored code as CF_HTML.</WCTX>
+// <CLOG>--highlight and --theme.</CLOG>
+
+const FRAGMENT_START: &str = "<html>\r\n<body>\r\n<!--StartFragment-->";
+const FRAGMENT_END: &str = "<!--EndFragment-->\r\n</body>\r\n</html>";
+/// The CF_HTML description block. Offsets are zero-padded to ten digits, so
+/// the header has the same length whatever they are.
+fn header(
+    start_html: usize,
+    end_html: usize,
+    start_fragment: usize,
+    end_fragment: usize,
+) -> String {
+    format!(
+        "Version:0.9\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\nStartFragment:{:010}\r\nEndFragment:{:010}\r\n",
+        start_html, end_html, start_fragment, end_fragment
+    )
+}
+/// Wraps an HTML fragment in the CF_HTML envelope Word and Outlook read.
+/// Every offset counts UTF-8 bytes from the start of the header, which is
+/// how Windows stores the format, not characters.
+pub fn cf_html(fragment: &str) -> String {
+    let start_html = header(0, 0, 0, 0).len();
+    let start_fragment = start_html + FRAGMENT_START.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + FRAGMENT_END.len();
+    format!(
+        "{}{}{}{}",
+        header(start_html, end_html, start_fragment, end_fragment),
+        FRAGMENT_START,
+        fragment,
+        FRAGMENT_END
+    )
+}
+/// The plain-text alternative set beside CF_HTML (what Notepad pastes):
+/// tags dropped, block ends and `<br>` as newlines, common entities decoded.
+pub fn plain_text(fragment: &str) -> String {
+    let mut text = String::with_capacity(fragment.len());
+    let mut rest = fragment;
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else {
+            // A stray '<' is text
+            text.push_str(&rest[open..]);
+            rest = "";
+            break;
+        };
+        let tag = rest[open + 1..open + close].trim().to_ascii_lowercase();
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        let ends_block = tag.starts_with('/')
+            && matches!(
+                name,
+                "p" | "div" | "li" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "pre"
+            );
+        if name == "br" || ends_block {
+            text.push('\n');
+        }
+        rest = &rest[open + close + 1..];
+    }
+    text.push_str(rest);
+    decode_entities(&text)
+}
+/// Text as HTML element content: `&`, `<` and `>` escaped.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", "\u{a0}")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// Reads an offset back out of a CF_HTML header.
+    fn offset(payload: &str, key: &str) -> usize {
+        let line = payload
+            .lines()
+            .find_map(|l| l.strip_prefix(&format!("{}:", key)))
+            .unwrap();
+        line.parse().unwrap()
+    }
+    #[test]
+    fn test_cf_html_fixture() {
+        // Known-good: 'é' is two bytes, so EndFragment - StartFragment is 9, not 8
+        assert_eq!(
+            cf_html("<b>é</b>"),
+            "Version:0.9\r\nStartHTML:0000000105\r\nEndHTML:0000000186\r\n\
+             StartFragment:0000000141\r\nEndFragment:0000000150\r\n\
+             <html>\r\n<body>\r\n<!--StartFragment--><b>é</b><!--EndFragment-->\r\n</body>\r\n</html>"
+        );
+        assert_eq!(cf_html("").len(), 177);
+    }
+    #[test]
+    fn test_cf_html_offsets_slice_the_fragment() {
+        for fragment in ["plain", "<p>naïve café ✓</p>", "<i>🎉</i>\r\nline two", ""] {
+            let payload = cf_html(fragment);
+            let bytes = payload.as_bytes();
+            let (start, end) = (
+                offset(&payload, "StartFragment"),
+                offset(&payload, "EndFragment"),
+            );
+            assert_eq!(&bytes[start..end], fragment.as_bytes());
+            assert!(payload[offset(&payload, "StartHTML")..].starts_with("<html>"));
+            assert_eq!(offset(&payload, "EndHTML"), bytes.len());
+        }
+    }
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(
+            plain_text("<h1>Title</h1><p>One &amp; <b>two</b></p><p>a<br/>b</p>"),
+            "Title\nOne & two\na\nb\n"
+        );
+        assert_eq!(plain_text("x < y"), "x < y");
+        assert_eq!(plain_text("&lt;tag&gt; &amp;lt;"), "<tag> &lt;");
+        assert_eq!(plain_text(&escape("a < b && c > d")), "a < b && c > d");
+    }
+}
+
+// <FILE>src/html.rs</FILE> - <DESC>CF_HTML clipboard payloads</DESC>
+// <VERS>END OF VERSION: 1.1.0 - 2026-10-16T11:56:34Z</VERS>
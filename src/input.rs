@@ -0,0 +1,273 @@
+// <FILE>src/input.rs</FILE> - <DESC>Stdin source detection</DESC>
+// <VERS>VERSION: 1.2.0 - 2026-10-16T14:01:30Z</VERS>
+// <WCTX>File lists from a file or stdin.</WCTX>
+// <CLOG>Added --files-from and -0/--null; read_file_list and parse_file_list in input.rs.</CLOG>
+
+use crate::debug_logger::create_logger;
+use anyhow::Context;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{BufRead, IsTerminal, Read, StdinLock};
+use std::os::fd::AsFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+/// Exit code when stdin holds no data (nothing is copied).
+pub const EXIT_EMPTY_INPUT: i32 = 4;
+/// What fd 0 is connected to, as far as text mode cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputSource {
+    /// fd 0 is not open. Rare in practice: the Rust runtime reopens a
+    /// closed fd 0 on /dev/null before main, so `<&-` usually shows up as `Null`.
+    Closed,
+    /// An interactive terminal: the user forgot to pipe something in
+    Terminal,
+    /// `/dev/null`
+    Null,
+    /// A pipe, file or device that reached EOF before yielding a byte
+    Empty,
+    /// Data is waiting (pipe, regular file, or a non-terminal character device)
+    Readable,
+}
+/// Stdin had nothing to copy. Kept as a type so main can map it to
+/// `EXIT_EMPTY_INPUT` instead of a generic failure.
+#[derive(Debug)]
+pub struct EmptyInput(pub InputSource);
+impl fmt::Display for EmptyInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = if self.0 == InputSource::Null {
+            "stdin is /dev/null (or was closed)"
+        } else {
+            "stdin was empty"
+        };
+        write!(
+            f,
+            "{}; nothing to copy (the clipboard was left unchanged)",
+            what
+        )
+    }
+}
+impl std::error::Error for EmptyInput {}
+/// Classifies stdin. Pipes and files are peeked: the first chunk stays in
+/// the process-wide stdin buffer, so later readers see every byte.
+pub fn input_source() -> InputSource {
+    let log = create_logger("input");
+    let stdin = std::io::stdin();
+    // dup fails with EBADF when fd 0 is not open
+    let Ok(owned) = stdin.as_fd().try_clone_to_owned() else {
+        return InputSource::Closed;
+    };
+    if stdin.is_terminal() {
+        return InputSource::Terminal;
+    }
+    let Ok(meta) = std::fs::File::from(owned).metadata() else {
+        return InputSource::Closed;
+    };
+    if meta.file_type().is_char_device() && is_dev_null(meta.rdev()) {
+        return InputSource::Null;
+    }
+    let held = STDIN_HEAD
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(Vec::len);
+    let source = match stdin.lock().fill_buf() {
+        // `peek_stdin` already took the first bytes out of the buffer
+        _ if held.is_some_and(|n| n > 0) => InputSource::Readable,
+        Ok([]) => InputSource::Empty,
+        // A read error surfaces again (with context) when the text processor reads
+        Ok(_) | Err(_) => InputSource::Readable,
+    };
+    log.debug(&format!("stdin: {:?} ({:?})", source, meta.file_type()));
+    source
+}
+fn is_dev_null(rdev: u64) -> bool {
+    std::fs::metadata("/dev/null").is_ok_and(|m| m.rdev() == rdev)
+}
+/// The text-mode guard: Ok only when stdin has data to copy.
+pub fn require_stdin() -> anyhow::Result<()> {
+    match input_source() {
+        InputSource::Readable => Ok(()),
+        InputSource::Terminal => anyhow::bail!("No input provided. Pipe data or specify files."),
+        InputSource::Closed => {
+            anyhow::bail!("No input provided: stdin is closed. Pipe data or specify files.")
+        }
+        empty @ (InputSource::Null | InputSource::Empty) => Err(EmptyInput(empty).into()),
+    }
+}
+/// A reader whose first bytes were read ahead (to sniff them) and are
+/// handed out again before the rest.
+pub struct Peeked<R> {
+    head: Vec<u8>,
+    pos: usize,
+    inner: R,
+}
+impl<R: Read> Peeked<R> {
+    /// Reads up to `len` bytes ahead; fewer only at EOF.
+    pub fn new(mut inner: R, len: usize) -> std::io::Result<Self> {
+        let mut head = Vec::with_capacity(len);
+        (&mut inner).take(len as u64).read_to_end(&mut head)?;
+        Ok(Self::with_head(head, inner))
+    }
+    fn with_head(head: Vec<u8>, inner: R) -> Self {
+        Peeked {
+            head,
+            pos: 0,
+            inner,
+        }
+    }
+    /// The bytes read ahead, whether or not they were consumed since.
+    pub fn head(&self) -> &[u8] {
+        &self.head
+    }
+}
+impl<R: BufRead> Read for Peeked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+impl<R: BufRead> BufRead for Peeked<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.pos < self.head.len() {
+            Ok(&self.head[self.pos..])
+        } else {
+            self.inner.fill_buf()
+        }
+    }
+    fn consume(&mut self, amt: usize) {
+        if self.pos < self.head.len() {
+            self.pos = (self.pos + amt).min(self.head.len());
+        } else {
+            self.inner.consume(amt);
+        }
+    }
+}
+/// Stdin's sniffed prefix, waiting for `stdin_reader`.
+static STDIN_HEAD: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+/// Reads up to `len` bytes of stdin ahead and returns them. They are not
+/// lost: `stdin_reader` hands them out first.
+pub fn peek_stdin(len: usize) -> std::io::Result<Vec<u8>> {
+    let mut held = STDIN_HEAD.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(head) = held.as_ref() {
+        return Ok(head.clone());
+    }
+    let head = Peeked::new(std::io::stdin().lock(), len)?.head;
+    *held = Some(head.clone());
+    Ok(head)
+}
+/// Stdin, starting with whatever `peek_stdin` read ahead.
+pub fn stdin_reader() -> Peeked<StdinLock<'static>> {
+    let head = STDIN_HEAD.lock().unwrap_or_else(|e| e.into_inner()).take();
+    Peeked::with_head(head.unwrap_or_default(), std::io::stdin().lock())
+}
+/// `--files-from`: the paths listed in `source` (`-` for stdin).
+pub fn read_file_list(source: &Path, nul: bool) -> anyhow::Result<Vec<PathBuf>> {
+    let data = if source == Path::new("-") {
+        require_stdin()?;
+        let mut data = Vec::new();
+        stdin_reader()
+            .read_to_end(&mut data)
+            .context("Failed to read the file list from stdin")?;
+        data
+    } else {
+        std::fs::read(source)
+            .with_context(|| format!("Failed to read the file list {:?}", source))?
+    };
+    let files = parse_file_list(&data, nul);
+    create_logger("input").debug(&format!("{:?} lists {} paths", source, files.len()));
+    Ok(files)
+}
+/// One path per line, trailing whitespace trimmed, blank lines and `#`
+/// comments skipped. With `nul` (`find -print0`, `git ls-files -z`) entries
+/// end at NUL and are taken byte for byte, so names may hold newlines or
+/// trailing spaces; only empty entries are skipped.
+pub fn parse_file_list(data: &[u8], nul: bool) -> Vec<PathBuf> {
+    let entries: Vec<&[u8]> = if nul {
+        data.split(|&b| b == 0).collect()
+    } else {
+        data.split(|&b| b == b'\n')
+            .map(|l| l.trim_ascii_end())
+            .filter(|l| !l.starts_with(b"#"))
+            .collect()
+    };
+    entries
+        .into_iter()
+        .filter(|e| !e.is_empty())
+        .map(|e| PathBuf::from(OsStr::from_bytes(e)))
+        .collect()
+}
+/// The empty-input error inside `err`, if that is what stopped the copy.
+pub fn empty(err: &anyhow::Error) -> Option<&EmptyInput> {
+    err.downcast_ref::<EmptyInput>()
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classifier::{inspect_bytes, ClipboardStrategy};
+    /// Hands out at most three bytes per read, like a slow pipe.
+    struct Trickle<'a>(&'a [u8]);
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+    fn peek(data: &[u8], len: usize) -> (ClipboardStrategy, Vec<u8>) {
+        let mut reader = Peeked::new(std::io::BufReader::new(Trickle(data)), len).unwrap();
+        let strategy = inspect_bytes(reader.head());
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        (strategy, all)
+    }
+    #[test]
+    fn test_peeked_prefixes() {
+        let text = b"first line\nsecond line\n".repeat(50);
+        assert_eq!(peek(&text, 64), (ClipboardStrategy::Text, text.clone()));
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend([0u8; 40]);
+        assert_eq!(peek(&png, 64), (ClipboardStrategy::Image, png.clone()));
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.extend([0u8; 100]);
+        assert_eq!(peek(&elf, 64), (ClipboardStrategy::File, elf.clone()));
+        // Shorter than the peek: the whole input is the head
+        assert_eq!(peek(b"hi", 64), (ClipboardStrategy::Text, b"hi".to_vec()));
+    }
+    #[test]
+    fn test_peeked_lines_span_the_head() {
+        let reader = Peeked::new(&b"abc\ndef\nghi"[..], 5).unwrap();
+        assert_eq!(reader.head(), b"abc\nd");
+        let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, ["abc", "def", "ghi"]);
+    }
+    #[test]
+    fn test_parse_file_list() {
+        let list = b"src/main.rs  \r\n\n# generated\nMy Notes.md\n  \nlast";
+        assert_eq!(
+            parse_file_list(list, false),
+            ["src/main.rs", "My Notes.md", "last"].map(PathBuf::from)
+        );
+        // -0: newlines and trailing spaces are part of the name
+        let list = b"two\nlines.txt\0# not a comment\0trailing \0\0";
+        assert_eq!(
+            parse_file_list(list, true),
+            ["two\nlines.txt", "# not a comment", "trailing "].map(PathBuf::from)
+        );
+        assert_eq!(
+            parse_file_list(b"caf\xe9.txt\n", false),
+            [PathBuf::from(OsStr::from_bytes(b"caf\xe9.txt"))]
+        );
+    }
+}
+
+// <FILE>src/input.rs</FILE> - <DESC>Stdin source detection</DESC>
+// <VERS>END OF VERSION: 1.2.0 - 2026-10-16T14:01:30Z</VERS>
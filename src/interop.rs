@@ -0,0 +1,140 @@
+// <FILE>src/interop.rs</FILE> - <DESC>Windows interop binary lookup</DESC>
+// <VERS>VERSION: 1.2.0 - 2026-10-16T13:09:05Z</VERS>
+// <WCTX>Work without wslpath where possible</WCTX>
+// <CLOG>Cached wslpath detection, explained absence, drive fast path both ways</CLOG>
+
+use crate::debug_logger::create_logger;
+use crate::environment;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+/// Overrides the Windows directory probed when a tool is not on PATH
+/// (e.g. `/mnt/d/Windows`, or a custom automount root).
+pub const WINDOWS_DIR_ENV: &str = "WSL_CLIP_WINDOWS_DIR";
+/// Where Windows lives on a default WSL install.
+const DEFAULT_WINDOWS_DIR: &str = "/mnt/c/Windows";
+/// A command for a Windows tool: by name when it is on PATH, else by its
+/// full path under the Windows directory. `appendWindowsPath = false` in
+/// wsl.conf drops the Windows directories from PATH, but interop still works.
+pub fn command(program: &str) -> Command {
+    match resolve(program) {
+        Some(path) => Command::new(path),
+        None => Command::new(program),
+    }
+}
+/// Where `program` was found, looked up once per process. PATH hits keep the
+/// bare name so the usual lookup (and its error messages) apply.
+pub fn resolve(program: &str) -> Option<PathBuf> {
+    static FOUND: OnceLock<Mutex<HashMap<String, Option<PathBuf>>>> = OnceLock::new();
+    let mut found = FOUND
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    found
+        .entry(program.to_string())
+        .or_insert_with(|| locate(program))
+        .clone()
+}
+fn locate(program: &str) -> Option<PathBuf> {
+    let log = create_logger("interop");
+    if on_path(program) {
+        log.debug(&format!("{}: on PATH", program));
+        return Some(PathBuf::from(program));
+    }
+    let hit = candidates(program).into_iter().find(|p| p.is_file());
+    match &hit {
+        Some(path) => log.debug(&format!("{}: not on PATH, using {:?}", program, path)),
+        None => log.debug(&format!("{}: not found", program)),
+    }
+    hit
+}
+/// True when `program` is a file in one of the PATH directories.
+pub fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+/// The Windows directories to search: the override first, then the default.
+fn windows_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os(WINDOWS_DIR_ENV).filter(|v| !v.is_empty()) {
+        dirs.push(PathBuf::from(dir));
+    }
+    let default = PathBuf::from(DEFAULT_WINDOWS_DIR);
+    if !dirs.contains(&default) {
+        dirs.push(default);
+    }
+    dirs
+}
+/// Well-known locations of `program` under each Windows directory.
+pub fn candidates(program: &str) -> Vec<PathBuf> {
+    candidates_in(&windows_dirs(), program)
+}
+fn candidates_in(dirs: &[PathBuf], program: &str) -> Vec<PathBuf> {
+    dirs.iter()
+        .map(|dir| match program {
+            "powershell.exe" => dir.join("System32/WindowsPowerShell/v1.0"),
+            // PowerShell 7 installs beside Windows, under Program Files
+            "pwsh.exe" => dir
+                .parent()
+                .unwrap_or(Path::new("/"))
+                .join("Program Files/PowerShell/7"),
+            _ => dir.join("System32"),
+        })
+        .map(|dir| dir.join(program))
+        .collect()
+}
+/// The interop hint for a failed spawn of `programs`, plus every location
+/// tried for the ones that were not found at all.
+pub fn hint(programs: &[&str]) -> String {
+    let mut hint = environment::interop_hint(environment::current()).to_string();
+    for program in programs.iter().filter(|p| resolve(p).is_none()) {
+        let tried: Vec<String> = candidates(program)
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect();
+        hint.push_str(&format!(
+            "\n  {} is not on PATH (appendWindowsPath disabled?) and was not found at: {}",
+            program,
+            tried.join(", ")
+        ));
+    }
+    if hint.contains("not found at") {
+        hint.push_str(&format!(
+            "\n  Set {} if Windows is installed elsewhere (e.g. /mnt/d/Windows)",
+            WINDOWS_DIR_ENV
+        ));
+    }
+    hint
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_candidates() {
+        let dirs = [
+            PathBuf::from("/mnt/d/Windows"),
+            PathBuf::from("/mnt/c/Windows"),
+        ];
+        assert_eq!(
+            candidates_in(&dirs, "clip.exe"),
+            vec![
+                PathBuf::from("/mnt/d/Windows/System32/clip.exe"),
+                PathBuf::from("/mnt/c/Windows/System32/clip.exe"),
+            ]
+        );
+        assert_eq!(
+            candidates_in(&dirs[1..], "powershell.exe"),
+            vec![PathBuf::from(
+                "/mnt/c/Windows/System32/WindowsPowerShell/v1.0/powershell.exe"
+            )]
+        );
+        assert_eq!(
+            candidates_in(&dirs[..1], "pwsh.exe"),
+            vec![PathBuf::from("/mnt/d/Program Files/PowerShell/7/pwsh.exe")]
+        );
+    }
+}
+
+// <FILE>src/interop.rs</FILE> - <DESC>Windows interop binary lookup</DESC>
+// <VERS>END OF VERSION: 1.2.0 - 2026-10-16T13:09:05Z</VERS>
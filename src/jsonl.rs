@@ -0,0 +1,48 @@
+// <FILE>src/jsonl.rs</FILE> - <DESC>JSONL record expansion</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:17:17Z</VERS>
+// <WCTX>Pretty-print single-line JSON logs while copying.</WCTX>
+// <CLOG>Initial creation with expand, MAX_JSON_LINE and RECORD_SEPARATOR.</CLOG>
+
+use serde_json::Value;
+/// Lines longer than this are passed through without a parse attempt.
+pub const MAX_JSON_LINE: usize = 1024 * 1024;
+/// Emitted after every expanded record so adjacent records stay distinct.
+pub const RECORD_SEPARATOR: &str = "---";
+/// Pretty-prints a line holding a single JSON object or array. Returns None
+/// for anything else (plain log lines, scalars, invalid or oversized JSON),
+/// which the caller emits unchanged.
+pub fn expand(line: &str) -> Option<String> {
+    if line.len() > MAX_JSON_LINE {
+        return None;
+    }
+    let trimmed = line.trim();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return None;
+    }
+    let value: Value = serde_json::from_str(trimmed).ok()?;
+    serde_json::to_string_pretty(&value).ok()
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand(r#"{"level":"info","msg":"ok","n":[1,2]}"#).unwrap(),
+            "{\n  \"level\": \"info\",\n  \"msg\": \"ok\",\n  \"n\": [\n    1,\n    2\n  ]\n}"
+        );
+        // Key order from the log line is kept
+        assert_eq!(
+            expand(r#"{"z":1,"a":2}"#).unwrap(),
+            "{\n  \"z\": 1,\n  \"a\": 2\n}"
+        );
+        assert!(expand("plain text line").is_none());
+        assert!(expand("42").is_none());
+        assert!(expand(r#"{"unterminated": "#).is_none());
+        let huge = format!("{{\"k\":\"{}\"}}", "x".repeat(MAX_JSON_LINE));
+        assert!(expand(&huge).is_none());
+    }
+}
+
+// <FILE>src/jsonl.rs</FILE> - <DESC>JSONL record expansion</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:17:17Z</VERS>
@@ -1,24 +1,76 @@
-// <FILE>src/main.rs</FILE> - <DESC>Integrated streaming and security fixes</DESC>
-// <VERS>VERSION: 2.3.0 - 2025-11-25T17:09:34Z</VERS>
-// <WCTX>Wired main to use start_text_stream and process_input(writer).</WCTX>
-// <CLOG>Updated Text Mode handling to use streaming pipeline.</CLOG>
+// <FILE>src/main.rs</FILE> - <DESC>ClipboardBackend dispatch</DESC>
+// <VERS>VERSION: 2.96.0 - 2026-10-16T14:45:17Z</VERS>
+// <WCTX>URL text copies are recorded in history.</WCTX>
+// <CLOG>start_history/finish_history shared by the stdin/file and url text paths.</CLOG>
 
+pub mod archive;
+pub mod checksum;
+pub mod cite;
 pub mod classifier;
+pub mod clear;
 pub mod clipboard;
+pub mod config;
+pub mod daemon;
+pub mod deadline;
 pub mod debug_config;
 pub mod debug_logger;
+pub mod dirs;
+pub mod encoding;
+pub mod environment;
+pub mod estimate;
+pub mod fetch;
+pub mod highlight;
+pub mod history;
+pub mod html;
+pub mod input;
+pub mod interop;
+pub mod jsonl;
+pub mod markdown;
+pub mod metrics;
+pub mod notify;
+pub mod options;
+pub mod paste;
 pub mod paths;
+pub mod peek;
+pub mod powershell;
+pub mod preflight;
+pub mod provider;
+pub mod quoting;
+pub mod registers;
+pub mod retry;
+pub mod rtf;
+pub mod sanitizer;
+pub mod shell_paste;
+pub mod size_guard;
+pub mod snapshot;
+pub mod staging;
+pub mod state_bundle;
+pub mod text_measure;
 pub mod text_processor;
-use anyhow::Result;
+pub mod timestamp;
+pub mod transcode;
+pub mod tree;
+pub mod watch;
+use anyhow::{Context, Result};
 use clap::{
     builder::styling::{AnsiColor, Effects, Styles},
-    Parser, Subcommand,
+    parser::ValueSource,
+    ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand,
 };
 use classifier::ClipboardStrategy;
-use clipboard::ClipboardMode;
+use clipboard::{ClipboardBackend, ClipboardContent, DropEffect, Format};
 use debug_logger::create_logger;
-use std::path::PathBuf;
-use text_processor::TextOptions;
+use encoding::TextEncoding;
+use history::FanoutWriter;
+use metrics::{CountingWriter, Event, Outcome};
+use options::{Effective, OptionLayer};
+use preflight::Preflight;
+use shell_paste::TargetShell;
+use std::io::{BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use text_processor::{FooterStyle, TextOptions};
+use timestamp::{Timestamp, TimestampMode};
 fn get_styles() -> Styles {
     Styles::styled()
         .header(AnsiColor::Cyan.on_default() | Effects::BOLD)
@@ -34,6 +86,7 @@ fn get_styles() -> Styles {
     long_about = "Delightfully smart clipboard integration for WSL2.\n\nAuto-detects content types, supports images, binaries, and text piping.\n\nBy default, ANSI color codes are stripped from text to ensure clean pasting.",
     styles = get_styles(),
     override_usage = "wsl-clip [OPTIONS] <COMMAND> | [FILES]...",
+    group = clap::ArgGroup::new("inputs").args(["files", "files_from"]).multiple(true),
     help_template = "\
 {before-help}{name} {version}
 {author-with-newline}{about-with-newline}
@@ -56,6 +109,101 @@ struct Cli {
     /// Files to copy (Text Mode or Smart Mode). If empty, reads from Stdin.
     #[arg()]
     files: Option<Vec<PathBuf>>,
+    /// Also copy the files listed in this file, one per line (`-`: read the list from stdin)
+    #[arg(long, value_name = "PATH")]
+    files_from: Option<PathBuf>,
+    /// With --files-from: the list is NUL-separated (find -print0, git ls-files -z)
+    #[arg(short = '0', long, requires = "files_from")]
+    null: bool,
+    /// Copy the text files under each directory argument (binary files are skipped)
+    #[arg(short = 'r', long, requires = "inputs")]
+    recursive: bool,
+    /// With -r: only files whose path under the directory matches this glob (repeatable)
+    #[arg(long, value_name = "GLOB", requires = "recursive")]
+    include: Vec<String>,
+    /// With -r: leave out files whose path under the directory matches this glob (repeatable)
+    #[arg(long, value_name = "GLOB", requires = "recursive")]
+    exclude: Vec<String>,
+    /// With -r: also walk dotfiles and dot-directories (.git, .env)
+    #[arg(long, requires = "recursive")]
+    hidden: bool,
+    /// With -r: honour .gitignore even outside a git repository (inside one it is the default)
+    #[arg(long, requires = "recursive")]
+    gitignore: bool,
+    /// With -r: walk every file, ignoring .gitignore, .ignore and global excludes
+    #[arg(long, requires = "recursive", conflicts_with = "gitignore")]
+    no_gitignore: bool,
+    /// With -r: how many levels below each directory to walk (1: its own files only)
+    #[arg(long, value_name = "N", requires = "recursive", value_parser = clap::value_parser!(u32).range(1..))]
+    max_depth: Option<u32>,
+    /// With -r: fail before copying anything when more files than this match (0: no limit)
+    #[arg(long, value_name = "N", requires = "recursive", default_value_t = tree::DEFAULT_MAX_FILES)]
+    max_files: usize,
+    /// Copy the text files, then copy them again every time they change (Ctrl-C stops)
+    #[arg(long, requires = "inputs")]
+    watch: bool,
+    /// Editor clipboard provider (Neovim g:clipboard): raw stdin in, or clipboard out, byte for byte
+    #[arg(long, value_enum, value_name = "MODE", conflicts_with_all = ["inputs", "watch"])]
+    provider: Option<provider::Provider>,
+    /// With --provider paste: convert CRLF to LF and drop a leading UTF-8 BOM
+    #[arg(long, requires = "provider")]
+    lf: bool,
+    /// Copy an image piped in on stdin (PNG, JPEG, GIF or BMP) as pixels
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "watch", "provider", "html", "rtf", "render_md", "highlight", "encoding", "both"]
+    )]
+    image: bool,
+    /// Copy stdin as a file object (e.g. a generated PDF) for pasting into Explorer or Outlook
+    #[arg(
+        long,
+        conflicts_with_all = ["inputs", "watch", "provider", "image", "html", "rtf", "render_md", "highlight", "encoding", "both"]
+    )]
+    as_file: bool,
+    /// Smart mode: copy every file this way instead of classifying it
+    /// (`image` takes exactly one file)
+    #[arg(
+        long = "as",
+        value_enum,
+        value_name = "STRATEGY",
+        conflicts_with_all = ["watch", "provider", "image", "as_file"]
+    )]
+    force_as: Option<ClipboardStrategy>,
+    /// Smart mode with mixed content: copy text, images and files one group
+    /// after another (`--split=files`: all as one file-object drop list)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "sequential",
+        conflicts_with_all = ["watch", "provider", "image", "as_file", "force_as", "html", "rtf", "render_md", "highlight"]
+    )]
+    split: Option<SplitMode>,
+    /// With --split: go on to the next group without waiting for Enter
+    #[arg(long, requires = "split")]
+    yes: bool,
+    /// File name for --as-file (default: "stdin" plus the detected extension)
+    #[arg(long, value_name = "NAME", requires = "as_file")]
+    name: Option<String>,
+    /// Image copies: also put the file itself on the clipboard, so Paint pastes
+    /// the pixels and Explorer or Outlook the file. `path`: copy the Windows
+    /// and the WSL path, one line each
+    #[arg(long, global = true, conflicts_with = "strip_metadata")]
+    both: bool,
+    /// Image copies: re-encode without EXIF/XMP/ICC metadata (GPS position, camera serial number)
+    #[arg(long, global = true)]
+    strip_metadata: bool,
+    /// JPEG quality when an image is re-encoded (--strip-metadata, img --max-dim/--scale)
+    #[arg(
+        long,
+        global = true,
+        value_name = "1-100",
+        default_value_t = transcode::DEFAULT_JPEG_QUALITY,
+        value_parser = clap::value_parser!(u8).range(1..=100)
+    )]
+    jpeg_quality: u8,
     /// Suppress file headers in Text Mode
     #[arg(short = 'n', long, global = true)]
     no_header: bool,
@@ -68,6 +216,176 @@ struct Cli {
     /// Wrap content in Markdown code blocks
     #[arg(long, global = true)]
     code: bool,
+    /// Copy the text as an HTML fragment (CF_HTML) that Word and Outlook paste formatted
+    #[arg(long, conflicts_with_all = ["provider", "watch"])]
+    html: bool,
+    /// Copy the text as an RTF document (with --code, fenced code is monospace)
+    #[arg(long, conflicts_with_all = ["provider", "watch"])]
+    rtf: bool,
+    /// Render the text as Markdown and copy the HTML, keeping the Markdown as plain text
+    #[arg(long, conflicts_with_all = ["html", "provider", "watch"])]
+    render_md: bool,
+    /// Copy syntax-highlighted HTML of each file, colored by its extension
+    #[arg(long, conflicts_with_all = ["html", "render_md", "provider", "watch"])]
+    highlight: bool,
+    /// Color theme for --highlight
+    #[arg(long, requires = "highlight", default_value = highlight::DEFAULT_THEME)]
+    theme: String,
+    /// Bytes sent to the clipboard tool for text copies (default: UTF-16LE to clip.exe, else UTF-8)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ENCODING",
+        conflicts_with_all = ["html", "rtf", "render_md", "highlight", "provider"]
+    )]
+    encoding: Option<TextEncoding>,
+    /// With --encoding windows-1252, fail on characters it lacks instead of writing '?'
+    #[arg(long, requires = "encoding")]
+    strict_encoding: bool,
+    /// Timestamp shown in file headers (READ: ...)
+    #[arg(long, global = true, value_enum, default_value_t = TimestampMode::Utc)]
+    timestamp: TimestampMode,
+    /// Custom strftime format for header timestamps (utc/local modes)
+    #[arg(long, global = true, value_name = "STRFTIME")]
+    timestamp_format: Option<String>,
+    /// Pin the header timestamp to this instant (RFC 3339)
+    #[arg(long, global = true, value_name = "RFC3339", value_parser = timestamp::parse_rfc3339)]
+    timestamp_value: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// Copy only these lines of each input (1-based, inclusive: 10:42, 10:, :42)
+    #[arg(long, global = true, value_name = "START:END", value_parser = cite::LineRange::parse)]
+    lines: Option<cite::LineRange>,
+    /// Fenced snippet followed by a provenance line (path, lines, git commit)
+    #[arg(long, global = true, conflicts_with = "single_line")]
+    cite: bool,
+    /// Byte-reproducible text output: pinned timestamp (SOURCE_DATE_EPOCH or
+    /// the Unix epoch), display-path order (--sort=size also works; mtime does not), no mtimes
+    #[arg(long, global = true)]
+    deterministic: bool,
+    /// Text bundles: reorder the files (default: as given on the command line)
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "KEY",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "name"
+    )]
+    sort: Option<text_processor::FileOrder>,
+    /// Include zero-byte files in text bundles (header only) instead of skipping them
+    #[arg(long, global = true)]
+    keep_empty: bool,
+    /// Multi-file footer layout
+    #[arg(long, global = true, value_enum, default_value_t = FooterStyle::Detailed)]
+    footer_style: FooterStyle,
+    /// Read each file fully before emitting it (consistent copy of files being written)
+    #[arg(long, global = true)]
+    snapshot: bool,
+    /// Join all lines into one command (optional separator, e.g. --single-line=' && ')
+    #[arg(
+        long,
+        global = true,
+        num_args = 0..=1,
+        require_equals = true,
+        value_name = "SEP"
+    )]
+    single_line: Option<Option<String>>,
+    /// Warn when the payload would run line-by-line if pasted into a shell
+    #[arg(long, global = true)]
+    paste_safe: bool,
+    /// Pretty-print lines that are JSON objects/arrays (other lines pass through)
+    #[arg(long, global = true)]
+    jsonl_pretty: bool,
+    /// Only copy lines matching this regex (matched before --jsonl-pretty expands them)
+    #[arg(long, global = true, value_name = "REGEX")]
+    grep: Option<String>,
+    /// Control characters to keep when sanitizing, e.g. `ff,cr` or `U+000C` (Tab is kept unless denied)
+    #[arg(long, global = true, value_name = "LIST", value_parser = sanitizer::parse_list, conflicts_with = "no_strip")]
+    allow_control: Option<sanitizer::ControlList>,
+    /// Control characters to strip even if allowed, e.g. `tab`
+    #[arg(long, global = true, value_name = "LIST", value_parser = sanitizer::parse_list, conflicts_with = "no_strip")]
+    deny_control: Option<sanitizer::ControlList>,
+    /// Required with --allow-control esc, which re-enables terminal escape injection
+    #[arg(long = "i-know-what-im-doing", global = true)]
+    i_know_what_im_doing: bool,
+    /// Shell the text will be pasted into (picks the --single-line separator)
+    #[arg(long, global = true, value_enum, default_value_t = TargetShell::Powershell)]
+    target_shell: TargetShell,
+    /// Show a desktop notification when a slow operation finishes
+    #[arg(long, global = true)]
+    notify: bool,
+    /// Minimum duration before --notify fires
+    #[arg(long, global = true, value_name = "SECS", default_value_t = notify::DEFAULT_THRESHOLD_SECS)]
+    notify_after: u64,
+    /// Copy Linux-filesystem files into the Windows temp folder before copying them as File/Image objects
+    #[arg(long, global = true)]
+    stage: bool,
+    /// File mode: paste as a move (Explorer shows the files as cut and removes them after the paste)
+    #[arg(long = "move", global = true, conflicts_with = "stage")]
+    move_files: bool,
+    /// File mode: add a sha256sum-compatible MANIFEST.sha256 to the drop list (and as the plain-text paste)
+    #[arg(long, global = true)]
+    manifest: bool,
+    /// Skip the reachability check before copying File/Image objects
+    #[arg(long, global = true)]
+    no_preflight: bool,
+    /// Fail instead of warning when a copied file's Windows path exceeds MAX_PATH (259 characters)
+    #[arg(long, global = true)]
+    strict_length: bool,
+    /// Spell WSL UNC paths as \\wsl$\... (older apps) or \\wsl.localhost\... (default: as wslpath prints them)
+    #[arg(long, global = true, value_enum, value_name = "FORM")]
+    unc: Option<paths::UncStyle>,
+    /// Symlinks: use what they point to, in every mode (the default)
+    #[arg(long, global = true, conflicts_with = "no_follow")]
+    follow: bool,
+    /// Symlinks: File/Image objects, `path` and `zip` take the link itself; text mode still reads through it and refuses dangling links
+    #[arg(long, global = true)]
+    no_follow: bool,
+    /// Smart mode: read the first bytes of every file, even ones with a known text extension
+    #[arg(long, global = true)]
+    no_fast_classify: bool,
+    /// Smart mode: ask `file --mime-type` about content that is text only by default and looks doubtful
+    #[arg(long, global = true)]
+    deep_classify: bool,
+    /// Also confirm via PowerShell that Windows can see each file (drvfs and Windows may disagree)
+    #[arg(long, global = true, conflicts_with = "no_preflight")]
+    verify_windows_access: bool,
+    /// Abort text copies whose output would exceed SIZE (e.g. 10M); nothing is copied. Also caps `url` downloads (default 8M)
+    #[arg(long, global = true, value_name = "SIZE", value_parser = size_guard::parse_size)]
+    max_size: Option<u64>,
+    /// After a --max-size abort, exit without draining the rest of stdin
+    #[arg(long, global = true, requires = "max_size")]
+    no_drain: bool,
+    /// Where copies go: clip.exe/PowerShell, or an OSC 52 terminal sequence (auto: OSC 52 when clip.exe is missing and a terminal is attached)
+    #[arg(long, global = true, value_enum, default_value_t = clipboard::Backend::Auto)]
+    backend: clipboard::Backend,
+    /// Give up on clip.exe/PowerShell after this long (seconds, or 90s / 2m; 0: never; default 15s)
+    #[arg(long, global = true, value_name = "DELAY", value_parser = clear::parse_delay)]
+    timeout: Option<Duration>,
+    /// Retry a locked Windows clipboard this many times (default 3)
+    #[arg(long, global = true, value_name = "N", conflicts_with = "no_retry")]
+    retries: Option<u32>,
+    /// First retry delay, doubling each time (ms, or 1s; default 200ms)
+    #[arg(long, global = true, value_name = "DELAY", value_parser = retry::parse_backoff)]
+    retry_delay: Option<Duration>,
+    /// Fail at once when the clipboard is locked (for scripts)
+    #[arg(long, global = true)]
+    no_retry: bool,
+    /// PowerShell for image and file copies (auto: pwsh.exe when installed)
+    #[arg(long, global = true, value_enum, default_value_t = clipboard::Shell::Auto)]
+    shell: clipboard::Shell,
+    /// Do not record this copy in the clipboard history (e.g. for secrets)
+    #[arg(long, global = true)]
+    no_history: bool,
+    /// Apply a [profile.<NAME>] option bundle from the config file ("help" lists them)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+    /// Print the effective options with where each value came from, then exit
+    #[arg(long, global = true)]
+    print_config: bool,
+    /// Print the resolved config/state/cache/data directories, then exit
+    #[arg(long, global = true, hide = true)]
+    paths: bool,
     /// Enable debug logging
     #[arg(long, global = true)]
     debug: bool,
@@ -75,43 +393,1115 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Force Image Mode (copy pixels)
-    Img { file: PathBuf },
+    Img {
+        file: PathBuf,
+        /// Downscale (after EXIF rotation) so neither side exceeds this many pixels
+        #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+        max_dim: Option<u32>,
+        /// Downscale each side to this percentage
+        #[arg(
+            long,
+            value_name = "PERCENT",
+            value_parser = clap::value_parser!(u32).range(1..=100),
+            conflicts_with = "max_dim"
+        )]
+        scale: Option<u32>,
+        /// SVG only: render this many pixels wide (default 1024)
+        #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+        width: Option<u32>,
+        /// SVG only: render at this DPI (96 is the drawing's own size)
+        #[arg(
+            long,
+            value_name = "DPI",
+            value_parser = clap::value_parser!(u32).range(1..),
+            conflicts_with = "width"
+        )]
+        dpi: Option<u32>,
+    },
     /// Force File Object Mode (copy as attachment)
-    File { files: Vec<PathBuf> },
-    /// Copy the Windows path string
-    Path { file: PathBuf },
+    File {
+        files: Vec<PathBuf>,
+        /// Copy the files that exist and can be read, listing the rest, instead of failing
+        #[arg(long)]
+        skip_missing: bool,
+    },
+    /// Zip files and folders into one archive and copy it as a file object
+    Zip {
+        #[arg(required = true, value_name = "PATHS")]
+        inputs: Vec<PathBuf>,
+        /// Archive name (default: the single argument's name plus .zip, else bundle.zip)
+        #[arg(long, value_name = "NAME")]
+        name: Option<String>,
+    },
+    /// Download a URL and copy it as smart mode would: text, image or file object
+    Url {
+        url: String,
+        /// Give up on the download after this long (seconds, or 90s / 2m; default 30s)
+        #[arg(long, value_name = "DELAY", value_parser = clear::parse_delay)]
+        fetch_timeout: Option<Duration>,
+    },
+    /// Copy the Windows path of each file (default: the current directory), joined by --sep
+    Path {
+        #[arg(value_name = "FILES", default_value = ".")]
+        files: Vec<PathBuf>,
+        /// Between paths: space, newline, crlf, tab, or any string such as ';'
+        #[arg(long, value_name = "SEP", default_value = "space", value_parser = paths::parse_separator, conflicts_with = "both")]
+        sep: String,
+        /// Copy the canonical WSL path instead (/mnt/c/... for Windows drives)
+        #[arg(long, conflicts_with = "both")]
+        wsl: bool,
+        /// Do not double-quote space-separated paths that contain spaces
+        #[arg(long)]
+        no_quote: bool,
+        /// Convert the paths as written, even ones that do not exist yet (symlinks are not resolved)
+        #[arg(long)]
+        literal: bool,
+        /// Windows paths with forward slashes (C:/Users/me, //wsl.localhost/...)
+        #[arg(long, conflicts_with = "wsl")]
+        slash: bool,
+        /// Quote every path for this shell's command line
+        #[arg(long, value_name = "STYLE", value_enum, conflicts_with = "no_quote")]
+        quote: Option<quoting::QuoteStyle>,
+    },
+    /// Preview the clipboard text with size, content hints and hidden-character warnings
+    Peek {
+        /// Lines to show
+        #[arg(long, value_name = "N", default_value_t = peek::DEFAULT_PEEK_LINES)]
+        max_lines: usize,
+        /// Bytes to show
+        #[arg(long, value_name = "N", default_value_t = peek::DEFAULT_PEEK_BYTES)]
+        max_bytes: usize,
+    },
+    /// List the formats on the Windows clipboard (text length, image size, file count)
+    Inspect {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show how smart mode would copy each file, and which check decided
+    Classify {
+        #[arg(required = true, value_name = "FILES")]
+        files: Vec<PathBuf>,
+        /// Print a JSON array instead of one line per file
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write the Windows clipboard text to stdout, byte for byte
+    Paste {
+        /// Convert CRLF to LF and drop a leading UTF-8 BOM
+        #[arg(long)]
+        lf: bool,
+        /// Write to this file instead of stdout
+        #[arg(short = 'o', long, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Save the clipboard image to this .png or .jpg file instead
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["lf", "output"])]
+        image: Option<PathBuf>,
+    },
+    /// Take a Windows screenshot onto the clipboard (the primary screen unless told otherwise)
+    Shot {
+        /// Open the snipping overlay and wait for the region picked there
+        #[arg(long)]
+        region: bool,
+        /// Capture every monitor as one image
+        #[arg(long, conflicts_with = "region")]
+        all_screens: bool,
+        /// Also save the screenshot to this .png or .jpg file
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+        /// How long --region waits for the snip (seconds, or 90s / 2m; default 60s)
+        #[arg(long, value_name = "DELAY", value_parser = clear::parse_delay, requires = "region")]
+        wait: Option<Duration>,
+        /// How often --region checks the clipboard (ms, or 1s; default 250ms)
+        #[arg(long, value_name = "DELAY", value_parser = retry::parse_backoff, requires = "region")]
+        poll: Option<Duration>,
+    },
+    /// Empty the Windows clipboard (e.g. after copying a secret)
+    Clear {
+        /// Clear after a delay instead (seconds, or 90s / 2m), in the background
+        #[arg(long, value_name = "DELAY", value_parser = clear::parse_delay)]
+        after: Option<Duration>,
+        /// Internal: this is the background process started by --after
+        #[arg(long, hide = true, requires = "after")]
+        detached: bool,
+    },
+    /// Save text (files or stdin, processed as for a copy) into a named register
+    Save {
+        /// Register name (letters, digits, '-', '_', '.')
+        #[arg(value_parser = registers::parse_name, required_unless_present = "list")]
+        name: Option<String>,
+        files: Vec<PathBuf>,
+        /// List saved registers with their sizes and ages instead
+        #[arg(long, conflicts_with_all = ["name", "files"])]
+        list: bool,
+    },
+    /// Put a saved register on the Windows clipboard
+    Load {
+        #[arg(value_parser = registers::parse_name)]
+        name: String,
+    },
+    /// Browse and restore clipboard history ([history] enabled = true)
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+    },
+    /// Serve copies and pastes from a warm PowerShell over a Unix socket
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonCommand>,
+    },
+    /// Bundle config and profiles (and optionally history) into a .tar.gz
+    ExportState {
+        file: PathBuf,
+        /// Include clipboard history (off by default: it holds copied content)
+        #[arg(long)]
+        history: bool,
+    },
+    /// Restore a bundle written by export-state
+    ImportState {
+        file: PathBuf,
+        /// Replace existing files that differ
+        #[arg(long, conflicts_with = "merge")]
+        overwrite: bool,
+        /// Add profiles and history entries by name, keeping local ones on collision
+        #[arg(long)]
+        merge: bool,
+    },
+}
+#[derive(Subcommand)]
+enum DaemonCommand {
+    /// Stop the running daemon
+    Stop,
+}
+#[derive(Subcommand)]
+enum HistoryCommand {
+    /// Recent entries, newest first
+    List {
+        /// Entries to show
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+    /// Put an entry back on the clipboard
+    Restore {
+        /// Entry id (or an unambiguous prefix of it)
+        id: String,
+    },
+}
+/// Error text for unreadable inputs. Names starting with '-' get a hint about
+/// `--`, since the user most likely meant an option or mistyped one.
+fn read_failure_message(path: &std::path::Path) -> String {
+    let mut msg = format!("Failed to read file: {:?}", path);
+    if path.to_string_lossy().starts_with('-') {
+        msg.push_str(
+            "\n  tip: no file by that name exists. If it is an option, check its spelling; \
+            file names starting with '-' must follow '--' (e.g. wsl-clip -- -n)",
+        );
+    }
+    msg
+}
+/// The options the user actually typed; clap defaults are left unset so
+/// lower layers (env, config) can supply them.
+fn cli_layer(cli: &Cli, matches: &ArgMatches) -> OptionLayer {
+    let typed = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let flag = |id: &str, value: bool| typed(id).then_some(value);
+    OptionLayer {
+        no_header: flag("no_header", cli.no_header),
+        no_strip: flag("no_strip", cli.no_strip),
+        crlf: flag("crlf", cli.crlf),
+        code: flag("code", cli.code),
+        snapshot: flag("snapshot", cli.snapshot),
+        paste_safe: flag("paste_safe", cli.paste_safe),
+        jsonl_pretty: flag("jsonl_pretty", cli.jsonl_pretty),
+        single_line: cli.single_line.clone().map(|sep| sep.unwrap_or_default()),
+        target_shell: typed("target_shell").then_some(cli.target_shell),
+        footer_style: typed("footer_style").then_some(cli.footer_style),
+        timestamp: typed("timestamp").then_some(cli.timestamp),
+        timestamp_format: cli.timestamp_format.clone(),
+    }
 }
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let started = Instant::now();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // `--as` replaces the subcommands' choice, so it cannot be combined with them
+    if let (Some(_), Some(name)) = (cli.force_as, matches.subcommand_name()) {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                format!(
+                    "the argument '--as <STRATEGY>' cannot be used with the '{}' subcommand",
+                    name
+                ),
+            )
+            .exit();
+    }
+    if cli.force_as.is_some() && cli.files.is_none() && cli.files_from.is_none() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                "'--as <STRATEGY>' needs file arguments (for stdin, use --image or --as-file)",
+            )
+            .exit();
+    }
     if cli.debug {
         debug_logger::enable_all();
     }
     let log = create_logger("main");
     log.debug("wsl-clip started");
-    match cli.command {
-        Some(Commands::Img { file }) => {
+    if cli.paths {
+        print!("{}", dirs::current().describe());
+        match config::config_path() {
+            Some(path) => println!("config file: {}", path.display()),
+            None => println!("config file: none (no HOME or XDG config directory)"),
+        }
+        return Ok(());
+    }
+    let settings = match config::Config::load() {
+        Ok(settings) => settings,
+        // Only --profile needs the file itself; a copy goes ahead on the defaults
+        Err(e) if cli.profile.is_none() => {
+            eprintln!("[WARN] {:#}; using the defaults", e);
+            config::Config::default()
+        }
+        Err(e) => return Err(e),
+    };
+    if cli.profile.as_deref() == Some("help") {
+        print!("{}", settings.describe_profiles());
+        return Ok(());
+    }
+    let effective = resolve_effective(
+        &settings,
+        &OptionLayer::from_env()?,
+        &cli_layer(&cli, &matches),
+        cli.profile.as_deref(),
+    )?;
+    if cli.print_config {
+        print!("{}", effective.render());
+        return Ok(());
+    }
+    let mut event = Event {
+        mode: "text",
+        outcome: Outcome::Failure,
+        bytes: 0,
+    };
+    let notify_after = cli.notify.then_some(Duration::from_secs(cli.notify_after));
+    let clip = clipboard::SystemClipboard {
+        preflight: preflight_mode(&cli),
+        strict_length: cli.strict_length,
+    };
+    let result = run(cli, &settings, &effective, &mut event, &clip);
+    if result.is_ok() {
+        event.outcome = Outcome::Success;
+    }
+    if let Some(threshold) = notify_after {
+        let elapsed = started.elapsed();
+        if notify::should_notify(elapsed, threshold) {
+            notify::send(environment::detect(), &event, elapsed);
+        }
+    }
+    metrics::record(&settings.metrics, &event);
+    if let Some(hit) = result.as_ref().err().and_then(size_guard::breach) {
+        eprintln!("Error: {}", hit);
+        std::process::exit(size_guard::EXIT_SIZE_LIMIT);
+    }
+    if let Some(empty) = result.as_ref().err().and_then(input::empty) {
+        eprintln!("Error: {}", empty);
+        std::process::exit(input::EXIT_EMPTY_INPUT);
+    }
+    result
+}
+/// Stacks config defaults, environment, the selected profile and CLI flags,
+/// lowest precedence first.
+fn resolve_effective(
+    settings: &config::Config,
+    env: &OptionLayer,
+    cli: &OptionLayer,
+    profile: Option<&str>,
+) -> Result<Effective> {
+    let mut layers = vec![
+        (options::Source::Config, &settings.defaults),
+        (options::Source::Env, env),
+    ];
+    let resolved;
+    if let Some(name) = profile {
+        let (layer, warnings) = settings.resolve_profile(name)?;
+        for w in warnings {
+            eprintln!("[WARN] {}", w);
+        }
+        resolved = layer;
+        layers.push((options::Source::Profile(name.to_string()), &resolved));
+    }
+    layers.push((options::Source::Cli, cli));
+    Ok(Effective::merge_layers(&layers))
+}
+/// `--no-preflight` / `--verify-windows-access`.
+fn preflight_mode(cli: &Cli) -> Preflight {
+    if cli.no_preflight {
+        Preflight::Off
+    } else if cli.verify_windows_access {
+        Preflight::Windows
+    } else {
+        Preflight::Local
+    }
+}
+/// Dispatches one invocation. Every copy goes through `clip`, so tests can
+/// run the whole dispatch against an in-memory clipboard.
+fn run(
+    mut cli: Cli,
+    settings: &config::Config,
+    eff: &Effective,
+    event: &mut Event,
+    clip: &dyn ClipboardBackend,
+) -> Result<()> {
+    let log = create_logger("main");
+    eff.validate()?;
+    let stage = cli.stage;
+    let strip = cli.strip_metadata.then_some(cli.jpeg_quality);
+    let effect = drop_effect(&cli);
+    log.debug(&format!("Platform: {:?}", environment::current()));
+    log.debug(&format!(
+        "Backend: {:?}",
+        clipboard::set_backend(cli.backend)
+    ));
+    clipboard::set_shell(cli.shell);
+    deadline::set(cli.timeout);
+    retry::set(cli.retries, cli.retry_delay, cli.no_retry);
+    clipboard::set_encoding(cli.encoding);
+    paths::set_unc(cli.unc.or(settings.paths.unc));
+    paths::set_follow(!cli.no_follow);
+    classifier::set_extensions(&settings.classifier);
+    classifier::set_sample_len(settings.classifier.sample_size);
+    classifier::set_fast_classify(!cli.no_fast_classify);
+    classifier::set_deep_classify(cli.deep_classify || settings.classifier.deep);
+    if let Some(fmt) = &eff.timestamp_format.value {
+        timestamp::validate_format(fmt)?;
+    }
+    let rich = rich_formats(&cli);
+    if !rich.is_empty() && cli.command.is_some() {
+        reject_rich(&cli)?;
+    }
+    if let Some(list) = cli.files_from.as_deref().filter(|_| cli.command.is_none()) {
+        let listed = input::read_file_list(list, cli.null)?;
+        let mut files = cli.files.take().unwrap_or_default();
+        files.extend(listed);
+        if files.is_empty() {
+            let name = match list.to_str() {
+                Some("-") => "stdin".to_string(),
+                _ => list.display().to_string(),
+            };
+            anyhow::bail!("--files-from: {} lists no files; nothing to copy", name);
+        }
+        cli.files = Some(files);
+    }
+    if let Some(files) = cli.files.as_ref().filter(|_| cli.command.is_none()) {
+        // A dangling link has no content to read, only a path of its own
+        if let Some(msg) = files.iter().find_map(|f| paths::dangling_message(f)) {
+            if !cli.no_follow {
+                anyhow::bail!("{} (--no-follow copies the link itself as a file)", msg);
+            }
+            if cli.force_as == Some(ClipboardStrategy::Text) {
+                anyhow::bail!("{}; text mode has nothing to read", msg);
+            }
+        }
+        if files.iter().any(|f| text_processor::is_stdin(f)) {
+            if cli.files_from.as_deref() == Some(Path::new("-")) {
+                anyhow::bail!(
+                    "`-` cannot read stdin: --files-from - already read the file list from it"
+                );
+            }
+            if cli.watch || cli.highlight {
+                anyhow::bail!(
+                    "{} reads each file again, which stdin (`-`) cannot be",
+                    if cli.watch { "--watch" } else { "--highlight" }
+                );
+            }
+            if cli.force_as.is_some_and(|s| s != ClipboardStrategy::Text) {
+                anyhow::bail!("stdin (`-`) can only be copied as text");
+            }
+            text_processor::check_stdin_placeholder(files)?;
+        }
+    }
+    if cli.recursive && cli.command.is_none() {
+        let rules = match (cli.gitignore, cli.no_gitignore) {
+            (_, true) => tree::IgnoreRules::Off,
+            (true, _) => tree::IgnoreRules::Always,
+            _ => tree::IgnoreRules::InRepo,
+        };
+        let mut filter = tree::TreeFilter::new(&cli.include, &cli.exclude, cli.hidden, rules)?;
+        filter.max_depth = cli.max_depth.map(|d| d as usize);
+        filter.max_files = cli.max_files;
+        let walk = tree::expand(cli.files.as_deref().unwrap_or_default(), &filter)?;
+        for path in &walk.binary {
+            eprintln!("[WARN] Skipped binary file: {}", path.display());
+        }
+        if !walk.roots.is_empty() {
+            println!("{}", walk.summary());
+        }
+        if walk.files.is_empty() {
+            anyhow::bail!(
+                "No text files matched; nothing to copy (the clipboard was left unchanged)"
+            );
+        }
+        cli.files = Some(walk.files);
+    }
+    match cli.command.take() {
+        Some(Commands::Img {
+            file,
+            max_dim,
+            scale,
+            width,
+            dpi,
+        }) => {
             log.debug(&format!("Command: Img, File: {:?}", file));
-            let win_path = paths::to_windows_path(&file)?;
-            clipboard::set_complex(&[win_path], ClipboardMode::Image)?;
-            println!("[OK] Copied Image to Clipboard");
+            event.mode = "image";
+            reject_manifest(cli.manifest)?;
+            reject_move(cli.move_files)?;
+            classifier::expect_image(&file)?;
+            if cli.both && (classifier::is_svg(&file) || max_dim.is_some() || scale.is_some()) {
+                anyhow::bail!(
+                    "--both copies the file as it is; it cannot be combined with \
+                     --max-dim, --scale or an SVG"
+                );
+            }
+            let mut notes = Vec::new();
+            let rendered = if classifier::is_svg(&file) {
+                let size = match (width, dpi) {
+                    (_, Some(dpi)) => transcode::SvgSize::Dpi(dpi),
+                    (width, None) => {
+                        transcode::SvgSize::Width(width.unwrap_or(transcode::DEFAULT_SVG_WIDTH))
+                    }
+                };
+                let (png, (w, h)) = transcode::render_svg(&file, size, &image_root())?;
+                log.debug(&format!("Rendered {:?} to {:?}", file, png.path()));
+                notes.push(format!("SVG rendered at {}x{}", w, h));
+                Some(png)
+            } else if width.is_some() || dpi.is_some() {
+                anyhow::bail!("--width and --dpi only apply to SVG files");
+            } else {
+                None
+            };
+            // A rendered SVG is already a clean, staged PNG removed when
+            // `rendered` drops
+            let (source, stage, strip) = match &rendered {
+                Some(png) => (png.path(), false, None),
+                None => (file.as_path(), stage, strip),
+            };
+            let resize = max_dim
+                .map(transcode::Resize::MaxDim)
+                .or(scale.map(transcode::Resize::Scale));
+            let resized = match resize {
+                Some(resize) => Some(transcode::resize(
+                    source,
+                    resize,
+                    cli.jpeg_quality,
+                    &image_root(),
+                )?),
+                None => None,
+            };
+            match resized.as_ref().map(|r| (&r.file, r.from, r.to)) {
+                Some((Some(small), (w, h), (to_w, to_h))) => {
+                    log.debug(&format!("Resized {:?} to {:?}", source, small.path()));
+                    // Written without metadata; removed when `resized` drops
+                    copy_image(clip, small.path(), false, None, false)?;
+                    notes.push(format!("{}x{} -> {}x{}", w, h, to_w, to_h));
+                }
+                Some((None, (w, h), _)) => {
+                    copy_image(clip, source, stage, strip, false)?;
+                    notes.push(format!("{}x{}, already within the limit", w, h));
+                }
+                None => copy_image(clip, source, stage, strip, cli.both)?,
+            }
+            if notes.is_empty() {
+                println!("[OK] Copied Image to Clipboard{}", both_note(cli.both));
+            } else {
+                println!("[OK] Copied Image to Clipboard ({})", notes.join("; "));
+            }
         }
-        Some(Commands::File { files }) => {
+        Some(Commands::File {
+            files,
+            skip_missing,
+        }) => {
             log.debug(&format!("Command: File, Files: {} count", files.len()));
-            let mut win_paths = Vec::new();
-            for f in files {
-                win_paths.push(paths::to_windows_path(&f)?);
+            event.mode = "file";
+            // Every argument is checked before any is converted, so all the
+            // bad ones are reported together
+            let (files, problems) = preflight::check_arguments(&files);
+            if !problems.is_empty() {
+                let total = files.len() + problems.len();
+                if !skip_missing || files.is_empty() {
+                    anyhow::bail!(preflight::arguments_message(&problems, total));
+                }
+                for problem in &problems {
+                    eprintln!(
+                        "[WARN] Skipped {} ({})",
+                        problem.path.display(),
+                        problem.reason
+                    );
+                }
             }
-            clipboard::set_complex(&win_paths, ClipboardMode::File)?;
+            let count = copy_file_objects(clip, &files, stage, cli.manifest, effect)?;
             println!(
-                "[OK] Copied {} File Object(s) to Clipboard",
-                win_paths.len()
+                "[OK] Copied {} File Object(s) to Clipboard{}{}{}",
+                count,
+                folder_note(&files),
+                manifest_note(cli.manifest),
+                move_note(effect)
             );
         }
-        Some(Commands::Path { file }) => {
-            log.debug(&format!("Command: Path, File: {:?}", file));
-            let win_path = paths::to_windows_path(&file)?;
-            clipboard::set_text_content(&win_path)?;
-            println!("[OK] Copied Path to Clipboard");
+        Some(Commands::Zip { inputs, name }) => {
+            log.debug(&format!("Command: Zip, Paths: {} count", inputs.len()));
+            event.mode = "zip";
+            reject_manifest(cli.manifest)?;
+            let (_, problems) = preflight::check_arguments(&inputs);
+            if !problems.is_empty() {
+                anyhow::bail!(preflight::arguments_message(&problems, inputs.len()));
+            }
+            let name = match name {
+                Some(name) => {
+                    check_file_name(&name)?;
+                    name
+                }
+                None if inputs.len() == 1 => {
+                    let base = archive::entry_base(&inputs[0])?;
+                    format!(
+                        "{}.zip",
+                        base.file_name().unwrap_or_default().to_string_lossy()
+                    )
+                }
+                None => "bundle.zip".to_string(),
+            };
+            let root = staging::root();
+            if !root.windows_visible {
+                anyhow::bail!("zip needs the Windows temp folder, which could not be resolved");
+            }
+            // Spooled like --as-file: kept for the paste, pruned a day later
+            let dest = root.spool().dir.join(&name);
+            let zipped = archive::create(&inputs, &dest, !cli.no_follow)?;
+            copy_file_objects(
+                clip,
+                std::slice::from_ref(&zipped.path),
+                false,
+                false,
+                effect,
+            )?;
+            event.bytes = zipped.bytes;
+            println!(
+                "[OK] Copied {} to Clipboard ({} entries, {} bytes){}",
+                name,
+                zipped.entries,
+                zipped.bytes,
+                move_note(effect)
+            );
+        }
+        Some(Commands::Url { url, fetch_timeout }) => {
+            log.debug(&format!("Command: Url, {}", url));
+            let max = cli.max_size.unwrap_or(fetch::DEFAULT_URL_MAX_SIZE);
+            let timeout = fetch_timeout.unwrap_or(fetch::DEFAULT_FETCH_TIMEOUT);
+            let download = fetch::download(&url, max, timeout)?;
+            if download.bytes.is_empty() {
+                anyhow::bail!("{} returned no content; nothing was copied", url);
+            }
+            event.bytes = download.bytes.len() as u64;
+            let name = download.name.as_deref();
+            match classifier::classify_download(
+                name,
+                download.content_type.as_deref(),
+                &download.bytes,
+            ) {
+                ClipboardStrategy::Image => {
+                    event.mode = "image";
+                    reject_manifest(cli.manifest)?;
+                    reject_move(cli.move_files)?;
+                    let ext = infer::get(&download.bytes).map_or("img", |kind| kind.extension());
+                    let mut staged = staging::StagedFile::create_in(&image_root(), "url", ext)?;
+                    staged.write_all(&download.bytes)?;
+                    staged.flush()?;
+                    // Removed when `staged` drops, after the copy
+                    copy_image(clip, staged.path(), false, strip, cli.both)?;
+                    println!(
+                        "[OK] Copied Image from {} ({} bytes){}",
+                        url,
+                        event.bytes,
+                        both_note(cli.both)
+                    );
+                }
+                ClipboardStrategy::File => {
+                    event.mode = "file";
+                    reject_manifest(cli.manifest)?;
+                    let root = staging::root();
+                    if !root.windows_visible {
+                        anyhow::bail!(
+                            "url needs the Windows temp folder for files, which could not be resolved"
+                        );
+                    }
+                    let name = match name {
+                        Some(name) => name.to_string(),
+                        None => format!(
+                            "download.{}",
+                            infer::get(&download.bytes).map_or("bin", |kind| kind.extension())
+                        ),
+                    };
+                    let (path, _) = spool_stdin(download.bytes.as_slice(), Some(&name), root)?;
+                    copy_file_objects(clip, std::slice::from_ref(&path), false, false, effect)?;
+                    println!(
+                        "[OK] Copied {} to Clipboard ({} bytes){}",
+                        path.file_name().unwrap_or_default().to_string_lossy(),
+                        event.bytes,
+                        move_note(effect)
+                    );
+                }
+                ClipboardStrategy::Text => {
+                    event.mode = "text";
+                    reject_manifest(cli.manifest)?;
+                    reject_move(cli.move_files)?;
+                    if cli.cite {
+                        anyhow::bail!(
+                            "--cite needs file arguments: a download has no path to cite"
+                        );
+                    }
+                    // Like files, the text is already at hand: no stdin to settle
+                    let opts = cli_text_options(&cli, settings, eff, true)?;
+                    // Processed like `curl -sL URL | wsl-clip`, then copied in one go
+                    let mut buffer = Vec::new();
+                    let mut guard =
+                        size_guard::GuardWriter::new(&mut buffer, cli.max_size.unwrap_or(u64::MAX));
+                    let report = text_processor::process_bytes(&download.bytes, &opts, &mut guard)?;
+                    for warning in &report.warnings {
+                        eprintln!("[WARN] {}", warning);
+                    }
+                    let mut capture = start_history(settings, cli.no_history, opts.encoding);
+                    let mut stream = clip.start_text_stream()?;
+                    let Some(writer) = &mut stream.stdin else {
+                        anyhow::bail!("Failed to acquire stdin for clip.exe");
+                    };
+                    FanoutWriter::new(writer, capture.as_mut()).write_all(&buffer)?;
+                    stream.wait()?;
+                    finish_history(capture, settings);
+                    event.bytes = buffer.len() as u64;
+                    println!("[OK] Copied Text from {} ({} bytes)", url, event.bytes);
+                }
+            }
+        }
+        Some(Commands::Path {
+            files,
+            sep,
+            wsl,
+            no_quote,
+            literal,
+            slash,
+            quote,
+        }) => {
+            log.debug(&format!("Command: Path, Files: {:?}", files));
+            event.mode = "path";
+            reject_manifest(cli.manifest)?;
+            reject_move(cli.move_files)?;
+            if files.iter().any(|f| f.as_os_str() == ".") {
+                std::env::current_dir().context(
+                    "The current directory no longer exists (deleted underneath the shell?)",
+                )?;
+            }
+            let quoted = |p: String| match quote {
+                Some(style) => quoting::quote(&p, style),
+                None => p,
+            };
+            let windows = |f: &PathBuf| -> Result<String> {
+                let win_path = if literal {
+                    paths::to_windows_path_literal(f)?
+                } else {
+                    paths::to_windows_path(f)?
+                };
+                Ok(if slash {
+                    paths::forward_slashes(&win_path)
+                } else {
+                    win_path
+                })
+            };
+            let convert = |f: &PathBuf| -> Result<String> {
+                if wsl {
+                    Ok(quoted(paths::to_wsl_path(f, literal)?))
+                } else if cli.both {
+                    // Two lines per file, whatever --sep says
+                    Ok(format!(
+                        "{}\n{}",
+                        quoted(windows(f)?),
+                        quoted(paths::to_wsl_path(f, literal)?)
+                    ))
+                } else {
+                    Ok(quoted(windows(f)?))
+                }
+            };
+            let converted = files.iter().map(convert).collect::<Result<Vec<_>>>()?;
+            let text = if cli.both {
+                converted.join("\n")
+            } else {
+                // An explicit --quote already did the quoting
+                paths::join_paths(&converted, &sep, !no_quote && quote.is_none())
+            };
+            clip.set_text(&text)?;
+            event.bytes = text.len() as u64;
+            let kind = match (wsl, cli.both) {
+                (true, _) => "WSL ",
+                (_, true) => "Windows and WSL ",
+                _ => "",
+            };
+            if converted.len() == 1 {
+                // Shown, since `wsl-clip path` alone copies whatever $PWD is
+                println!(
+                    "[OK] Copied {}Path to Clipboard: {}",
+                    kind,
+                    converted[0].replace('\n', " | ")
+                );
+            } else {
+                println!("[OK] Copied {} {}Paths to Clipboard", converted.len(), kind);
+            }
+        }
+        Some(Commands::Peek {
+            max_lines,
+            max_bytes,
+        }) => {
+            log.debug("Command: Peek");
+            event.mode = "peek";
+            let text = match clipboard::read_clipboard()? {
+                ClipboardContent::Text(text) => text,
+                ClipboardContent::Other(format) => {
+                    println!("Clipboard holds non-text data: {}", format);
+                    return Ok(());
+                }
+                ClipboardContent::Empty => {
+                    println!("Clipboard is empty");
+                    return Ok(());
+                }
+            };
+            let analysis = peek::analyze(&text);
+            let (shown, truncated) = peek::preview(&text, max_lines, max_bytes);
+            print!("{}", peek::render_safe(&shown));
+            if !shown.is_empty() && !shown.ends_with('\n') {
+                println!();
+            }
+            let mut summary = format!("--- {} bytes, {} lines", analysis.bytes, analysis.lines);
+            if truncated {
+                summary.push_str(&format!(
+                    " (preview: first {} lines / {} bytes)",
+                    max_lines, max_bytes
+                ));
+            }
+            println!("{}", summary);
+            if !analysis.hints.is_empty() {
+                let hints: Vec<String> = analysis.hints.iter().map(|h| h.to_string()).collect();
+                println!("Hints: {}", hints.join(", "));
+            }
+            for warning in &analysis.warnings {
+                eprintln!("[WARN] {}", warning);
+            }
+        }
+        Some(Commands::Paste {
+            image: Some(dest), ..
+        }) => {
+            log.debug(&format!("Command: Paste, Image: {:?}", dest));
+            event.mode = "paste-image";
+            event.bytes = paste::save_image(&dest)?;
+            println!(
+                "[OK] Saved clipboard image to {} ({} bytes)",
+                dest.display(),
+                event.bytes
+            );
+        }
+        Some(Commands::Paste { lf, output, .. }) => {
+            log.debug(&format!("Command: Paste, LF: {}, Output: {:?}", lf, output));
+            event.mode = "paste";
+            event.bytes = paste::run(lf, output.as_deref())?;
+            log.debug(&format!("Pasted {} bytes", event.bytes));
+            if let Some(path) = &output {
+                println!("[OK] Pasted {} bytes to {}", event.bytes, path.display());
+            }
+        }
+        Some(Commands::Shot {
+            region,
+            all_screens,
+            out,
+            wait,
+            poll,
+        }) => {
+            let area = if region {
+                clipboard::ShotArea::Region {
+                    wait: wait.unwrap_or(clipboard::DEFAULT_SNIP_WAIT),
+                    poll: poll.unwrap_or(clipboard::DEFAULT_SNIP_POLL),
+                }
+            } else if all_screens {
+                clipboard::ShotArea::AllScreens
+            } else {
+                clipboard::ShotArea::Primary
+            };
+            log.debug(&format!("Command: Shot, {:?}, Out: {:?}", area, out));
+            event.mode = "shot";
+            let (shot, saved) = match &out {
+                Some(dest) => {
+                    let (shot, bytes) = paste::save_screenshot(area, dest)?;
+                    event.bytes = bytes;
+                    let note = format!(", saved to {} ({} bytes)", dest.display(), bytes);
+                    (shot, note)
+                }
+                None => (clipboard::screenshot(area, None)?, String::new()),
+            };
+            println!(
+                "[OK] Copied Screenshot to Clipboard ({}x{}){}",
+                shot.size.width, shot.size.height, saved
+            );
+        }
+        Some(Commands::Inspect { json }) => {
+            log.debug("Command: Inspect");
+            event.mode = "inspect";
+            let info = clipboard::inspect_clipboard()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                print!("{}", info.render_table());
+            }
+        }
+        Some(Commands::Classify { files, json }) => {
+            log.debug(&format!("Command: Classify, Files: {:?}", files));
+            event.mode = "classify";
+            let mut failed = 0;
+            let mut entries = Vec::new();
+            for file in &files {
+                match classifier::explain(file) {
+                    Ok(found) if json => entries.push(serde_json::json!({
+                        "path": file,
+                        "strategy": found.strategy,
+                        "rule": found.rule,
+                        "mime": found.mime,
+                    })),
+                    Ok(found) => println!(
+                        "{}: {:?} ({}{})",
+                        file.display(),
+                        found.strategy,
+                        found.rule,
+                        found.mime.map(|m| format!(", {}", m)).unwrap_or_default()
+                    ),
+                    Err(e) => {
+                        failed += 1;
+                        if json {
+                            entries.push(serde_json::json!({
+                                "path": file,
+                                "error": format!("{:#}", e),
+                            }));
+                        } else {
+                            eprintln!("[ERR] {}: {:#}", file.display(), e);
+                        }
+                    }
+                }
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            }
+            if failed > 0 {
+                anyhow::bail!(
+                    "{} of {} files could not be classified",
+                    failed,
+                    files.len()
+                );
+            }
+        }
+        Some(Commands::Clear { after, detached }) => {
+            log.debug(&format!("Command: Clear, After: {:?}", after));
+            event.mode = "clear";
+            match after {
+                Some(delay) if detached => {
+                    std::thread::sleep(delay);
+                    clipboard::clear()?;
+                }
+                Some(delay) => {
+                    let pid = clear::schedule(delay)?;
+                    println!(
+                        "[OK] Clipboard will be cleared in {}s (background pid {})",
+                        delay.as_secs(),
+                        pid
+                    );
+                }
+                None => {
+                    clipboard::clear()?;
+                    println!("[OK] Clipboard cleared");
+                }
+            }
+        }
+        Some(Commands::Save {
+            name: None, list, ..
+        }) => {
+            log.debug(&format!("Command: Save, List: {}", list));
+            event.mode = "save";
+            let regs = registers::list(&registers::dir()?)?;
+            print!(
+                "{}",
+                registers::render_list(&regs, std::time::SystemTime::now())
+            );
+        }
+        Some(Commands::Save {
+            name: Some(name),
+            files,
+            ..
+        }) => {
+            log.debug(&format!(
+                "Command: Save, Name: {}, Files: {:?}",
+                name, files
+            ));
+            event.mode = "save";
+            let files = (!files.is_empty()).then_some(files);
+            let opts = cli_text_options(&cli, settings, eff, files.is_some())?;
+            let mut slot = registers::Slot::create(&registers::dir()?, &name)?;
+            let report = text_processor::process_input(files, &opts, &mut slot)?;
+            for warning in &report.warnings {
+                eprintln!("[WARN] {}", warning);
+            }
+            event.bytes = slot.commit()?;
+            println!("[OK] Saved {} bytes to register '{}'", event.bytes, name);
+        }
+        Some(Commands::Load { name }) => {
+            log.debug(&format!("Command: Load, Name: {}", name));
+            event.mode = "load";
+            let mut file = registers::open(&registers::dir()?, &name)?;
+            let mut stream = clip.start_text_stream()?;
+            let Some(writer) = stream.stdin.as_mut() else {
+                anyhow::bail!("Failed to acquire stdin for clip.exe");
+            };
+            event.bytes = std::io::copy(&mut file, writer)?;
+            stream.wait()?;
+            println!("[OK] Loaded register '{}' ({} bytes)", name, event.bytes);
+        }
+        Some(Commands::Daemon { action }) => {
+            let path = daemon::socket_path();
+            match action {
+                None => {
+                    log.debug(&format!("Command: Daemon, Socket: {:?}", path));
+                    event.mode = "daemon";
+                    daemon::serve(&path)?;
+                }
+                Some(DaemonCommand::Stop) => {
+                    log.debug(&format!("Command: Daemon Stop, Socket: {:?}", path));
+                    event.mode = "daemon-stop";
+                    if !daemon::stop(&path)? {
+                        anyhow::bail!("No daemon is listening on {}", path.display());
+                    }
+                    println!("[OK] Daemon stopped");
+                }
+            }
+        }
+        Some(Commands::History { action }) => {
+            let dir = settings.history.resolved_dir()?;
+            match action {
+                HistoryCommand::List { limit } => {
+                    log.debug(&format!("Command: History List, Dir: {:?}", dir));
+                    event.mode = "history";
+                    print!(
+                        "{}",
+                        history::render_list(&history::read_index(&dir)?, limit)
+                    );
+                    if !settings.history.enabled {
+                        eprintln!("[WARN] History is off; enable it with [history] enabled = true");
+                    }
+                }
+                HistoryCommand::Restore { id } => {
+                    log.debug(&format!("Command: History Restore, Id: {}", id));
+                    event.mode = "history-restore";
+                    let entry = history::find(&dir, &id)?;
+                    let path = history::entry_file(&dir, &entry);
+                    let mut file = std::fs::File::open(&path)
+                        .with_context(|| format!("Failed to open history entry: {:?}", path))?;
+                    let mut stream = clip.start_text_stream()?;
+                    let Some(writer) = stream.stdin.as_mut() else {
+                        anyhow::bail!("Failed to acquire stdin for clip.exe");
+                    };
+                    event.bytes = std::io::copy(&mut file, writer)?;
+                    stream.wait()?;
+                    println!("[OK] Restored {} ({} bytes)", entry.id, event.bytes);
+                    if entry.truncated {
+                        eprintln!(
+                            "[WARN] Entry was stored truncated: {} of {} bytes restored",
+                            entry.captured, entry.bytes
+                        );
+                    }
+                }
+            }
+        }
+        Some(Commands::ExportState { file, history }) => {
+            log.debug(&format!("Command: ExportState, File: {:?}", file));
+            event.mode = "export-state";
+            let targets = state_bundle::Targets::current(settings);
+            let members = state_bundle::export(&file, &targets, history)?;
+            println!(
+                "[OK] Exported {} item(s) to {}",
+                members.len(),
+                file.display()
+            );
+            if !history {
+                println!("History not included (use --history to add it)");
+            }
+        }
+        Some(Commands::ImportState {
+            file,
+            overwrite,
+            merge,
+        }) => {
+            log.debug(&format!("Command: ImportState, File: {:?}", file));
+            event.mode = "import-state";
+            let mode = if overwrite {
+                state_bundle::Conflict::Overwrite
+            } else if merge {
+                state_bundle::Conflict::Merge
+            } else {
+                state_bundle::Conflict::Abort
+            };
+            let targets = state_bundle::Targets::current(settings);
+            let report = state_bundle::import(&file, &targets, mode)?;
+            for note in &report.notes {
+                println!("  {}", note);
+            }
+            println!(
+                "[OK] Imported {} file(s) ({} already up to date)",
+                report.written.len(),
+                report.unchanged
+            );
+        }
+        None if cli.provider.is_some() => provider_mode(&cli, event, clip)?,
+        None if cli.watch => watch_text(&cli, settings, eff, event, clip)?,
+        None if cli.image => {
+            log.debug("Command: Image from stdin");
+            event.mode = "image";
+            reject_manifest(cli.manifest)?;
+            reject_move(cli.move_files)?;
+            input::require_stdin()?;
+            let (staged, bytes) = stage_image(std::io::stdin().lock(), &image_root())?;
+            // Dropping `staged` removes the file, on success or failure
+            copy_image(clip, staged.path(), false, strip, false)?;
+            event.bytes = bytes;
+            println!(
+                "[OK] Copied Image to Clipboard ({} bytes from stdin)",
+                bytes
+            );
+        }
+        None if cli.as_file => {
+            log.debug("Command: File from stdin");
+            event.mode = "file";
+            input::require_stdin()?;
+            let (path, bytes) = spool_stdin(
+                std::io::stdin().lock(),
+                cli.name.as_deref(),
+                staging::root(),
+            )?;
+            copy_file_objects(
+                clip,
+                std::slice::from_ref(&path),
+                false,
+                cli.manifest,
+                effect,
+            )?;
+            event.bytes = bytes;
+            println!(
+                "[OK] Copied {} as a File Object ({} bytes from stdin){}{}",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                bytes,
+                manifest_note(cli.manifest),
+                move_note(effect)
+            );
         }
         None => {
             // Smart Mode Dispatch
@@ -120,14 +1510,49 @@ fn main() -> Result<()> {
                     let mut img_count = 0;
                     let mut file_count = 0;
                     let mut text_count = 0;
-                    for f in files {
-                        match classifier::inspect(f) {
-                            Ok(ClipboardStrategy::Image) => img_count += 1,
-                            Ok(ClipboardStrategy::File) => file_count += 1,
-                            Ok(ClipboardStrategy::Text) => text_count += 1,
+                    let forced = forced_note(cli.force_as);
+                    match cli.force_as {
+                        Some(ClipboardStrategy::Image) if files.len() != 1 => anyhow::bail!(
+                            "--as image takes exactly one file ({} given); use --as file for several",
+                            files.len()
+                        ),
+                        Some(ClipboardStrategy::Image) => {
+                            classifier::expect_image(&files[0])?;
+                            img_count = 1;
+                        }
+                        Some(ClipboardStrategy::File) => file_count = files.len(),
+                        Some(ClipboardStrategy::Text) => text_count = files.len(),
+                        None => {}
+                    }
+                    let mut groups = SplitGroups::default();
+                    for f in files.iter().filter(|_| cli.force_as.is_none()) {
+                        // `-` and pipes (`<(git diff)`) are text read where they stand
+                        // in the bundle; sniffing would eat bytes they cannot give again
+                        let inspected =
+                            if text_processor::is_stdin(f) || text_processor::is_stream(f) {
+                                Ok(ClipboardStrategy::Text)
+                            } else if paths::dangling_target(f).is_some() {
+                                // Only under --no-follow: the link goes as a file object
+                                Ok(ClipboardStrategy::File)
+                            } else {
+                                classifier::inspect(f)
+                            };
+                        match inspected {
+                            Ok(ClipboardStrategy::Image) => {
+                                img_count += 1;
+                                groups.images.push(f.clone());
+                            }
+                            Ok(ClipboardStrategy::File) => {
+                                file_count += 1;
+                                groups.files.push(f.clone());
+                            }
+                            Ok(ClipboardStrategy::Text) => {
+                                text_count += 1;
+                                groups.text.push(f.clone());
+                            }
                             Err(e) => {
                                 log.warn(&format!("Classification failed for {:?}: {}", f, e));
-                                anyhow::bail!("Failed to read file: {:?}", f);
+                                anyhow::bail!("{}", read_failure_message(f));
                             }
                         }
                     }
@@ -135,42 +1560,75 @@ fn main() -> Result<()> {
                     let categories_present =
                         (img_count > 0) as u8 + (file_count > 0) as u8 + (text_count > 0) as u8;
                     if categories_present > 1 {
-                        anyhow::bail!(
-                            "Mixed content detected! ({} images, {} files/assets, {} text). \
-                            Please run separate commands for each type.",
-                            img_count,
-                            file_count,
-                            text_count
-                        );
+                        match cli.split {
+                            None => anyhow::bail!(
+                                "Mixed content detected! ({} images, {} files/assets, {} text). \
+                                Please run separate commands for each type, or pass --split.",
+                                img_count,
+                                file_count,
+                                text_count
+                            ),
+                            Some(SplitMode::Files) => {
+                                log.debug("Smart Mode: Mixed content -> File Mode (--split=files)");
+                                event.mode = "file";
+                                let count =
+                                    copy_file_objects(clip, files, stage, cli.manifest, effect)?;
+                                println!(
+                                    "[OK] Copied {} Files{} (mixed content as one drop list, --split=files)",
+                                    count,
+                                    move_note(effect)
+                                );
+                                return Ok(());
+                            }
+                            Some(SplitMode::Sequential) => {
+                                return split_copy(&cli, settings, eff, event, clip, groups);
+                            }
+                        }
+                    }
+                    if !rich.is_empty() && text_count == 0 {
+                        reject_rich(&cli)?;
                     }
                     // 2. Image Mode
                     if img_count > 0 {
                         if files.len() == 1 {
                             log.debug("Smart Mode: Single Image");
-                            let win_path = paths::to_windows_path(&files[0])?;
-                            clipboard::set_complex(&[win_path], ClipboardMode::Image)?;
-                            println!("[OK] Copied Image to Clipboard");
+                            event.mode = "image";
+                            reject_manifest(cli.manifest)?;
+                            reject_move(cli.move_files)?;
+                            copy_image(clip, &files[0], stage, strip, cli.both)?;
+                            println!(
+                                "[OK] Copied Image to Clipboard{}{}",
+                                both_note(cli.both),
+                                forced
+                            );
                             return Ok(());
                         } else {
                             log.debug("Smart Mode: Multiple Images -> File Mode");
-                            let mut win_paths = Vec::new();
-                            for f in files {
-                                win_paths.push(paths::to_windows_path(f)?);
-                            }
-                            clipboard::set_complex(&win_paths, ClipboardMode::File)?;
-                            println!("[OK] Copied {} Images as Files", win_paths.len());
+                            event.mode = "file";
+                            let count =
+                                copy_file_objects(clip, files, stage, cli.manifest, effect)?;
+                            println!(
+                                "[OK] Copied {} Images as Files{}{}",
+                                count,
+                                manifest_note(cli.manifest),
+                                move_note(effect)
+                            );
                             return Ok(());
                         }
                     }
                     // 3. File/Asset Mode
                     if file_count > 0 {
                         log.debug("Smart Mode: Files/Assets detected");
-                        let mut win_paths = Vec::new();
-                        for f in files {
-                            win_paths.push(paths::to_windows_path(f)?);
-                        }
-                        clipboard::set_complex(&win_paths, ClipboardMode::File)?;
-                        println!("[OK] Copied {} Files", win_paths.len());
+                        event.mode = "file";
+                        let count = copy_file_objects(clip, files, stage, cli.manifest, effect)?;
+                        println!(
+                            "[OK] Copied {} Files{}{}{}{}",
+                            count,
+                            folder_note(files),
+                            manifest_note(cli.manifest),
+                            move_note(effect),
+                            forced
+                        );
                         return Ok(());
                     }
                     log.debug("Smart Mode: Text Mode");
@@ -178,34 +1636,1558 @@ fn main() -> Result<()> {
             }
             // 4. Default / Text Mode (Streaming)
             log.debug("Command: Default (Text Mode)");
-            let opts = TextOptions {
-                no_header: cli.no_header,
-                strip_ansi: !cli.no_strip,
-                use_markdown: cli.code,
-                use_crlf: cli.crlf,
-            };
+            reject_manifest(cli.manifest)?;
+            reject_move(cli.move_files)?;
+            let opts = cli_text_options(&cli, settings, eff, cli.files.is_some())?;
+            if cli.debug || cli.max_size.is_some() {
+                let est = estimate::estimate(cli.files.as_deref(), &opts)?;
+                log.debug(&format!(
+                    "Estimated output: {} bytes (lower bound {}, {:?}) from {} input bytes",
+                    est.expected, est.lower, est.confidence, est.input_bytes
+                ));
+                // File sizes are known up front: refuse before clip.exe starts
+                if let Some(limit) = cli.max_size.filter(|&l| est.lower > l) {
+                    return Err(size_guard::SizeLimitExceeded { limit, seen: 0 }.into());
+                }
+            }
+            if !rich.is_empty() {
+                event.mode = match rich[..] {
+                    [Format::Html] if cli.render_md => "markdown",
+                    [Format::Html] if cli.highlight => "highlight",
+                    [Format::Html] => "html",
+                    [Format::Rtf] => "rtf",
+                    _ => "rich",
+                };
+                let source = html_source(&cli);
+                event.bytes = copy_rich(clip, cli.files, opts, cli.max_size, &rich, source)?;
+                println!(
+                    "[OK] Copied {} and plain text ({} bytes)",
+                    clipboard::formats_label(&rich),
+                    event.bytes
+                );
+                return Ok(());
+            }
+            let mut capture = start_history(settings, cli.no_history, opts.encoding);
+            let requested = cli.files.as_ref().map_or(0, Vec::len);
+            let empty = |f: &PathBuf| f.metadata().is_ok_and(|m| m.is_file() && m.len() == 0);
+            if requested > 0 && !opts.keep_empty && cli.files.iter().flatten().all(empty) {
+                anyhow::bail!(
+                    "Every file is empty; nothing to copy (the clipboard was left unchanged). --keep-empty copies their headers"
+                );
+            }
+            let included;
             // Start the clip.exe process first to get the pipe
-            let mut stream = clipboard::start_text_stream()?;
+            let mut stream = clip.start_text_stream()?;
             if let Some(writer) = &mut stream.stdin {
-                // Stream content directly to the pipe
-                text_processor::process_input(cli.files, &opts, writer)?;
+                // Stream content directly to the pipe, teeing into history at the very end
+                let mut fanout = FanoutWriter::new(writer, capture.as_mut());
+                let mut counter = CountingWriter::new(&mut fanout);
+                let from_stdin = cli.files.is_none();
+                let mut guard =
+                    size_guard::GuardWriter::new(&mut counter, cli.max_size.unwrap_or(u64::MAX));
+                let result = text_processor::process_input(cli.files, &opts, &mut guard);
+                let report = match result {
+                    Err(e) if size_guard::breach(&e).is_some() => {
+                        event.bytes = counter.bytes;
+                        stream.abort()?;
+                        if from_stdin && !cli.no_drain {
+                            let drained =
+                                size_guard::drain(std::io::stdin(), size_guard::DRAIN_LIMIT);
+                            log.debug(&format!(
+                                "Drained {} bytes of stdin after --max-size",
+                                drained
+                            ));
+                        }
+                        return Err(e);
+                    }
+                    Err(e) if encoding::unmappable(&e).is_some() => {
+                        // --strict-encoding: the text so far is not committed
+                        stream.abort()?;
+                        return Err(e);
+                    }
+                    other => other?,
+                };
+                event.bytes = counter.bytes;
+                included = report.sent.len();
+                log.debug(&format!("Actual output: {} bytes", counter.bytes));
+                for warning in &report.warnings {
+                    eprintln!("[WARN] {}", warning);
+                }
             } else {
                 anyhow::bail!("Failed to acquire stdin for clip.exe");
             }
             // Wait for clip.exe to finish
             stream.wait()?;
+            finish_history(capture, settings);
             let mut msg = "[OK] Copied Text".to_string();
-            if cli.no_strip {
+            if requested > 1 {
+                msg.push_str(&format!(" from {} of {} files", included, requested));
+            }
+            msg.push_str(&forced_note(cli.force_as));
+            if !opts.strip_ansi {
                 msg.push_str(" (Raw ANSI)");
             }
             if opts.use_crlf {
                 msg.push_str(" (CRLF)");
             }
+            if opts.single_line.is_some() {
+                msg.push_str(" (Single Line)");
+            }
+            if let Some(encoding) = opts.encoding {
+                msg.push_str(&format!(" ({})", encoding.label()));
+            }
             println!("{}", msg);
         }
     }
     Ok(())
 }
+/// Starts recording a text copy for `wsl-clip history`, unless history is
+/// off, skipped with --no-history, or the text is not UTF-8.
+fn start_history(
+    settings: &config::Config,
+    no_history: bool,
+    encoding: Option<TextEncoding>,
+) -> Option<history::Capture> {
+    let log = create_logger("main");
+    if !settings.history.enabled {
+        None
+    } else if no_history {
+        log.debug("History skipped (--no-history)");
+        None
+    } else if encoding.is_some_and(|e| e != TextEncoding::Utf8) {
+        // History entries are UTF-8 text
+        log.debug("History skipped (--encoding)");
+        None
+    } else {
+        history::Capture::start(&settings.history)
+            .map_err(|e| log.debug(&format!("History disabled for this copy: {:#}", e)))
+            .ok()
+    }
+}
+/// Commits a capture once the clipboard has the text, then evicts entries
+/// past the configured limits.
+fn finish_history(capture: Option<history::Capture>, settings: &config::Config) {
+    let log = create_logger("main");
+    let Some(entry) = capture.and_then(history::Capture::finish) else {
+        return;
+    };
+    log.debug(&format!(
+        "History entry {} ({} bytes, truncated: {})",
+        entry.id, entry.captured, entry.truncated
+    ));
+    match settings
+        .history
+        .resolved_dir()
+        .and_then(|dir| history::evict(&dir, &settings.history))
+    {
+        Ok(evicted) if !evicted.is_empty() => {
+            log.debug(&format!("Evicted {} old history entries", evicted.len()))
+        }
+        Ok(_) => {}
+        Err(e) => log.debug(&format!("History eviction failed: {:#}", e)),
+    }
+}
+/// The Linux paths to copy as File/Image objects: Linux-filesystem inputs
+/// are staged when asked, and ones Windows cannot reach (WSL1) refused.
+/// The backend converts them and runs the reachability pre-flight.
+fn object_sources(files: &[PathBuf], stage: bool) -> Result<Vec<PathBuf>> {
+    let platform = environment::current();
+    let mut sources = Vec::new();
+    for f in files {
+        let abs = dunce::canonicalize(f).unwrap_or_else(|_| f.clone());
+        if stage && !environment::is_windows_drive_path(&abs) {
+            if f.is_dir() {
+                anyhow::bail!(
+                    "--stage copies single files; {} is a folder (copy it without --stage)",
+                    f.display()
+                );
+            }
+            sources.push(stage_copy(f)?);
+            continue;
+        }
+        if let Some(msg) = environment::object_path_error(platform, &abs) {
+            anyhow::bail!(msg);
+        }
+        // `project/` and `project` are the same drop-list entry
+        sources.push(f.components().collect());
+    }
+    Ok(sources)
+}
+/// One image as pixels. With `strip` (--strip-metadata, holding the JPEG
+/// quality), a re-encoded copy without metadata goes instead; formats that
+/// cannot be rewritten go as they are, with a warning. With `both`
+/// (--both), the file goes too, as a drop list in the same copy.
+fn copy_image(
+    clip: &dyn ClipboardBackend,
+    file: &Path,
+    stage: bool,
+    strip: Option<u8>,
+    both: bool,
+) -> Result<()> {
+    if let Some(quality) = strip {
+        match transcode::strip_metadata(file, quality, &image_root())? {
+            transcode::Stripped::Rewritten(clean) => {
+                create_logger("main").debug(&format!(
+                    "Metadata stripped: {:?} -> {:?}",
+                    file,
+                    clean.path()
+                ));
+                // Removed when `clean` drops, after the copy
+                return clip.set_image(clean.path());
+            }
+            transcode::Stripped::NothingToStrip => {}
+            transcode::Stripped::Unsupported(format) => eprintln!(
+                "[WARN] --strip-metadata cannot rewrite {} images; copying {} as it is",
+                format,
+                file.display()
+            ),
+        }
+    }
+    let sources = object_sources(&[file.to_path_buf()], stage)?;
+    if both {
+        return clip.set_image_and_file(&sources[0]);
+    }
+    clip.set_image(&sources[0])
+}
+/// Where images made on the fly (stdin, resized) are written: the Windows
+/// temp folder, or /tmp for a native Linux clipboard tool.
+fn image_root() -> staging::StagingRoot {
+    if clipboard::backend().is_native() {
+        staging::StagingRoot::local()
+    } else {
+        staging::root().clone()
+    }
+}
+/// Buffers image bytes from `input` into a staged file named for their type
+/// (`stdin-image.png`), checked by magic bytes. Returns the file and its size.
+fn stage_image(
+    mut input: impl Read,
+    root: &staging::StagingRoot,
+) -> Result<(staging::StagedFile, u64)> {
+    let mut head = Vec::new();
+    input
+        .by_ref()
+        .take(262)
+        .read_to_end(&mut head)
+        .context("Failed to read stdin")?;
+    let ext = match infer::get(&head).map(|kind| kind.extension()) {
+        Some(ext @ ("png" | "jpg" | "gif" | "bmp")) => ext,
+        Some(other) => anyhow::bail!(
+            "stdin is not a PNG, JPEG, GIF or BMP image (it looks like .{})",
+            other
+        ),
+        None => anyhow::bail!("stdin is not a PNG, JPEG, GIF or BMP image"),
+    };
+    let mut staged = staging::StagedFile::create_in(root, "stdin-image", ext)?;
+    staged.write_all(&head)?;
+    let bytes = head.len() as u64
+        + std::io::copy(&mut input, &mut staged).context("Failed to stage stdin image")?;
+    staged.flush()?;
+    if classifier::inspect(staged.path())? != ClipboardStrategy::Image {
+        anyhow::bail!("stdin is not an image");
+    }
+    Ok((staged, bytes))
+}
+/// Streams `data` into a file of its own under the staging root's spool
+/// directory and keeps it there, since a pasted file object is read long
+/// after wsl-clip exits. Spooled files older than a day are removed first.
+/// The file is named `name`, or "stdin" plus the extension its magic bytes
+/// suggest (`.bin` when they suggest nothing).
+fn spool_stdin(
+    mut data: impl Read,
+    name: Option<&str>,
+    root: &staging::StagingRoot,
+) -> Result<(PathBuf, u64)> {
+    if !root.windows_visible {
+        anyhow::bail!("--as-file needs the Windows temp folder, which could not be resolved");
+    }
+    if let Some(name) = name {
+        check_file_name(name)?;
+    }
+    let dir = root.spool();
+    let mut head = Vec::new();
+    data.by_ref()
+        .take(262)
+        .read_to_end(&mut head)
+        .context("Failed to read stdin")?;
+    if head.is_empty() {
+        return Err(input::EmptyInput(input::InputSource::Empty).into());
+    }
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => format!(
+            "stdin.{}",
+            infer::get(&head).map_or("bin", |kind| kind.extension())
+        ),
+    };
+    let path = Path::new(&name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().unwrap_or_default().to_string_lossy();
+    let mut staged = staging::StagedFile::create_in(&dir, &stem, &ext)?;
+    staged.write_all(&head)?;
+    let bytes = head.len() as u64
+        + std::io::copy(&mut data, &mut staged).context("Failed to spool stdin")?;
+    staged.flush()?;
+    Ok((staged.keep(), bytes))
+}
+/// `--name` values are file names, never paths.
+fn check_file_name(name: &str) -> Result<()> {
+    if Path::new(name).file_name() != Some(std::ffi::OsStr::new(name)) {
+        anyhow::bail!("--name takes a file name, not a path: {:?}", name);
+    }
+    Ok(())
+}
+/// Copies `files` as a drop list. With --manifest, MANIFEST.sha256 joins the
+/// list and its text becomes the plain-text format. `effect` is --move's.
+/// Returns the file count.
+fn copy_file_objects(
+    clip: &dyn ClipboardBackend,
+    files: &[PathBuf],
+    stage: bool,
+    manifest: bool,
+    effect: DropEffect,
+) -> Result<usize> {
+    let mut sources = object_sources(files, stage)?;
+    if !manifest {
+        clip.set_file_drop(&sources, None, effect)?;
+        return Ok(sources.len());
+    }
+    if let Some(dir) = files.iter().find(|f| f.is_dir()) {
+        anyhow::bail!(
+            "--manifest checksums files; {} is a folder (list the files inside it instead)",
+            dir.display()
+        );
+    }
+    let text = checksum::manifest(files)?;
+    sources.push(stage_manifest(&text)?);
+    clip.set_file_drop(&sources, Some(text), effect)?;
+    Ok(files.len())
+}
+/// Writes the manifest into its own staging subdirectory so it keeps the
+/// exact name MANIFEST.sha256 when pasted.
+fn stage_manifest(text: &str) -> Result<PathBuf> {
+    let root = staging::root();
+    if !root.windows_visible {
+        anyhow::bail!("--manifest needs the Windows temp folder, which could not be resolved");
+    }
+    let dir = root.subdir(&format!(
+        "manifest-{}-{}",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+        std::process::id()
+    ));
+    let (stem, ext) = checksum::MANIFEST_NAME
+        .split_once('.')
+        .unwrap_or((checksum::MANIFEST_NAME, ""));
+    let mut staged = staging::StagedFile::create_in(&dir, stem, ext)?;
+    staged
+        .write_all(text.as_bytes())
+        .context("Failed to write MANIFEST.sha256")?;
+    Ok(staged.keep())
+}
+fn manifest_note(manifest: bool) -> &'static str {
+    if manifest {
+        " with MANIFEST.sha256"
+    } else {
+        ""
+    }
+}
+/// " (2 files, 1 folder)" when `files` includes folders, else nothing.
+fn folder_note(files: &[PathBuf]) -> String {
+    let folders = files.iter().filter(|f| f.is_dir()).count();
+    if folders == 0 {
+        return String::new();
+    }
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    match files.len() - folders {
+        0 => format!(" ({})", plural(folders, "folder")),
+        n => format!(" ({}, {})", plural(n, "file"), plural(folders, "folder")),
+    }
+}
+fn move_note(effect: DropEffect) -> &'static str {
+    match effect {
+        DropEffect::Copy => "",
+        DropEffect::Move => " (cut: pasting moves them)",
+    }
+}
+fn both_note(both: bool) -> &'static str {
+    if both {
+        " (pixels and file)"
+    } else {
+        ""
+    }
+}
+/// Success-line suffix for a mode picked with `--as`.
+fn forced_note(forced: Option<ClipboardStrategy>) -> String {
+    forced
+        .and_then(|s| clap::ValueEnum::to_possible_value(&s))
+        .map(|v| format!(" (forced with --as {})", v.get_name()))
+        .unwrap_or_default()
+}
+/// `--move` cuts file objects; everything else copies.
+fn drop_effect(cli: &Cli) -> DropEffect {
+    if cli.move_files {
+        DropEffect::Move
+    } else {
+        DropEffect::Copy
+    }
+}
+fn reject_move(move_files: bool) -> Result<()> {
+    if move_files {
+        anyhow::bail!(
+            "--move only applies to file-object copies; images and text are always copied \
+             (try `wsl-clip file --move ...`)"
+        );
+    }
+    Ok(())
+}
+fn reject_manifest(manifest: bool) -> Result<()> {
+    if manifest {
+        anyhow::bail!("--manifest only applies to file-object copies (try `wsl-clip file ...`)");
+    }
+    Ok(())
+}
+/// Copies a file into the Windows temp folder and keeps it there, since
+/// pasted file objects are read long after wsl-clip exits.
+fn stage_copy(path: &Path) -> Result<PathBuf> {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let mut staged = staging::StagedFile::create(&stem, &ext)?;
+    if !staged.windows_visible {
+        anyhow::bail!("--stage needs the Windows temp folder, which could not be resolved");
+    }
+    let mut source = std::fs::File::open(path).with_context(|| read_failure_message(path))?;
+    std::io::copy(&mut source, &mut staged)
+        .with_context(|| format!("Failed to stage {:?}", path))?;
+    Ok(staged.keep())
+}
+/// `--provider copy|paste`. Only the explicit `--crlf` / `--lf` flags
+/// apply: config defaults and profiles would break the byte-for-byte contract.
+fn provider_mode(cli: &Cli, event: &mut Event, clip: &dyn ClipboardBackend) -> Result<()> {
+    let log = create_logger("main");
+    match cli.provider {
+        Some(provider::Provider::Copy) => {
+            log.debug(&format!("Command: Provider Copy, CRLF: {}", cli.crlf));
+            event.mode = "provider-copy";
+            if cli.lf {
+                anyhow::bail!("--lf applies to --provider paste (use --crlf when copying)");
+            }
+            event.bytes = provider::copy(clip, cli.crlf)?;
+        }
+        Some(provider::Provider::Paste) => {
+            log.debug(&format!("Command: Provider Paste, LF: {}", cli.lf));
+            event.mode = "provider-paste";
+            if cli.crlf {
+                anyhow::bail!("--crlf applies to --provider copy (use --lf when pasting)");
+            }
+            event.bytes = provider::paste(cli.lf)?;
+        }
+        None => {}
+    }
+    Ok(())
+}
+/// `--html` / `--rtf` / `--render-md` / `--highlight`: the formats asked
+/// for beside plain text (HTML and RTF may both be).
+fn rich_formats(cli: &Cli) -> Vec<Format> {
+    let mut formats = Vec::new();
+    if cli.html || cli.render_md || cli.highlight {
+        formats.push(Format::Html);
+    }
+    if cli.rtf {
+        formats.push(Format::Rtf);
+    }
+    formats
+}
+fn reject_rich(cli: &Cli) -> Result<()> {
+    let flag = if cli.render_md {
+        "--render-md"
+    } else if cli.highlight {
+        "--highlight"
+    } else if cli.html {
+        "--html"
+    } else {
+        "--rtf"
+    };
+    anyhow::bail!("{} only applies to text copies (stdin or text files)", flag)
+}
+/// Where the HTML of a rich copy comes from.
+enum HtmlSource {
+    /// `--html`: the text is the fragment
+    Fragment,
+    /// `--render-md`: rendered from the text as Markdown
+    Markdown,
+    /// `--highlight`: each file colored by its syntax, in this theme
+    Highlight(String),
+}
+fn html_source(cli: &Cli) -> HtmlSource {
+    if cli.render_md {
+        HtmlSource::Markdown
+    } else if cli.highlight {
+        HtmlSource::Highlight(cli.theme.clone())
+    } else {
+        HtmlSource::Fragment
+    }
+}
+/// `--html` / `--rtf`: the processed text is buffered (a CF_HTML header needs
+/// its length) and set in one operation as the formatted payloads plus the
+/// plain text, so a terminal paste still gets something. With `--html` the
+/// text is an HTML fragment, and the plain text has its tags stripped. With
+/// `--render-md` the HTML is rendered from the text, file headers become
+/// `<h4>`, and the plain text is the Markdown itself; `--highlight` keeps
+/// the text the same way.
+fn copy_rich(
+    clip: &dyn ClipboardBackend,
+    files: Option<Vec<PathBuf>>,
+    mut opts: TextOptions,
+    max_size: Option<u64>,
+    formats: &[Format],
+    source: HtmlSource,
+) -> Result<u64> {
+    // Checked before anything is read
+    let highlighter = match &source {
+        HtmlSource::Highlight(theme) => Some(highlight::Highlighter::new(theme)?),
+        _ => None,
+    };
+    let html = formats.contains(&Format::Html) && matches!(source, HtmlSource::Fragment);
+    if html {
+        // A `# FILE:` header or footer would show up in the document
+        opts.no_header = true;
+    }
+    let mut buffer = Vec::new();
+    let mut guard = size_guard::GuardWriter::new(&mut buffer, max_size.unwrap_or(u64::MAX));
+    let report = text_processor::process_input(files.clone(), &opts, &mut guard)?;
+    for warning in &report.warnings {
+        eprintln!("[WARN] {}", warning);
+    }
+    let text = String::from_utf8_lossy(&buffer);
+    let plain = if html {
+        html::plain_text(&text)
+    } else {
+        text.to_string()
+    };
+    let mut payloads = Vec::new();
+    for format in formats {
+        let payload = match format {
+            Format::Html => html::cf_html(&match (&source, &highlighter) {
+                (_, Some(highlighter)) => {
+                    highlight_files(highlighter, files.clone(), &opts, &text)?
+                }
+                (HtmlSource::Markdown, _) => markdown::render(&text, !opts.no_header),
+                _ => text.to_string(),
+            }),
+            // Beside HTML, the document holds what the HTML reads as, not its tags
+            Format::Rtf => rtf::document(&plain, opts.use_markdown),
+            Format::Text => continue,
+        };
+        payloads.push((*format, payload));
+    }
+    payloads.insert(0, (Format::Text, plain));
+    clip.set_multi(&payloads)?;
+    Ok(text.len() as u64)
+}
+/// `--highlight`: a `<pre>` per file, colored by the file's extension. Files
+/// are read again, one at a time, through the same filters as the plain
+/// text, and colored line by line; stdin has no extension, so `text` (already
+/// read) is set in plain monospace.
+fn highlight_files(
+    highlighter: &highlight::Highlighter,
+    files: Option<Vec<PathBuf>>,
+    opts: &TextOptions,
+    text: &str,
+) -> Result<String> {
+    let mut out = String::with_capacity(text.len() * 2);
+    let Some(mut files) = files else {
+        let mut block = highlighter.block(None, &mut out);
+        block.write_all(text.as_bytes())?;
+        block.finish()?;
+        return Ok(out);
+    };
+    let body = TextOptions {
+        no_header: true,
+        use_markdown: false,
+        use_crlf: false,
+        cite: None,
+        ..opts.clone()
+    };
+    let timestamp = opts.timestamp.render();
+    text_processor::order_files(&mut files, opts);
+    for path in files {
+        if text_processor::skip_reason(&path, opts).is_some() {
+            continue;
+        }
+        if !opts.no_header {
+            let header = text_processor::file_header(&path, timestamp.as_deref(), None);
+            out.push_str(&highlighter.heading(header.trim_start_matches("# ").trim_end()));
+        }
+        let mut block = highlighter.block(Some(&path), &mut out);
+        text_processor::process_input(Some(vec![path.clone()]), &body, &mut block)?;
+        block.finish()?;
+    }
+    Ok(out)
+}
+/// `--watch`: the text-mode copy of `cli.files`, repeated on every change.
+fn watch_text(
+    cli: &Cli,
+    settings: &config::Config,
+    eff: &Effective,
+    event: &mut Event,
+    clip: &dyn ClipboardBackend,
+) -> Result<()> {
+    let log = create_logger("main");
+    log.debug("Command: Watch (Text Mode)");
+    event.mode = "watch";
+    let files = cli.files.clone().unwrap_or_default();
+    for f in &files {
+        match classifier::inspect(f) {
+            Ok(ClipboardStrategy::Text) => {}
+            Ok(_) => anyhow::bail!("--watch only re-copies text files; {:?} is not one", f),
+            Err(_) => anyhow::bail!("{}", read_failure_message(f)),
+        }
+    }
+    let opts = cli_text_options(cli, settings, eff, true)?;
+    watch::run(&files, || {
+        event.bytes = copy_text_files(clip, &files, &opts)?;
+        Ok(event.bytes)
+    })
+}
+/// Streams `files` through the text pipeline onto the clipboard and returns
+/// the bytes copied. A failed run keeps the previous clipboard.
+fn copy_text_files(
+    clip: &dyn ClipboardBackend,
+    files: &[PathBuf],
+    opts: &TextOptions,
+) -> Result<u64> {
+    let mut stream = clip.start_text_stream()?;
+    let Some(writer) = stream.stdin.as_mut() else {
+        anyhow::bail!("Failed to acquire stdin for clip.exe");
+    };
+    let mut counter = CountingWriter::new(writer);
+    match text_processor::process_input(Some(files.to_vec()), opts, &mut counter) {
+        Ok(report) => {
+            for warning in &report.warnings {
+                eprintln!("[WARN] {}", warning);
+            }
+            let bytes = counter.bytes;
+            stream.wait()?;
+            Ok(bytes)
+        }
+        Err(e) => {
+            // Keep the previous copy rather than commit half of this one
+            stream.abort()?;
+            Err(e)
+        }
+    }
+}
+/// How `--split` copies mixed-content arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum SplitMode {
+    /// Text, then images, then files: each group replaces the last
+    Sequential,
+    /// Everything as one file-object drop list
+    Files,
+}
+/// Smart-mode arguments by how they would be copied on their own.
+#[derive(Debug, Default)]
+struct SplitGroups {
+    text: Vec<PathBuf>,
+    images: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+}
+/// `--split`: the text group, then the images, then the files, each
+/// replacing the one before on the clipboard. Between groups it waits for
+/// Enter (unless --yes), so each can be pasted before the next arrives.
+fn split_copy(
+    cli: &Cli,
+    settings: &config::Config,
+    eff: &Effective,
+    event: &mut Event,
+    clip: &dyn ClipboardBackend,
+    groups: SplitGroups,
+) -> Result<()> {
+    let log = create_logger("main");
+    let plural = |n: usize, one: &str| format!("{} {}{}", n, one, if n == 1 { "" } else { "s" });
+    let mut steps: Vec<(String, ClipboardStrategy, Vec<PathBuf>)> = Vec::new();
+    if !groups.text.is_empty() {
+        let label = format!("text from {}", plural(groups.text.len(), "file"));
+        steps.push((label, ClipboardStrategy::Text, groups.text));
+    }
+    if !groups.images.is_empty() {
+        let label = match groups.images.len() {
+            1 => "the image".to_string(),
+            n => format!("{} as files", plural(n, "image")),
+        };
+        steps.push((label, ClipboardStrategy::Image, groups.images));
+    }
+    if !groups.files.is_empty() {
+        let label = plural(groups.files.len(), "file object");
+        steps.push((label, ClipboardStrategy::File, groups.files));
+    }
+    if !cli.yes && !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "--split waits for Enter between groups, but stdin is not a terminal; pass --yes to go on without asking"
+        );
+    }
+    event.mode = "split";
+    let order: Vec<&str> = steps.iter().map(|(label, ..)| label.as_str()).collect();
+    println!(
+        "Mixed content: copying {} groups in turn ({}); each replaces the one before",
+        steps.len(),
+        order.join(", then ")
+    );
+    let total = steps.len();
+    for (n, (label, strategy, files)) in steps.iter().enumerate() {
+        if n > 0 {
+            confirm_next(label, cli.yes)?;
+        }
+        log.debug(&format!(
+            "Split group {}/{}: {:?} {:?}",
+            n + 1,
+            total,
+            strategy,
+            files
+        ));
+        match strategy {
+            ClipboardStrategy::Text => {
+                let opts = cli_text_options(cli, settings, eff, true)?;
+                event.bytes = copy_text_files(clip, files, &opts)?;
+            }
+            ClipboardStrategy::Image if files.len() == 1 => {
+                let strip = cli.strip_metadata.then_some(cli.jpeg_quality);
+                copy_image(clip, &files[0], cli.stage, strip, cli.both)?;
+            }
+            _ => {
+                copy_file_objects(clip, files, cli.stage, cli.manifest, drop_effect(cli))?;
+            }
+        }
+        println!("[{}/{}] Copied {}", n + 1, total, label);
+    }
+    println!(
+        "[OK] Split copy done: the clipboard now holds only the last group ({})",
+        order[total - 1]
+    );
+    Ok(())
+}
+/// Waits for Enter before the next `--split` group replaces the clipboard.
+fn confirm_next(next: &str, yes: bool) -> Result<()> {
+    if yes {
+        println!("Replacing the clipboard with {} (--yes)", next);
+        return Ok(());
+    }
+    eprint!(
+        "Paste it now. Press Enter to replace the clipboard with {} (Ctrl-C keeps it): ",
+        next
+    );
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        anyhow::bail!("Stopped before copying {} (stdin closed)", next);
+    }
+    Ok(())
+}
+/// Sniffs the start of stdin (kept for the text reader) and refuses an
+/// image or binary, which would reach the clipboard as mojibake.
+fn reject_binary_stdin() -> Result<()> {
+    let head = input::peek_stdin(classifier::sample_len()).context("Failed to read stdin")?;
+    let (strategy, reason) = classifier::inspect_bytes_with_reason(&head);
+    create_logger("main").debug(&format!(
+        "stdin sniffed as {:?} ({}) from {} bytes",
+        strategy,
+        reason,
+        head.len()
+    ));
+    match strategy {
+        // Stray control bytes in a pipe are the sanitizer's job; only a
+        // signature says the whole stream is not text
+        ClipboardStrategy::Text => Ok(()),
+        ClipboardStrategy::File if reason == classifier::Rule::NonPrintableRatio => Ok(()),
+        ClipboardStrategy::Image => {
+            let kind = infer::get(&head).map_or("an".to_string(), |kind| {
+                format!("a {}", kind.extension().to_uppercase())
+            });
+            anyhow::bail!(
+                "stdin looks like {} image; use --image to copy it as an image, or --as-file to copy it as a file",
+                kind
+            )
+        }
+        ClipboardStrategy::File => anyhow::bail!(
+            "stdin looks like binary data ({}); use --as-file to copy it as a file",
+            reason
+        ),
+    }
+}
+/// Text-mode options for this invocation: the effective options plus
+/// --grep, the sanitizer set, --cite and a pinned header timestamp. Checks
+/// stdin when there are no files, so empty input copies nothing.
+fn cli_text_options(
+    cli: &Cli,
+    settings: &config::Config,
+    eff: &Effective,
+    has_files: bool,
+) -> Result<TextOptions> {
+    let log = create_logger("main");
+    let grep = cli
+        .grep
+        .as_deref()
+        .map(regex::Regex::new)
+        .transpose()
+        .context("Invalid --grep pattern")?;
+    let controls = sanitizer::ControlSet::with(
+        cli.allow_control.as_ref().map_or(&[][..], |l| &l.0),
+        cli.deny_control.as_ref().map_or(&[][..], |l| &l.0),
+        cli.i_know_what_im_doing,
+    )?;
+    log.debug(&format!("Sanitizer keeps: {}", controls.describe()));
+    if cli.cite && !has_files {
+        anyhow::bail!("--cite needs file arguments: stdin has no path or lines to cite");
+    }
+    if !has_files {
+        // Settle what stdin holds before clip.exe starts
+        input::require_stdin()?;
+        reject_binary_stdin()?;
+    } else if cli
+        .files
+        .iter()
+        .flatten()
+        .any(|f| text_processor::is_stdin(f))
+    {
+        reject_binary_stdin()?;
+    }
+    if cli.deterministic && cli.sort == Some(text_processor::FileOrder::Mtime) {
+        anyhow::bail!(
+            "--sort=mtime cannot be combined with --deterministic: modification times change \
+             whenever a file is recreated, so the order would too (use --sort=name or --sort=size)"
+        );
+    }
+    if let Some(encoding) = cli.encoding.filter(|&e| e != TextEncoding::Utf8) {
+        clipboard::require_windows(&format!("--encoding {}", encoding.label()))?;
+    }
+    let mut opts = TextOptions {
+        grep,
+        controls,
+        deterministic: cli.deterministic,
+        sort: cli.sort,
+        keep_empty: cli.keep_empty,
+        lines: cli.lines,
+        encoding: cli.encoding,
+        strict_encoding: cli.strict_encoding,
+        ..text_options(eff)
+    };
+    if cli.cite {
+        // The citation replaces the header and needs the fence to attach to
+        opts.cite = Some(settings.cite.clone());
+        opts.no_header = true;
+        opts.use_markdown = true;
+    }
+    let source_date_epoch = std::env::var("SOURCE_DATE_EPOCH").ok();
+    if let Some(at) = timestamp::pinned(
+        cli.timestamp_value,
+        cli.deterministic,
+        source_date_epoch.as_deref(),
+    )? {
+        log.debug(&format!("Header timestamp pinned to {}", at.to_rfc3339()));
+        opts.timestamp = Timestamp::at(eff.timestamp.value, eff.timestamp_format.value.clone(), at);
+    }
+    Ok(opts)
+}
+fn text_options(eff: &Effective) -> TextOptions {
+    TextOptions {
+        no_header: eff.no_header.value,
+        strip_ansi: !eff.no_strip.value,
+        use_markdown: eff.code.value,
+        use_crlf: eff.crlf.value,
+        timestamp: Timestamp::now(eff.timestamp.value, eff.timestamp_format.value.clone()),
+        footer_style: eff.footer_style.value,
+        snapshot: eff.snapshot.value,
+        single_line: eff.single_line_separator(),
+        paste_safe: eff.paste_safe.value,
+        target_shell: eff.target_shell.value,
+        grep: None,
+        jsonl_pretty: eff.jsonl_pretty.value,
+        controls: sanitizer::ControlSet::default(),
+        deterministic: false,
+        sort: None,
+        keep_empty: false,
+        lines: None,
+        cite: None,
+        encoding: None,
+        strict_encoding: false,
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clipboard::memory::{Copied, MemoryClipboard};
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("wsl-clip").chain(args.iter().copied())).unwrap()
+    }
+    #[test]
+    fn test_dash_names_after_separator_are_files() {
+        let cli = parse(&["--", "-n", "--code"]);
+        assert!(cli.command.is_none());
+        assert!(!cli.no_header && !cli.code);
+        assert_eq!(
+            cli.files.unwrap(),
+            vec![PathBuf::from("-n"), PathBuf::from("--code")]
+        );
+        // Options before the separator still apply
+        let cli = parse(&["--crlf", "--", "-n"]);
+        assert!(cli.crlf);
+        assert_eq!(cli.files.unwrap(), vec![PathBuf::from("-n")]);
+        // A subcommand name after '--' is a file, not a command
+        let cli = parse(&["--", "file"]);
+        assert!(cli.command.is_none());
+        assert_eq!(cli.files.unwrap(), vec![PathBuf::from("file")]);
+    }
+    #[test]
+    fn test_clear_arguments() {
+        assert!(matches!(
+            parse(&["clear"]).command,
+            Some(Commands::Clear {
+                after: None,
+                detached: false
+            })
+        ));
+        let cli = parse(&["clear", "--after", "2m"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Clear { after: Some(d), detached: false }) if d == Duration::from_secs(120)
+        ));
+        let args = clear::detached_args(Duration::from_secs(30));
+        let cli = Cli::try_parse_from(std::iter::once("wsl-clip".to_string()).chain(args)).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Clear {
+                after: Some(_),
+                detached: true
+            })
+        ));
+        let reject = |args: &[&str]| {
+            Cli::try_parse_from(std::iter::once("wsl-clip").chain(args.iter().copied())).is_err()
+        };
+        assert!(reject(&["clear", "--after", "soon"]));
+        assert!(reject(&["clear", "--detached"]));
+    }
+    #[test]
+    fn test_provider_arguments() {
+        let cli = parse(&["--provider", "copy", "--crlf"]);
+        assert_eq!(cli.provider, Some(provider::Provider::Copy));
+        assert!(cli.crlf && cli.files.is_none());
+        let cli = parse(&["--provider", "paste", "--lf"]);
+        assert!(cli.provider == Some(provider::Provider::Paste) && cli.lf);
+        // `paste --lf` is still the subcommand's own flag
+        assert!(matches!(
+            parse(&["paste", "--lf"]).command,
+            Some(Commands::Paste { lf: true, .. })
+        ));
+        let reject = |args: &[&str]| {
+            Cli::try_parse_from(std::iter::once("wsl-clip").chain(args.iter().copied())).is_err()
+        };
+        assert!(reject(&["--lf"]));
+        assert!(reject(&["--provider", "copy", "notes.md"]));
+        assert!(reject(&["--provider", "yank"]));
+        // The snip wait belongs to --region; one area at a time
+        assert!(reject(&["shot", "--wait", "5"]));
+        assert!(reject(&["shot", "--region", "--all-screens"]));
+        assert!(!reject(&["shot", "--region", "--poll", "100ms"]));
+    }
+    #[test]
+    fn test_dash_names_in_subcommands() {
+        match parse(&["img", "--", "-shot.png"]).command {
+            Some(Commands::Img { file, .. }) => assert_eq!(file, PathBuf::from("-shot.png")),
+            _ => panic!("expected img"),
+        }
+        match parse(&["file", "a.pdf", "--", "-n", "--debug"]).command {
+            Some(Commands::File { files, .. }) => assert_eq!(
+                files,
+                vec![
+                    PathBuf::from("a.pdf"),
+                    PathBuf::from("-n"),
+                    PathBuf::from("--debug")
+                ]
+            ),
+            _ => panic!("expected file"),
+        }
+        match parse(&["path", "--", "--code"]).command {
+            Some(Commands::Path { files, .. }) => assert_eq!(files, [PathBuf::from("--code")]),
+            _ => panic!("expected path"),
+        }
+    }
+    #[test]
+    fn test_unknown_dash_token_is_rejected_by_parser() {
+        assert!(Cli::try_parse_from(["wsl-clip", "-zz"]).is_err());
+    }
+    fn effective(args: &[&str], config: &str) -> Effective {
+        let matches = Cli::command()
+            .try_get_matches_from(std::iter::once("wsl-clip").chain(args.iter().copied()))
+            .unwrap();
+        let cli = Cli::from_arg_matches(&matches).unwrap();
+        let settings = config::Config::parse(config).unwrap();
+        resolve_effective(
+            &settings,
+            &OptionLayer::default(),
+            &cli_layer(&cli, &matches),
+            cli.profile.as_deref(),
+        )
+        .unwrap()
+    }
+    #[test]
+    fn test_conflict_between_config_and_cli() {
+        let err = effective(&["--code"], "[defaults]\nsingle_line = \"; \"\n")
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--single-line (config file) conflicts with --code (command line)"));
+        // The same pair typed on one command line is caught by the same table
+        let err = effective(&["--single-line", "--code"], "")
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--single-line (command line) conflicts with --code (command line)"));
+    }
+    #[test]
+    fn test_clap_defaults_do_not_override_config() {
+        let eff = effective(&[], "[defaults]\nfooter_style = \"compact\"\n");
+        assert_eq!(eff.footer_style.value, FooterStyle::Compact);
+        let eff = effective(
+            &["img", "x.png", "--footer-style", "detailed"],
+            "[defaults]\nfooter_style = \"compact\"\n",
+        );
+        assert_eq!(eff.footer_style.value, FooterStyle::Detailed);
+        assert_eq!(eff.footer_style.source, options::Source::Cli);
+        let opts = text_options(&effective(&["--single-line", "--target-shell", "bash"], ""));
+        assert_eq!(opts.single_line.as_deref(), Some(" && "));
+    }
+    #[test]
+    fn test_profile_sits_between_config_and_cli() {
+        let config = "[defaults]\ncrlf = true\ncode = true\n[profile.review]\ncode = false\nsnapshot = true\n";
+        let eff = effective(&["--profile", "review", "--snapshot"], config);
+        assert_eq!(eff.crlf.source, options::Source::Config);
+        assert!(!eff.code.value);
+        assert_eq!(eff.code.source, options::Source::Profile("review".into()));
+        assert_eq!(eff.snapshot.source, options::Source::Cli);
+        assert!(eff.render().contains("code = false  # profile 'review'\n"));
+        // Without --profile the bundle is inert
+        assert!(effective(&[], config).code.value);
+    }
+    #[test]
+    fn test_read_failure_hint() {
+        assert!(read_failure_message(std::path::Path::new("-zz")).contains("must follow '--'"));
+        assert!(!read_failure_message(std::path::Path::new("notes.md")).contains("tip"));
+    }
+    /// Runs the whole dispatch for `args` against an in-memory clipboard.
+    fn dispatch(args: &[&str]) -> (Result<()>, Vec<Copied>) {
+        let matches = Cli::command()
+            .try_get_matches_from(std::iter::once("wsl-clip").chain(args.iter().copied()))
+            .unwrap();
+        let cli = Cli::from_arg_matches(&matches).unwrap();
+        let mut settings = config::Config::default();
+        settings.history.enabled = false;
+        let eff = resolve_effective(
+            &settings,
+            &OptionLayer::default(),
+            &cli_layer(&cli, &matches),
+            None,
+        )
+        .unwrap();
+        let mut event = Event {
+            mode: "text",
+            outcome: Outcome::Failure,
+            bytes: 0,
+        };
+        let clip = MemoryClipboard::default();
+        let result = run(cli, &settings, &eff, &mut event, &clip);
+        (result, clip.copies())
+    }
+    fn inputs() -> (tempfile::TempDir, impl Fn(&str, &[u8]) -> String) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        (dir, move |name: &str, content: &[u8]| {
+            let path = root.join(name);
+            std::fs::write(&path, content).unwrap();
+            path.display().to_string()
+        })
+    }
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x02\0\0\0";
+    const PDF: &[u8] = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n1 0 obj\n";
+    fn text(copies: &[Copied]) -> String {
+        match copies {
+            [Copied::Text(bytes)] => String::from_utf8(bytes.clone()).unwrap(),
+            other => panic!("expected one text copy, got {:?}", other),
+        }
+    }
+    #[test]
+    fn test_smart_mode_text() {
+        let (_dir, file) = inputs();
+        let notes = file("notes.txt", b"one\ntwo\n");
+        let (result, copies) = dispatch(&[&notes]);
+        result.unwrap();
+        let copied = text(&copies);
+        assert!(
+            copied.starts_with(&format!("# FILE: {} READ: ", notes)),
+            "{}",
+            copied
+        );
+        assert!(copied.contains("one\ntwo\n"));
+        let (result, copies) = dispatch(&["-n", &notes]);
+        result.unwrap();
+        assert_eq!(text(&copies), "one\ntwo\n");
+        let (result, copies) = dispatch(&["-n", "--crlf", &notes]);
+        result.unwrap();
+        assert_eq!(text(&copies), "one\r\ntwo\r\n");
+        let (result, copies) = dispatch(&["-n", "--code", &notes]);
+        result.unwrap();
+        assert_eq!(text(&copies), "```txt\none\ntwo\n```\n");
+    }
+    #[test]
+    fn test_smart_mode_objects() {
+        let (_dir, file) = inputs();
+        let png = file("shot.png", PNG);
+        let pdf = file("report.pdf", PDF);
+        let (result, copies) = dispatch(&[&png]);
+        result.unwrap();
+        assert_eq!(copies, vec![Copied::Image(png.clone().into())]);
+        let (result, copies) = dispatch(&[&pdf]);
+        result.unwrap();
+        assert_eq!(
+            copies,
+            vec![Copied::Files {
+                files: vec![pdf.clone().into()],
+                text: None,
+                effect: DropEffect::Copy
+            }]
+        );
+        // Several images go as files, not pixels
+        let second = file("second.png", PNG);
+        let (result, copies) = dispatch(&[&png, &second]);
+        result.unwrap();
+        assert_eq!(
+            copies,
+            vec![Copied::Files {
+                files: vec![png.clone().into(), second.into()],
+                text: None,
+                effect: DropEffect::Copy
+            }]
+        );
+    }
+    #[test]
+    fn test_split_mixed_content() {
+        let (_dir, file) = inputs();
+        let notes = file("notes.txt", b"one\ntwo\n");
+        let png = file("shot.png", PNG);
+        let pdf = file("report.pdf", PDF);
+        let files = |paths: &[&String]| Copied::Files {
+            files: paths.iter().map(|p| PathBuf::from(p.as_str())).collect(),
+            text: None,
+            effect: DropEffect::Copy,
+        };
+        let is_text = |c: &Copied| matches!(c, Copied::Text(t) if t.ends_with(b"one\ntwo\n\n"));
+        // Without --split the mix is still refused
+        let (result, copies) = dispatch(&[&notes, &png]);
+        assert!(result.unwrap_err().to_string().contains("or pass --split"));
+        assert!(copies.is_empty());
+        // Text, then the image
+        let (result, copies) = dispatch(&["--split", "--yes", &png, &notes]);
+        result.unwrap();
+        assert_eq!(copies.len(), 2);
+        assert!(is_text(&copies[0]));
+        assert_eq!(copies[1], Copied::Image(png.clone().into()));
+        // Text, then the files
+        let (result, copies) = dispatch(&["--split", "--yes", &pdf, &notes]);
+        result.unwrap();
+        assert!(is_text(&copies[0]));
+        assert_eq!(copies[1..], [files(&[&pdf])]);
+        // All three, in the fixed order
+        let (result, copies) = dispatch(&["--split", "--yes", &pdf, &png, &notes]);
+        result.unwrap();
+        assert_eq!(copies.len(), 3);
+        assert!(is_text(&copies[0]));
+        assert_eq!(
+            copies[1..],
+            [Copied::Image(png.clone().into()), files(&[&pdf])]
+        );
+        // Folded into one drop list, in argument order
+        let (result, copies) = dispatch(&["--split=files", &pdf, &png, &notes]);
+        result.unwrap();
+        assert_eq!(copies, [files(&[&pdf, &png, &notes])]);
+        // One kind of content needs no splitting
+        let (result, copies) = dispatch(&["--split", &notes]);
+        result.unwrap();
+        assert!(is_text(&copies[0]) && copies.len() == 1);
+    }
+    #[test]
+    fn test_stdin_image_staging() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = staging::StagingRoot {
+            dir: dir.path().to_path_buf(),
+            windows_visible: true,
+        };
+        let jpeg = b"\xFF\xD8\xFF\xE0\0\x10JFIF\0";
+        let gif = b"GIF89a\x01\0\x01\0\0\0\0;";
+        let bmp = b"BM\x3A\0\0\0\0\0\0\0\x36\0\0\0\x28\0\0\0";
+        for (bytes, name) in [
+            (PNG, "stdin-image.png"),
+            (&jpeg[..], "stdin-image.jpg"),
+            (&gif[..], "stdin-image.gif"),
+            (&bmp[..], "stdin-image.bmp"),
+        ] {
+            let (staged, size) = stage_image(bytes, &root).unwrap();
+            assert_eq!(staged.path(), dir.path().join(name));
+            assert_eq!(std::fs::read(staged.path()).unwrap(), bytes);
+            assert_eq!(size, bytes.len() as u64);
+            // Removed as soon as the copy is done with it
+            let path = staged.path().to_path_buf();
+            drop(staged);
+            assert!(!path.exists());
+        }
+        // Larger than the sniffed head: every byte arrives
+        let big = [PNG, &[7u8; 100_000][..]].concat();
+        let (staged, size) = stage_image(&big[..], &root).unwrap();
+        assert_eq!(std::fs::read(staged.path()).unwrap(), big);
+        assert_eq!(size, big.len() as u64);
+        drop(staged);
+        let Err(err) = stage_image(&b"just text\n"[..], &root) else {
+            panic!("text staged as an image");
+        };
+        assert_eq!(
+            err.to_string(),
+            "stdin is not a PNG, JPEG, GIF or BMP image"
+        );
+        let Err(err) = stage_image(PDF, &root) else {
+            panic!("PDF staged as an image");
+        };
+        assert!(err.to_string().ends_with("(it looks like .pdf)"));
+        // Refused input leaves nothing behind
+        let left: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name() != "manifest.jsonl")
+            .collect();
+        assert!(left.is_empty(), "{:?}", left);
+    }
+    #[test]
+    fn test_spool_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = staging::StagingRoot {
+            dir: dir.path().to_path_buf(),
+            windows_visible: true,
+        };
+        let (path, bytes) = spool_stdin(PDF, Some("report.pdf"), &root).unwrap();
+        assert_eq!(path.file_name().unwrap(), "report.pdf");
+        assert!(path.starts_with(dir.path().join("spool")));
+        assert_eq!(std::fs::read(&path).unwrap(), PDF);
+        assert_eq!(bytes, PDF.len() as u64);
+        // Unnamed: the extension comes from the magic bytes
+        let (path, _) = spool_stdin(PDF, None, &root).unwrap();
+        assert_eq!(path.file_name().unwrap(), "stdin.pdf");
+        let (path, _) = spool_stdin(&b"plain"[..], None, &root).unwrap();
+        assert_eq!(path.file_name().unwrap(), "stdin.bin");
+        let Err(err) = spool_stdin(&b""[..], None, &root) else {
+            panic!("empty stdin spooled");
+        };
+        assert!(input::empty(&err).is_some());
+        let Err(err) = spool_stdin(PDF, Some("../report.pdf"), &root) else {
+            panic!("path accepted as a name");
+        };
+        assert!(err.to_string().starts_with("--name takes a file name"));
+        let local = staging::StagingRoot::local();
+        let Err(err) = spool_stdin(PDF, None, &local) else {
+            panic!("spooled where Windows cannot see it");
+        };
+        assert!(err.to_string().contains("Windows temp folder"));
+    }
+    #[test]
+    fn test_forced_modes() {
+        let (_dir, file) = inputs();
+        let png = file("shot.png", PNG);
+        let notes = file("notes.txt", b"hi\n");
+        let (result, copies) = dispatch(&["file", &png]);
+        result.unwrap();
+        assert_eq!(
+            copies,
+            vec![Copied::Files {
+                files: vec![png.clone().into()],
+                text: None,
+                effect: DropEffect::Copy
+            }]
+        );
+        let (result, copies) = dispatch(&["img", &png]);
+        result.unwrap();
+        assert_eq!(copies, vec![Copied::Image(png.into())]);
+        // Text is refused before anything reaches the clipboard
+        let (result, copies) = dispatch(&["img", &notes]);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .ends_with("is not an image: detected text/plain"));
+        assert!(copies.is_empty());
+    }
+    #[test]
+    fn test_image_and_file() {
+        let (_dir, file) = inputs();
+        let png = file("shot.png", PNG);
+        let (result, copies) = dispatch(&["--both", &png]);
+        result.unwrap();
+        assert_eq!(copies, vec![Copied::ImageAndFile(png.clone().into())]);
+        let (result, copies) = dispatch(&["img", &png, "--both"]);
+        result.unwrap();
+        assert_eq!(copies, vec![Copied::ImageAndFile(png.clone().into())]);
+        // A resized copy is removed after the copy: nothing to drop
+        let (result, copies) = dispatch(&["img", "--both", "--max-dim", "10", &png]);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("--both copies the file as it is"));
+        assert!(copies.is_empty());
+        let reject = |args: &[&str]| {
+            Cli::try_parse_from(std::iter::once("wsl-clip").chain(args.iter().copied())).is_err()
+        };
+        assert!(reject(&["--both", "--strip-metadata", &png]));
+        assert!(reject(&["--both", "--image"]));
+        assert!(reject(&["img", &png, "--both", "--strip-metadata"]));
+    }
+    #[test]
+    fn test_folders_are_file_objects() {
+        let (dir, file) = inputs();
+        let pdf = file("report.pdf", PDF);
+        let project = dir.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        let slashed = format!("{}/", project.display());
+        for args in [vec!["file", &slashed, &pdf], vec![&slashed, &pdf]] {
+            let (result, copies) = dispatch(&args);
+            result.unwrap();
+            assert_eq!(
+                copies,
+                vec![Copied::Files {
+                    files: vec![project.clone(), pdf.clone().into()],
+                    text: None,
+                    effect: DropEffect::Copy
+                }]
+            );
+        }
+        assert_eq!(
+            folder_note(&[project.clone(), pdf.clone().into(), pdf.into()]),
+            " (2 files, 1 folder)"
+        );
+        let (result, copies) = dispatch(&["file", "--manifest", &slashed]);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("--manifest checksums files"));
+        assert!(copies.is_empty());
+    }
+    #[test]
+    fn test_file_arguments_checked_up_front() {
+        let (dir, file) = inputs();
+        let a = file("a.pdf", PDF);
+        let c = file("c.pdf", PDF);
+        let b = dir.path().join("b.pdf").display().to_string();
+        let d = dir.path().join("d.pdf").display().to_string();
+        let (result, copies) = dispatch(&["file", &a, &b, &c, &d]);
+        let msg = result.unwrap_err().to_string();
+        assert!(msg.starts_with("2 of 4 files cannot be copied:"), "{}", msg);
+        assert!(msg.contains(&format!("{} (does not exist)", b)) && msg.contains(&d));
+        assert!(copies.is_empty());
+        let (result, copies) = dispatch(&["file", "--skip-missing", &b, &d]);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("2 of 2 files cannot be copied:"));
+        assert!(copies.is_empty());
+        let (result, copies) = dispatch(&["file", "--skip-missing", &a, &b, &c, &d]);
+        result.unwrap();
+        assert_eq!(
+            copies,
+            vec![Copied::Files {
+                files: vec![a.into(), c.into()],
+                text: None,
+                effect: DropEffect::Copy
+            }]
+        );
+    }
+    #[test]
+    fn test_move_files() {
+        let (_dir, file) = inputs();
+        let pdf = file("report.pdf", PDF);
+        let png = file("shot.png", PNG);
+        for args in [vec!["file", "--move", &pdf], vec!["--move", &pdf]] {
+            let (result, copies) = dispatch(&args);
+            result.unwrap();
+            assert_eq!(
+                copies,
+                vec![Copied::Files {
+                    files: vec![pdf.clone().into()],
+                    text: None,
+                    effect: DropEffect::Move
+                }]
+            );
+        }
+        // Images and text are always copied
+        let notes = file("notes.txt", b"hi\n");
+        for args in [
+            vec!["--move", &png],
+            vec!["img", "--move", &png],
+            vec!["--move", &notes],
+        ] {
+            let (result, copies) = dispatch(&args);
+            assert!(result
+                .unwrap_err()
+                .to_string()
+                .starts_with("--move only applies"));
+            assert!(copies.is_empty());
+        }
+        assert!(Cli::try_parse_from(["wsl-clip", "--move", "--stage", "a.pdf"]).is_err());
+    }
+    #[test]
+    fn test_dispatch_errors_copy_nothing() {
+        let (_dir, file) = inputs();
+        let png = file("shot.png", PNG);
+        let notes = file("notes.txt", b"hi\n");
+        let (result, copies) = dispatch(&[&png, &notes]);
+        assert!(result.unwrap_err().to_string().contains("Mixed content"));
+        assert!(copies.is_empty());
+        let (result, copies) = dispatch(&["/nonexistent/wsl-clip-test.txt"]);
+        assert!(result.is_err());
+        assert!(copies.is_empty());
+        let big = file("big.txt", &[b'x'; 4096]);
+        let (result, copies) = dispatch(&["-n", "--max-size", "100", &big]);
+        assert!(size_guard::breach(&result.unwrap_err()).is_some());
+        assert!(copies.is_empty());
+        let (result, copies) = dispatch(&["--manifest", &png]);
+        assert!(result.is_err());
+        assert!(copies.is_empty());
+        let (result, copies) = dispatch(&["--html", &png]);
+        assert!(result.unwrap_err().to_string().contains("--html"));
+        assert!(copies.is_empty());
+    }
+    #[test]
+    fn test_html_mode() {
+        let (_dir, file) = inputs();
+        let a = file("a.html", b"<p>caf\xc3\xa9 &amp; <b>bold</b></p>\n");
+        let b = file("b.html", b"<ul><li>one</li></ul>\n");
+        // No headers or footer in the document, even for several files
+        let (result, copies) = dispatch(&["--html", &a, &b]);
+        result.unwrap();
+        let fragment = "<p>caf\u{e9} &amp; <b>bold</b></p>\n<ul><li>one</li></ul>\n";
+        let plain = "caf\u{e9} & bold\n\none\n\n";
+        assert_eq!(
+            copies,
+            [Copied::Multi(vec![
+                (Format::Text, plain.into()),
+                (Format::Html, html::cf_html(fragment)),
+            ])]
+        );
+        // With --rtf too: one copy, three formats, the RTF from the plain text
+        let (result, copies) = dispatch(&["--html", "--rtf", &a, &b]);
+        result.unwrap();
+        assert_eq!(
+            copies,
+            [Copied::Multi(vec![
+                (Format::Text, plain.into()),
+                (Format::Html, html::cf_html(fragment)),
+                (Format::Rtf, rtf::document(plain, false)),
+            ])]
+        );
+    }
+    #[test]
+    fn test_rtf_mode() {
+        let (_dir, file) = inputs();
+        let a = file("a.rs", b"fn a() {}\n");
+        let b = file("b.rs", b"fn b() {}\n");
+        // Headers stay; with --code the fenced source is monospace
+        let (result, copies) = dispatch(&["--rtf", "--code", "--deterministic", &a, &b]);
+        result.unwrap();
+        let [Copied::Multi(formats)] = &copies[..] else {
+            panic!("{:?}", copies);
+        };
+        let [(Format::Text, text), (Format::Rtf, data)] = &formats[..] else {
+            panic!("{:?}", formats);
+        };
+        assert!(text.starts_with(&format!("# FILE: {}", a)));
+        assert_eq!(*data, rtf::document(text, true));
+        assert!(data.contains("\\f1\\fs20 fn a() \\{\\}\\par"));
+    }
+    #[test]
+    fn test_highlight_mode() {
+        let (_dir, file) = inputs();
+        let a = file("a.rs", b"fn a() {}\n// skip\n");
+        let b = file("b.zzz", b"x < y\n");
+        let (result, copies) = dispatch(&[
+            "--highlight",
+            "--code",
+            "--deterministic",
+            "--grep",
+            "^[^/]",
+            &a,
+            &b,
+        ]);
+        result.unwrap();
+        let [Copied::Multi(formats)] = &copies[..] else {
+            panic!("{:?}", copies);
+        };
+        let [(Format::Text, text), (Format::Html, data)] = &formats[..] else {
+            panic!("{:?}", formats);
+        };
+        // The text is the usual copy; the HTML has no fences
+        assert!(text.contains("```rs\nfn a() {}\n```"));
+        assert!(!data.contains("```"));
+        assert!(!data.contains("skip"), "--grep applies to the HTML too");
+        assert_eq!(data.matches("<pre").count(), 2);
+        assert!(data.contains(&format!("FILE: {} READ: 1970-01-01T00:00:00Z</p>", a)));
+        let (rs, zzz) = data.split_at(data.find(&format!("FILE: {}", b)).unwrap());
+        assert!(rs.contains("<span style=\"color:#"));
+        assert!(!zzz.contains("<span") && zzz.contains(">x &lt; y\n</pre>"));
+        let (result, copies) = dispatch(&["--highlight", "--theme", "Neon", &a]);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown theme 'Neon'"));
+        assert!(copies.is_empty());
+        assert!(Cli::try_parse_from(["wsl-clip", "--theme", "InspiredGitHub"]).is_err());
+    }
+    #[test]
+    fn test_render_md_mode() {
+        let (_dir, file) = inputs();
+        let a = file("a.md", b"# Plan\n\n**bold**\n");
+        let b = file("b.md", b"- one\n");
+        let (result, copies) = dispatch(&["--render-md", "--deterministic", &a, &b]);
+        result.unwrap();
+        let [Copied::Multi(formats)] = &copies[..] else {
+            panic!("{:?}", copies);
+        };
+        let [(Format::Text, text), (Format::Html, data)] = &formats[..] else {
+            panic!("{:?}", formats);
+        };
+        // The plain text is the Markdown as copied, headers and all
+        assert!(text.starts_with(&format!("# FILE: {}", a)));
+        assert!(text.contains("**bold**"));
+        assert_eq!(*data, html::cf_html(&markdown::render(text, true)));
+        assert!(data.contains(&format!("<h4>FILE: {}", a)));
+        assert!(data.contains("<h1>Plan</h1>"));
+        // -n drops the headers from both
+        let (result, copies) = dispatch(&["--render-md", "-n", &a, &b]);
+        result.unwrap();
+        assert_eq!(
+            copies,
+            [Copied::Multi(vec![
+                (Format::Text, "# Plan\n\n**bold**\n- one\n".into()),
+                (
+                    Format::Html,
+                    html::cf_html(&markdown::render("# Plan\n\n**bold**\n- one\n", false))
+                ),
+            ])]
+        );
+        let png = file("shot.png", PNG);
+        let (result, _) = dispatch(&["--render-md", &png]);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("--render-md only applies"));
+    }
+}
 
-// <FILE>src/main.rs</FILE> - <DESC>Integrated streaming and security fixes</DESC>
-// <VERS>END OF VERSION: 2.3.0 - 2025-11-25T17:09:34Z</VERS>
+// <FILE>src/main.rs</FILE> - <DESC>ClipboardBackend dispatch</DESC>
+// <VERS>END OF VERSION: 2.96.0 - 2026-10-16T14:45:17Z</VERS>
@@ -0,0 +1,97 @@
+// <FILE>src/markdown.rs</FILE> - <DESC>Markdown rendered to an HTML fragment</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T13:40:00Z</VERS>
+// <WCTX>--render-md renders Markdown to CF_HTML.</WCTX>
+// <CLOG>Initial creation with render.</CLOG>
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+/// Kept inside the fragment: Outlook and Teams paste only what lies between
+/// the fragment markers, so a stylesheet in `<head>` would be dropped.
+const STYLE: &str = "<style>\n\
+    p, li, td, th { font-family: Calibri, Arial, sans-serif; font-size: 11pt; }\n\
+    pre, code { font-family: Consolas, monospace; font-size: 10pt; }\n\
+    pre { background: #f6f8fa; padding: 8px; }\n\
+    table { border-collapse: collapse; }\n\
+    td, th { border: 1px solid #d0d7de; padding: 4px 8px; }\n\
+    </style>\n";
+/// The `# KEYWORD:` lines wsl-clip itself writes around files (headers and
+/// the multi-file footer).
+const ANNOTATIONS: [&str; 5] = ["FILE: ", "SENT: ", "TOTAL: ", "SKIPPED: ", "End of FILES."];
+/// `markdown` as an HTML fragment with a small stylesheet. Fenced code
+/// becomes `<pre><code>`; tables, strikethrough and task lists are on. With
+/// `headers`, wsl-clip's own `# FILE:` and footer lines render as `<h4>`
+/// instead of top-level headings.
+pub fn render(markdown: &str, headers: bool) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut events: Vec<Event> = Parser::new_ext(markdown, options).collect();
+    if headers {
+        demote_annotations(&mut events);
+    }
+    let mut html = String::from(STYLE);
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+fn demote_annotations(events: &mut [Event]) {
+    for i in 0..events.len() {
+        let annotation = matches!(
+            events[i],
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            })
+        ) && matches!(&events.get(i + 1), Some(Event::Text(t)) if ANNOTATIONS.iter().any(|a| t.starts_with(a)));
+        if !annotation {
+            continue;
+        }
+        if let Event::Start(Tag::Heading { level, .. }) = &mut events[i] {
+            *level = HeadingLevel::H4;
+        }
+        if let Some(end) = events[i..]
+            .iter_mut()
+            .find(|e| matches!(e, Event::End(TagEnd::Heading(_))))
+        {
+            *end = Event::End(TagEnd::Heading(HeadingLevel::H4));
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// The rendered HTML after the stylesheet.
+    fn body(markdown: &str, headers: bool) -> String {
+        render(markdown, headers)
+            .strip_prefix(STYLE)
+            .unwrap()
+            .to_string()
+    }
+    #[test]
+    fn test_golden_notes() {
+        assert_eq!(
+            render(include_str!("../tests/golden/notes.md"), false),
+            include_str!("../tests/golden/notes.html")
+        );
+    }
+    #[test]
+    fn test_golden_files() {
+        // Two files as the text processor emits them, headers and footer included
+        assert_eq!(
+            render(include_str!("../tests/golden/files.md"), true),
+            include_str!("../tests/golden/files.html")
+        );
+    }
+    #[test]
+    fn test_annotations_only_with_headers() {
+        assert_eq!(body("# FILE: a.md\n", false), "<h1>FILE: a.md</h1>\n");
+        assert_eq!(body("# FILE: a.md\n", true), "<h4>FILE: a.md</h4>\n");
+        // A heading of the user's own stays as written
+        assert_eq!(body("# Plan\n", true), "<h1>Plan</h1>\n");
+        // So does one inside a code block
+        assert_eq!(
+            body("```\n# FILE: x\n```\n", true),
+            "<pre><code># FILE: x\n</code></pre>\n"
+        );
+    }
+}
+
+// <FILE>src/markdown.rs</FILE> - <DESC>Markdown rendered to an HTML fragment</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T13:40:00Z</VERS>
@@ -0,0 +1,307 @@
+// <FILE>src/metrics.rs</FILE> - <DESC>Opt-in usage metrics sinks</DESC>
+// <VERS>VERSION: 1.2.0 - 2026-10-16T14:43:24Z</VERS>
+// <WCTX>statsd over IPv6.</WCTX>
+// <CLOG>StatsdSink binds in the resolved address family.</CLOG>
+
+use crate::debug_logger::create_logger;
+use crate::dirs;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+/// `[metrics]` section of the config file. Off unless `enabled = true`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// Prometheus textfile-collector output (defaults under ~/.local/state/wsl-clip)
+    pub textfile: Option<PathBuf>,
+    /// `host:port` of a statsd daemon; replaces the textfile unless one is set explicitly
+    pub statsd: Option<String>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    Failure,
+}
+impl Outcome {
+    fn label(&self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Failure => "failure",
+        }
+    }
+}
+/// One wsl-clip invocation, as seen by the metrics sinks.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub mode: &'static str,
+    pub outcome: Outcome,
+    pub bytes: u64,
+}
+pub trait MetricsSink {
+    fn record(&self, event: &Event) -> Result<()>;
+}
+const INVOCATIONS: &str = "wsl_clip_invocations_total";
+const BYTES: &str = "wsl_clip_bytes_total";
+/// Counters kept in node_exporter textfile-collector format, merged in place.
+pub struct TextfileSink {
+    pub path: PathBuf,
+}
+impl MetricsSink for TextfileSink {
+    fn record(&self, event: &Event) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Serialize concurrent invocations on a sidecar lock file
+        let lock_path = self.path.with_extension("prom.lock");
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open metrics lock: {:?}", lock_path))?;
+        lock.lock()?;
+        let existing = std::fs::read_to_string(&self.path).unwrap_or_default();
+        let merged = merge_textfile(&existing, event);
+        // Write-then-rename so the collector never scrapes a half-written file
+        let tmp = self
+            .path
+            .with_extension(format!("prom.{}.tmp", std::process::id()));
+        let mut out = File::create(&tmp)?;
+        out.write_all(merged.as_bytes())?;
+        out.sync_all()?;
+        std::fs::rename(&tmp, &self.path)?;
+        lock.unlock()?;
+        Ok(())
+    }
+}
+/// Parses the existing counters, applies the event, and re-renders the file.
+pub fn merge_textfile(existing: &str, event: &Event) -> String {
+    let mut counters: BTreeMap<String, u64> = BTreeMap::new();
+    for line in existing.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.rsplit_once(' ') {
+            if let Ok(v) = value.parse::<u64>() {
+                counters.insert(key.to_string(), v);
+            }
+        }
+    }
+    let invocation_key = format!(
+        "{}{{mode=\"{}\",outcome=\"{}\"}}",
+        INVOCATIONS,
+        event.mode,
+        event.outcome.label()
+    );
+    *counters.entry(invocation_key).or_insert(0) += 1;
+    let bytes_key = format!("{}{{mode=\"{}\"}}", BYTES, event.mode);
+    *counters.entry(bytes_key).or_insert(0) += event.bytes;
+    let mut out = String::new();
+    for (name, help) in [
+        (INVOCATIONS, "wsl-clip invocations by mode and outcome."),
+        (BYTES, "Bytes sent to the clipboard by mode."),
+    ] {
+        out.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} counter\n",
+            name, help, name
+        ));
+        for (key, value) in counters
+            .iter()
+            .filter(|(k, _)| k.starts_with(&format!("{}{{", name)))
+        {
+            out.push_str(&format!("{} {}\n", key, value));
+        }
+    }
+    out
+}
+/// Fires a single UDP packet at a statsd daemon.
+pub struct StatsdSink {
+    pub addr: String,
+}
+impl MetricsSink for StatsdSink {
+    fn record(&self, event: &Event) -> Result<()> {
+        let target = self
+            .addr
+            .to_socket_addrs()
+            .with_context(|| format!("Cannot resolve statsd address {}", self.addr))?
+            .next()
+            .with_context(|| format!("statsd address {} resolved to nothing", self.addr))?;
+        // Bind in the target's family; an IPv4 socket cannot reach [::1]
+        let local = match target {
+            SocketAddr::V4(_) => "0.0.0.0:0",
+            SocketAddr::V6(_) => "[::]:0",
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.send_to(statsd_packet(event).as_bytes(), target)?;
+        Ok(())
+    }
+}
+pub fn statsd_packet(event: &Event) -> String {
+    format!(
+        "wsl_clip.invocations.{}.{}:1|c\nwsl_clip.bytes.{}:{}|c",
+        event.mode,
+        event.outcome.label(),
+        event.mode,
+        event.bytes
+    )
+}
+fn default_textfile() -> Option<PathBuf> {
+    match dirs::current().state.persistent() {
+        Ok(root) => Some(root.join("wsl_clip.prom")),
+        Err(reason) => {
+            create_logger("metrics").debug(&format!("Textfile metrics disabled: {}", reason));
+            None
+        }
+    }
+}
+pub fn sinks(config: &MetricsConfig) -> Vec<Box<dyn MetricsSink>> {
+    let mut sinks: Vec<Box<dyn MetricsSink>> = Vec::new();
+    if !config.enabled {
+        return sinks;
+    }
+    if let Some(addr) = &config.statsd {
+        sinks.push(Box::new(StatsdSink { addr: addr.clone() }));
+    }
+    let textfile = match (&config.textfile, &config.statsd) {
+        (Some(p), _) => Some(p.clone()),
+        (None, None) => default_textfile(),
+        (None, Some(_)) => None,
+    };
+    if let Some(path) = textfile {
+        sinks.push(Box::new(TextfileSink { path }));
+    }
+    sinks
+}
+/// Records the event on every sink. Failures are logged and swallowed:
+/// metrics must never affect the copy.
+pub fn record_to(sinks: &[Box<dyn MetricsSink + '_>], event: &Event) {
+    let log = create_logger("metrics");
+    for sink in sinks {
+        if let Err(e) = sink.record(event) {
+            log.warn(&format!("Failed to record metrics: {:#}", e));
+        }
+    }
+}
+pub fn record(config: &MetricsConfig, event: &Event) {
+    record_to(&sinks(config), event);
+}
+/// Pass-through writer that counts bytes for the metrics event.
+pub struct CountingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    pub bytes: u64,
+}
+impl<'a, W: Write> CountingWriter<'a, W> {
+    pub fn new(inner: &'a mut W) -> Self {
+        CountingWriter { inner, bytes: 0 }
+    }
+}
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    fn event(mode: &'static str, outcome: Outcome, bytes: u64) -> Event {
+        Event {
+            mode,
+            outcome,
+            bytes,
+        }
+    }
+    #[test]
+    fn test_textfile_merge() {
+        let first = merge_textfile("", &event("text", Outcome::Success, 10));
+        assert!(first.contains("wsl_clip_invocations_total{mode=\"text\",outcome=\"success\"} 1\n"));
+        assert!(first.contains("# TYPE wsl_clip_bytes_total counter\n"));
+        let second = merge_textfile(&first, &event("text", Outcome::Success, 5));
+        let third = merge_textfile(&second, &event("image", Outcome::Failure, 0));
+        assert!(third.contains("wsl_clip_invocations_total{mode=\"text\",outcome=\"success\"} 2\n"));
+        assert!(
+            third.contains("wsl_clip_invocations_total{mode=\"image\",outcome=\"failure\"} 1\n")
+        );
+        assert!(third.contains("wsl_clip_bytes_total{mode=\"text\"} 15\n"));
+        assert_eq!(third.matches("# HELP").count(), 2);
+    }
+    #[test]
+    fn test_textfile_sink_roundtrip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let sink = TextfileSink {
+            path: dir.path().join("nested").join("wsl_clip.prom"),
+        };
+        sink.record(&event("file", Outcome::Success, 3))?;
+        sink.record(&event("file", Outcome::Success, 4))?;
+        let content = std::fs::read_to_string(&sink.path)?;
+        assert!(
+            content.contains("wsl_clip_invocations_total{mode=\"file\",outcome=\"success\"} 2\n")
+        );
+        assert!(content.contains("wsl_clip_bytes_total{mode=\"file\"} 7\n"));
+        Ok(())
+    }
+    #[test]
+    fn test_statsd_packet() -> Result<()> {
+        assert_eq!(
+            statsd_packet(&event("text", Outcome::Success, 42)),
+            "wsl_clip.invocations.text.success:1|c\nwsl_clip.bytes.text:42|c"
+        );
+        let server = UdpSocket::bind("127.0.0.1:0")?;
+        let sink = StatsdSink {
+            addr: server.local_addr()?.to_string(),
+        };
+        sink.record(&event("path", Outcome::Failure, 0))?;
+        let mut buf = [0u8; 256];
+        let n = server.recv(&mut buf)?;
+        assert!(
+            std::str::from_utf8(&buf[..n])?.starts_with("wsl_clip.invocations.path.failure:1|c")
+        );
+        // IPv6 targets need an IPv6 socket (skipped where the host has no ::1)
+        if let Ok(server) = UdpSocket::bind("[::1]:0") {
+            let sink = StatsdSink {
+                addr: server.local_addr()?.to_string(),
+            };
+            sink.record(&event("text", Outcome::Success, 1))?;
+            let n = server.recv(&mut buf)?;
+            assert!(std::str::from_utf8(&buf[..n])?.starts_with("wsl_clip.invocations.text"));
+        }
+        Ok(())
+    }
+    struct FailingSink<'a> {
+        calls: &'a RefCell<u32>,
+    }
+    impl MetricsSink for FailingSink<'_> {
+        fn record(&self, _event: &Event) -> Result<()> {
+            *self.calls.borrow_mut() += 1;
+            anyhow::bail!("disk full")
+        }
+    }
+    #[test]
+    fn test_failures_are_swallowed() {
+        let calls = RefCell::new(0);
+        {
+            let sinks: Vec<Box<dyn MetricsSink + '_>> = vec![
+                Box::new(FailingSink { calls: &calls }),
+                Box::new(FailingSink { calls: &calls }),
+            ];
+            // Neither failure panics nor stops the second sink from running
+            record_to(&sinks, &event("text", Outcome::Success, 1));
+        }
+        assert_eq!(*calls.borrow(), 2);
+        assert!(sinks(&MetricsConfig::default()).is_empty());
+    }
+}
+
+// <FILE>src/metrics.rs</FILE> - <DESC>Opt-in usage metrics sinks</DESC>
+// <VERS>END OF VERSION: 1.2.0 - 2026-10-16T14:43:24Z</VERS>
@@ -0,0 +1,184 @@
+// <FILE>src/notify.rs</FILE> - <DESC>Completion notifications</DESC>
+// <VERS>VERSION: 1.3.0 - 2026-10-16T14:23:34Z</VERS>
+// <WCTX>Toast arguments encoding</WCTX>
+// <CLOG>toast_command uses -EncodedCommand/-EncodedArguments so title and body reach $args whole</CLOG>
+
+use crate::debug_logger::create_logger;
+use crate::environment::Platform;
+use crate::interop;
+use crate::metrics::{Event, Outcome};
+use crate::powershell;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+/// Operations shorter than this finish while the user is still watching.
+pub const DEFAULT_THRESHOLD_SECS: u64 = 5;
+/// Longest we wait on a notifier before exiting regardless.
+const NOTIFY_TIMEOUT: Duration = Duration::from_millis(500);
+pub fn should_notify(elapsed: Duration, threshold: Duration) -> bool {
+    elapsed >= threshold
+}
+/// Title and body shown for a finished operation.
+pub fn message(event: &Event, elapsed: Duration) -> (String, String) {
+    let title = match event.outcome {
+        Outcome::Success => "wsl-clip: copy finished",
+        Outcome::Failure => "wsl-clip: copy failed",
+    };
+    let body = format!(
+        "{} mode, {} bytes, {:.1}s",
+        event.mode,
+        event.bytes,
+        elapsed.as_secs_f64()
+    );
+    (title.to_string(), body)
+}
+/// Windows toast through the WinRT API, falling back to msg.exe when the
+/// toast API is unavailable. Title and body travel as `$args` through
+/// -EncodedArguments, so PowerShell never splits or parses them.
+pub fn toast_command(title: &str, body: &str) -> Command {
+    let script = "try { \
+        [Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+        $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+        $text = $xml.GetElementsByTagName('text'); \
+        [void]$text.Item(0).AppendChild($xml.CreateTextNode($args[0])); \
+        [void]$text.Item(1).AppendChild($xml.CreateTextNode($args[1])); \
+        $toast = [Windows.UI.Notifications.ToastNotification]::new($xml); \
+        [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('wsl-clip').Show($toast) \
+        } catch { msg.exe * /TIME:10 ($args[0] + ': ' + $args[1]) }";
+    let mut cmd = interop::command("powershell.exe");
+    cmd.arg("-NoProfile")
+        .arg("-NonInteractive")
+        .arg("-EncodedCommand")
+        .arg(powershell::encode_command(script))
+        .arg("-EncodedArguments")
+        .arg(powershell::encode_arguments(&[title, body]));
+    cmd
+}
+pub fn notify_send_command(title: &str, body: &str) -> Command {
+    let mut cmd = Command::new("notify-send");
+    cmd.arg("--app-name=wsl-clip")
+        .arg("--expire-time=10000")
+        .arg(title)
+        .arg(body);
+    cmd
+}
+pub fn command_for(platform: Platform, title: &str, body: &str) -> Command {
+    match platform {
+        Platform::Wsl1 | Platform::Wsl2 => toast_command(title, body),
+        Platform::Linux => notify_send_command(title, body),
+    }
+}
+/// Fire-and-forget: waits briefly to log an immediate failure, then leaves
+/// the notifier running on its own. Never returns an error.
+pub fn send(platform: Platform, event: &Event, elapsed: Duration) {
+    let log = create_logger("notify");
+    let (title, body) = message(event, elapsed);
+    let mut cmd = command_for(platform, &title, &body);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            log.warn(&format!("Notifier unavailable: {}", e));
+            return;
+        }
+    };
+    let started = Instant::now();
+    while started.elapsed() < NOTIFY_TIMEOUT {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                if !status.success() {
+                    log.warn(&format!("Notifier exited with {}", status));
+                }
+                return;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => {
+                log.warn(&format!("Failed to poll notifier: {}", e));
+                return;
+            }
+        }
+    }
+    log.debug("Notifier still running; not waiting for it");
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+    /// What PowerShell decodes from an -Encoded* value.
+    fn decode(encoded: &str) -> String {
+        use base64::Engine;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map(|p| u16::from_le_bytes([p[0], p[1]]))
+            .collect();
+        String::from_utf16(&units).unwrap()
+    }
+    #[test]
+    fn test_threshold_gating() {
+        let threshold = Duration::from_secs(DEFAULT_THRESHOLD_SECS);
+        assert!(!should_notify(Duration::from_millis(4999), threshold));
+        assert!(should_notify(Duration::from_secs(5), threshold));
+        assert!(should_notify(Duration::from_secs(1), Duration::ZERO));
+    }
+    #[test]
+    fn test_message() {
+        let event = Event {
+            mode: "text",
+            outcome: Outcome::Failure,
+            bytes: 2048,
+        };
+        let (title, body) = message(&event, Duration::from_millis(7250));
+        assert_eq!(title, "wsl-clip: copy failed");
+        assert_eq!(body, "text mode, 2048 bytes, 7.2s");
+    }
+    #[test]
+    fn test_command_construction() {
+        let toast = command_for(Platform::Wsl2, "T'itle", "$(rm -rf)");
+        assert_eq!(toast.get_program(), "powershell.exe");
+        let a = args(&toast);
+        assert_eq!(
+            [&a[..3], &a[4..5]].concat(),
+            [
+                "-NoProfile",
+                "-NonInteractive",
+                "-EncodedCommand",
+                "-EncodedArguments"
+            ]
+        );
+        let script = decode(&a[3]);
+        assert!(script.contains("ToastNotificationManager") && script.contains("msg.exe"));
+        // User text arrives whole as $args[0] and $args[1], never as script
+        assert!(!script.contains("T'itle"));
+        let xml = decode(&a[5]);
+        let values: Vec<&str> = xml
+            .split("<S>")
+            .skip(1)
+            .map(|item| item.split("</S>").next().unwrap())
+            .collect();
+        assert_eq!(values, ["T'itle", "$(rm -rf)"]);
+        let spaced = args(&command_for(Platform::Wsl2, "wsl-clip: copy failed", "a b"));
+        assert!(decode(&spaced[5]).contains("<S>wsl-clip: copy failed</S><S>a b</S>"));
+        let linux = command_for(Platform::Linux, "title", "body");
+        assert_eq!(linux.get_program(), "notify-send");
+        assert_eq!(
+            args(&linux),
+            [
+                "--app-name=wsl-clip",
+                "--expire-time=10000",
+                "title",
+                "body"
+            ]
+        );
+    }
+}
+
+// <FILE>src/notify.rs</FILE> - <DESC>Completion notifications</DESC>
+// <VERS>END OF VERSION: 1.3.0 - 2026-10-16T14:23:34Z</VERS>
@@ -0,0 +1,445 @@
+// <FILE>src/options.rs</FILE> - <DESC>Layered option resolution</DESC>
+// <VERS>VERSION: 1.2.0 - 2026-10-16T10:24:41Z</VERS>
+// <WCTX>Profiles join the option layer stack.</WCTX>
+// <CLOG>Added Source::Profile, merge_layers and render.</CLOG>
+
+use crate::shell_paste::TargetShell;
+use crate::text_processor::FooterStyle;
+use crate::timestamp::TimestampMode;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fmt;
+/// Where an effective option value came from. Later layers win.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    Config,
+    Env,
+    /// A `[profile.<name>]` selected with --profile
+    Profile(String),
+    Cli,
+}
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => f.write_str("default"),
+            Source::Config => f.write_str("config file"),
+            Source::Env => f.write_str("environment"),
+            Source::Profile(name) => write!(f, "profile '{}'", name),
+            Source::Cli => f.write_str("command line"),
+        }
+    }
+}
+/// A partial set of text options supplied by one layer (config `[defaults]`,
+/// `WSL_CLIP_*` environment variables, or explicit CLI flags).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OptionLayer {
+    pub no_header: Option<bool>,
+    pub no_strip: Option<bool>,
+    pub crlf: Option<bool>,
+    pub code: Option<bool>,
+    pub snapshot: Option<bool>,
+    pub paste_safe: Option<bool>,
+    pub jsonl_pretty: Option<bool>,
+    /// Separator; an empty string selects the target shell's default
+    pub single_line: Option<String>,
+    pub target_shell: Option<TargetShell>,
+    pub footer_style: Option<FooterStyle>,
+    pub timestamp: Option<TimestampMode>,
+    pub timestamp_format: Option<String>,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layered<T> {
+    pub value: T,
+    pub source: Source,
+}
+/// Fully merged options with per-value provenance.
+#[derive(Debug, Clone)]
+pub struct Effective {
+    pub no_header: Layered<bool>,
+    pub no_strip: Layered<bool>,
+    pub crlf: Layered<bool>,
+    pub code: Layered<bool>,
+    pub snapshot: Layered<bool>,
+    pub paste_safe: Layered<bool>,
+    pub jsonl_pretty: Layered<bool>,
+    pub single_line: Layered<Option<String>>,
+    pub target_shell: Layered<TargetShell>,
+    pub footer_style: Layered<FooterStyle>,
+    pub timestamp: Layered<TimestampMode>,
+    pub timestamp_format: Layered<Option<String>>,
+}
+fn pick<T: Clone>(default: T, layers: &[(Source, Option<T>)]) -> Layered<T> {
+    layers
+        .iter()
+        .rev()
+        .find_map(|(source, v)| {
+            v.clone().map(|value| Layered {
+                value,
+                source: source.clone(),
+            })
+        })
+        .unwrap_or(Layered {
+            value: default,
+            source: Source::Default,
+        })
+}
+macro_rules! merge_field {
+    ($layers:expr, $field:ident, $default:expr) => {
+        pick(
+            $default,
+            &$layers
+                .iter()
+                .map(|(s, l)| (s.clone(), l.$field.clone()))
+                .collect::<Vec<_>>(),
+        )
+    };
+}
+/// Like merge_field, for options whose unset state is None.
+macro_rules! merge_optional {
+    ($layers:expr, $field:ident) => {
+        pick(
+            None,
+            &$layers
+                .iter()
+                .map(|(s, l)| (s.clone(), l.$field.clone().map(Some)))
+                .collect::<Vec<_>>(),
+        )
+    };
+}
+impl Effective {
+    /// Merges layers in precedence order: config < env < CLI.
+    pub fn merge(config: &OptionLayer, env: &OptionLayer, cli: &OptionLayer) -> Self {
+        Self::merge_layers(&[
+            (Source::Config, config),
+            (Source::Env, env),
+            (Source::Cli, cli),
+        ])
+    }
+    /// Merges an arbitrary stack of layers, lowest precedence first.
+    pub fn merge_layers(layers: &[(Source, &OptionLayer)]) -> Self {
+        Effective {
+            no_header: merge_field!(layers, no_header, false),
+            no_strip: merge_field!(layers, no_strip, false),
+            crlf: merge_field!(layers, crlf, false),
+            code: merge_field!(layers, code, false),
+            snapshot: merge_field!(layers, snapshot, false),
+            paste_safe: merge_field!(layers, paste_safe, false),
+            jsonl_pretty: merge_field!(layers, jsonl_pretty, false),
+            single_line: merge_optional!(layers, single_line),
+            target_shell: merge_field!(layers, target_shell, TargetShell::Powershell),
+            footer_style: merge_field!(layers, footer_style, FooterStyle::Detailed),
+            timestamp: merge_field!(layers, timestamp, TimestampMode::Utc),
+            timestamp_format: merge_optional!(layers, timestamp_format),
+        }
+    }
+    /// Resolved `--single-line` separator, if joining is enabled.
+    pub fn single_line_separator(&self) -> Option<String> {
+        self.single_line.value.as_ref().map(|sep| {
+            if sep.is_empty() {
+                self.target_shell.value.default_separator().to_string()
+            } else {
+                sep.clone()
+            }
+        })
+    }
+    /// The layer that switched an option on, or None if it is inactive.
+    /// Valued options count as active once any layer sets them explicitly.
+    pub fn active(&self, flag: &str) -> Option<Source> {
+        let on = |l: &Layered<bool>| l.value.then(|| l.source.clone());
+        match flag {
+            "no-header" => on(&self.no_header),
+            "no-strip" => on(&self.no_strip),
+            "crlf" => on(&self.crlf),
+            "code" => on(&self.code),
+            "snapshot" => on(&self.snapshot),
+            "paste-safe" => on(&self.paste_safe),
+            "jsonl-pretty" => on(&self.jsonl_pretty),
+            "single-line" => self
+                .single_line
+                .value
+                .as_ref()
+                .map(|_| self.single_line.source.clone()),
+            "footer-style" => explicit(&self.footer_style.source),
+            "timestamp-format" => explicit(&self.timestamp_format.source),
+            _ => None,
+        }
+    }
+    /// The effective configuration, one `key = value  # source` line per option.
+    pub fn render(&self) -> String {
+        fn line(out: &mut String, key: &str, value: String, source: &Source) {
+            out.push_str(&format!("{} = {}  # {}\n", key, value, source));
+        }
+        fn text(v: &Option<String>) -> String {
+            v.as_ref()
+                .map(|s| format!("{:?}", s))
+                .unwrap_or_else(|| "(unset)".to_string())
+        }
+        let mut out = String::new();
+        for (key, l) in [
+            ("no_header", &self.no_header),
+            ("no_strip", &self.no_strip),
+            ("crlf", &self.crlf),
+            ("code", &self.code),
+            ("snapshot", &self.snapshot),
+            ("paste_safe", &self.paste_safe),
+            ("jsonl_pretty", &self.jsonl_pretty),
+        ] {
+            line(&mut out, key, l.value.to_string(), &l.source);
+        }
+        line(
+            &mut out,
+            "single_line",
+            text(&self.single_line.value),
+            &self.single_line.source,
+        );
+        line(
+            &mut out,
+            "target_shell",
+            enum_name(&self.target_shell.value),
+            &self.target_shell.source,
+        );
+        line(
+            &mut out,
+            "footer_style",
+            enum_name(&self.footer_style.value),
+            &self.footer_style.source,
+        );
+        line(
+            &mut out,
+            "timestamp",
+            enum_name(&self.timestamp.value),
+            &self.timestamp.source,
+        );
+        line(
+            &mut out,
+            "timestamp_format",
+            text(&self.timestamp_format.value),
+            &self.timestamp_format.source,
+        );
+        out
+    }
+    /// Checks every conflict rule against the merged options and reports all
+    /// violations at once, naming the layer each value came from.
+    pub fn validate(&self) -> Result<()> {
+        let violations: Vec<String> = CONFLICTS
+            .iter()
+            .filter_map(|rule| {
+                let a = self.active(rule.a)?;
+                let b = self.active(rule.b)?;
+                Some(format!(
+                    "  --{} ({}) conflicts with --{} ({}): {}",
+                    rule.a, a, rule.b, b, rule.reason
+                ))
+            })
+            .collect();
+        if violations.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Incompatible options:\n{}\nRemove one of each pair (check your config file and WSL_CLIP_* variables).",
+            violations.join("\n")
+        )
+    }
+}
+fn explicit(source: &Source) -> Option<Source> {
+    (*source != Source::Default).then(|| source.clone())
+}
+fn enum_name<T: ValueEnum>(v: &T) -> String {
+    v.to_possible_value()
+        .map(|p| p.get_name().to_string())
+        .unwrap_or_default()
+}
+/// A pair of options that cannot be combined.
+pub struct Rule {
+    pub a: &'static str,
+    pub b: &'static str,
+    pub reason: &'static str,
+}
+/// Single source of truth for incompatible option pairs.
+pub const CONFLICTS: &[Rule] = &[
+    Rule {
+        a: "single-line",
+        b: "code",
+        reason: "a one-line command cannot carry Markdown fences",
+    },
+    Rule {
+        a: "single-line",
+        b: "crlf",
+        reason: "joined output has no line endings to convert",
+    },
+    Rule {
+        a: "single-line",
+        b: "paste-safe",
+        reason: "joined output is already a single line",
+    },
+    Rule {
+        a: "single-line",
+        b: "jsonl-pretty",
+        reason: "expanded records would be joined back into one line",
+    },
+    Rule {
+        a: "no-header",
+        b: "footer-style",
+        reason: "the footer is suppressed along with the headers",
+    },
+    Rule {
+        a: "no-header",
+        b: "timestamp-format",
+        reason: "timestamps only appear in headers",
+    },
+];
+/// Options that rewrite the emitted text; each must be covered by the table.
+pub const TRANSFORM_FLAGS: &[&str] = &["code", "crlf", "single-line", "jsonl-pretty"];
+fn parse_bool(key: &str, raw: &str) -> Result<bool> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" | "" => Ok(false),
+        other => anyhow::bail!("{}: expected a boolean, got '{}'", key, other),
+    }
+}
+fn parse_enum<T: ValueEnum>(key: &str, raw: &str) -> Result<T> {
+    T::from_str(raw.trim(), true).map_err(|e| anyhow::anyhow!("{}: {}", key, e))
+}
+impl OptionLayer {
+    /// Reads `WSL_CLIP_*` variables through a lookup (std::env::var in production).
+    pub fn from_env_with(get: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let bool_var = |name: &str| -> Result<Option<bool>> {
+            get(name).map(|v| parse_bool(name, &v)).transpose()
+        };
+        Ok(OptionLayer {
+            no_header: bool_var("WSL_CLIP_NO_HEADER")?,
+            no_strip: bool_var("WSL_CLIP_NO_STRIP")?,
+            crlf: bool_var("WSL_CLIP_CRLF")?,
+            code: bool_var("WSL_CLIP_CODE")?,
+            snapshot: bool_var("WSL_CLIP_SNAPSHOT")?,
+            paste_safe: bool_var("WSL_CLIP_PASTE_SAFE")?,
+            jsonl_pretty: bool_var("WSL_CLIP_JSONL_PRETTY")?,
+            single_line: get("WSL_CLIP_SINGLE_LINE"),
+            target_shell: get("WSL_CLIP_TARGET_SHELL")
+                .map(|v| parse_enum("WSL_CLIP_TARGET_SHELL", &v))
+                .transpose()?,
+            footer_style: get("WSL_CLIP_FOOTER_STYLE")
+                .map(|v| parse_enum("WSL_CLIP_FOOTER_STYLE", &v))
+                .transpose()?,
+            timestamp: get("WSL_CLIP_TIMESTAMP")
+                .map(|v| parse_enum("WSL_CLIP_TIMESTAMP", &v))
+                .transpose()?,
+            timestamp_format: get("WSL_CLIP_TIMESTAMP_FORMAT"),
+        })
+    }
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_with(|k| std::env::var(k).ok())
+            .context("Invalid WSL_CLIP_* environment variable")
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn cli(f: impl FnOnce(&mut OptionLayer)) -> OptionLayer {
+        let mut l = OptionLayer::default();
+        f(&mut l);
+        l
+    }
+    #[test]
+    fn test_precedence_and_sources() {
+        let config = cli(|l| {
+            l.crlf = Some(true);
+            l.code = Some(true);
+        });
+        let env = cli(|l| l.code = Some(false));
+        let args = cli(|l| l.crlf = Some(false));
+        let eff = Effective::merge(&config, &env, &args);
+        assert_eq!(
+            eff.crlf,
+            Layered {
+                value: false,
+                source: Source::Cli
+            }
+        );
+        assert_eq!(
+            eff.code,
+            Layered {
+                value: false,
+                source: Source::Env
+            }
+        );
+        assert_eq!(eff.no_header.source, Source::Default);
+        assert!(eff.timestamp_format.value.is_none());
+    }
+    #[test]
+    fn test_single_line_separator() {
+        let eff = Effective::merge(
+            &cli(|l| l.single_line = Some(String::new())),
+            &OptionLayer::default(),
+            &cli(|l| l.target_shell = Some(TargetShell::Bash)),
+        );
+        assert_eq!(eff.single_line_separator().as_deref(), Some(" && "));
+    }
+    #[test]
+    fn test_every_transform_flag_has_a_rule() {
+        for flag in TRANSFORM_FLAGS {
+            assert!(
+                CONFLICTS.iter().any(|r| r.a == *flag || r.b == *flag),
+                "transform flag --{} is missing from CONFLICTS",
+                flag
+            );
+        }
+        // Every rule refers to flags the checker knows how to evaluate
+        let all_on = Effective::merge(
+            &OptionLayer::default(),
+            &OptionLayer::default(),
+            &cli(|l| {
+                l.no_header = Some(true);
+                l.crlf = Some(true);
+                l.code = Some(true);
+                l.paste_safe = Some(true);
+                l.jsonl_pretty = Some(true);
+                l.single_line = Some(String::new());
+                l.footer_style = Some(FooterStyle::Compact);
+                l.timestamp_format = Some("%Y".into());
+            }),
+        );
+        for rule in CONFLICTS {
+            assert!(all_on.active(rule.a).is_some(), "unknown flag {}", rule.a);
+            assert!(all_on.active(rule.b).is_some(), "unknown flag {}", rule.b);
+        }
+    }
+    #[test]
+    fn test_validate_lists_every_violation_with_layers() {
+        let eff = Effective::merge(
+            &cli(|l| l.single_line = Some("; ".into())),
+            &cli(|l| l.crlf = Some(true)),
+            &cli(|l| l.code = Some(true)),
+        );
+        let err = eff.validate().unwrap_err().to_string();
+        assert!(err.contains("--single-line (config file) conflicts with --code (command line)"));
+        assert!(err.contains("--single-line (config file) conflicts with --crlf (environment)"));
+        assert!(Effective::merge(
+            &OptionLayer::default(),
+            &OptionLayer::default(),
+            &OptionLayer::default()
+        )
+        .validate()
+        .is_ok());
+    }
+    #[test]
+    fn test_env_layer() -> Result<()> {
+        let env = OptionLayer::from_env_with(|k| match k {
+            "WSL_CLIP_CRLF" => Some("yes".into()),
+            "WSL_CLIP_TIMESTAMP" => Some("Local".into()),
+            _ => None,
+        })?;
+        assert_eq!(env.crlf, Some(true));
+        assert_eq!(env.timestamp, Some(TimestampMode::Local));
+        assert!(
+            OptionLayer::from_env_with(|k| (k == "WSL_CLIP_CODE").then(|| "maybe".into())).is_err()
+        );
+        Ok(())
+    }
+}
+
+// <FILE>src/options.rs</FILE> - <DESC>Layered option resolution</DESC>
+// <VERS>END OF VERSION: 1.2.0 - 2026-10-16T10:24:41Z</VERS>
@@ -0,0 +1,327 @@
+// <FILE>src/paste.rs</FILE> - <DESC>Clipboard paste output</DESC>
+// <VERS>VERSION: 1.6.0 - 2026-10-16T14:29:50Z</VERS>
+// <WCTX>paste -o file modes</WCTX>
+// <CLOG>paste -o keeps the replaced file's mode, else 0666 less the umask</CLOG>
+
+use crate::clipboard::{self, ClipboardContent, ClipboardRead, ClipboardReader, ImageFormat};
+use crate::daemon;
+use crate::debug_logger::create_logger;
+use crate::paths;
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+const BOM: &[u8] = b"\xef\xbb\xbf";
+/// Copies clipboard text from `reader` to `out` without buffering all of it.
+/// With `lf`, CRLF becomes LF (a pair split across reads included) and a
+/// leading UTF-8 BOM is dropped. A lone `\r` is content, not a line ending,
+/// and a missing final newline stays missing. Returns the bytes written.
+pub fn copy_text<R: BufRead, W: Write>(mut reader: R, out: &mut W, lf: bool) -> io::Result<u64> {
+    if !lf {
+        return io::copy(&mut reader, out);
+    }
+    // The BOM is checked on the first three bytes, however the pipe splits them
+    let mut head = [0u8; 3];
+    let mut filled = 0;
+    while filled < head.len() {
+        match reader.read(&mut head[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    let head = &head[..filled];
+    let mut lines = LfWriter {
+        out,
+        pending_cr: false,
+        written: 0,
+    };
+    if head != BOM {
+        lines.write(head)?;
+    }
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        let n = chunk.len();
+        lines.write(chunk)?;
+        reader.consume(n);
+    }
+    lines.finish()
+}
+/// CRLF -> LF over a byte stream. UTF-8 continuation bytes never equal
+/// `\r` or `\n`, so working on bytes is safe.
+struct LfWriter<'a, W: Write> {
+    out: &'a mut W,
+    /// A `\r` ended the previous chunk; whether it is kept depends on the next byte
+    pending_cr: bool,
+    written: u64,
+}
+impl<W: Write> LfWriter<'_, W> {
+    fn write(&mut self, mut chunk: &[u8]) -> io::Result<()> {
+        if self.pending_cr && !chunk.is_empty() {
+            self.pending_cr = false;
+            if chunk[0] != b'\n' {
+                self.emit(b"\r")?;
+            }
+        }
+        while let Some(i) = chunk.iter().position(|&b| b == b'\r') {
+            self.emit(&chunk[..i])?;
+            match chunk.get(i + 1) {
+                Some(b'\n') => {}
+                Some(_) => self.emit(b"\r")?,
+                None => self.pending_cr = true,
+            }
+            chunk = &chunk[i + 1..];
+        }
+        self.emit(chunk)
+    }
+    fn emit(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.written += bytes.len() as u64;
+        self.out.write_all(bytes)
+    }
+    fn finish(mut self) -> io::Result<u64> {
+        if self.pending_cr {
+            self.emit(b"\r")?;
+        }
+        Ok(self.written)
+    }
+}
+/// Streams clipboard text into `out`. Nothing to paste is an error, so
+/// scripts can tell an empty clipboard from an empty line.
+pub fn paste_to<R: BufRead, W: Write>(reader: R, out: &mut W, lf: bool) -> Result<u64> {
+    let written = copy_text(reader, out, lf)?;
+    out.flush()?;
+    if written == 0 {
+        anyhow::bail!("Clipboard is empty; nothing to paste");
+    }
+    Ok(written)
+}
+/// Clipboard text still in powershell.exe's pipe, or already fetched by the daemon.
+pub(crate) enum Source {
+    Pipe(ClipboardReader),
+    Daemon(io::Cursor<Vec<u8>>),
+}
+impl Source {
+    pub(crate) fn reader(&mut self) -> &mut dyn BufRead {
+        match self {
+            Source::Pipe(text) => &mut text.reader,
+            Source::Daemon(text) => text,
+        }
+    }
+    pub(crate) fn finish(self) -> Result<()> {
+        match self {
+            Source::Pipe(text) => text.finish(),
+            Source::Daemon(_) => Ok(()),
+        }
+    }
+    pub(crate) fn abort(self) -> Result<()> {
+        match self {
+            Source::Pipe(text) => text.abort(),
+            Source::Daemon(_) => Ok(()),
+        }
+    }
+}
+/// What paste found on the clipboard.
+pub(crate) enum Found {
+    Text(Source),
+    Empty,
+    Other(String),
+}
+/// Asks the daemon when one is running, else PowerShell directly.
+pub(crate) fn open() -> Result<Found> {
+    match daemon::client().map(|mut client| client.paste()) {
+        Some(Ok(ClipboardContent::Text(text))) => Ok(Found::Text(Source::Daemon(io::Cursor::new(
+            text.into_bytes(),
+        )))),
+        Some(Ok(ClipboardContent::Empty)) => Ok(Found::Empty),
+        Some(Ok(ClipboardContent::Other(format))) => Ok(Found::Other(format)),
+        failed => {
+            if let Some(Err(e)) = failed {
+                create_logger("daemon")
+                    .warn(&format!("Daemon paste failed, reading directly: {:#}", e));
+            }
+            Ok(match clipboard::open_clipboard()? {
+                ClipboardRead::Text(text) => Found::Text(Source::Pipe(text)),
+                ClipboardRead::Other(format) => Found::Other(format),
+                ClipboardRead::Empty => Found::Empty,
+            })
+        }
+    }
+}
+fn open_text() -> Result<Source> {
+    match open()? {
+        Found::Text(text) => Ok(text),
+        Found::Empty => anyhow::bail!("Clipboard is empty; nothing to paste"),
+        Found::Other(format) => anyhow::bail!(
+            "Clipboard holds non-text data: {} (paste only handles text)",
+            format
+        ),
+    }
+}
+/// `wsl-clip paste`: the clipboard text to stdout, or to `output` (replaced
+/// atomically, so a failed paste never leaves half a file). Returns the bytes written.
+pub fn run(lf: bool, output: Option<&Path>) -> Result<u64> {
+    let mut text = open_text()?;
+    let Some(path) = output else {
+        return match paste_to(text.reader(), &mut io::stdout().lock(), lf) {
+            // A reader that exits early (`| head`) is not an error
+            Err(e) if broken_pipe(&e) => {
+                text.abort()?;
+                Ok(0)
+            }
+            Err(e) => {
+                text.abort()?;
+                Err(e)
+            }
+            Ok(n) => {
+                text.finish()?;
+                Ok(n)
+            }
+        };
+    };
+    let mut file = BufWriter::new(output_tempfile(path)?);
+    let written = match paste_to(text.reader(), &mut file, lf) {
+        Ok(n) => n,
+        Err(e) => {
+            text.abort()?;
+            return Err(e);
+        }
+    };
+    text.finish()?;
+    file.into_inner()
+        .map_err(|e| e.into_error())?
+        .persist(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(written)
+}
+/// A temp file beside `target`, with the mode the replaced file had, else
+/// 0666 less the umask, as a plain create would give (a bare
+/// NamedTempFile is 0600, and `persist` would carry that over).
+fn output_tempfile(target: &Path) -> Result<tempfile::NamedTempFile> {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = parent_dir(target);
+    let tmp = tempfile::Builder::new()
+        .permissions(std::fs::Permissions::from_mode(0o666))
+        .tempfile_in(dir)
+        .with_context(|| format!("Cannot write to {}", dir.display()))?;
+    if let Some(meta) = std::fs::metadata(target).ok().filter(|m| m.is_file()) {
+        tmp.as_file()
+            .set_permissions(meta.permissions())
+            .with_context(|| format!("Cannot write to {}", dir.display()))?;
+    }
+    Ok(tmp)
+}
+/// `wsl-clip paste --image`: saves the clipboard bitmap as PNG or JPEG
+/// (by `dest`'s extension). Windows writes it to %TEMP%; the file is then
+/// copied into `dest` and the Windows copy removed. Returns the file size.
+pub fn save_image(dest: &Path) -> Result<u64> {
+    let format = ImageFormat::from_path(dest)?;
+    save_from_windows(dest, || clipboard::save_image(format))
+}
+/// `wsl-clip shot --out`: takes the screenshot, saving it as `dest`'s
+/// format, and moves Windows' copy into `dest`. Returns the shot and the file size.
+pub fn save_screenshot(area: clipboard::ShotArea, dest: &Path) -> Result<(clipboard::Shot, u64)> {
+    let format = ImageFormat::from_path(dest)?;
+    let mut shot = None;
+    let size = save_from_windows(dest, || {
+        let taken = clipboard::screenshot(area, Some(format))?;
+        let win_path = taken
+            .saved
+            .clone()
+            .context("PowerShell did not save the screenshot")?;
+        shot = Some(taken);
+        Ok(win_path)
+    })?;
+    Ok((shot.context("No screenshot was taken")?, size))
+}
+/// Runs `save`, which has Windows write an image under %TEMP% and returns
+/// its Windows path, then moves that file into `dest`.
+fn save_from_windows(dest: &Path, save: impl FnOnce() -> Result<String>) -> Result<u64> {
+    let log = create_logger("clipboard");
+    // Fail on an unwritable destination before asking Windows for anything
+    let mut tmp = tempfile::NamedTempFile::new_in(parent_dir(dest))
+        .with_context(|| format!("Cannot write to {}", parent_dir(dest).display()))?;
+    let win_path = save()?;
+    let saved = paths::to_linux_path(&win_path)?;
+    let size = io::copy(
+        &mut std::fs::File::open(&saved)
+            .with_context(|| format!("Cannot read the saved image at {:?}", saved))?,
+        tmp.as_file_mut(),
+    )?;
+    if let Err(e) = std::fs::remove_file(&saved) {
+        log.debug(&format!("Could not remove {:?}: {}", saved, e));
+    }
+    tmp.persist(dest)
+        .with_context(|| format!("Failed to write {}", dest.display()))?;
+    Ok(size)
+}
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    }
+}
+pub(crate) fn broken_pipe(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn lf(input: &[u8], chunk: usize) -> String {
+        let mut out = Vec::new();
+        copy_text(io::BufReader::with_capacity(chunk, input), &mut out, true).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+    #[test]
+    fn test_empty_clipboard_is_an_error() {
+        let err = paste_to(&b""[..], &mut Vec::new(), false).unwrap_err();
+        assert!(err.to_string().contains("Clipboard is empty"));
+        // A BOM alone is nothing to paste either
+        let err = paste_to(BOM, &mut Vec::new(), true).unwrap_err();
+        assert!(err.to_string().contains("Clipboard is empty"));
+    }
+    #[test]
+    fn test_multi_line_content() -> Result<()> {
+        let windows = "first\r\n\tsecond ✓\r\n\r\nlast";
+        let mut raw = Vec::new();
+        paste_to(windows.as_bytes(), &mut raw, false)?;
+        assert_eq!(raw, windows.as_bytes());
+        assert_eq!(lf(windows.as_bytes(), 8192), "first\n\tsecond ✓\n\nlast");
+        Ok(())
+    }
+    #[test]
+    fn test_lf_edge_cases() {
+        // A lone \r mid-line is kept, including one ending a chunk
+        assert_eq!(lf(b"a\rb\r\nc", 8192), "a\rb\nc");
+        assert_eq!(lf(b"a\rb", 2), "a\rb");
+        assert_eq!(lf(b"tail\r", 8192), "tail\r");
+        // No trailing newline stays that way; CRLF split across reads still joins
+        for chunk in 1..=6 {
+            assert_eq!(lf(b"x\r\ny\r\nz", chunk), "x\ny\nz", "chunk {}", chunk);
+            assert_eq!(lf(b"\xef\xbb\xbfhi\r\n", chunk), "hi\n", "chunk {}", chunk);
+        }
+        // Only a leading BOM goes
+        assert_eq!(lf(b"a\xef\xbb\xbf", 1), "a\u{feff}");
+        let mut out = Vec::new();
+        copy_text(&b"\xef\xbb"[..], &mut out, true).unwrap();
+        assert_eq!(out, b"\xef\xbb");
+    }
+    #[test]
+    fn test_broken_pipe_is_detected() {
+        struct Closed;
+        impl Write for Closed {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = paste_to(&b"x\n"[..], &mut Closed, true).unwrap_err();
+        assert!(broken_pipe(&err));
+    }
+}
+
+// <FILE>src/paste.rs</FILE> - <DESC>Clipboard paste output</DESC>
+// <VERS>END OF VERSION: 1.6.0 - 2026-10-16T14:29:50Z</VERS>
@@ -1,22 +1,315 @@
 // <FILE>wsl-clip/src/paths.rs</FILE> - <DESC>Instrumented with debug logging</DESC>
-// <VERS>VERSION: 1.2.0 - 2025-11-24T14:52:13Z</VERS>
-// <WCTX>Added logging to wslpath conversion.</WCTX>
-// <CLOG>Added logging.</CLOG>
+// <VERS>VERSION: 1.13.0 - 2026-10-16T14:12:06Z</VERS>
+// <WCTX>Symlink policy across modes</WCTX>
+// <CLOG>Added --follow/--no-follow; --no-follow converts a link's own path and dangling links get a clear error</CLOG>
 
 use crate::debug_logger::create_logger;
+use crate::environment::{self, Platform};
 use anyhow::{Context, Result};
-use std::path::Path;
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
+/// `--unc`: how Windows paths to Linux-filesystem files spell the WSL share.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+pub enum UncStyle {
+    /// \\wsl$\<distro>\..., which older Windows apps require
+    #[value(name = "wsl$")]
+    #[serde(rename = "wsl$")]
+    Wsl,
+    /// \\wsl.localhost\<distro>\..., the current default
+    #[value(name = "localhost")]
+    #[serde(rename = "localhost")]
+    Localhost,
+}
+/// `[paths]` section of the config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PathsConfig {
+    /// Default for --unc
+    pub unc: Option<UncStyle>,
+}
+static UNC: OnceLock<Option<UncStyle>> = OnceLock::new();
+/// Fixes the UNC spelling for this process (`--unc`, else `[paths] unc`).
+/// `None` keeps whatever wslpath prints.
+pub fn set_unc(style: Option<UncStyle>) {
+    let _ = UNC.set(style);
+}
+static FOLLOW: OnceLock<bool> = OnceLock::new();
+/// Fixes the symlink policy for this process: `false` (--no-follow) makes
+/// path conversion stop at the link instead of resolving through it.
+pub fn set_follow(follow: bool) {
+    let _ = FOLLOW.set(follow);
+}
+/// Whether symlinks are resolved (--follow, the default).
+pub fn follow() -> bool {
+    *FOLLOW.get().unwrap_or(&true)
+}
+/// Where a symlink points, if it is one whose target does not exist.
+pub fn dangling_target(path: &Path) -> Option<PathBuf> {
+    let is_link = std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    if !is_link || std::fs::metadata(path).is_ok() {
+        return None;
+    }
+    std::fs::read_link(path).ok()
+}
+/// `x is a symlink to y, which does not exist`, for a dangling link.
+pub fn dangling_message(path: &Path) -> Option<String> {
+    let target = dangling_target(path)?;
+    Some(format!(
+        "{} is a symlink to {}, which does not exist",
+        path.display(),
+        target.display()
+    ))
+}
+/// The absolute path of `path`. With `follow`, fully canonical; without it,
+/// only the folder is resolved, so a symlink (even a dangling one) keeps
+/// its own name and location.
+pub fn absolute(path: &Path, follow: bool) -> Result<PathBuf> {
+    let fail = || match dangling_message(path) {
+        Some(msg) => anyhow::anyhow!(msg),
+        None => anyhow::anyhow!("Failed to resolve path: {:?}", path),
+    };
+    match path.file_name() {
+        Some(name) if !follow => {
+            std::fs::symlink_metadata(path).map_err(|_| fail())?;
+            let parent = match path.parent() {
+                Some(p) if !p.as_os_str().is_empty() => p,
+                _ => Path::new("."),
+            };
+            Ok(dunce::canonicalize(parent).map_err(|_| fail())?.join(name))
+        }
+        _ => dunce::canonicalize(path).map_err(|_| fail()),
+    }
+}
+/// The WSL share hosts, as wslpath may print them.
+const WSL_HOSTS: [&str; 2] = ["wsl$", "wsl.localhost"];
+/// Respells a `\\wsl$\` or `\\wsl.localhost\` prefix as `style` asks.
+/// Drive paths and other UNC shares come back unchanged.
+pub fn rewrite_unc(win_path: &str, style: UncStyle) -> String {
+    let Some(rest) = win_path.strip_prefix(r"\\") else {
+        return win_path.to_string();
+    };
+    let (host, share) = rest.split_once('\\').unwrap_or((rest, ""));
+    if share.is_empty() || !WSL_HOSTS.iter().any(|h| host.eq_ignore_ascii_case(h)) {
+        return win_path.to_string();
+    }
+    let host = match style {
+        UncStyle::Wsl => "wsl$",
+        UncStyle::Localhost => "wsl.localhost",
+    };
+    format!(r"\\{}\{}", host, share)
+}
+/// The Windows path of an existing file, following symlinks unless
+/// --no-follow said otherwise.
 pub fn to_windows_path(path: &Path) -> Result<String> {
+    if !follow() {
+        return to_windows_path_unresolved(path);
+    }
     let log = create_logger("paths");
-    let abs_path =
-        dunce::canonicalize(path).with_context(|| format!("Failed to resolve path: {:?}", path))?;
+    let abs_path = absolute(path, true)?;
     log.debug(&format!("Canonicalized path: {:?}", abs_path));
-    let output = Command::new("wslpath")
-        .arg("-w")
-        .arg(&abs_path)
-        .output()
-        .with_context(|| "Failed to execute wslpath")?;
+    if let Some(win_path) = drive_path(&abs_path, environment::automount_root()) {
+        log.debug(&format!("Windows path (drive, no wslpath): {}", win_path));
+        return Ok(win_path);
+    }
+    wslpath_windows(&abs_path, false)
+}
+/// `to_windows_path` for the link itself: the folder is resolved, the last
+/// component is not, and `wslpath -a` is told not to resolve it either.
+pub fn to_windows_path_unresolved(path: &Path) -> Result<String> {
+    let abs_path = absolute(path, false)?;
+    create_logger("paths").debug(&format!("Unresolved path: {:?}", abs_path));
+    if let Some(win_path) = drive_path(&abs_path, environment::automount_root()) {
+        return Ok(win_path);
+    }
+    wslpath_windows(&abs_path, true)
+}
+/// The Windows path of a file on a drive mount, worked out without wslpath:
+/// `<root>/c/Users/me` -> `C:\Users\me`. None for anything else, and for
+/// names only wslpath can map (see `needs_wslpath`).
+pub fn drive_path(abs_path: &Path, automount_root: &Path) -> Option<String> {
+    let mut parts = abs_path.strip_prefix(automount_root).ok()?.components();
+    let drive = parts.next()?.as_os_str().to_str()?;
+    let letter = match drive.as_bytes() {
+        [c] if c.is_ascii_alphabetic() => c.to_ascii_uppercase() as char,
+        _ => return None,
+    };
+    let mut win_path = format!("{}:\\", letter);
+    for (i, part) in parts.enumerate() {
+        let Component::Normal(name) = part else {
+            return None;
+        };
+        let name = name.to_str()?;
+        // Windows drops a trailing dot or space, so the name would not round-trip
+        if name.chars().any(needs_wslpath) || name.ends_with(['.', ' ']) {
+            return None;
+        }
+        if i > 0 {
+            win_path.push('\\');
+        }
+        win_path.push_str(name);
+    }
+    Some(win_path)
+}
+/// `to_windows_path` for a batch, in order. wslpath runs once per mount
+/// the files live on (the Linux root, each Windows drive) and the rest of
+/// each path is joined on. Names wslpath would remap (`:` `*` `?` and the
+/// like) and any mount it cannot convert fall back to a run per file, so an
+/// error still names the file it is about.
+pub fn to_windows_paths(paths: &[PathBuf]) -> Result<Vec<String>> {
+    let log = create_logger("paths");
+    let started = std::time::Instant::now();
+    let follow = follow();
+    let absolute = paths
+        .iter()
+        .map(|p| absolute(p, follow))
+        .collect::<Result<Vec<_>>>()?;
+    let mut mounts = environment::drvfs_mounts().unwrap_or_default();
+    // Longest first, so nested mounts win
+    mounts.sort_by_key(|m| std::cmp::Reverse(m.components().count()));
+    let mut prefixes: std::collections::HashMap<PathBuf, Option<String>> = Default::default();
+    let mut runs = 0;
+    let mut out = Vec::with_capacity(paths.len());
+    let automount_root = environment::automount_root();
+    for (path, abs_path) in paths.iter().zip(&absolute) {
+        if let Some(win_path) = drive_path(abs_path, automount_root) {
+            out.push(win_path);
+            continue;
+        }
+        let mount = mounts
+            .iter()
+            .find(|m| abs_path.starts_with(m))
+            .map_or(Path::new("/"), |m| m.as_path());
+        let rest = abs_path.strip_prefix(mount).unwrap_or(abs_path);
+        let joined = rest
+            .to_str()
+            .filter(|r| !r.chars().any(needs_wslpath))
+            .and_then(|r| {
+                let prefix = prefixes.entry(mount.to_path_buf()).or_insert_with(|| {
+                    runs += 1;
+                    wslpath_windows(mount, false)
+                        .map_err(|e| log.debug(&format!("No prefix for {:?}: {:#}", mount, e)))
+                        .ok()
+                });
+                let prefix = prefix.as_deref()?;
+                Some(join_windows(prefix, r))
+            });
+        out.push(match joined {
+            Some(win_path) => win_path,
+            None => {
+                runs += 1;
+                wslpath_windows(abs_path, !follow)
+                    .with_context(|| format!("Cannot convert {}", path.display()))?
+            }
+        });
+    }
+    log.debug(&format!(
+        "Converted {} paths with {} wslpath runs in {:?}",
+        paths.len(),
+        runs,
+        started.elapsed()
+    ));
+    Ok(out)
+}
+/// Characters Windows file names cannot hold: wslpath maps them into the
+/// private use area, so only wslpath knows the result.
+fn needs_wslpath(c: char) -> bool {
+    matches!(c, '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+}
+/// `C:\` + `Users/me` -> `C:\Users\me`.
+fn join_windows(prefix: &str, rest: &str) -> String {
+    let prefix = prefix.trim_end_matches('\\');
+    if rest.is_empty() {
+        // wslpath ends a drive root with `\` (`C:\`) and a share without
+        return if prefix.ends_with(':') {
+            format!("{}\\", prefix)
+        } else {
+            prefix.to_string()
+        };
+    }
+    format!("{}\\{}", prefix, rest.replace('/', "\\"))
+}
+/// `path --literal`: converts `path` whether or not it exists. It is made
+/// absolute lexically (symlinks stay as written) and `wslpath -a` does the rest.
+pub fn to_windows_path_literal(path: &Path) -> Result<String> {
+    let abs_path = lexical_absolute(path)?;
+    create_logger("paths").debug(&format!("Literal path: {:?}", abs_path));
+    if let Some(win_path) = drive_path(&abs_path, environment::automount_root()) {
+        return Ok(win_path);
+    }
+    wslpath_windows(&abs_path, true)
+}
+/// `path` against the working directory, with `.` dropped and `..` taking
+/// off the component before it (never past `/`). Nothing is looked up.
+pub fn lexical_absolute(path: &Path) -> Result<PathBuf> {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .context("Failed to read the current directory")?
+            .join(path)
+    };
+    let mut out = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    Ok(out)
+}
+/// Whether wslpath is on PATH, looked up once per process. Plain Linux and
+/// most containers have none; text copies never need it.
+pub fn wslpath_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let found = crate::interop::on_path("wslpath");
+        create_logger("paths").debug(&format!(
+            "wslpath: {}",
+            if found { "on PATH" } else { "not found" }
+        ));
+        found
+    })
+}
+/// Why a path that needs wslpath cannot be converted without it.
+pub fn wslpath_missing(platform: Platform, automount_root: &Path) -> String {
+    let cause = match platform {
+        Platform::Linux => "this is not WSL (plain Linux or a container)",
+        Platform::Wsl1 | Platform::Wsl2 => {
+            "WSL normally provides /usr/bin/wslpath; this distribution lacks it or runs without interop"
+        }
+    };
+    format!(
+        "wslpath is not available: {}. Only files under {}/<drive> can be converted without it (set {} if the drives are mounted elsewhere)",
+        cause,
+        automount_root.display(),
+        environment::AUTOMOUNT_ROOT_ENV
+    )
+}
+/// `wslpath` with `args`, or the explanation above when it is missing.
+fn run_wslpath(args: &[&std::ffi::OsStr]) -> Result<std::process::Output> {
+    let missing = || wslpath_missing(environment::current(), environment::automount_root());
+    if !wslpath_available() {
+        anyhow::bail!(missing());
+    }
+    Command::new("wslpath").args(args).output().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            anyhow::anyhow!(missing())
+        } else {
+            anyhow::Error::new(e).context("Failed to execute wslpath")
+        }
+    })
+}
+fn wslpath_windows(abs_path: &Path, literal: bool) -> Result<String> {
+    let log = create_logger("paths");
+    let flags: &[&str] = if literal { &["-a", "-w"] } else { &["-w"] };
+    let mut args: Vec<&std::ffi::OsStr> = flags.iter().map(std::ffi::OsStr::new).collect();
+    args.push(abs_path.as_os_str());
+    let output = run_wslpath(&args)?;
     if !output.status.success() {
         let err = String::from_utf8_lossy(&output.stderr);
         log.error(&format!("wslpath failed: {}", err.trim()));
@@ -24,14 +317,175 @@ pub fn to_windows_path(path: &Path) -> Result<String> {
     }
     let win_path = String::from_utf8(output.stdout)
         .with_context(|| "wslpath output returned invalid UTF-8")?;
-    let trimmed = win_path.trim().to_string();
+    let mut trimmed = win_path.trim().to_string();
+    if let Some(style) = UNC.get().copied().flatten() {
+        trimmed = rewrite_unc(&trimmed, style);
+    }
     log.debug(&format!("Windows path: {}", trimmed));
     Ok(trimmed)
 }
+/// `path --wsl`: the canonical Linux path, without asking wslpath
+/// (`/mnt/c/...` for files on Windows drives); under --no-follow, the
+/// link's own. With `literal`, the lexical one.
+pub fn to_wsl_path(path: &Path, literal: bool) -> Result<String> {
+    let abs_path = if literal {
+        lexical_absolute(path)?
+    } else {
+        absolute(path, follow())?
+    };
+    abs_path
+        .into_os_string()
+        .into_string()
+        .map_err(|p| anyhow::anyhow!("Path is not valid UTF-8: {:?}", p))
+}
+/// The reverse of `to_windows_path`: a Windows path (e.g. a file PowerShell
+/// wrote under %TEMP%) as seen from WSL, via `wslpath -u`.
+pub fn to_linux_path(win_path: &str) -> Result<PathBuf> {
+    let log = create_logger("paths");
+    if let Some(linux) = linux_drive_path(win_path, environment::automount_root()) {
+        log.debug(&format!("Linux path (drive, no wslpath): {:?}", linux));
+        return Ok(linux);
+    }
+    let output = run_wslpath(&["-u".as_ref(), win_path.as_ref()])?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        log.error(&format!("wslpath -u failed: {}", err.trim()));
+        anyhow::bail!("wslpath -u failed for {}: {}", win_path, err.trim());
+    }
+    let linux = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    log.debug(&format!("Linux path: {:?}", linux));
+    Ok(linux)
+}
+/// `drive_path` the other way round: `C:\Users\me` -> `<root>/c/Users/me`.
+/// None for UNC paths and for names wslpath would have to map.
+pub fn linux_drive_path(win_path: &str, automount_root: &Path) -> Option<PathBuf> {
+    let mut chars = win_path.chars();
+    let letter = chars.next().filter(char::is_ascii_alphabetic)?;
+    if chars.next() != Some(':') {
+        return None;
+    }
+    let rest = chars.as_str();
+    if !(rest.is_empty() || rest.starts_with(['\\', '/'])) {
+        return None;
+    }
+    let mut out = automount_root.join(letter.to_ascii_lowercase().to_string());
+    for name in rest.split(['\\', '/']).filter(|n| !n.is_empty()) {
+        if name == "." || name == ".." || name.chars().any(needs_wslpath) {
+            return None;
+        }
+        out.push(name);
+    }
+    Some(out)
+}
+/// Longest Windows path, in UTF-16 code units, that programs without
+/// long-path support can open (MAX_PATH, 260, less the terminating NUL).
+pub const MAX_PATH_LEN: usize = 259;
+/// A path's length as Win32 counts it: UTF-16 code units, not bytes.
+pub fn utf16_len(win_path: &str) -> usize {
+    win_path.encode_utf16().count()
+}
+/// The `\\?\` form of a Windows path, which long-path-aware programs open
+/// past MAX_PATH: `\\?\C:\...` for drive paths, `\\?\UNC\server\...` for
+/// shares such as `\\wsl.localhost`. None when there is no such form.
+pub fn long_path_form(win_path: &str) -> Option<String> {
+    if win_path.starts_with(r"\\?\") {
+        return None;
+    }
+    if let Some(unc) = win_path.strip_prefix(r"\\") {
+        return Some(format!(r"\\?\UNC\{}", unc));
+    }
+    let bytes = win_path.as_bytes();
+    let drive = bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && &bytes[1..3] == br":\";
+    drive.then(|| format!(r"\\?\{}", win_path))
+}
+/// Paths longer than MAX_PATH_LEN are switched to their long-path form,
+/// with one warning naming them all; `strict` (--strict-length) makes that
+/// an error instead.
+pub fn fit_max_path(win_paths: Vec<String>, strict: bool) -> Result<Vec<String>> {
+    let long: Vec<String> = win_paths
+        .iter()
+        .filter(|p| utf16_len(p) > MAX_PATH_LEN)
+        .map(|p| format!("  {} ({} characters)", p, utf16_len(p)))
+        .collect();
+    if long.is_empty() {
+        return Ok(win_paths);
+    }
+    if strict {
+        anyhow::bail!(
+            "{} path(s) exceed the Windows MAX_PATH limit of {} characters:\n{}",
+            long.len(),
+            MAX_PATH_LEN,
+            long.join("\n")
+        );
+    }
+    eprintln!(
+        "[WARN] {} path(s) exceed the Windows MAX_PATH limit of {} characters; \
+         using the \\\\?\\ long-path form, which older programs may still refuse:\n{}",
+        long.len(),
+        MAX_PATH_LEN,
+        long.join("\n")
+    );
+    Ok(win_paths
+        .into_iter()
+        .map(|p| match long_path_form(&p) {
+            Some(prefixed) if utf16_len(&p) > MAX_PATH_LEN => prefixed,
+            _ => p,
+        })
+        .collect())
+}
+/// `path --slash`: `C:/Users/me/file`, `//wsl.localhost/Ubuntu/...` for UNC
+/// paths. Runs of separators (wslpath can mix them) become one; a `\\?\`
+/// prefix goes, since it only works with backslashes.
+pub fn forward_slashes(win_path: &str) -> String {
+    let path = win_path.replace('\\', "/");
+    let path = match (path.strip_prefix("//?/UNC/"), path.strip_prefix("//?/")) {
+        (Some(share), _) => format!("//{}", share),
+        (None, Some(local)) => local.to_string(),
+        (None, None) => path,
+    };
+    let (mut out, rest) = match path.strip_prefix("//") {
+        Some(rest) => ("//".to_string(), rest.trim_start_matches('/')),
+        None => (String::new(), path.as_str()),
+    };
+    for c in rest.chars() {
+        if !(c == '/' && out.ends_with('/')) {
+            out.push(c);
+        }
+    }
+    out
+}
+/// Parses `path --sep`: `space`, `newline`, `crlf` or `tab` by name, any
+/// other string as it is (`;`, `,`).
+pub fn parse_separator(raw: &str) -> Result<String, String> {
+    Ok(match raw.to_ascii_lowercase().as_str() {
+        "" => return Err("the separator cannot be empty".to_string()),
+        "space" => " ".to_string(),
+        "newline" | "nl" | "lf" => "\n".to_string(),
+        "crlf" => "\r\n".to_string(),
+        "tab" => "\t".to_string(),
+        _ => raw.to_string(),
+    })
+}
+/// Joins paths for `wsl-clip path`. With `quote`, paths joined by a space
+/// are double-quoted when they contain one, so a command line reads them as
+/// one argument each. A single path is never quoted.
+pub fn join_paths(paths: &[String], sep: &str, quote: bool) -> String {
+    let quote = quote && sep == " " && paths.len() > 1;
+    paths
+        .iter()
+        .map(|p| {
+            if quote && p.contains(' ') {
+                format!("\"{}\"", p)
+            } else {
+                p.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(sep)
+}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
     #[test]
     fn test_wslpath_resolution() {
         let p = PathBuf::from("/bin/sh");
@@ -40,7 +494,307 @@ mod tests {
             assert!(res.is_ok());
         }
     }
+    #[test]
+    fn test_wsl_path_is_canonical() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir(dir.path().join("sub"))?;
+        std::fs::write(dir.path().join("a.txt"), "a")?;
+        let canonical = dunce::canonicalize(dir.path().join("a.txt"))?;
+        assert_eq!(
+            to_wsl_path(&dir.path().join("sub/../a.txt"), false)?,
+            canonical.to_string_lossy()
+        );
+        assert!(to_wsl_path(&dir.path().join("gone"), false).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_symlinks_followed_or_kept() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let root = dunce::canonicalize(dir.path())?;
+        std::fs::create_dir(root.join("real"))?;
+        std::fs::write(root.join("real/a.txt"), "a")?;
+        std::os::unix::fs::symlink("real/a.txt", root.join("relative"))?;
+        std::os::unix::fs::symlink(root.join("real/a.txt"), root.join("absolute"))?;
+        std::os::unix::fs::symlink("gone.txt", root.join("dangling"))?;
+        // A linked folder is still resolved; only the last component is kept
+        std::os::unix::fs::symlink(root.join("real"), root.join("dir"))?;
+        for link in ["relative", "absolute"] {
+            assert_eq!(absolute(&root.join(link), true)?, root.join("real/a.txt"));
+            assert_eq!(absolute(&root.join(link), false)?, root.join(link));
+        }
+        assert_eq!(
+            absolute(&root.join("dir/a.txt"), false)?,
+            root.join("real/a.txt")
+        );
+        assert_eq!(
+            absolute(&root.join("dangling"), false)?,
+            root.join("dangling")
+        );
+        let err = absolute(&root.join("dangling"), true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{} is a symlink to gone.txt, which does not exist",
+                root.join("dangling").display()
+            )
+        );
+        assert!(absolute(&root.join("missing"), false).is_err());
+        assert_eq!(dangling_target(&root.join("relative")), None);
+        Ok(())
+    }
+    #[test]
+    fn test_lexical_absolute() -> Result<()> {
+        let cwd = std::env::current_dir()?;
+        assert_eq!(
+            lexical_absolute(Path::new("dist/output.zip"))?,
+            cwd.join("dist/output.zip")
+        );
+        assert_eq!(
+            lexical_absolute(Path::new("./a/./b/../../dist/x.zip"))?,
+            cwd.join("dist/x.zip")
+        );
+        assert_eq!(
+            lexical_absolute(Path::new("/mnt/c/build/../out/./app.exe"))?,
+            PathBuf::from("/mnt/c/out/app.exe")
+        );
+        // `..` stops at the root
+        assert_eq!(
+            lexical_absolute(Path::new("/../../etc//x"))?,
+            PathBuf::from("/etc/x")
+        );
+        // Symlinks are not followed; missing files are fine
+        assert_eq!(
+            to_wsl_path(Path::new("/no/such/../file"), true)?,
+            "/no/file"
+        );
+        Ok(())
+    }
+    /// (Linux path, what `wslpath -w` printed for it), automount root `/mnt`.
+    const WSLPATH_DRIVE_CASES: &[(&str, &str)] = &[
+        ("/mnt/c", r"C:\"),
+        ("/mnt/c/", r"C:\"),
+        ("/mnt/d/Projects/app", r"D:\Projects\app"),
+        (
+            "/mnt/c/Users/me/My Documents/a b.txt",
+            r"C:\Users\me\My Documents\a b.txt",
+        ),
+        ("/mnt/c/Program Files (x86)/x", r"C:\Program Files (x86)\x"),
+        ("/mnt/e/naïve/日本語.txt", r"E:\naïve\日本語.txt"),
+        ("/mnt/c/a%b/$x/it's/[1]", r"C:\a%b\$x\it's\[1]"),
+        ("/mnt/c/Users//me/", r"C:\Users\me"),
+    ];
+    #[test]
+    fn test_drive_path_matches_wslpath() {
+        for (linux, windows) in WSLPATH_DRIVE_CASES {
+            assert_eq!(
+                drive_path(Path::new(linux), Path::new("/mnt")).as_deref(),
+                Some(*windows),
+                "{}",
+                linux
+            );
+        }
+        // A custom automount root
+        assert_eq!(
+            drive_path(Path::new("/windows/c/x"), Path::new("/windows/")).as_deref(),
+            Some(r"C:\x")
+        );
+        // wslpath's job: the Linux filesystem, other mounts, remapped names
+        for linux in [
+            "/home/me/a.txt",
+            "/mnt/wsl/shared",
+            "/mnt/cd/x",
+            "/mnt/1/x",
+            "/mnt/c/a:b",
+            "/mnt/c/what?",
+            "/mnt/c/dots.",
+            "/mnt/c/space ",
+            "/windows/c/x",
+        ] {
+            assert_eq!(
+                drive_path(Path::new(linux), Path::new("/mnt")),
+                None,
+                "{}",
+                linux
+            );
+        }
+    }
+    #[test]
+    fn test_rewrite_unc() {
+        // Both directions
+        assert_eq!(
+            rewrite_unc(r"\\wsl.localhost\Ubuntu\home\me", UncStyle::Wsl),
+            r"\\wsl$\Ubuntu\home\me"
+        );
+        assert_eq!(
+            rewrite_unc(r"\\wsl$\Ubuntu\home\me", UncStyle::Localhost),
+            r"\\wsl.localhost\Ubuntu\home\me"
+        );
+        assert_eq!(
+            rewrite_unc(r"\\WSL.LOCALHOST\Ubuntu", UncStyle::Wsl),
+            r"\\wsl$\Ubuntu"
+        );
+        // Already in the target form
+        for (path, style) in [
+            (r"\\wsl$\Ubuntu\x", UncStyle::Wsl),
+            (r"\\wsl.localhost\Ubuntu\x", UncStyle::Localhost),
+        ] {
+            assert_eq!(rewrite_unc(path, style), path);
+        }
+        // Never anything else
+        for path in [
+            r"C:\Users\me",
+            r"\\server\wsl$\x",
+            r"\\wsl.localhost.example.com\share\x",
+            r"\\wsl$",
+            r"\\?\C:\wsl$\x",
+        ] {
+            assert_eq!(rewrite_unc(path, UncStyle::Localhost), path);
+            assert_eq!(rewrite_unc(path, UncStyle::Wsl), path);
+        }
+    }
+    #[test]
+    fn test_linux_drive_path() {
+        let root = Path::new("/mnt");
+        assert_eq!(
+            linux_drive_path(r"C:\Users\me\AppData\Local\Temp", root),
+            Some(PathBuf::from("/mnt/c/Users/me/AppData/Local/Temp"))
+        );
+        assert_eq!(
+            linux_drive_path(r"d:\", root),
+            Some(PathBuf::from("/mnt/d"))
+        );
+        assert_eq!(
+            linux_drive_path("E:/a b/c", Path::new("/windows")),
+            Some(PathBuf::from("/windows/e/a b/c"))
+        );
+        for win in [
+            r"\\wsl.localhost\Ubuntu\x",
+            r"C:relative",
+            r"C:\a\..\b",
+            r"CD:\x",
+            "",
+        ] {
+            assert_eq!(linux_drive_path(win, root), None, "{}", win);
+        }
+    }
+    #[test]
+    fn test_wslpath_missing() {
+        let linux = wslpath_missing(Platform::Linux, Path::new("/mnt"));
+        assert!(linux.contains("not WSL") && linux.contains("/mnt/<drive>"));
+        assert!(linux.contains(environment::AUTOMOUNT_ROOT_ENV));
+        assert!(wslpath_missing(Platform::Wsl2, Path::new("/mnt")).contains("without interop"));
+    }
+    #[test]
+    fn test_join_windows() {
+        assert_eq!(join_windows(r"C:\", "Users/me/a.txt"), r"C:\Users\me\a.txt");
+        assert_eq!(join_windows(r"C:\", ""), r"C:\");
+        assert_eq!(
+            join_windows(r"\\wsl.localhost\Ubuntu\", "home/me"),
+            r"\\wsl.localhost\Ubuntu\home\me"
+        );
+        assert_eq!(
+            join_windows(r"\\wsl.localhost\Ubuntu", ""),
+            r"\\wsl.localhost\Ubuntu"
+        );
+        assert!(needs_wslpath(':') && needs_wslpath('\\') && !needs_wslpath('é'));
+    }
+    #[test]
+    fn test_forward_slashes() {
+        assert_eq!(
+            forward_slashes(r"C:\Users\me\file.txt"),
+            "C:/Users/me/file.txt"
+        );
+        assert_eq!(forward_slashes(r"D:\"), "D:/");
+        assert_eq!(
+            forward_slashes(r"\\wsl.localhost\Ubuntu\home\me"),
+            "//wsl.localhost/Ubuntu/home/me"
+        );
+        assert_eq!(forward_slashes(r"\\server\share\"), "//server/share/");
+        // Mixed and doubled separators
+        assert_eq!(forward_slashes(r"C:\Users/me\\x//y"), "C:/Users/me/x/y");
+        assert_eq!(
+            forward_slashes(r"\\\wsl.localhost/Ubuntu\\a"),
+            "//wsl.localhost/Ubuntu/a"
+        );
+        // Long-path prefixes have no forward-slash form
+        assert_eq!(forward_slashes(r"\\?\C:\deep\x"), "C:/deep/x");
+        assert_eq!(
+            forward_slashes(r"\\?\UNC\wsl.localhost\Ubuntu\x"),
+            "//wsl.localhost/Ubuntu/x"
+        );
+    }
+    #[test]
+    fn test_join_paths() {
+        let paths = [r"C:\My Docs\a.txt".to_string(), r"C:\b.txt".to_string()];
+        assert_eq!(
+            join_paths(&paths, " ", true),
+            r#""C:\My Docs\a.txt" C:\b.txt"#
+        );
+        assert_eq!(join_paths(&paths, " ", false), r"C:\My Docs\a.txt C:\b.txt");
+        assert_eq!(
+            join_paths(&paths, &parse_separator("newline").unwrap(), true),
+            "C:\\My Docs\\a.txt\nC:\\b.txt"
+        );
+        assert_eq!(
+            join_paths(&paths, &parse_separator(";").unwrap(), true),
+            r"C:\My Docs\a.txt;C:\b.txt"
+        );
+        // One path comes out exactly as it always did
+        assert_eq!(join_paths(&paths[..1], " ", true), r"C:\My Docs\a.txt");
+        assert!(parse_separator("").is_err());
+    }
+    /// `\\wsl.localhost\Ubuntu\home\dev\` plus `depth` 20-character folders
+    /// and `name`.
+    fn deep_path(depth: usize, name: &str) -> String {
+        let mut path = r"\\wsl.localhost\Ubuntu\home\dev".to_string();
+        for i in 0..depth {
+            path.push_str(&format!(r"\folder-number-{:05}", i));
+        }
+        path + r"\" + name
+    }
+    #[test]
+    fn test_length_counts_utf16_units() {
+        assert_eq!(utf16_len(r"C:\a.txt"), 8);
+        // Two bytes in UTF-8, one code unit in UTF-16
+        assert_eq!(utf16_len("C:\\\u{e9}"), 4);
+        // A code unit pair for anything beyond the BMP
+        assert_eq!(utf16_len("\u{1f600}"), 2);
+        // 400 bytes of "é" are 200 code units: within the limit
+        let accents = format!(r"C:\{}", "\u{e9}".repeat(200));
+        assert!(accents.len() > MAX_PATH_LEN && utf16_len(&accents) <= MAX_PATH_LEN);
+        assert_eq!(
+            fit_max_path(vec![accents.clone()], true).unwrap(),
+            vec![accents]
+        );
+    }
+    #[test]
+    fn test_long_path_form() {
+        assert_eq!(
+            long_path_form(r"\\wsl.localhost\Ubuntu\a.txt").as_deref(),
+            Some(r"\\?\UNC\wsl.localhost\Ubuntu\a.txt")
+        );
+        assert_eq!(
+            long_path_form(r"C:\Users\a.txt").as_deref(),
+            Some(r"\\?\C:\Users\a.txt")
+        );
+        assert_eq!(long_path_form(r"\\?\C:\a.txt"), None);
+        assert_eq!(long_path_form("a.txt"), None);
+    }
+    #[test]
+    fn test_long_paths_are_prefixed_or_refused() {
+        let short = deep_path(1, "a.txt");
+        let long = deep_path(12, "b.txt");
+        assert!(utf16_len(&long) > MAX_PATH_LEN);
+        let fitted = fit_max_path(vec![short.clone(), long.clone()], false).unwrap();
+        assert_eq!(fitted[0], short);
+        assert_eq!(fitted[1], format!(r"\\?\UNC\{}", &long[2..]));
+        let err = fit_max_path(vec![short, long.clone()], true)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("1 path(s) exceed the Windows MAX_PATH limit of 259 characters"));
+        assert!(err.contains(&format!("{} ({} characters)", long, utf16_len(&long))));
+    }
 }
 
 // <FILE>wsl-clip/src/paths.rs</FILE> - <DESC>Instrumented with debug logging</DESC>
-// <VERS>END OF VERSION: 1.2.0 - 2025-11-24T14:52:13Z</VERS>
+// <VERS>END OF VERSION: 1.13.0 - 2026-10-16T14:12:06Z</VERS>
@@ -0,0 +1,185 @@
+// <FILE>src/peek.rs</FILE> - <DESC>Clipboard preview analysis</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:21:40Z</VERS>
+// <WCTX>Bounded preview plus content hints and pastejacking warnings for clipboard text.</WCTX>
+// <CLOG>Initial creation with Hint, Analysis, analyze, preview and render_safe.</CLOG>
+
+use crate::text_measure;
+use crate::text_processor::{ansi_regex, is_stripped_control};
+use std::fmt;
+pub const DEFAULT_PEEK_LINES: usize = 20;
+pub const DEFAULT_PEEK_BYTES: usize = 2048;
+/// Content hints reported alongside a preview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    Json,
+    Diff,
+    Ansi,
+    Control,
+    Invisible,
+}
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Hint::Json => "looks like JSON",
+            Hint::Diff => "looks like a diff",
+            Hint::Ansi => "contains ANSI escapes",
+            Hint::Control => "contains control characters",
+            Hint::Invisible => "contains invisible Unicode",
+        };
+        f.write_str(s)
+    }
+}
+/// Summary of clipboard text produced by `analyze`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    pub bytes: usize,
+    pub lines: usize,
+    pub hints: Vec<Hint>,
+    /// Pastejacking-style findings worth a [WARN]
+    pub warnings: Vec<String>,
+}
+/// Zero-width and bidi-override characters that change what a paste means
+/// without showing up on screen. ZWJ is left out: emoji sequences need it.
+pub fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}' | '\u{200C}' | '\u{200E}' | '\u{200F}' | '\u{2060}' | '\u{FEFF}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2066}'..='\u{2069}'
+    )
+}
+fn looks_like_json(text: &str) -> bool {
+    let trimmed = text.trim();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return false;
+    }
+    serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+        || trimmed
+            .lines()
+            .next()
+            .is_some_and(|l| serde_json::from_str::<serde_json::Value>(l.trim()).is_ok())
+}
+fn looks_like_diff(text: &str) -> bool {
+    let mut minus = false;
+    let mut plus = false;
+    for line in text.lines() {
+        if line.starts_with("diff --git ") || line.starts_with("@@ -") {
+            return true;
+        }
+        minus |= line.starts_with("--- ");
+        plus |= line.starts_with("+++ ");
+    }
+    minus && plus
+}
+pub fn analyze(text: &str) -> Analysis {
+    let mut hints = Vec::new();
+    let mut warnings = Vec::new();
+    if looks_like_json(text) {
+        hints.push(Hint::Json);
+    }
+    if looks_like_diff(text) {
+        hints.push(Hint::Diff);
+    }
+    let re = ansi_regex();
+    if re.is_match(text) {
+        hints.push(Hint::Ansi);
+    }
+    // Colour codes are reported above; any ESC left over (OSC, cursor moves) is
+    // a control. Line breaks are structure, and the sanitizer never sees them.
+    let controls = re
+        .replace_all(text, "")
+        .chars()
+        .filter(|&c| c != '\n' && c != '\r' && is_stripped_control(c))
+        .count();
+    if controls > 0 {
+        hints.push(Hint::Control);
+        warnings.push(format!(
+            "{} control character(s) (backspace, bell, ...) can hide or rewrite text when pasted",
+            controls
+        ));
+    }
+    let invisible = text.chars().filter(|&c| is_invisible(c)).count();
+    if invisible > 0 {
+        hints.push(Hint::Invisible);
+        warnings.push(format!(
+            "{} invisible Unicode character(s) (zero-width or bidi override) are present",
+            invisible
+        ));
+    }
+    Analysis {
+        bytes: text.len(),
+        lines: text.lines().count(),
+        hints,
+        warnings,
+    }
+}
+/// First `max_lines` lines, capped at `max_bytes` without tearing a
+/// grapheme. Returns the preview and whether it was truncated.
+pub fn preview(text: &str, max_lines: usize, max_bytes: usize) -> (String, bool) {
+    let mut end = text.len();
+    if let Some((i, _)) = text.match_indices('\n').nth(max_lines.saturating_sub(1)) {
+        end = if max_lines == 0 { 0 } else { i + 1 };
+    }
+    let cut = text_measure::truncate_bytes(&text[..end], max_bytes);
+    (cut.to_string(), cut.len() < text.len())
+}
+/// Renders a preview safe to print: controls, escapes and invisible
+/// characters are shown as `\u{..}` instead of acting on the terminal.
+pub fn render_safe(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c == '\n' || c == '\t' || !(is_stripped_control(c) || is_invisible(c)) {
+                c.to_string()
+            } else {
+                c.escape_unicode().to_string()
+            }
+        })
+        .collect()
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_hints() {
+        assert_eq!(analyze(r#"{"a": [1, 2]}"#).hints, vec![Hint::Json]);
+        assert_eq!(analyze("{\"a\":1}\n{\"a\":2}\n").hints, vec![Hint::Json]);
+        assert_eq!(
+            analyze("--- a/x.rs\n+++ b/x.rs\n@@ -1 +1 @@\n-a\n+b\n").hints,
+            vec![Hint::Diff]
+        );
+        assert_eq!(analyze("\x1b[31mred\x1b[0m\n").hints, vec![Hint::Ansi]);
+        let plain = analyze("just text\r\nwith windows endings\tand tabs\n");
+        assert!(plain.hints.is_empty() && plain.warnings.is_empty());
+        assert_eq!((plain.bytes, plain.lines), (41, 2));
+        assert!(analyze("{not json").hints.is_empty());
+    }
+    #[test]
+    fn test_pastejacking_warnings() {
+        let a = analyze("ls\x08\x08rm -rf ~\n");
+        assert_eq!(a.hints, vec![Hint::Control]);
+        assert!(a.warnings[0].starts_with("2 control"));
+        let a = analyze("safe\u{200B}command \u{202E}txt.exe\n");
+        assert_eq!(a.hints, vec![Hint::Invisible]);
+        assert!(a.warnings[0].starts_with("2 invisible"));
+        // Emoji ZWJ sequences are not flagged
+        assert!(analyze("👨\u{200D}👩\u{200D}👧").warnings.is_empty());
+        // A non-colour escape (OSC 52 clipboard write) still counts as a control
+        let a = analyze("\x1b]52;c;ZXZpbA==\x07");
+        assert_eq!(a.hints, vec![Hint::Control]);
+    }
+    #[test]
+    fn test_preview_bounds() {
+        let text = "one\ntwo\nthree\nfour\n";
+        assert_eq!(preview(text, 2, 1024), ("one\ntwo\n".to_string(), true));
+        assert_eq!(preview(text, 20, 1024), (text.to_string(), false));
+        assert_eq!(preview(text, 20, 6), ("one\ntw".to_string(), true));
+        assert_eq!(preview("日本語", 20, 4), ("日".to_string(), true));
+        assert_eq!(
+            render_safe("a\x1b[0m\u{200B}b\n"),
+            "a\\u{1b}[0m\\u{200b}b\n"
+        );
+    }
+}
+
+// <FILE>src/peek.rs</FILE> - <DESC>Clipboard preview analysis</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:21:40Z</VERS>
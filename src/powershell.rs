@@ -0,0 +1,103 @@
+// <FILE>src/powershell.rs</FILE> - <DESC>PowerShell command-line encoding</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T12:40:00Z</VERS>
+// <WCTX>-EncodedCommand for the PowerShell scripts.</WCTX>
+// <CLOG>Initial creation with encode_command, encode_arguments and clixml_string.</CLOG>
+
+//! `-EncodedCommand` / `-EncodedArguments`: the script and its arguments
+//! reach PowerShell as base64, so nothing in either is ever parsed by the
+//! Windows command line or spliced into script text. This is how PowerShell
+//! itself starts `powershell { ... } -args ...`.
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+/// Base64 of the UTF-16LE script, as `-EncodedCommand` expects.
+pub fn encode_command(script: &str) -> String {
+    BASE64.encode(utf16le(script))
+}
+/// `-EncodedArguments`: the arguments as a CLIXML ArrayList of strings,
+/// UTF-16LE and base64. They arrive as the script's `$args`, unchanged.
+pub fn encode_arguments<S: AsRef<str>>(args: &[S]) -> String {
+    let items: String = args
+        .iter()
+        .map(|a| format!("<S>{}</S>", clixml_string(a.as_ref())))
+        .collect();
+    let xml = format!(
+        "<Objs Version=\"1.1.0.1\" xmlns=\"http://schemas.microsoft.com/powershell/2004/04\">\
+         <Obj RefId=\"0\"><TN RefId=\"0\"><T>System.Collections.ArrayList</T><T>System.Object</T></TN>\
+         <LST>{}</LST></Obj></Objs>",
+        items
+    );
+    BASE64.encode(utf16le(&xml))
+}
+fn utf16le(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+/// A string as CLIXML element text: XML-escaped, with control characters
+/// (newlines included) and `_` written as `_xHHHH_`, which the deserializer
+/// turns back into the character.
+fn clixml_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '_' => out.push_str("_x005F_"),
+            c if c.is_control() => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    out.push_str(&format!("_x{:04X}_", unit));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_encode_command() {
+        // powershell.exe -EncodedCommand RwBlAHQALQBEAGEAdABlAA== runs Get-Date
+        assert_eq!(encode_command("Get-Date"), "RwBlAHQALQBEAGEAdABlAA==");
+        let decoded = BASE64.decode(encode_command("$a = '✓'")).unwrap();
+        assert_eq!(decoded[..4], [b'$', 0, b'a', 0]);
+        assert_eq!(decoded.len(), "$a = '✓'".encode_utf16().count() * 2);
+    }
+    #[test]
+    fn test_encode_arguments() {
+        let xml = |args: &[&str]| {
+            let units: Vec<u16> = BASE64
+                .decode(encode_arguments(args))
+                .unwrap()
+                .chunks(2)
+                .map(|p| u16::from_le_bytes([p[0], p[1]]))
+                .collect();
+            String::from_utf16(&units).unwrap()
+        };
+        assert_eq!(
+            xml(&["a"]),
+            "<Objs Version=\"1.1.0.1\" xmlns=\"http://schemas.microsoft.com/powershell/2004/04\">\
+             <Obj RefId=\"0\"><TN RefId=\"0\"><T>System.Collections.ArrayList</T><T>System.Object</T></TN>\
+             <LST><S>a</S></LST></Obj></Objs>"
+        );
+        assert!(xml(&[]).contains("<LST></LST>"));
+        assert!(xml(&["x", "y"]).contains("<LST><S>x</S><S>y</S></LST>"));
+    }
+    #[test]
+    fn test_clixml_string() {
+        assert_eq!(
+            clixml_string("a\"b`c$(d)'e"),
+            "a\"b`c$(d)'e",
+            "no quoting in element text"
+        );
+        assert_eq!(clixml_string("<&>"), "&lt;&amp;&gt;");
+        assert_eq!(clixml_string("one\r\ntwo\t"), "one_x000D__x000A_two_x0009_");
+        // A literal _xHHHH_ must not decode as an escape
+        assert_eq!(clixml_string("_x0041_"), "_x005F_x0041_x005F_");
+        assert_eq!(clixml_string("🎉.png"), "🎉.png");
+    }
+}
+
+// <FILE>src/powershell.rs</FILE> - <DESC>PowerShell command-line encoding</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T12:40:00Z</VERS>
@@ -0,0 +1,284 @@
+// <FILE>src/preflight.rs</FILE> - <DESC>File/Image reachability pre-flight</DESC>
+// <VERS>VERSION: 1.5.0 - 2026-10-16T14:23:00Z</VERS>
+// <WCTX>Windows access check encoding</WCTX>
+// <CLOG>check_windows passes paths via -EncodedCommand/-EncodedArguments and drains stdout while waiting</CLOG>
+
+use crate::deadline;
+use crate::debug_logger::create_logger;
+use crate::interop;
+use crate::paths;
+use crate::powershell;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+/// Upper bound for the whole batch; a hung network mount must not hang us.
+pub const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(5);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preflight {
+    Off,
+    /// Stat each file through its Linux-side path
+    Local,
+    /// Local stat plus a PowerShell Test-Path batch on the Windows paths
+    Windows,
+}
+/// A file that failed the reachability check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unreachable {
+    pub path: PathBuf,
+    pub reason: &'static str,
+}
+/// Runs `probe` over every path on a worker thread. Paths not checked when
+/// the timeout expires are reported as timed out.
+pub fn check_with(
+    paths: &[PathBuf],
+    timeout: Duration,
+    probe: impl Fn(&Path) -> bool + Send + 'static,
+) -> Vec<Unreachable> {
+    let (tx, rx) = mpsc::channel();
+    let owned = paths.to_vec();
+    std::thread::spawn(move || {
+        for (i, p) in owned.iter().enumerate() {
+            if tx.send((i, probe(p))).is_err() {
+                return;
+            }
+        }
+    });
+    let deadline = Instant::now() + timeout;
+    let mut checked = vec![false; paths.len()];
+    let mut missing = Vec::new();
+    while checked.iter().any(|c| !c) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left) {
+            Ok((i, ok)) => {
+                checked[i] = true;
+                if !ok {
+                    missing.push(Unreachable {
+                        path: paths[i].clone(),
+                        reason: "not reachable",
+                    });
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    for (i, done) in checked.iter().enumerate() {
+        if !done {
+            missing.push(Unreachable {
+                path: paths[i].clone(),
+                reason: "timed out",
+            });
+        }
+    }
+    missing
+}
+/// Why `path` cannot go in a drop list, if it cannot: a file must open and a
+/// folder must list.
+pub fn access_problem(path: &Path) -> Option<&'static str> {
+    // Under --no-follow the link itself is copied, whatever it points to
+    if !paths::follow() && path.is_symlink() {
+        return None;
+    }
+    let opened = match std::fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => std::fs::read_dir(path).map(drop),
+        Ok(_) => std::fs::File::open(path).map(drop),
+        Err(e) => Err(e),
+    };
+    match opened {
+        Ok(()) => None,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Some("does not exist"),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Some("permission denied"),
+        Err(_) => Some("cannot be read"),
+    }
+}
+/// `file` arguments split into those that can be copied and those that
+/// cannot, each checked once, before any path conversion.
+pub fn check_arguments(paths: &[PathBuf]) -> (Vec<PathBuf>, Vec<Unreachable>) {
+    let mut usable = Vec::new();
+    let mut problems = Vec::new();
+    for path in paths {
+        match access_problem(path) {
+            Some(reason) => problems.push(Unreachable {
+                path: path.clone(),
+                reason,
+            }),
+            None => usable.push(path.clone()),
+        }
+    }
+    (usable, problems)
+}
+/// One error naming every argument `check_arguments` refused.
+pub fn arguments_message(problems: &[Unreachable], total: usize) -> String {
+    let list: Vec<String> = problems
+        .iter()
+        .map(|u| format!("  {} ({})", u.path.display(), u.reason))
+        .collect();
+    let hint = if problems.len() < total {
+        "\n(use --skip-missing to copy the others)"
+    } else {
+        ""
+    };
+    format!(
+        "{} of {} files cannot be copied:\n{}{}",
+        problems.len(),
+        total,
+        list.join("\n"),
+        hint
+    )
+}
+/// Cheap metadata stat through drvfs (catches unplugged drives and dropped mounts).
+pub fn check_local(paths: &[PathBuf]) -> Vec<Unreachable> {
+    check_with(paths, PREFLIGHT_TIMEOUT, |p| {
+        std::fs::metadata(p).is_ok() || (!paths::follow() && p.is_symlink())
+    })
+}
+/// One PowerShell call for the whole batch; returns the Windows paths that
+/// Test-Path cannot see. The paths travel as `-EncodedArguments`, so no
+/// space, `;` or `$(...)` in a name is ever parsed as script.
+pub fn check_windows(win_paths: &[String]) -> Result<Vec<String>> {
+    let script =
+        powershell::encode_command("$args | Where-Object { -not (Test-Path -LiteralPath $_) }");
+    let mut child = interop::command("powershell.exe")
+        .arg("-NoProfile")
+        .arg("-NonInteractive")
+        .arg("-EncodedCommand")
+        .arg(script)
+        .arg("-EncodedArguments")
+        .arg(powershell::encode_arguments(win_paths))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to execute powershell.exe")?;
+    // Read while waiting: a long list of missing paths would fill the pipe
+    // and stall the child until the deadline
+    let stdout = child.stdout.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = std::io::Read::read_to_string(&mut pipe, &mut text);
+            text
+        })
+    });
+    if deadline::wait(&mut child, Some(PREFLIGHT_TIMEOUT))?.is_none() {
+        anyhow::bail!(
+            "Windows access check timed out after {}s (use --no-preflight to skip it)",
+            PREFLIGHT_TIMEOUT.as_secs()
+        );
+    }
+    let stdout = stdout
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    Ok(stdout
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+/// Fails up front, listing every unreachable file, instead of letting the
+/// paste fail inside Explorer later.
+pub fn ensure_reachable(files: &[PathBuf], win_paths: &[String], mode: Preflight) -> Result<()> {
+    let log = create_logger("preflight");
+    if mode == Preflight::Off {
+        return Ok(());
+    }
+    let mut problems: Vec<String> = check_local(files)
+        .into_iter()
+        .map(|u| format!("  {} ({})", u.path.display(), u.reason))
+        .collect();
+    if mode == Preflight::Windows && problems.is_empty() {
+        for p in check_windows(win_paths)? {
+            problems.push(format!("  {} (not visible to Windows)", p));
+        }
+    }
+    log.debug(&format!(
+        "Preflight ({:?}) checked {} files, {} unreachable",
+        mode,
+        files.len(),
+        problems.len()
+    ));
+    if problems.is_empty() {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "{} of {} files are unreachable (drive removed or mount disconnected?):\n{}",
+        problems.len(),
+        files.len(),
+        problems.join("\n")
+    )
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_vanished_files_are_listed() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let files: Vec<PathBuf> = ["a.pdf", "b.pdf", "c.pdf"]
+            .iter()
+            .map(|n| dir.path().join(n))
+            .collect();
+        for f in &files {
+            std::fs::write(f, b"x")?;
+        }
+        let win: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+        assert!(ensure_reachable(&files, &win, Preflight::Local).is_ok());
+        // The mount "disconnects" between conversion and the check
+        std::fs::remove_file(&files[0])?;
+        std::fs::remove_file(&files[2])?;
+        let err = ensure_reachable(&files, &win, Preflight::Local)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("2 of 3 files are unreachable"));
+        assert!(err.contains("a.pdf (not reachable)") && err.contains("c.pdf"));
+        assert!(!err.contains("b.pdf"));
+        assert!(ensure_reachable(&files, &win, Preflight::Off).is_ok());
+        Ok(())
+    }
+    #[test]
+    fn test_arguments_are_checked_together() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let paths: Vec<PathBuf> = ["a.pdf", "b.pdf", "c.pdf", "d.pdf"]
+            .iter()
+            .map(|n| dir.path().join(n))
+            .collect();
+        std::fs::write(&paths[0], b"x")?;
+        std::fs::write(&paths[2], b"x")?;
+        let (usable, problems) = check_arguments(&paths);
+        assert_eq!(usable, vec![paths[0].clone(), paths[2].clone()]);
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().all(|u| u.reason == "does not exist"));
+        let msg = arguments_message(&problems, paths.len());
+        assert!(msg.starts_with("2 of 4 files cannot be copied:\n"));
+        assert!(msg.contains("b.pdf (does not exist)") && msg.contains("d.pdf (does not exist)"));
+        assert!(msg.ends_with("(use --skip-missing to copy the others)"));
+        // Nothing left to copy: no point suggesting the flag
+        let (usable, problems) = check_arguments(&[paths[1].clone(), paths[3].clone()]);
+        assert!(usable.is_empty());
+        assert!(!arguments_message(&problems, 2).contains("--skip-missing"));
+        // Folders count as present
+        assert_eq!(access_problem(dir.path()), None);
+        Ok(())
+    }
+    #[test]
+    fn test_check_is_time_bounded() {
+        let files = vec![PathBuf::from("/fast"), PathBuf::from("/hung")];
+        let started = Instant::now();
+        let missing = check_with(&files, Duration::from_millis(100), |p| {
+            if p == Path::new("/hung") {
+                std::thread::sleep(Duration::from_secs(2));
+            }
+            true
+        });
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(
+            missing,
+            vec![Unreachable {
+                path: PathBuf::from("/hung"),
+                reason: "timed out"
+            }]
+        );
+    }
+}
+
+// <FILE>src/preflight.rs</FILE> - <DESC>File/Image reachability pre-flight</DESC>
+// <VERS>END OF VERSION: 1.5.0 - 2026-10-16T14:23:00Z</VERS>
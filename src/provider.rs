@@ -0,0 +1,164 @@
+// <FILE>src/provider.rs</FILE> - <DESC>Editor clipboard provider</DESC>
+// <VERS>VERSION: 1.1.0 - 2026-10-16T11:20:33Z</VERS>
+// <WCTX>ClipboardBackend trait.</WCTX>
+// <CLOG>copy takes a backend.</CLOG>
+
+use crate::clipboard::ClipboardBackend;
+use crate::debug_logger::create_logger;
+use crate::paste::{self, Found};
+use anyhow::{Context, Result};
+use std::io::{self, Read, Write};
+/// `--provider`: the two halves of an editor clipboard provider (Neovim's
+/// `g:clipboard`). Both are byte-faithful: no headers, no sanitizing, and
+/// the trailing newline is exactly what it was. Neovim reads a final
+/// newline on paste as "line-wise", so dropping or adding one changes the
+/// register type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Provider {
+    /// Raw stdin to the clipboard
+    Copy,
+    /// The clipboard text to stdout
+    Paste,
+}
+/// Copies `input` to `out` unchanged, or with `crlf` turns each LF not
+/// already preceded by CR into CRLF (so Windows text is not doubled).
+/// Returns the bytes written.
+pub fn copy_raw<R: Read, W: Write>(mut input: R, out: &mut W, crlf: bool) -> io::Result<u64> {
+    if !crlf {
+        return io::copy(&mut input, out);
+    }
+    let mut buf = [0u8; 8192];
+    let mut after_cr = false;
+    let mut written = 0u64;
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let mut start = 0;
+        for (i, &b) in buf[..n].iter().enumerate() {
+            if b == b'\n' && !after_cr {
+                out.write_all(&buf[start..i])?;
+                out.write_all(b"\r")?;
+                written += (i - start) as u64 + 1;
+                start = i;
+            }
+            after_cr = b == b'\r';
+        }
+        out.write_all(&buf[start..n])?;
+        written += (n - start) as u64;
+    }
+    Ok(written)
+}
+/// `wsl-clip --provider copy`: stdin onto the clipboard, byte for byte.
+/// Empty input is a valid (empty) yank, not an error.
+pub fn copy(clip: &dyn ClipboardBackend, crlf: bool) -> Result<u64> {
+    let mut stream = clip.start_text_stream()?;
+    let Some(writer) = stream.stdin.as_mut() else {
+        anyhow::bail!("Failed to acquire stdin for clip.exe");
+    };
+    match copy_raw(io::stdin().lock(), writer, crlf) {
+        Ok(n) => {
+            stream.wait()?;
+            Ok(n)
+        }
+        Err(e) => {
+            stream.abort()?;
+            Err(e).context("Failed to read stdin")
+        }
+    }
+}
+/// `wsl-clip --provider paste`: the clipboard text to stdout. An empty or
+/// non-text clipboard pastes nothing and succeeds, as editors expect.
+pub fn paste(lf: bool) -> Result<u64> {
+    let log = create_logger("provider");
+    let mut text = match paste::open()? {
+        Found::Text(text) => text,
+        Found::Empty => {
+            log.debug("Clipboard is empty");
+            return Ok(0);
+        }
+        Found::Other(format) => {
+            log.debug(&format!("Clipboard holds non-text data: {}", format));
+            return Ok(0);
+        }
+    };
+    let mut out = io::stdout().lock();
+    let result = paste::copy_text(text.reader(), &mut out, lf)
+        .and_then(|n| out.flush().map(|_| n))
+        .map_err(anyhow::Error::from);
+    match result {
+        Ok(n) => {
+            text.finish()?;
+            Ok(n)
+        }
+        Err(e) => {
+            text.abort()?;
+            if paste::broken_pipe(&e) {
+                return Ok(0);
+            }
+            Err(e)
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn crlf(input: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let n = copy_raw(input, &mut out, true).unwrap();
+        assert_eq!(n, out.len() as u64);
+        out
+    }
+    /// A provider copy followed by a provider paste.
+    fn round_trip(input: &[u8], convert: bool) -> Vec<u8> {
+        let mut clipboard = Vec::new();
+        copy_raw(input, &mut clipboard, convert).unwrap();
+        let mut pasted = Vec::new();
+        paste::copy_text(&clipboard[..], &mut pasted, convert).unwrap();
+        pasted
+    }
+    const CASES: &[&[u8]] = &[
+        b"",
+        b"\n",
+        b"\n\n",
+        b"word",
+        b"line\n",
+        b"two\nlines\n",
+        b"no final newline\nhere",
+        b"trailing blank\n\n",
+        b"\x1b[31mred\x1b[0m\t\x07\n",
+        "caf\u{e9} \u{2713}\n".as_bytes(),
+    ];
+    #[test]
+    fn test_round_trips_are_byte_for_byte() {
+        for case in CASES {
+            assert_eq!(round_trip(case, false), *case, "raw {:?}", case);
+            // --crlf on copy and --lf on paste undo each other
+            assert_eq!(round_trip(case, true), *case, "crlf/lf {:?}", case);
+        }
+        // Without --lf, Windows line endings and lone CRs come back untouched
+        for case in [&b"a\r\nb\r\n"[..], b"a\rb", b"\xef\xbb\xbfbom\n"] {
+            assert_eq!(round_trip(case, false), case);
+        }
+    }
+    #[test]
+    fn test_crlf_conversion() {
+        assert_eq!(crlf(b"a\nb"), b"a\r\nb");
+        assert_eq!(crlf(b"a\n"), b"a\r\n");
+        assert_eq!(crlf(b"\n\n"), b"\r\n\r\n");
+        // Existing CRLF is not doubled, even split across reads
+        assert_eq!(crlf(b"a\r\nb\n"), b"a\r\nb\r\n");
+        let mut out = Vec::new();
+        copy_raw(io::Read::chain(&b"a\r"[..], &b"\nb"[..]), &mut out, true).unwrap();
+        assert_eq!(out, b"a\r\nb");
+        // No newline is added where there was none
+        assert_eq!(crlf(b"end"), b"end");
+        assert_eq!(crlf(b""), b"");
+    }
+}
+
+// <FILE>src/provider.rs</FILE> - <DESC>Editor clipboard provider</DESC>
+// <VERS>END OF VERSION: 1.1.0 - 2026-10-16T11:20:33Z</VERS>
@@ -0,0 +1,172 @@
+// <FILE>src/quoting.rs</FILE> - <DESC>Shell quoting for copied paths</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T14:10:00Z</VERS>
+// <WCTX>Paste-ready paths for cmd, PowerShell and bash.</WCTX>
+// <CLOG>Initial creation with QuoteStyle and quote.</CLOG>
+
+/// How `path --quote` makes a path one argument of a shell's command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum QuoteStyle {
+    /// cmd.exe: "..." with each % kept out of variable expansion
+    Cmd,
+    /// PowerShell: '...' with single quotes doubled
+    Powershell,
+    /// bash: backslash escapes, $'..' for control characters
+    Bash,
+    /// As converted, nothing added
+    None,
+}
+/// PowerShell reads the typographic single quotes as `'` too, so each is
+/// doubled like one.
+const POWERSHELL_QUOTES: &[char] = &['\'', '\u{2018}', '\u{2019}', '\u{201A}', '\u{201B}'];
+/// Quotes `path` for `style`.
+pub fn quote(path: &str, style: QuoteStyle) -> String {
+    match style {
+        QuoteStyle::Cmd => cmd(path),
+        QuoteStyle::Powershell => powershell(path),
+        QuoteStyle::Bash => bash(path),
+        QuoteStyle::None => path.to_string(),
+    }
+}
+/// cmd expands `%NAME%` even inside quotes. Each `%` leaves the quotes as
+/// `^%`: the expansion pass then sees a name ending in `^` or `"`, which no
+/// variable has, and the caret pass turns `^%` back into `%`. A `"` (only
+/// possible in a Linux path) is doubled, which the C runtime reads as one.
+fn cmd(path: &str) -> String {
+    let mut out = String::with_capacity(path.len() + 2);
+    out.push('"');
+    for c in path.chars() {
+        match c {
+            '%' => out.push_str("\"^%\""),
+            '"' => out.push_str("\"\""),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+/// Inside `'...'` nothing expands (`$`, backticks, `"`); only the quote
+/// characters need doubling.
+fn powershell(path: &str) -> String {
+    let mut out = String::with_capacity(path.len() + 2);
+    out.push('\'');
+    for c in path.chars() {
+        if POWERSHELL_QUOTES.contains(&c) {
+            out.push(c);
+        }
+        out.push(c);
+    }
+    out.push('\'');
+    out
+}
+/// Letters, digits and a few punctuation marks go as they are; any other
+/// printable character gets a backslash (so `\\server\share` keeps its
+/// backslashes). A backslash before a newline would join lines instead,
+/// so control characters go as `$'\n'` / `$'\xHH'`.
+fn bash(path: &str) -> String {
+    let mut out = String::with_capacity(path.len() * 2);
+    for c in path.chars() {
+        match c {
+            c if c.is_alphanumeric() => out.push(c),
+            '_' | '-' | '.' | '/' | ':' | ',' | '+' | '=' | '@' | '%' => out.push(c),
+            '\n' => out.push_str("$'\\n'"),
+            '\t' => out.push_str("$'\\t'"),
+            c if c.is_control() && (c as u32) < 0x80 => {
+                out.push_str(&format!("$'\\x{:02x}'", c as u32))
+            }
+            c => {
+                out.push('\\');
+                out.push(c);
+            }
+        }
+    }
+    if out.is_empty() {
+        out.push_str("''");
+    }
+    out
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_cmd() {
+        assert_eq!(
+            quote(r"C:\Users\Ana\file.txt", QuoteStyle::Cmd),
+            r#""C:\Users\Ana\file.txt""#
+        );
+        assert_eq!(
+            quote(r"C:\My Docs\a b.txt", QuoteStyle::Cmd),
+            r#""C:\My Docs\a b.txt""#
+        );
+        // %PATH% must not expand; & ^ and ! are literal inside the quotes
+        assert_eq!(
+            quote(r"C:\%PATH%\x", QuoteStyle::Cmd),
+            r#""C:\"^%"PATH"^%"\x""#
+        );
+        assert_eq!(
+            quote(r"C:\100% done", QuoteStyle::Cmd),
+            r#""C:\100"^%" done""#
+        );
+        assert_eq!(quote(r"C:\a&b^c!", QuoteStyle::Cmd), r#""C:\a&b^c!""#);
+        assert_eq!(
+            quote("/home/u/say \"hi\"", QuoteStyle::Cmd),
+            r#""/home/u/say ""hi""""#
+        );
+        assert_eq!(quote(r"C:\$x`y'z", QuoteStyle::Cmd), r#""C:\$x`y'z""#);
+    }
+    #[test]
+    fn test_powershell() {
+        assert_eq!(
+            quote(r"C:\My Docs\a.txt", QuoteStyle::Powershell),
+            r"'C:\My Docs\a.txt'"
+        );
+        assert_eq!(
+            quote(r"C:\Bob's\x", QuoteStyle::Powershell),
+            r"'C:\Bob''s\x'"
+        );
+        assert_eq!(quote("C:\\''", QuoteStyle::Powershell), "'C:\\'''''");
+        // Typographic quotes end a PowerShell string too
+        assert_eq!(
+            quote("C:\\it\u{2019}s\u{2018}", QuoteStyle::Powershell),
+            "'C:\\it\u{2019}\u{2019}s\u{2018}\u{2018}'"
+        );
+        // Nothing expands inside single quotes
+        assert_eq!(
+            quote(r#"C:\$env:TEMP\`n\"x"\50%"#, QuoteStyle::Powershell),
+            r#"'C:\$env:TEMP\`n\"x"\50%'"#
+        );
+    }
+    #[test]
+    fn test_bash() {
+        assert_eq!(
+            quote(r"\\wsl.localhost\Ubuntu\home\a b", QuoteStyle::Bash),
+            r"\\\\wsl.localhost\\Ubuntu\\home\\a\ b"
+        );
+        assert_eq!(
+            quote("/mnt/c/Temp/x.txt", QuoteStyle::Bash),
+            "/mnt/c/Temp/x.txt"
+        );
+        assert_eq!(
+            quote("C:\\$HOME`id`", QuoteStyle::Bash),
+            "C:\\\\\\$HOME\\`id\\`"
+        );
+        assert_eq!(
+            quote(r#"it's "q" (1)&;|<>*?[]{}#~!"#, QuoteStyle::Bash),
+            r#"it\'s\ \"q\"\ \(1\)\&\;\|\<\>\*\?\[\]\{\}\#\~\!"#
+        );
+        assert_eq!(
+            quote("a\nb\tc\x07", QuoteStyle::Bash),
+            "a$'\\n'b$'\\t'c$'\\x07'"
+        );
+        assert_eq!(quote("100%/é", QuoteStyle::Bash), "100%/é");
+        assert_eq!(quote("", QuoteStyle::Bash), "''");
+    }
+    #[test]
+    fn test_none() {
+        for path in [r"C:\My Docs\it's 100%.txt", "", "/tmp/$x"] {
+            assert_eq!(quote(path, QuoteStyle::None), path);
+        }
+    }
+}
+
+// <FILE>src/quoting.rs</FILE> - <DESC>Shell quoting for copied paths</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T14:10:00Z</VERS>
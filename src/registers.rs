@@ -0,0 +1,196 @@
+// <FILE>src/registers.rs</FILE> - <DESC>Named clipboard registers</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:59:14Z</VERS>
+// <WCTX>save/load registers.</WCTX>
+// <CLOG>Initial creation with parse_name, dir, Slot, open, list, age and render_list.</CLOG>
+
+use crate::debug_logger::create_logger;
+use crate::dirs;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+/// Longest register name accepted.
+const MAX_NAME_LEN: usize = 64;
+/// Clap value parser: letters, digits, `-`, `_` and `.`, not starting with a
+/// dot. Names are file names, so anything else could escape the directory.
+pub fn parse_name(raw: &str) -> Result<String, String> {
+    let valid = !raw.is_empty()
+        && raw.len() <= MAX_NAME_LEN
+        && !raw.starts_with('.')
+        && raw
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(raw.to_string())
+    } else {
+        Err(format!(
+            "invalid register name '{}' (use up to {} letters, digits, '-', '_' or '.', not starting with '.')",
+            raw, MAX_NAME_LEN
+        ))
+    }
+}
+/// `registers/` in the data root.
+pub fn dir() -> Result<PathBuf> {
+    dirs::current()
+        .data
+        .persistent()
+        .map(|root| root.join("registers"))
+        .map_err(|reason| anyhow::anyhow!("No usable registers directory: {}", reason))
+}
+/// A register being written. Nothing replaces the old content until `commit`.
+pub struct Slot {
+    path: PathBuf,
+    tmp: io::BufWriter<tempfile::NamedTempFile>,
+}
+impl Slot {
+    pub fn create(dir: &Path, name: &str) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create registers directory: {:?}", dir))?;
+        let tmp = tempfile::NamedTempFile::new_in(dir)
+            .with_context(|| format!("Cannot write to {:?}", dir))?;
+        Ok(Slot {
+            path: dir.join(name),
+            tmp: io::BufWriter::new(tmp),
+        })
+    }
+    /// Atomically replaces the register; returns its size.
+    pub fn commit(self) -> Result<u64> {
+        let tmp = self.tmp.into_inner().map_err(|e| e.into_error())?;
+        let bytes = tmp.as_file().metadata()?.len();
+        tmp.persist(&self.path)
+            .with_context(|| format!("Failed to write register {:?}", self.path))?;
+        create_logger("registers").debug(&format!("Saved {:?} ({} bytes)", self.path, bytes));
+        Ok(bytes)
+    }
+}
+impl Write for Slot {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tmp.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.tmp.flush()
+    }
+}
+/// Opens a register for loading.
+pub fn open(dir: &Path, name: &str) -> Result<File> {
+    let path = dir.join(name);
+    match File::open(&path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            anyhow::bail!("No register named '{}' (see `wsl-clip save --list`)", name)
+        }
+        other => other.with_context(|| format!("Failed to open register {:?}", path)),
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    pub name: String,
+    pub bytes: u64,
+    pub modified: SystemTime,
+}
+/// Saved registers, by name. Stray files that are not valid names (such as
+/// an interrupted save's temp file) are skipped.
+pub fn list(dir: &Path) -> Result<Vec<Register>> {
+    let entries = match std::fs::read_dir(dir) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        other => other.with_context(|| format!("Failed to read {:?}", dir))?,
+    };
+    let mut registers = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if !meta.is_file() || parse_name(&name).is_err() {
+            continue;
+        }
+        registers.push(Register {
+            name,
+            bytes: meta.len(),
+            modified: meta.modified()?,
+        });
+    }
+    registers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(registers)
+}
+/// `5s`, `12m`, `3h` or `2d`: the largest whole unit.
+pub fn age(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+/// The `save --list` table.
+pub fn render_list(registers: &[Register], now: SystemTime) -> String {
+    if registers.is_empty() {
+        return "No registers saved\n".to_string();
+    }
+    let width = registers
+        .iter()
+        .map(|r| r.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("NAME".len());
+    let mut out = format!("{:<width$}  {:>9}  AGE\n", "NAME", "BYTES");
+    for r in registers {
+        let elapsed = now.duration_since(r.modified).unwrap_or_default();
+        out.push_str(&format!(
+            "{:<width$}  {:>9}  {} ago\n",
+            r.name,
+            r.bytes,
+            age(elapsed)
+        ));
+    }
+    out
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_names_cannot_escape() {
+        assert!(parse_name("notes").is_ok());
+        assert!(parse_name("a.b-c_1").is_ok());
+        for bad in ["", "..", "../x", "a/b", ".hidden", "a\\b", "sp ace", "é"] {
+            assert!(parse_name(bad).is_err(), "{:?}", bad);
+        }
+        assert!(parse_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+    #[test]
+    fn test_save_list_and_load() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let regs = dir.path().join("registers");
+        assert!(list(&regs)?.is_empty());
+        let mut slot = Slot::create(&regs, "a")?;
+        slot.write_all(b"first\n")?;
+        // An uncommitted save leaves no register behind
+        drop(slot);
+        assert!(list(&regs)?.is_empty());
+        let mut slot = Slot::create(&regs, "a")?;
+        slot.write_all(b"second\n")?;
+        assert_eq!(slot.commit()?, 7);
+        let mut out = String::new();
+        io::Read::read_to_string(&mut open(&regs, "a")?, &mut out)?;
+        assert_eq!(out, "second\n");
+        let err = open(&regs, "missing").unwrap_err();
+        assert!(err.to_string().contains("No register named 'missing'"));
+        let listed = list(&regs)?;
+        assert_eq!(listed.len(), 1);
+        let table = render_list(&listed, listed[0].modified + Duration::from_secs(150));
+        assert_eq!(table, "NAME      BYTES  AGE\na             7  2m ago\n");
+        Ok(())
+    }
+    #[test]
+    fn test_age() {
+        assert_eq!(age(Duration::from_secs(59)), "59s");
+        assert_eq!(age(Duration::from_secs(3599)), "59m");
+        assert_eq!(age(Duration::from_secs(7200)), "2h");
+        assert_eq!(age(Duration::from_secs(3 * 86400)), "3d");
+    }
+}
+
+// <FILE>src/registers.rs</FILE> - <DESC>Named clipboard registers</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:59:14Z</VERS>
@@ -0,0 +1,178 @@
+// <FILE>src/retry.rs</FILE> - <DESC>Retries while the Windows clipboard is locked</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T12:25:00Z</VERS>
+// <WCTX>Retry locked clipboard operations.</WCTX>
+// <CLOG>Initial creation with Policy, set, Locked, again and parse_backoff.</CLOG>
+
+use crate::debug_logger::create_logger;
+use anyhow::Result;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Duration;
+pub const DEFAULT_RETRIES: u32 = 3;
+pub const DEFAULT_BACKOFF: Duration = Duration::from_millis(200);
+/// Override the defaults (`--retries` / `--retry-delay` win).
+pub const RETRIES_ENV: &str = "WSL_CLIP_RETRIES";
+pub const BACKOFF_ENV: &str = "WSL_CLIP_RETRY_DELAY";
+/// Text held in memory for a retry. Larger streams are not retried: they
+/// cannot be replayed without buffering all of them.
+pub const REPLAY_LIMIT: usize = 1024 * 1024;
+/// How often, and how patiently, a locked clipboard is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    /// Extra attempts after the first
+    pub retries: u32,
+    /// Wait before the first retry; doubles each time
+    pub backoff: Duration,
+}
+static POLICY: OnceLock<Policy> = OnceLock::new();
+/// Fixes the policy for this process: flags, else the environment, else the
+/// defaults. `--no-retry` means a single attempt.
+pub fn set(retries: Option<u32>, backoff: Option<Duration>, no_retry: bool) -> Policy {
+    *POLICY.get_or_init(|| {
+        let log = create_logger("clipboard");
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let retries = retries.or_else(|| {
+            let raw = env(RETRIES_ENV)?;
+            raw.trim()
+                .parse()
+                .map_err(|_| log.warn(&format!("Ignoring {}={}", RETRIES_ENV, raw)))
+                .ok()
+        });
+        let backoff = backoff.or_else(|| {
+            parse_backoff(&env(BACKOFF_ENV)?)
+                .map_err(|e| log.warn(&format!("Ignoring {}: {}", BACKOFF_ENV, e)))
+                .ok()
+        });
+        let policy = Policy {
+            retries: if no_retry {
+                0
+            } else {
+                retries.unwrap_or(DEFAULT_RETRIES)
+            },
+            backoff: backoff.unwrap_or(DEFAULT_BACKOFF),
+        };
+        log.debug(&format!("Retry policy: {:?}", policy));
+        policy
+    })
+}
+pub fn policy() -> Policy {
+    set(None, None, false)
+}
+/// Parses `--retry-delay`: milliseconds, or with an `ms` / `s` suffix (`250`, `250ms`, `1s`).
+pub fn parse_backoff(raw: &str) -> Result<Duration, String> {
+    let t = raw.trim().to_ascii_lowercase();
+    let (number, scale) = if let Some(ms) = t.strip_suffix("ms") {
+        (ms, 1)
+    } else if let Some(s) = t.strip_suffix('s') {
+        (s, 1000)
+    } else {
+        (t.as_str(), 1)
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(scale))
+        .map(Duration::from_millis)
+        .ok_or_else(|| format!("invalid delay '{}' (expected e.g. 250ms or 1s)", raw))
+}
+/// A failure that another attempt may fix: the clipboard was held open by
+/// another program (a clipboard manager, RDP, Teams).
+#[derive(Debug)]
+pub struct Locked(pub String);
+impl fmt::Display for Locked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl std::error::Error for Locked {}
+/// Re-runs `op` while `first` (and then each retry) failed with `Locked`,
+/// backing off exponentially. Any other outcome is returned as is.
+pub fn again<T>(first: Result<T>, what: &str, op: impl FnMut() -> Result<T>) -> Result<T> {
+    again_with(policy(), first, what, op)
+}
+fn again_with<T>(
+    policy: Policy,
+    first: Result<T>,
+    what: &str,
+    mut op: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let log = create_logger("clipboard");
+    let mut result = first;
+    let mut backoff = policy.backoff;
+    for attempt in 1..=policy.retries {
+        match &result {
+            Err(e) if e.downcast_ref::<Locked>().is_some() => log.warn(&format!(
+                "{} failed ({:#}); retry {}/{} in {}ms",
+                what,
+                e,
+                attempt,
+                policy.retries,
+                backoff.as_millis()
+            )),
+            _ => return result,
+        }
+        std::thread::sleep(backoff);
+        backoff = backoff.saturating_mul(2);
+        result = op();
+    }
+    result
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const FAST: Policy = Policy {
+        retries: 3,
+        backoff: Duration::from_millis(1),
+    };
+    #[test]
+    fn test_retries_only_locked_failures() {
+        // Locked twice, then fine: two retries
+        let mut calls = 0;
+        let first: Result<()> = Err(Locked("busy".into()).into());
+        let result = again_with(FAST, first, "clip.exe", || {
+            calls += 1;
+            if calls < 2 {
+                Err(Locked("busy".into()).into())
+            } else {
+                Ok(())
+            }
+        });
+        assert!(result.is_ok());
+        assert_eq!(calls, 2);
+        // Any other error is final
+        let mut calls = 0;
+        let result: Result<()> = again_with(FAST, Err(anyhow::anyhow!("bad file")), "x", || {
+            calls += 1;
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 0);
+        // Out of retries: the last error surfaces
+        let mut calls = 0;
+        let result: Result<()> = again_with(FAST, Err(Locked("busy".into()).into()), "x", || {
+            calls += 1;
+            Err(Locked(format!("busy {}", calls)).into())
+        });
+        assert_eq!(result.unwrap_err().to_string(), "busy 3");
+        let none = Policy { retries: 0, ..FAST };
+        assert!(again_with(
+            none,
+            Err::<(), _>(Locked("busy".into()).into()),
+            "x",
+            || Ok(())
+        )
+        .is_err());
+    }
+    #[test]
+    fn test_parse_backoff() {
+        assert_eq!(parse_backoff("250"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_backoff("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_backoff(" 2S "), Ok(Duration::from_secs(2)));
+        assert!(parse_backoff("soon").is_err());
+        assert!(parse_backoff("-1").is_err());
+    }
+}
+
+// <FILE>src/retry.rs</FILE> - <DESC>Retries while the Windows clipboard is locked</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T12:25:00Z</VERS>
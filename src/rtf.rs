@@ -0,0 +1,126 @@
+// <FILE>src/rtf.rs</FILE> - <DESC>Minimal RTF documents from plain text</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T13:10:00Z</VERS>
+// <WCTX>Rich Text Format clipboard output (--rtf).</WCTX>
+// <CLOG>Initial creation with document and escape.</CLOG>
+
+/// Body text in Calibri 11pt (`\f0\fs22`); code in Consolas 10pt (`\f1\fs20`).
+const PROLOGUE: &str = "{\\rtf1\\ansi\\ansicpg1252\\deff0\\uc1\
+    {\\fonttbl{\\f0\\fswiss Calibri;}{\\f1\\fmodern Consolas;}}\n\\f0\\fs22 ";
+const BODY_FONT: &str = "\\f0\\fs22 ";
+const CODE_FONT: &str = "\\f1\\fs20 ";
+/// Source lines are broken here. RTF readers ignore raw newlines, so this
+/// only keeps the document readable; a long text line stays one paragraph.
+const WRAP_AT: usize = 200;
+/// An RTF document holding `text`, one paragraph per line. With `code`
+/// (`--code`), lines inside ``` fences are set in a monospace font and the
+/// fence lines themselves are dropped; headers and other lines stay in the
+/// body font.
+pub fn document(text: &str, code: bool) -> String {
+    let mut out = Writer {
+        rtf: String::with_capacity(text.len() + PROLOGUE.len() + 64),
+        column: 0,
+    };
+    out.rtf.push_str(PROLOGUE);
+    let mut in_code = false;
+    for line in text.split_terminator('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if code && line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            out.word(if in_code { CODE_FONT } else { BODY_FONT });
+            continue;
+        }
+        for c in line.chars() {
+            escape(c, &mut out);
+        }
+        out.rtf.push_str("\\par\n");
+        out.column = 0;
+    }
+    out.rtf.push('}');
+    out.rtf
+}
+struct Writer {
+    rtf: String,
+    column: usize,
+}
+impl Writer {
+    fn word(&mut self, token: &str) {
+        self.rtf.push_str(token);
+        self.column += token.len();
+        if self.column >= WRAP_AT {
+            self.rtf.push('\n');
+            self.column = 0;
+        }
+    }
+}
+/// One character of text as RTF: `\`, `{` and `}` escaped, tabs as `\tab`,
+/// anything outside ASCII as `\uN?` (N a signed 16-bit UTF-16 unit; `?` is
+/// the fallback for readers without Unicode). Other control characters are
+/// dropped.
+fn escape(c: char, out: &mut Writer) {
+    match c {
+        '\\' | '{' | '}' => out.word(&format!("\\{}", c)),
+        '\t' => out.word("\\tab "),
+        c if c.is_ascii_control() => {}
+        c if c.is_ascii() => out.word(c.encode_utf8(&mut [0; 1])),
+        c => {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                out.word(&format!("\\u{}?", *unit as i16));
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /// The document body: everything after the prologue, minus the closing brace.
+    fn body(rtf: &str) -> &str {
+        rtf.strip_prefix(PROLOGUE)
+            .and_then(|b| b.strip_suffix('}'))
+            .unwrap()
+    }
+    #[test]
+    fn test_escaping() {
+        assert_eq!(
+            body(&document("a\\b {c}\tend\n", false)),
+            "a\\\\b \\{c\\}\\tab end\\par\n"
+        );
+        // CRLF is one line break; other control characters go
+        assert_eq!(body(&document("x\r\ny\x07\n", false)), "x\\par\ny\\par\n");
+        assert_eq!(document("", false), format!("{}}}", PROLOGUE));
+        assert!(document("", false).starts_with("{\\rtf1\\ansi"));
+    }
+    #[test]
+    fn test_unicode() {
+        assert_eq!(
+            body(&document("café ✓", false)),
+            "caf\\u233? \\u10003?\\par\n"
+        );
+        // Outside the BMP: a surrogate pair, each unit signed
+        assert_eq!(body(&document("🎉", false)), "\\u-10180?\\u-8311?\\par\n");
+        // A last line without a newline is still a paragraph
+        assert_eq!(body(&document("a\nb", false)), "a\\par\nb\\par\n");
+    }
+    #[test]
+    fn test_code_fences_are_monospace() {
+        let text = "# FILE: a.rs\n```rust\nfn main() {}\n```\nafter\n";
+        assert_eq!(
+            body(&document(text, true)),
+            "# FILE: a.rs\\par\n\\f1\\fs20 fn main() \\{\\}\\par\n\\f0\\fs22 after\\par\n"
+        );
+        // Without --code, fences are just text
+        assert!(body(&document(text, false)).contains("```rust\\par"));
+    }
+    #[test]
+    fn test_very_long_lines() {
+        let line = "x".repeat(100_000) + "é";
+        let rtf = document(&line, false);
+        assert!(rtf.lines().all(|l| l.len() <= WRAP_AT + 16));
+        // Source newlines are not content: still one paragraph of the same text
+        let joined: String = body(&rtf).split('\n').collect();
+        assert_eq!(joined, format!("{}\\u233?\\par", "x".repeat(100_000)));
+    }
+}
+
+// <FILE>src/rtf.rs</FILE> - <DESC>Minimal RTF documents from plain text</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T13:10:00Z</VERS>
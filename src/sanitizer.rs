@@ -0,0 +1,155 @@
+// <FILE>src/sanitizer.rs</FILE> - <DESC>Sanitizer control allow-list</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:36:43Z</VERS>
+// <WCTX>Auditable per-invocation control-character set.</WCTX>
+// <CLOG>Initial creation with parse_control, parse_list and ControlSet.</CLOG>
+
+use anyhow::Result;
+use std::collections::BTreeSet;
+const ESC: char = '\u{1b}';
+/// Names accepted by --allow-control / --deny-control.
+const NAMES: &[(&str, char)] = &[
+    ("nul", '\u{0}'),
+    ("bel", '\u{7}'),
+    ("bell", '\u{7}'),
+    ("bs", '\u{8}'),
+    ("backspace", '\u{8}'),
+    ("tab", '\t'),
+    ("ht", '\t'),
+    ("lf", '\n'),
+    ("newline", '\n'),
+    ("vt", '\u{b}'),
+    ("ff", '\u{c}'),
+    ("formfeed", '\u{c}'),
+    ("cr", '\r'),
+    ("esc", ESC),
+    ("escape", ESC),
+];
+/// A comma-separated list of C0 control characters, as typed on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlList(pub Vec<char>);
+/// Parses one entry: a name (`tab`, `esc`), `\xHH`, or `U+HHHH`.
+pub fn parse_control(token: &str) -> Result<char, String> {
+    let t = token.trim();
+    let lower = t.to_ascii_lowercase();
+    if let Some((_, c)) = NAMES.iter().find(|(name, _)| *name == lower) {
+        return Ok(*c);
+    }
+    let hex = lower
+        .strip_prefix("\\x")
+        .or_else(|| lower.strip_prefix("u+"))
+        .ok_or_else(|| {
+            format!(
+                "unknown control character '{}' (use a name like tab or cr, \\x0c, or U+000C)",
+                t
+            )
+        })?;
+    let code =
+        u32::from_str_radix(hex, 16).map_err(|_| format!("invalid code point in '{}'", t))?;
+    if code >= 0x20 {
+        return Err(format!(
+            "'{}' is not a C0 control character (only U+0000..U+001F can be allowed)",
+            t
+        ));
+    }
+    Ok(char::from_u32(code).expect("C0 range is valid"))
+}
+/// Clap value parser for a comma-separated list.
+pub fn parse_list(raw: &str) -> Result<ControlList, String> {
+    raw.split(',')
+        .filter(|t| !t.trim().is_empty())
+        .map(parse_control)
+        .collect::<Result<Vec<_>, _>>()
+        .map(ControlList)
+}
+/// Control characters the sanitizer lets through. Everything else that
+/// `char::is_control` reports is removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlSet {
+    allowed: BTreeSet<char>,
+}
+impl Default for ControlSet {
+    /// Tab only; line breaks are handled structurally by the line loop.
+    fn default() -> Self {
+        ControlSet {
+            allowed: BTreeSet::from(['\t']),
+        }
+    }
+}
+impl ControlSet {
+    /// The default set plus `allow`, minus `deny` (deny wins). Allowing ESC
+    /// reopens ANSI injection, so it needs `unsafe_ok`.
+    pub fn with(allow: &[char], deny: &[char], unsafe_ok: bool) -> Result<Self> {
+        let mut allowed = ControlSet::default().allowed;
+        allowed.extend(allow.iter().copied());
+        for c in deny {
+            allowed.remove(c);
+        }
+        if allowed.contains(&ESC) && !unsafe_ok {
+            anyhow::bail!(
+                "Refusing to allow ESC: it re-enables terminal escape injection. \
+                 Add --i-know-what-im-doing if this is intended."
+            );
+        }
+        Ok(ControlSet { allowed })
+    }
+    pub fn strips(&self, c: char) -> bool {
+        c.is_control() && !self.allowed.contains(&c)
+    }
+    /// The kept set for --debug, e.g. `tab, U+000C`.
+    pub fn describe(&self) -> String {
+        if self.allowed.is_empty() {
+            return "none".to_string();
+        }
+        self.allowed
+            .iter()
+            .map(|&c| match NAMES.iter().find(|(_, n)| *n == c) {
+                Some((name, _)) => name.to_string(),
+                None => format!("U+{:04X}", c as u32),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_parse_forms() {
+        assert_eq!(parse_control("tab"), Ok('\t'));
+        assert_eq!(parse_control(" FormFeed "), Ok('\u{c}'));
+        assert_eq!(parse_control("\\x0c"), Ok('\u{c}'));
+        assert_eq!(parse_control("U+000C"), Ok('\u{c}'));
+        assert_eq!(parse_control("u+1b"), Ok(ESC));
+        assert!(parse_control("U+0020").unwrap_err().contains("not a C0"));
+        assert!(parse_control("\\x7f").is_err());
+        assert!(parse_control("U+zz").is_err());
+        assert!(parse_control("tabs").unwrap_err().contains("unknown"));
+        assert_eq!(
+            parse_list("tab,cr,,U+0007"),
+            Ok(ControlList(vec!['\t', '\r', '\u{7}']))
+        );
+        assert!(parse_list("tab,space").is_err());
+    }
+    #[test]
+    fn test_esc_guard_and_deny() -> Result<()> {
+        assert!(ControlSet::with(&[ESC], &[], false).is_err());
+        assert!(ControlSet::with(&[ESC], &[], true)?
+            .describe()
+            .contains("esc"));
+        // Denied ESC is never an issue, even when also allowed
+        assert!(ControlSet::with(&[ESC], &[ESC], false).is_ok());
+        let set = ControlSet::with(&['\u{c}'], &['\t'], false)?;
+        assert!(set.strips('\t') && !set.strips('\u{c}') && set.strips('\u{8}'));
+        assert!(!set.strips('a'));
+        assert_eq!(set.describe(), "ff");
+        assert_eq!(ControlSet::default().describe(), "tab");
+        assert_eq!(
+            ControlSet::with(&['\u{1}'], &[], false)?.describe(),
+            "U+0001, tab"
+        );
+        Ok(())
+    }
+}
+
+// <FILE>src/sanitizer.rs</FILE> - <DESC>Sanitizer control allow-list</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:36:43Z</VERS>
@@ -0,0 +1,137 @@
+// <FILE>src/shell_paste.rs</FILE> - <DESC>Shell paste-safety helpers</DESC>
+// <VERS>VERSION: 1.1.0 - 2026-10-16T10:12:41Z</VERS>
+// <WCTX>TargetShell loadable from config/env.</WCTX>
+// <CLOG>TargetShell derives Deserialize.</CLOG>
+
+use std::io::{self, Write};
+/// Shell that will receive the paste; picks the default `--single-line` separator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetShell {
+    #[default]
+    Powershell,
+    Bash,
+    Cmd,
+}
+impl TargetShell {
+    /// Windows PowerShell 5.1 has no `&&`, so it gets a plain statement separator.
+    pub fn default_separator(&self) -> &'static str {
+        match self {
+            TargetShell::Powershell => "; ",
+            TargetShell::Bash => " && ",
+            TargetShell::Cmd => " & ",
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetShell::Powershell => "PowerShell",
+            TargetShell::Bash => "bash",
+            TargetShell::Cmd => "cmd",
+        }
+    }
+}
+/// Joins non-blank lines with `sep`. No trailing newline is added, so the
+/// pasted command waits for Enter instead of executing immediately.
+pub fn join_lines(text: &str, sep: &str) -> String {
+    text.lines()
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+/// Warning for payloads a terminal would run line-by-line on paste.
+pub fn multiline_warning(
+    lines: u64,
+    ends_with_newline: bool,
+    shell: TargetShell,
+) -> Option<String> {
+    if lines < 2 {
+        return None;
+    }
+    let tail = if ends_with_newline {
+        "every line, including the last, runs immediately"
+    } else {
+        "all but the last line run immediately"
+    };
+    Some(format!(
+        "Multi-line payload ({} lines): if pasted into {}, {}; use --single-line to join them",
+        lines,
+        shell.name(),
+        tail
+    ))
+}
+/// Pass-through writer that counts line breaks for the paste-safety check.
+pub struct LineCounter<'a, W: Write> {
+    inner: &'a mut W,
+    pub newlines: u64,
+    pub last_byte: Option<u8>,
+}
+impl<'a, W: Write> LineCounter<'a, W> {
+    pub fn new(inner: &'a mut W) -> Self {
+        LineCounter {
+            inner,
+            newlines: 0,
+            last_byte: None,
+        }
+    }
+    /// Number of logical lines written (a final unterminated line counts).
+    pub fn lines(&self) -> u64 {
+        match self.last_byte {
+            None => 0,
+            Some(b'\n') => self.newlines,
+            Some(_) => self.newlines + 1,
+        }
+    }
+}
+impl<W: Write> Write for LineCounter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        let written = &buf[..n];
+        self.newlines += written.iter().filter(|&&b| b == b'\n').count() as u64;
+        if let Some(&b) = written.last() {
+            self.last_byte = Some(b);
+        }
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_join_lines() {
+        assert_eq!(join_lines("cd /tmp\n\nls -la\n", "; "), "cd /tmp; ls -la");
+        assert_eq!(join_lines("a\r\nb", " && "), "a && b");
+        assert_eq!(join_lines("single\n", "; "), "single");
+    }
+    #[test]
+    fn test_default_separators() {
+        assert_eq!(TargetShell::Powershell.default_separator(), "; ");
+        assert_eq!(TargetShell::Bash.default_separator(), " && ");
+        assert_eq!(TargetShell::Cmd.default_separator(), " & ");
+    }
+    #[test]
+    fn test_multiline_warning_trigger() {
+        assert!(multiline_warning(1, true, TargetShell::Powershell).is_none());
+        let w = multiline_warning(3, true, TargetShell::Powershell).unwrap();
+        assert!(w.contains("3 lines") && w.contains("PowerShell"));
+        assert!(multiline_warning(2, false, TargetShell::Bash)
+            .unwrap()
+            .contains("all but the last"));
+    }
+    #[test]
+    fn test_line_counter() {
+        let mut out = Vec::new();
+        let mut counter = LineCounter::new(&mut out);
+        counter.write_all(b"a\nb\nc").unwrap();
+        assert_eq!(counter.lines(), 3);
+        counter.write_all(b"\n").unwrap();
+        assert_eq!(counter.lines(), 3);
+        assert_eq!(out, b"a\nb\nc\n");
+    }
+}
+
+// <FILE>src/shell_paste.rs</FILE> - <DESC>Shell paste-safety helpers</DESC>
+// <VERS>END OF VERSION: 1.1.0 - 2026-10-16T10:12:41Z</VERS>
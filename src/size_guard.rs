@@ -0,0 +1,143 @@
+// <FILE>src/size_guard.rs</FILE> - <DESC>Hard output size limit</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:26:13Z</VERS>
+// <WCTX>Abort oversized text copies without committing a partial clipboard.</WCTX>
+// <CLOG>Initial creation with GuardWriter, SizeLimitExceeded, parse_size, breach and drain.</CLOG>
+
+use anyhow::Result;
+use std::fmt;
+use std::io::{self, Read, Write};
+/// Process exit code when --max-size aborts a copy.
+pub const EXIT_SIZE_LIMIT: i32 = 3;
+/// Upper bound on stdin discarded after a breach, so a producer writing into
+/// a closed pipe does not die of SIGPIPE while we still exit promptly.
+pub const DRAIN_LIMIT: u64 = 16 * 1024 * 1024;
+/// The copy was aborted because its output would exceed --max-size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeLimitExceeded {
+    pub limit: u64,
+    /// Output bytes accepted before the breaching write
+    pub seen: u64,
+}
+impl fmt::Display for SizeLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Output exceeds --max-size {} bytes ({} bytes accepted before the limit); nothing was copied",
+            self.limit, self.seen
+        )
+    }
+}
+impl std::error::Error for SizeLimitExceeded {}
+/// Parses `4096`, `512K`, `10M` or `1G` (binary multiples, optional trailing `B`).
+pub fn parse_size(raw: &str) -> Result<u64, String> {
+    let upper = raw.trim().to_ascii_uppercase();
+    let digits = upper.strip_suffix('B').unwrap_or(&upper);
+    let (number, shift) = match digits.chars().last() {
+        Some('K') => (&digits[..digits.len() - 1], 10),
+        Some('M') => (&digits[..digits.len() - 1], 20),
+        Some('G') => (&digits[..digits.len() - 1], 30),
+        _ => (digits, 0),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(1 << shift))
+        .ok_or_else(|| format!("invalid size '{}' (expected e.g. 4096, 512K, 10M)", raw))
+}
+/// Counts post-transform bytes and refuses the write that would cross the
+/// limit. Nothing past the limit reaches the inner writer.
+pub struct GuardWriter<'a, W: Write> {
+    inner: &'a mut W,
+    limit: u64,
+    pub bytes: u64,
+}
+impl<'a, W: Write> GuardWriter<'a, W> {
+    pub fn new(inner: &'a mut W, limit: u64) -> Self {
+        GuardWriter {
+            inner,
+            limit,
+            bytes: 0,
+        }
+    }
+}
+impl<W: Write> Write for GuardWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.bytes + buf.len() as u64 > self.limit {
+            return Err(io::Error::other(SizeLimitExceeded {
+                limit: self.limit,
+                seen: self.bytes,
+            }));
+        }
+        let n = self.inner.write(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+/// Finds a guard breach anywhere in an error chain.
+pub fn breach(err: &anyhow::Error) -> Option<SizeLimitExceeded> {
+    err.chain().find_map(|e| {
+        e.downcast_ref::<SizeLimitExceeded>().copied().or_else(|| {
+            e.downcast_ref::<io::Error>()
+                .and_then(|io| io.get_ref())
+                .and_then(|inner| inner.downcast_ref::<SizeLimitExceeded>())
+                .copied()
+        })
+    })
+}
+/// Reads and discards up to `bound` bytes. Returns the bytes discarded.
+pub fn drain<R: Read>(reader: R, bound: u64) -> u64 {
+    io::copy(&mut reader.take(bound), &mut io::sink()).unwrap_or(0)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_processor::TextOptions;
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("512k"), Ok(512 * 1024));
+        assert_eq!(parse_size("10MB"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("1G"), Ok(1 << 30));
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("").is_err());
+    }
+    #[test]
+    fn test_breach_stops_before_the_limit() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("big.log");
+        std::fs::write(&file, "0123456789\n".repeat(1000))?;
+        let mut sink = Vec::new();
+        let mut guard = GuardWriter::new(&mut sink, 100);
+        let opts = TextOptions {
+            no_header: true,
+            ..TextOptions::default()
+        };
+        let err =
+            crate::text_processor::process_input(Some(vec![file]), &opts, &mut guard).unwrap_err();
+        let hit = breach(&err).expect("breach in error chain");
+        // Post-transform bytes: whole 11-byte lines fit, the 10th line does not
+        assert_eq!(
+            hit,
+            SizeLimitExceeded {
+                limit: 100,
+                seen: 99
+            }
+        );
+        assert_eq!(sink.len(), 99);
+        assert!(breach(&anyhow::anyhow!("unrelated")).is_none());
+        Ok(())
+    }
+    #[test]
+    fn test_drain_is_bounded() {
+        let producer = io::repeat(b'x');
+        assert_eq!(drain(producer, 4096), 4096);
+        assert_eq!(drain(&b"tail"[..], 4096), 4);
+    }
+}
+
+// <FILE>src/size_guard.rs</FILE> - <DESC>Hard output size limit</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:26:13Z</VERS>
@@ -0,0 +1,137 @@
+// <FILE>src/snapshot.rs</FILE> - <DESC>Point-in-time file snapshots</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:04:13Z</VERS>
+// <WCTX>Added --snapshot support and change detection for actively-written files.</WCTX>
+// <CLOG>Initial creation with FileState, Snapshot::take and check_unchanged.</CLOG>
+
+use crate::debug_logger::create_logger;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::SystemTime;
+/// Files up to this size are snapshotted in memory; larger ones go through a temp file.
+pub const DEFAULT_MEMORY_LIMIT: u64 = 16 * 1024 * 1024;
+/// Size and modification time of a file at a single point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileState {
+    pub size: u64,
+    pub mtime: Option<SystemTime>,
+}
+impl FileState {
+    pub fn of(path: &Path) -> Result<Self> {
+        let meta =
+            std::fs::metadata(path).with_context(|| format!("Failed to stat file: {:?}", path))?;
+        Ok(FileState {
+            size: meta.len(),
+            mtime: meta.modified().ok(),
+        })
+    }
+    /// RFC 3339 rendering of the mtime for headers ("unknown" if the FS has none).
+    pub fn mtime_display(&self) -> String {
+        match self.mtime {
+            Some(t) => DateTime::<Utc>::from(t)
+                .format("%Y-%m-%dT%H:%M:%SZ")
+                .to_string(),
+            None => "unknown".to_string(),
+        }
+    }
+}
+/// A point-in-time copy of a file, readable after the original has moved on.
+pub struct Snapshot {
+    pub state: FileState,
+    pub reader: Box<dyn BufRead>,
+}
+impl Snapshot {
+    /// Reads the whole file before any bytes are emitted. Small files stay in
+    /// memory; larger ones are copied into an anonymous temp file.
+    pub fn take(path: &Path, memory_limit: u64) -> Result<Self> {
+        let log = create_logger("snapshot");
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+        let before = FileState::of(path)?;
+        let reader: Box<dyn BufRead>;
+        let size;
+        if before.size <= memory_limit {
+            let mut buf = Vec::with_capacity(before.size as usize);
+            file.read_to_end(&mut buf)
+                .with_context(|| format!("Failed to snapshot file: {:?}", path))?;
+            size = buf.len() as u64;
+            log.debug(&format!(
+                "In-memory snapshot of {:?} ({} bytes)",
+                path, size
+            ));
+            reader = Box::new(Cursor::new(buf));
+        } else {
+            let mut spool = tempfile::tempfile().context("Failed to create snapshot temp file")?;
+            size = io::copy(&mut file, &mut spool)
+                .with_context(|| format!("Failed to snapshot file: {:?}", path))?;
+            spool.seek(SeekFrom::Start(0))?;
+            log.debug(&format!(
+                "Temp-file snapshot of {:?} ({} bytes)",
+                path, size
+            ));
+            reader = Box::new(BufReader::new(spool));
+        }
+        Ok(Snapshot {
+            state: FileState {
+                size,
+                mtime: before.mtime,
+            },
+            reader,
+        })
+    }
+}
+/// Compares a file against an earlier state; returns a warning if it changed.
+pub fn check_unchanged(path: &Path, before: &FileState) -> Option<String> {
+    match FileState::of(path) {
+        Ok(after) if &after == before => None,
+        Ok(after) => Some(format!(
+            "File changed during copy: {} (size {} -> {} bytes); use --snapshot for a consistent copy",
+            path.display(),
+            before.size,
+            after.size
+        )),
+        Err(_) => Some(format!(
+            "File changed during copy: {} (no longer readable)",
+            path.display()
+        )),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+    #[test]
+    fn test_snapshot_memory_and_spool() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        write!(file, "alpha\nbeta\n")?;
+        for limit in [DEFAULT_MEMORY_LIMIT, 0] {
+            let mut snap = Snapshot::take(file.path(), limit)?;
+            // Writers appending after the snapshot must not leak into it
+            writeln!(file.as_file(), "gamma")?;
+            let mut content = String::new();
+            snap.reader.read_to_string(&mut content)?;
+            assert!(content.starts_with("alpha\nbeta\n"));
+            assert_eq!(snap.state.size as usize, content.len());
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_check_unchanged() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        write!(file, "data")?;
+        let before = FileState::of(file.path())?;
+        assert!(check_unchanged(file.path(), &before).is_none());
+        write!(file, " more")?;
+        file.flush()?;
+        assert!(check_unchanged(file.path(), &before)
+            .unwrap()
+            .contains("File changed during copy"));
+        Ok(())
+    }
+}
+
+// <FILE>src/snapshot.rs</FILE> - <DESC>Point-in-time file snapshots</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:04:13Z</VERS>
@@ -0,0 +1,346 @@
+// <FILE>src/staging.rs</FILE> - <DESC>Staged temp files</DESC>
+// <VERS>VERSION: 1.6.0 - 2026-10-16T12:41:07Z</VERS>
+// <WCTX>zip bundles</WCTX>
+// <CLOG>Add the zip subcommand</CLOG>
+
+use crate::debug_logger::create_logger;
+use crate::interop;
+use crate::paths;
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+const MANIFEST_FILE: &str = "manifest.jsonl";
+/// How long spooled files (`--as-file` stdin, `zip` archives) wait for a
+/// paste before a later run removes them.
+pub const SPOOL_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Directory that staged files are created in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagingRoot {
+    pub dir: PathBuf,
+    /// False when Windows' temp dir could not be resolved and /tmp is used;
+    /// such files are only fit for text-only uses.
+    pub windows_visible: bool,
+}
+impl StagingRoot {
+    /// Builds the root from a probe of the Windows temp directory (as a Linux path).
+    pub fn resolve_with(probe: impl FnOnce() -> Option<PathBuf>) -> Self {
+        match probe() {
+            Some(win_temp) => StagingRoot {
+                dir: win_temp.join("wsl-clip"),
+                windows_visible: true,
+            },
+            None => {
+                eprintln!(
+                    "[WARN] Windows temp directory unavailable; staging in /tmp (not visible to Windows apps)"
+                );
+                StagingRoot::local()
+            }
+        }
+    }
+    /// The Linux temp dir, for files only Linux tools read.
+    pub fn local() -> Self {
+        StagingRoot {
+            dir: std::env::temp_dir().join("wsl-clip"),
+            windows_visible: false,
+        }
+    }
+}
+impl StagingRoot {
+    /// A child root, so a staged file can keep its exact name.
+    pub fn subdir(&self, name: &str) -> StagingRoot {
+        StagingRoot {
+            dir: self.dir.join(clean_stem(name)),
+            windows_visible: self.windows_visible,
+        }
+    }
+    /// A fresh `<time>-<pid>` directory under `spool/` for a file that must
+    /// outlive this run (it is pasted later). Spooled entries older than
+    /// SPOOL_MAX_AGE are removed first.
+    pub fn spool(&self) -> StagingRoot {
+        let spool = self.subdir("spool");
+        let pruned = prune(&spool.dir, SPOOL_MAX_AGE, SystemTime::now());
+        if pruned > 0 {
+            create_logger("staging").debug(&format!("Removed {} old spooled file(s)", pruned));
+        }
+        spool.subdir(&format!(
+            "{}-{}",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S"),
+            std::process::id()
+        ))
+    }
+}
+/// Asks Windows for %TEMP% and maps it back into the WSL filesystem.
+fn probe_windows_temp() -> Option<PathBuf> {
+    let log = create_logger("staging");
+    let out = interop::command("cmd.exe")
+        .args(["/D", "/C", "echo %TEMP%"])
+        .current_dir("/")
+        .output()
+        .ok()?;
+    let win = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if !out.status.success() || win.is_empty() || win.contains('%') {
+        log.debug("cmd.exe did not report %TEMP%");
+        return None;
+    }
+    let Ok(dir) = paths::to_linux_path(&win) else {
+        log.debug(&format!("wslpath could not map {}", win));
+        return None;
+    };
+    log.debug(&format!("Windows temp root: {:?}", dir));
+    dir.is_dir().then_some(dir)
+}
+/// The staging root, resolved once per process.
+pub fn root() -> &'static StagingRoot {
+    static ROOT: OnceLock<StagingRoot> = OnceLock::new();
+    ROOT.get_or_init(|| StagingRoot::resolve_with(probe_windows_temp))
+}
+/// Keeps names readable in Explorer while removing anything that is not
+/// portable across Windows and Linux file systems.
+fn clean_stem(prefix: &str) -> String {
+    let stem: String = prefix
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let stem = stem.trim_matches('.');
+    if stem.is_empty() {
+        "wsl-clip".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+/// A file created in the staging root. Removed on drop unless `keep()` is
+/// called (file objects on the clipboard are read lazily, long after exit).
+pub struct StagedFile {
+    path: PathBuf,
+    file: Option<File>,
+    kept: bool,
+    pub windows_visible: bool,
+}
+impl StagedFile {
+    pub fn create(prefix: &str, ext: &str) -> Result<Self> {
+        Self::create_in(root(), prefix, ext)
+    }
+    /// Creates `<prefix>.<ext>`, or `<prefix>-N.<ext>` if that name is taken.
+    pub fn create_in(root: &StagingRoot, prefix: &str, ext: &str) -> Result<Self> {
+        std::fs::create_dir_all(&root.dir)
+            .with_context(|| format!("Failed to create staging directory: {:?}", root.dir))?;
+        let stem = clean_stem(prefix);
+        let ext = ext.trim_start_matches('.');
+        for n in 0..1000 {
+            let name = match (n, ext.is_empty()) {
+                (0, true) => stem.clone(),
+                (0, false) => format!("{}.{}", stem, ext),
+                (_, true) => format!("{}-{}", stem, n),
+                (_, false) => format!("{}-{}.{}", stem, n, ext),
+            };
+            let path = root.dir.join(name);
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            match options.open(&path) {
+                Ok(file) => {
+                    record(&root.dir, &path)?;
+                    return Ok(StagedFile {
+                        path,
+                        file: Some(file),
+                        kept: false,
+                        windows_visible: root.windows_visible,
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to create staged file: {:?}", path))
+                }
+            }
+        }
+        anyhow::bail!(
+            "Too many staged files named {}.{} in {:?}",
+            stem,
+            ext,
+            root.dir
+        )
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Closes the file and leaves it in place for a later reader.
+    pub fn keep(mut self) -> PathBuf {
+        self.file = None;
+        self.kept = true;
+        self.path.clone()
+    }
+}
+impl Write for StagedFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.file.as_mut() {
+            Some(f) => f.write(buf),
+            None => Err(io::Error::other("staged file is closed")),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(f) => f.flush(),
+            None => Ok(()),
+        }
+    }
+}
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if !self.kept {
+            self.file = None;
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+/// Appends a created file to the manifest so kept files can be found and
+/// cleaned up later.
+fn record(dir: &Path, path: &Path) -> Result<()> {
+    let line = serde_json::json!({
+        "path": path,
+        "created": chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        "pid": std::process::id(),
+    });
+    let manifest = dir.join(MANIFEST_FILE);
+    let mut out = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&manifest)
+        .with_context(|| format!("Failed to open staging manifest: {:?}", manifest))?;
+    out.write_all(format!("{}\n", line).as_bytes())?;
+    Ok(())
+}
+/// Removes the entries of `dir` (files or whole directories) last modified
+/// more than `max_age` before `now`, returning how many went. A missing
+/// `dir` has nothing to prune; entries that cannot be removed are skipped.
+pub fn prune(dir: &Path, max_age: Duration, now: SystemTime) -> usize {
+    let log = create_logger("staging");
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        if entry.file_name() == MANIFEST_FILE {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let age = meta
+            .modified()
+            .ok()
+            .and_then(|m| now.duration_since(m).ok());
+        if age.is_none_or(|age| age <= max_age) {
+            continue;
+        }
+        let path = entry.path();
+        let result = if meta.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => log.debug(&format!("Could not prune {:?}: {}", path, e)),
+        }
+    }
+    removed
+}
+/// Paths recorded in the manifest, oldest first.
+pub fn manifest_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let manifest = dir.join(MANIFEST_FILE);
+    if !manifest.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = std::fs::read_to_string(&manifest)?;
+    Ok(raw
+        .lines()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .filter_map(|v| v["path"].as_str().map(PathBuf::from))
+        .collect())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn temp_root() -> (tempfile::TempDir, StagingRoot) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = StagingRoot::resolve_with(|| Some(dir.path().to_path_buf()));
+        (dir, root)
+    }
+    #[test]
+    fn test_collision_free_names_and_manifest() -> Result<()> {
+        let (_tmp, root) = temp_root();
+        let a = StagedFile::create_in(&root, "screen shot", "png")?;
+        let b = StagedFile::create_in(&root, "screen shot", ".png")?;
+        assert_eq!(a.path().file_name().unwrap(), "screen_shot.png");
+        assert_eq!(b.path().file_name().unwrap(), "screen_shot-1.png");
+        assert_eq!(
+            manifest_entries(&root.dir)?,
+            vec![a.path().to_path_buf(), b.path().to_path_buf()]
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(a.path())?.permissions().mode();
+            assert_eq!(mode & 0o077, 0);
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_prune_by_age() -> Result<()> {
+        let (_tmp, root) = temp_root();
+        let spool = root.subdir("spool");
+        let kept = StagedFile::create_in(&spool.subdir("a"), "report", "pdf")?.keep();
+        StagedFile::create_in(&spool, "loose", "zip")?.keep();
+        let now = SystemTime::now();
+        assert_eq!(prune(&spool.dir, SPOOL_MAX_AGE, now), 0);
+        assert!(kept.exists());
+        // A day later the directory and the loose file go; the manifest stays
+        let later = now + SPOOL_MAX_AGE + Duration::from_secs(60);
+        assert_eq!(prune(&spool.dir, SPOOL_MAX_AGE, later), 2);
+        assert!(!kept.exists());
+        assert!(spool.dir.join(MANIFEST_FILE).exists());
+        assert_eq!(prune(&root.dir.join("missing"), SPOOL_MAX_AGE, later), 0);
+        Ok(())
+    }
+    #[test]
+    fn test_keep_and_drop() -> Result<()> {
+        let (_tmp, root) = temp_root();
+        let mut dropped = StagedFile::create_in(&root, "convert", "png")?;
+        dropped.write_all(b"pixels")?;
+        let dropped_path = dropped.path().to_path_buf();
+        drop(dropped);
+        assert!(!dropped_path.exists());
+        let mut kept = StagedFile::create_in(&root, "bundle", "zip")?;
+        kept.write_all(b"zip")?;
+        let kept_path = kept.keep();
+        assert_eq!(std::fs::read(&kept_path)?, b"zip");
+        Ok(())
+    }
+    #[test]
+    fn test_fallback_when_windows_temp_unresolvable() -> Result<()> {
+        let root = StagingRoot::resolve_with(|| None);
+        assert!(!root.windows_visible);
+        assert!(root.dir.starts_with(std::env::temp_dir()));
+        let f = StagedFile::create_in(&root, "stdin", "txt")?;
+        assert!(!f.windows_visible);
+        assert!(f.path().exists());
+        Ok(())
+    }
+}
+
+// <FILE>src/staging.rs</FILE> - <DESC>Staged temp files</DESC>
+// <VERS>END OF VERSION: 1.6.0 - 2026-10-16T12:41:07Z</VERS>
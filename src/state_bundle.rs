@@ -0,0 +1,555 @@
+// <FILE>src/state_bundle.rs</FILE> - <DESC>State export/import bundles</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T10:42:08Z</VERS>
+// <WCTX>Portable tool state between machines.</WCTX>
+// <CLOG>Initial creation with export, import (abort/overwrite/merge), member validation and a versioned manifest.</CLOG>
+
+use crate::config::{self, Config};
+use crate::debug_logger::create_logger;
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+/// Bundle layout version. A layout change bumps this and teaches `import`
+/// to migrate older bundles.
+pub const FORMAT_VERSION: u32 = 1;
+const MANIFEST_MEMBER: &str = "wsl-clip-state.json";
+const CONFIG_MEMBER: &str = "config/config.toml";
+const HISTORY_DIR: &str = "history";
+const HISTORY_INDEX: &str = "index.jsonl";
+/// `wsl-clip-state.json`, the first member of every bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format: u32,
+    pub tool_version: String,
+    pub created: String,
+    /// Every other member, so an importer can tell what was bundled
+    pub members: Vec<String>,
+}
+/// Where state lives on this machine. None means the location is unusable
+/// (no HOME, read-only data dir).
+#[derive(Debug, Clone)]
+pub struct Targets {
+    pub config: Option<PathBuf>,
+    pub history: Option<PathBuf>,
+}
+impl Targets {
+    pub fn current(settings: &Config) -> Self {
+        Targets {
+            config: config::config_path(),
+            history: settings.history.resolved_dir().ok(),
+        }
+    }
+}
+/// How `import` treats files that already exist with different content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict {
+    /// List the collisions and change nothing
+    Abort,
+    Overwrite,
+    /// Add profiles and history entries by name; local ones win on collision
+    Merge,
+}
+/// Writes a `.tar.gz` bundle of the config (profiles included) and,
+/// if asked, the clipboard history. Returns the bundled member names.
+pub fn export(dest: &Path, targets: &Targets, include_history: bool) -> Result<Vec<String>> {
+    let log = create_logger("state");
+    let mut members: Vec<(String, Vec<u8>, u32)> = Vec::new();
+    if let Some(path) = targets.config.as_deref().filter(|p| p.exists()) {
+        let raw = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+        members.push((CONFIG_MEMBER.to_string(), raw, 0o644));
+    }
+    if include_history {
+        let dir = targets
+            .history
+            .as_deref()
+            .context("No usable history directory to export")?;
+        for path in history_files(dir)? {
+            let raw = std::fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            // History holds copied content: keep it owner-only
+            members.push((format!("{}/{}", HISTORY_DIR, name), raw, 0o600));
+        }
+    }
+    let created = chrono::Utc::now();
+    let manifest = BundleManifest {
+        format: FORMAT_VERSION,
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        created: created.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        members: members.iter().map(|(name, _, _)| name.clone()).collect(),
+    };
+    let parent = match dest.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let tmp = tempfile::NamedTempFile::new_in(parent)
+        .with_context(|| format!("Failed to create a bundle next to {:?}", dest))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(tmp, Compression::default()));
+    let mut append = |name: &str, data: &[u8], mode: u32| -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(mode);
+        header.set_mtime(created.timestamp().max(0) as u64);
+        header.set_entry_type(tar::EntryType::Regular);
+        builder
+            .append_data(&mut header, name, data)
+            .with_context(|| format!("Failed to add {} to the bundle", name))
+    };
+    append(
+        MANIFEST_MEMBER,
+        &serde_json::to_vec_pretty(&manifest)?,
+        0o644,
+    )?;
+    for (name, data, mode) in &members {
+        append(name, data, *mode)?;
+    }
+    let tmp = builder.into_inner()?.finish()?;
+    tmp.persist(dest)
+        .with_context(|| format!("Failed to write bundle {:?}", dest))?;
+    log.debug(&format!(
+        "Exported {} members to {:?}",
+        manifest.members.len(),
+        dest
+    ));
+    Ok(manifest.members)
+}
+/// Regular files directly inside the history directory (none if it does not exist yet).
+fn history_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to list {:?}", dir))? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+/// What `import` did.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub written: Vec<PathBuf>,
+    pub unchanged: usize,
+    /// Merge decisions worth telling the user about (local copies kept)
+    pub notes: Vec<String>,
+}
+/// One bundle member, checked and mapped to its place on this machine.
+struct Planned {
+    member: String,
+    target: PathBuf,
+    data: Vec<u8>,
+}
+/// Restores a bundle written by `export`. Nothing is written until every
+/// member has been validated, and (under `Conflict::Abort`) until it is
+/// clear no existing file would change.
+pub fn import(src: &Path, targets: &Targets, mode: Conflict) -> Result<ImportReport> {
+    let log = create_logger("state");
+    let file = File::open(src).with_context(|| format!("Failed to open bundle {:?}", src))?;
+    let (manifest, members) =
+        read_bundle(file).with_context(|| format!("Invalid state bundle {:?}", src))?;
+    log.debug(&format!(
+        "Bundle format {} from wsl-clip {} ({})",
+        manifest.format, manifest.tool_version, manifest.created
+    ));
+    let mut plan = Vec::new();
+    for (member, data) in members {
+        let target = if member == CONFIG_MEMBER {
+            targets
+                .config
+                .clone()
+                .context("No config location on this machine (set HOME or WSL_CLIP_CONFIG)")?
+        } else {
+            let name = member.strip_prefix("history/").unwrap_or(&member);
+            targets
+                .history
+                .as_deref()
+                .context("No usable history directory on this machine")?
+                .join(name)
+        };
+        plan.push(Planned {
+            member,
+            target,
+            data,
+        });
+    }
+    let mut report = ImportReport::default();
+    let mut writes = Vec::new();
+    let mut collisions = Vec::new();
+    for item in plan {
+        let existing = match std::fs::read(&item.target) {
+            Ok(raw) => Some(raw),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {:?}", item.target));
+            }
+        };
+        let Some(local) = existing else {
+            writes.push((item.target, item.data));
+            continue;
+        };
+        if local == item.data {
+            report.unchanged += 1;
+            continue;
+        }
+        match mode {
+            Conflict::Abort => collisions.push(item.target),
+            Conflict::Overwrite => writes.push((item.target, item.data)),
+            Conflict::Merge => {
+                if let Some(merged) = merge(&item, &local, &mut report.notes)? {
+                    writes.push((item.target, merged));
+                }
+            }
+        }
+    }
+    if !collisions.is_empty() {
+        let list: Vec<String> = collisions
+            .iter()
+            .map(|p| format!("  {}", p.display()))
+            .collect();
+        anyhow::bail!(
+            "Import would change {} existing file(s):\n{}\n\
+             Use --overwrite to replace them, or --merge to combine profiles and history",
+            collisions.len(),
+            list.join("\n")
+        );
+    }
+    for (target, data) in writes {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+        std::fs::write(&target, data).with_context(|| format!("Failed to write {:?}", target))?;
+        report.written.push(target);
+    }
+    Ok(report)
+}
+/// The merged content for a colliding member, or None to keep the local file.
+fn merge(item: &Planned, local: &[u8], notes: &mut Vec<String>) -> Result<Option<Vec<u8>>> {
+    let text = |raw: &[u8]| String::from_utf8_lossy(raw).into_owned();
+    if item.member == CONFIG_MEMBER {
+        let merged = merge_config(&text(local), &text(&item.data), notes)
+            .with_context(|| format!("Failed to merge {:?}", item.target))?;
+        return Ok(Some(merged.into_bytes()));
+    }
+    if item.member == format!("{}/{}", HISTORY_DIR, HISTORY_INDEX) {
+        return Ok(Some(
+            merge_index(&text(local), &text(&item.data)).into_bytes(),
+        ));
+    }
+    notes.push(format!(
+        "{} differs; kept the local copy",
+        item.target.display()
+    ));
+    Ok(None)
+}
+/// Adds the bundle's profiles and top-level sections that are missing
+/// locally. Local values win on collision; the local file's comments and
+/// layout are preserved.
+pub fn merge_config(local: &str, incoming: &str, notes: &mut Vec<String>) -> Result<String> {
+    let mut doc: toml_edit::DocumentMut = local.parse()?;
+    let theirs: toml_edit::DocumentMut = incoming.parse()?;
+    let local_values: toml::Table = toml::from_str(local)?;
+    let their_values: toml::Table = toml::from_str(incoming)?;
+    for (key, item) in theirs.iter() {
+        if key == "profile" {
+            let Some(profiles) = item.as_table_like() else {
+                continue;
+            };
+            if !doc.contains_key("profile") {
+                let mut table = toml_edit::Table::new();
+                table.set_implicit(true);
+                doc.insert("profile", toml_edit::Item::Table(table));
+            }
+            let mine = doc["profile"]
+                .as_table_like_mut()
+                .context("local 'profile' is not a table")?;
+            for (name, profile) in profiles.iter() {
+                if !mine.contains_key(name) {
+                    mine.insert(name, profile.clone());
+                    notes.push(format!("Added profile '{}'", name));
+                } else if profile_value(&local_values, name) != profile_value(&their_values, name) {
+                    notes.push(format!("Profile '{}' differs; kept the local one", name));
+                }
+            }
+            continue;
+        }
+        if !doc.contains_key(key) {
+            doc.insert(key, item.clone());
+        } else if local_values.get(key) != their_values.get(key) {
+            notes.push(format!("Config [{}] differs; kept the local one", key));
+        }
+    }
+    Ok(doc.to_string())
+}
+fn profile_value<'a>(config: &'a toml::Table, name: &str) -> Option<&'a toml::Value> {
+    config.get("profile").and_then(|p| p.get(name))
+}
+/// Local index lines, then bundle entries whose id is not already present.
+fn merge_index(local: &str, incoming: &str) -> String {
+    let id = |line: &str| {
+        serde_json::from_str::<serde_json::Value>(line)
+            .ok()
+            .and_then(|v| v.get("id").and_then(|id| id.as_str()).map(String::from))
+    };
+    let known: HashSet<String> = local.lines().filter_map(id).collect();
+    let mut out = local.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    for line in incoming.lines() {
+        if id(line).is_some_and(|i| !known.contains(&i)) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+/// A validated member name and its content.
+type Member = (String, Vec<u8>);
+/// Reads and validates every member. Anything but the manifest, the config
+/// and flat history files is rejected, as are links and `..`/absolute paths.
+fn read_bundle<R: Read>(reader: R) -> Result<(BundleManifest, Vec<Member>)> {
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let mut manifest = None;
+    let mut members = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let raw_path = entry.path()?.into_owned();
+        let kind = entry.header().entry_type();
+        if kind.is_dir() {
+            continue;
+        }
+        let member = checked_member(&raw_path)?;
+        if !kind.is_file() {
+            anyhow::bail!(
+                "Refusing archive member {:?}: only regular files are allowed",
+                raw_path
+            );
+        }
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        if member == MANIFEST_MEMBER {
+            manifest = Some(serde_json::from_slice::<BundleManifest>(&data)?);
+        } else {
+            members.push((member, data));
+        }
+    }
+    let manifest = manifest.context("Missing wsl-clip-state.json; not a wsl-clip state bundle")?;
+    match manifest.format {
+        FORMAT_VERSION => {}
+        newer if newer > FORMAT_VERSION => anyhow::bail!(
+            "Bundle format {} is newer than this wsl-clip understands ({}); upgrade wsl-clip",
+            newer,
+            FORMAT_VERSION
+        ),
+        old => anyhow::bail!("Unknown bundle format {}", old),
+    }
+    Ok((manifest, members))
+}
+/// The member name if `path` is one of the places a bundle may write.
+fn checked_member(path: &Path) -> Result<String> {
+    let parts: Option<Vec<&str>> = path
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    let allowed = matches!(
+        parts.as_deref(),
+        Some([MANIFEST_MEMBER] | ["config", "config.toml"] | [HISTORY_DIR, _])
+    );
+    if !allowed {
+        anyhow::bail!(
+            "Refusing archive member {:?}: outside the wsl-clip state directories",
+            path
+        );
+    }
+    Ok(parts.unwrap_or_default().join("/"))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    struct Machine {
+        dir: tempfile::TempDir,
+    }
+    impl Machine {
+        fn new() -> Self {
+            Machine {
+                dir: tempfile::tempdir().unwrap(),
+            }
+        }
+        fn targets(&self) -> Targets {
+            Targets {
+                config: Some(self.dir.path().join("config.toml")),
+                history: Some(self.dir.path().join("history")),
+            }
+        }
+        fn write(&self, rel: &str, content: &str) {
+            let path = self.dir.path().join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        fn read(&self, rel: &str) -> String {
+            std::fs::read_to_string(self.dir.path().join(rel)).unwrap()
+        }
+        fn bundle(&self, history: bool) -> PathBuf {
+            let dest = self.dir.path().join("state.tar.gz");
+            export(&dest, &self.targets(), history).unwrap();
+            dest
+        }
+    }
+    const CONFIG: &str = "# mine\n[defaults]\ncode = true\n[profile.review]\ncrlf = true\n";
+    fn index_line(id: &str) -> String {
+        format!(
+            "{{\"id\":\"{}\",\"timestamp\":\"t\",\"bytes\":1,\"captured\":1,\"hash\":\"h\",\"truncated\":false}}\n",
+            id
+        )
+    }
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let old = Machine::new();
+        old.write("config.toml", CONFIG);
+        old.write("history/index.jsonl", &index_line("e1"));
+        old.write("history/e1.txt", "secret\n");
+        // History stays out unless asked for
+        let without = old.bundle(false);
+        let new = Machine::new();
+        let report = import(&without, &new.targets(), Conflict::Abort)?;
+        assert_eq!(report.written.len(), 1);
+        assert!(!new.dir.path().join("history").exists());
+        let with = old.bundle(true);
+        let report = import(&with, &new.targets(), Conflict::Abort)?;
+        assert_eq!((report.written.len(), report.unchanged), (2, 1));
+        assert_eq!(new.read("config.toml"), CONFIG);
+        assert_eq!(new.read("history/e1.txt"), "secret\n");
+        assert_eq!(new.read("history/index.jsonl"), index_line("e1"));
+        Ok(())
+    }
+    /// A bundle with one hand-built member, bypassing tar's own path checks.
+    fn raw_bundle(dir: &Path, name: &[u8], kind: tar::EntryType) -> PathBuf {
+        let dest = dir.join("evil.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(&dest).unwrap(),
+            Compression::default(),
+        ));
+        let manifest = serde_json::to_vec(&BundleManifest {
+            format: FORMAT_VERSION,
+            tool_version: "0".into(),
+            created: "t".into(),
+            members: vec![],
+        })
+        .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_entry_type(tar::EntryType::Regular);
+        builder
+            .append_data(&mut header, MANIFEST_MEMBER, &manifest[..])
+            .unwrap();
+        let mut header = tar::Header::new_old();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name);
+        header.set_size(4);
+        header.set_entry_type(kind);
+        header.set_cksum();
+        builder.append(&header, &b"evil"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        dest
+    }
+    #[test]
+    fn test_traversal_is_rejected_before_writing() {
+        let machine = Machine::new();
+        for (name, kind) in [
+            (&b"../outside"[..], tar::EntryType::Regular),
+            (b"history/../../outside", tar::EntryType::Regular),
+            (b"/etc/passwd", tar::EntryType::Regular),
+            (b"history/sub/deep.txt", tar::EntryType::Regular),
+            (b"history/link", tar::EntryType::Symlink),
+        ] {
+            let bundle = raw_bundle(machine.dir.path(), name, kind);
+            let err = import(&bundle, &machine.targets(), Conflict::Overwrite).unwrap_err();
+            assert!(
+                format!("{:#}", err).contains("Refusing archive member"),
+                "{:#}",
+                err
+            );
+        }
+        assert!(!machine.dir.path().join("history").exists());
+        assert!(!machine.dir.path().join("outside").exists());
+    }
+    #[test]
+    fn test_conflict_modes() -> Result<()> {
+        let old = Machine::new();
+        old.write(
+            "config.toml",
+            "[defaults]\ncode = false\n[profile.review]\ncrlf = false\n[profile.ship]\ncode = true\n",
+        );
+        old.write("history/index.jsonl", &index_line("e1"));
+        old.write("history/e1.txt", "theirs\n");
+        let bundle = old.bundle(true);
+        let setup = || {
+            let new = Machine::new();
+            new.write("config.toml", CONFIG);
+            new.write("history/index.jsonl", &index_line("e0"));
+            new.write("history/e1.txt", "mine\n");
+            new
+        };
+        // Abort: every collision is listed, nothing changes
+        let new = setup();
+        let err = import(&bundle, &new.targets(), Conflict::Abort).unwrap_err();
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("3 existing file(s)") && msg.contains("config.toml"));
+        assert_eq!(new.read("config.toml"), CONFIG);
+        // Overwrite: the bundle wins
+        let new = setup();
+        import(&bundle, &new.targets(), Conflict::Overwrite)?;
+        assert_eq!(new.read("config.toml"), old.read("config.toml"));
+        assert_eq!(new.read("history/e1.txt"), "theirs\n");
+        // Merge: new profiles and entries are added, local ones kept
+        let new = setup();
+        let report = import(&bundle, &new.targets(), Conflict::Merge)?;
+        let merged = new.read("config.toml");
+        assert!(merged.starts_with(CONFIG), "{}", merged);
+        let parsed = Config::parse(&merged)?;
+        assert_eq!(parsed.profile["review"]["crlf"].as_bool(), Some(true));
+        assert_eq!(parsed.profile["ship"]["code"].as_bool(), Some(true));
+        assert_eq!(parsed.defaults.code, Some(true));
+        assert_eq!(
+            new.read("history/index.jsonl"),
+            index_line("e0") + &index_line("e1")
+        );
+        assert_eq!(new.read("history/e1.txt"), "mine\n");
+        assert!(report.notes.contains(&"Added profile 'ship'".to_string()));
+        assert!(report
+            .notes
+            .contains(&"Profile 'review' differs; kept the local one".to_string()));
+        Ok(())
+    }
+    #[test]
+    fn test_newer_format_is_refused() {
+        let machine = Machine::new();
+        let dest = machine.dir.path().join("future.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            File::create(&dest).unwrap(),
+            Compression::default(),
+        ));
+        let manifest = br#"{"format":99,"tool_version":"9","created":"t","members":[]}"#;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        builder
+            .append_data(&mut header, MANIFEST_MEMBER, &manifest[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        let err = import(&dest, &machine.targets(), Conflict::Abort).unwrap_err();
+        assert!(format!("{:#}", err).contains("newer than this wsl-clip"));
+    }
+}
+
+// <FILE>src/state_bundle.rs</FILE> - <DESC>State export/import bundles</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T10:42:08Z</VERS>
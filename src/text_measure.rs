@@ -0,0 +1,94 @@
+// <FILE>src/text_measure.rs</FILE> - <DESC>Grapheme-aware text measurement</DESC>
+// <VERS>VERSION: 1.1.0 - 2026-10-16T14:28:20Z</VERS>
+// <WCTX>Only the helpers in use</WCTX>
+// <CLOG>Removed display_width and wrap, which had no caller; history and peek use truncate_width and truncate_bytes</CLOG>
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+/// Display columns of one grapheme cluster (CJK and emoji count as 2);
+/// unicode-width measures ZWJ sequences and flags as the single glyph they
+/// render as.
+fn grapheme_width(g: &str) -> usize {
+    g.width()
+}
+/// Longest prefix that fits in `max` columns, cut on a grapheme boundary.
+/// Returns the prefix and whether anything was dropped.
+pub fn truncate_width(s: &str, max: usize) -> (&str, bool) {
+    let mut used = 0;
+    for (i, g) in s.grapheme_indices(true) {
+        used += grapheme_width(g);
+        if used > max {
+            return (&s[..i], true);
+        }
+    }
+    (s, false)
+}
+/// Longest prefix of at most `max` bytes that does not split a grapheme.
+pub fn truncate_bytes(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = 0;
+    for (i, g) in s.grapheme_indices(true) {
+        if i + g.len() > max {
+            break;
+        }
+        end = i + g.len();
+    }
+    &s[..end]
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const SAMPLES: &[&str] = &[
+        "👨‍👩‍👧‍👦 family",
+        "🇯🇵🇩🇪 flags",
+        "한국어 텍스트",
+        "e\u{301}te\u{301} cafe\u{301}",
+        "日本語のテキスト",
+        "plain ascii text",
+    ];
+    fn display_width(s: &str) -> usize {
+        s.graphemes(true).map(grapheme_width).sum()
+    }
+    /// Every piece must be a concatenation of whole graphemes of the input.
+    fn assert_untorn(original: &str, piece: &str) {
+        let all: Vec<&str> = original.graphemes(true).collect();
+        let parts: Vec<&str> = piece.graphemes(true).collect();
+        assert!(
+            all.windows(parts.len().max(1))
+                .any(|w| parts.is_empty() || w == parts.as_slice()),
+            "torn grapheme in {:?} from {:?}",
+            piece,
+            original
+        );
+    }
+    #[test]
+    fn test_widths() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("👨‍👩‍👧‍👦"), 2);
+        assert_eq!(display_width("🇯🇵"), 2);
+        assert_eq!(display_width("e\u{301}"), 1);
+    }
+    #[test]
+    fn test_truncation_never_tears_graphemes() {
+        for s in SAMPLES {
+            for max in 0..=display_width(s) + 1 {
+                let (cut, dropped) = truncate_width(s, max);
+                assert!(display_width(cut) <= max);
+                assert_eq!(dropped, cut.len() < s.len());
+                assert_untorn(s, cut);
+            }
+            for max in 0..=s.len() {
+                let cut = truncate_bytes(s, max);
+                assert!(cut.len() <= max);
+                assert_untorn(s, cut);
+            }
+        }
+        assert_eq!(truncate_bytes("👨‍👩‍👧‍👦!", 10), "");
+    }
+}
+
+// <FILE>src/text_measure.rs</FILE> - <DESC>Grapheme-aware text measurement</DESC>
+// <VERS>END OF VERSION: 1.1.0 - 2026-10-16T14:28:20Z</VERS>
@@ -1,20 +1,131 @@
-// <FILE>src/text_processor.rs</FILE> - <DESC>Streaming text processor with security sanitization</DESC>
-// <VERS>VERSION: 2.2.0 - 2025-11-25T17:17:02Z</VERS>
-// <WCTX>Implemented Safe Text whitelist (strip \b, \a, etc., keep \t) in default mode.</WCTX>
-// <CLOG>Added char filtering logic to write_line; added security test case.</CLOG>
+// <FILE>src/text_processor.rs</FILE> - <DESC>Text streaming pipeline</DESC>
+// <VERS>VERSION: 2.25.0 - 2026-10-16T14:44:15Z</VERS>
+// <WCTX>One copy per file however it is spelled.</WCTX>
+// <CLOG>order_files dedupes on the resolved path.</CLOG>
 
+use crate::cite::{self, CiteConfig, Emitted, LineRange};
 use crate::debug_logger::create_logger;
+use crate::encoding::{self, DecodingReader, EncodingWriter, TextEncoding};
+use crate::input;
+use crate::jsonl;
+use crate::paths;
+use crate::sanitizer::ControlSet;
+use crate::shell_paste::{self, LineCounter, TargetShell};
+use crate::snapshot::{self, FileState, Snapshot};
+use crate::timestamp::Timestamp;
 use anyhow::{Context, Result};
-use chrono::Utc;
 use regex::Regex;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+/// Layout of the end-of-files footer emitted for multi-file copies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FooterStyle {
+    /// One line per file with line/byte counts, totals, and skipped files
+    #[default]
+    Detailed,
+    /// Legacy single line: `# End of FILES. SENT: <paths>`
+    Compact,
+}
+/// `--sort`: the order files are emitted in when argv order is not wanted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FileOrder {
+    /// By path (the displayed path under --deterministic)
+    Name,
+    /// Oldest modification first
+    Mtime,
+    /// Smallest first
+    Size,
+}
+/// Per-file accounting gathered while streaming.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentFile {
+    pub display: String,
+    pub lines: u64,
+    pub bytes: u64,
+}
+/// A requested input that was not emitted, with the reason it was dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    pub display: String,
+    pub reason: String,
+}
+/// Outcome of a text run: what was emitted, what was dropped, and any warnings.
+#[derive(Debug, Default)]
+pub struct ProcessReport {
+    pub sent: Vec<SentFile>,
+    pub skipped: Vec<SkippedFile>,
+    pub warnings: Vec<String>,
+}
+#[derive(Clone, Default)]
 pub struct TextOptions {
     pub no_header: bool,
     pub strip_ansi: bool,
     pub use_markdown: bool,
     pub use_crlf: bool,
+    pub timestamp: Timestamp,
+    pub footer_style: FooterStyle,
+    pub snapshot: bool,
+    /// Join all lines with this separator (buffered transform)
+    pub single_line: Option<String>,
+    /// Warn when the payload would execute line-by-line in a terminal
+    pub paste_safe: bool,
+    pub target_shell: TargetShell,
+    /// Keep only lines matching this pattern (tested before any expansion)
+    pub grep: Option<Regex>,
+    /// Pretty-print lines that hold a JSON object/array
+    pub jsonl_pretty: bool,
+    /// Control characters that survive sanitization
+    pub controls: ControlSet,
+    /// Reproducible output: display-path order, no mtimes in headers
+    pub deterministic: bool,
+    /// Reorder the files; `None` keeps them as given
+    pub sort: Option<FileOrder>,
+    /// Emit a header for zero-byte files instead of skipping them
+    pub keep_empty: bool,
+    /// Only these source lines of each input
+    pub lines: Option<LineRange>,
+    /// Append a provenance citation after each file (--cite)
+    pub cite: Option<CiteConfig>,
+    /// Transcode the output (--encoding); `None` leaves it UTF-8
+    pub encoding: Option<TextEncoding>,
+    /// Fail on characters the encoding lacks instead of writing `?`
+    pub strict_encoding: bool,
+}
+/// Where the text comes from.
+enum Input<'a> {
+    Files(Vec<PathBuf>),
+    Stdin,
+    /// Already in memory (`url` downloads); treated like stdin
+    Bytes(&'a [u8]),
+}
+/// One stretch of the output, in the order the sources were given.
+enum Source<'a> {
+    File(PathBuf),
+    /// Bare stdin, or `-` among the files
+    Stdin,
+    Bytes(&'a [u8]),
+}
+impl From<PathBuf> for Source<'_> {
+    fn from(path: PathBuf) -> Self {
+        if is_stdin(&path) {
+            Source::Stdin
+        } else {
+            Source::File(path)
+        }
+    }
+}
+impl Source<'_> {
+    /// How the footer names it.
+    fn display(&self) -> String {
+        match self {
+            Source::File(path) => path.to_string_lossy().to_string(),
+            Source::Stdin => "stdin".to_string(),
+            Source::Bytes(_) => "text".to_string(),
+        }
+    }
 }
 /// Streams processed content directly to the writer (clipboard pipe)
 /// This avoids loading entire files into memory.
@@ -22,28 +133,96 @@ pub fn process_input<W: Write>(
     files: Option<Vec<PathBuf>>,
     opts: &TextOptions,
     writer: &mut W,
-) -> Result<()> {
+) -> Result<ProcessReport> {
+    let input = match files {
+        Some(files) => Input::Files(files),
+        None => Input::Stdin,
+    };
+    process_encoded(input, opts, writer)
+}
+/// `process_input` for text already in memory, as if it came on stdin.
+pub fn process_bytes<W: Write>(
+    data: &[u8],
+    opts: &TextOptions,
+    writer: &mut W,
+) -> Result<ProcessReport> {
+    process_encoded(Input::Bytes(data), opts, writer)
+}
+fn process_encoded<W: Write>(
+    input: Input<'_>,
+    opts: &TextOptions,
+    writer: &mut W,
+) -> Result<ProcessReport> {
+    let Some(encoding) = opts.encoding.filter(|&e| e != TextEncoding::Utf8) else {
+        return process_utf8(input, opts, writer);
+    };
+    // Last, so every transform before it still sees UTF-8
+    let mut encoder = EncodingWriter::new(writer, encoding, opts.strict_encoding);
+    let report = process_utf8(input, opts, &mut encoder)?;
+    encoder.finish()?;
+    Ok(report)
+}
+fn process_utf8<W: Write>(
+    input: Input<'_>,
+    opts: &TextOptions,
+    writer: &mut W,
+) -> Result<ProcessReport> {
+    if let Some(sep) = &opts.single_line {
+        // Buffered: headers, fences and CRLF would break a one-line command
+        let inner = TextOptions {
+            no_header: true,
+            use_markdown: false,
+            use_crlf: false,
+            single_line: None,
+            paste_safe: false,
+            ..opts.clone()
+        };
+        let mut buffer = Vec::new();
+        let report = stream_input(input, &inner, &mut buffer)?;
+        let joined = shell_paste::join_lines(&String::from_utf8_lossy(&buffer), sep);
+        writer.write_all(joined.as_bytes())?;
+        return Ok(report);
+    }
+    if opts.paste_safe {
+        let mut counter = LineCounter::new(writer);
+        let mut report = stream_input(input, opts, &mut counter)?;
+        let ends_with_newline = counter.last_byte == Some(b'\n');
+        if let Some(warning) =
+            shell_paste::multiline_warning(counter.lines(), ends_with_newline, opts.target_shell)
+        {
+            report.warnings.push(warning);
+        }
+        return Ok(report);
+    }
+    stream_input(input, opts, writer)
+}
+fn stream_input<W: Write>(
+    input: Input<'_>,
+    opts: &TextOptions,
+    writer: &mut W,
+) -> Result<ProcessReport> {
     let log = create_logger("text_processor");
     // Pre-compile regex if needed
     let ansi_regex = if opts.strip_ansi {
-        Some(Regex::new(r"\x1B\[([0-9]{1,2}(;[0-9]{1,2})*)?[m|K]").unwrap())
+        Some(ansi_regex())
     } else {
         None
     };
-    let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
-    // Helper to write a line with transforms
-    let write_line = |w: &mut W, line: &str| -> Result<()> {
+    let timestamp = opts.timestamp.render();
+    let mut report = ProcessReport::default();
+    // Helper to write a line with transforms (returns bytes written)
+    let write_line = |w: &mut W, line: &str| -> Result<u64> {
         let mut processed = line.to_string();
         // 1. Strip ANSI Sequences first (so we don't leave dangling brackets)
         if let Some(re) = &ansi_regex {
             processed = re.replace_all(&processed, "").to_string();
             // 2. Security Sanitization (Pastejacking prevention)
-            // Strip all control characters except Tab (\t).
+            // Strip all control characters outside the allowed set (Tab by default).
             // Note: Newlines are handled structurally by the loop, so they aren't in 'line'.
             // This removes \b (backspace), \r (stray carriage return), \a (bell), etc.
             processed = processed
                 .chars()
-                .filter(|&c| !c.is_control() || c == '\t')
+                .filter(|&c| !opts.controls.strips(c))
                 .collect();
         }
         if opts.use_crlf {
@@ -52,99 +231,358 @@ pub fn process_input<W: Write>(
             // We just append \r\n.
             w.write_all(processed.as_bytes())?;
             w.write_all(b"\r\n")?;
+            Ok(processed.len() as u64 + 2)
         } else {
             w.write_all(processed.as_bytes())?;
             w.write_all(b"\n")?;
+            Ok(processed.len() as u64 + 1)
         }
-        Ok(())
     };
-    if let Some(mut file_list) = files {
-        if file_list.is_empty() {
-            // Should have been caught by caller, but handle gracefully
-            return Ok(());
-        }
-        file_list.sort();
-        log.debug(&format!("Processing {} files (streaming)", file_list.len()));
-        let total_files = file_list.len();
-        let mut processed_list = Vec::new();
-        for path in file_list {
-            if !path.exists() || !path.is_file() {
-                log.warn(&format!("Skipped invalid file: {:?}", path));
-                continue;
-            }
-            processed_list.push(path.to_string_lossy().to_string());
-            // Header
-            if !opts.no_header {
-                let header = format!("# FILE: {} READ: {}\n", path.display(), timestamp);
-                if opts.use_crlf {
-                    writer.write_all(header.replace("\n", "\r\n").as_bytes())?;
-                } else {
-                    writer.write_all(header.as_bytes())?;
-                }
+    // Filter, then expand (returns bytes and lines written)
+    let emit = |w: &mut W, line: &str| -> Result<(u64, u64)> {
+        if let Some(re) = &opts.grep {
+            if !re.is_match(line) {
+                return Ok((0, 0));
             }
-            // Markdown Start
-            if opts.use_markdown {
-                let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-                let md_block = format!("```{}\n", ext);
-                if opts.use_crlf {
-                    writer.write_all(md_block.replace("\n", "\r\n").as_bytes())?;
-                } else {
-                    writer.write_all(md_block.as_bytes())?;
+        }
+        if opts.jsonl_pretty {
+            if let Some(pretty) = jsonl::expand(line) {
+                let mut bytes = 0;
+                let mut lines = 0;
+                for l in pretty.lines().chain([jsonl::RECORD_SEPARATOR]) {
+                    bytes += write_line(w, l)?;
+                    lines += 1;
                 }
+                return Ok((bytes, lines));
             }
-            // Stream Content
-            let file =
-                File::open(&path).with_context(|| format!("Failed to read file: {:?}", path))?;
-            let reader = BufReader::new(file);
-            for line_res in reader.lines() {
-                let line = line_res.context("Failed to read line")?;
-                write_line(writer, &line)?;
+        }
+        Ok((write_line(w, line)?, 1))
+    };
+    // A bundle (file arguments, `-` among them) gets headers, fences and a
+    // footer; bare stdin or in-memory text is copied as it is
+    let (bundle, sources): (bool, Vec<Source<'_>>) = match input {
+        Input::Files(mut files) => {
+            if files.is_empty() {
+                // Should have been caught by caller, but handle gracefully
+                return Ok(ProcessReport::default());
             }
-            // Markdown End
-            if opts.use_markdown {
-                let md_end = "```\n";
-                if opts.use_crlf {
-                    writer.write_all(md_end.replace("\n", "\r\n").as_bytes())?;
+            check_stdin_placeholder(&files)?;
+            order_files(&mut files, opts);
+            (true, files.into_iter().map(Source::from).collect())
+        }
+        Input::Stdin => (false, vec![Source::Stdin]),
+        Input::Bytes(data) => (false, vec![Source::Bytes(data)]),
+    };
+    log.debug(&format!("Processing {} sources (streaming)", sources.len()));
+    let total = sources.len();
+    // Headers, fences and citations are built LF-terminated
+    let write_block = |w: &mut W, block: &str| -> Result<()> {
+        if opts.use_crlf {
+            w.write_all(block.replace("\n", "\r\n").as_bytes())?;
+        } else {
+            w.write_all(block.as_bytes())?;
+        }
+        Ok(())
+    };
+    for source in sources {
+        let (reader, before): (Box<dyn BufRead + '_>, Option<FileState>) = match &source {
+            Source::File(path) => {
+                if let Some(reason) = skip_reason(path, opts) {
+                    if reason == "empty" {
+                        // `.gitkeep` and friends: a bare header is noise
+                        log.debug(&format!("Skipped empty file: {:?}", path));
+                    } else {
+                        let warning = format!("Skipped {} ({})", path.display(), reason);
+                        log.warn(&warning);
+                        report.warnings.push(warning);
+                    }
+                    report.skipped.push(SkippedFile {
+                        display: source.display(),
+                        reason: reason.to_string(),
+                    });
+                    continue;
+                }
+                // Snapshot (or record state for the post-read change check)
+                if opts.snapshot {
+                    let snap = Snapshot::take(path, snapshot::DEFAULT_MEMORY_LIMIT)?;
+                    (snap.reader, Some(snap.state))
                 } else {
-                    writer.write_all(md_end.as_bytes())?;
+                    let before = FileState::of(path)?;
+                    let file = File::open(path)
+                        .with_context(|| format!("Failed to read file: {:?}", path))?;
+                    (Box::new(BufReader::new(file)), Some(before))
                 }
             }
-            // Spacer between files
-            if !opts.no_header {
-                if opts.use_crlf {
-                    writer.write_all(b"\r\n")?;
-                } else {
-                    writer.write_all(b"\n")?;
+            Source::Stdin => {
+                log.debug("Reading from Stdin (Streaming)");
+                if !bundle {
+                    input::require_stdin()?;
                 }
+                (Box::new(input::stdin_reader()), None)
+            }
+            Source::Bytes(data) => {
+                log.debug(&format!("Reading {} bytes from memory", data.len()));
+                (Box::new(*data), None)
             }
+        };
+        let path = match &source {
+            Source::File(path) => Some(path.as_path()),
+            _ => None,
+        };
+        if bundle && !opts.no_header {
+            let header = match path {
+                Some(path) => file_header(
+                    path,
+                    timestamp.as_deref(),
+                    before.as_ref().and_then(|b| header_state(b, opts)).as_ref(),
+                ),
+                None => stdin_header(timestamp.as_deref()),
+            };
+            write_block(writer, &header)?;
+        }
+        if bundle && opts.use_markdown {
+            // Stdin has no extension, so its fence has no language
+            write_block(writer, &fence_open(path.unwrap_or(Path::new(""))))?;
         }
-        if !opts.no_header && total_files > 1 {
-            let footer = format!("# End of FILES. SENT: {}\n", processed_list.join(" "));
-            if opts.use_crlf {
-                writer.write_all(footer.replace("\n", "\r\n").as_bytes())?;
-            } else {
-                writer.write_all(footer.as_bytes())?;
+        // Stream Content
+        let mut stat = SentFile {
+            display: source.display(),
+            lines: 0,
+            bytes: 0,
+        };
+        let mut emitted = Emitted::default();
+        let mut reader = BufReader::new(DecodingReader::new(reader)?);
+        for (n, line_res) in (1..).zip((&mut reader).lines()) {
+            // Stdin is read to the end even past --lines, so the writer never sees a broken pipe
+            if path.is_some() && opts.lines.is_some_and(|r| r.is_past(n)) {
+                break;
+            }
+            let line = match path {
+                Some(_) => line_res.context("Failed to read line")?,
+                None => line_res.context("Failed to read line from stdin")?,
+            };
+            if !opts.lines.is_none_or(|r| r.contains(n)) {
+                continue;
             }
+            let (bytes, lines) = emit(writer, &line)?;
+            emitted.record(n, lines > 0);
+            stat.bytes += bytes;
+            stat.lines += lines;
+        }
+        let decoded = reader.get_ref();
+        let name = match &source {
+            Source::File(path) => path.display().to_string(),
+            Source::Stdin => "stdin".to_string(),
+            Source::Bytes(_) => "The text".to_string(),
+        };
+        if decoded.encoding() != encoding_rs::UTF_8 {
+            log.debug(&format!(
+                "Decoded {} from {}",
+                name,
+                decoded.encoding().name()
+            ));
+        }
+        if decoded.replaced {
+            let warning = encoding::replaced_warning(&name, decoded.encoding());
+            log.warn(&warning);
+            report.warnings.push(warning);
+        }
+        // A pipe's size and mtime move as it is written; there is nothing to compare
+        let settled = before.as_ref().filter(|_| !opts.snapshot);
+        if let (Some(path), Some(before)) = (path.filter(|p| !is_stream(p)), settled) {
+            if let Some(warning) = snapshot::check_unchanged(path, before) {
+                log.warn(&warning);
+                report.warnings.push(warning);
+            }
+        }
+        if !bundle {
+            continue;
+        }
+        if opts.use_markdown {
+            write_block(writer, FENCE_CLOSE)?;
+        }
+        if let (Some(path), Some(cite_opts)) = (path, &opts.cite) {
+            let line = cite::citation(
+                &path.to_string_lossy(),
+                &emitted,
+                cite::git_info(path).as_ref(),
+                cite_opts.url.as_deref(),
+            );
+            write_block(writer, &line)?;
         }
+        // Spacer between files
+        if !opts.no_header {
+            write_block(writer, "\n")?;
+        }
+        report.sent.push(stat);
+    }
+    if bundle && !opts.no_header && total > 1 {
+        let footer = build_footer(opts.footer_style, &report.sent, &report.skipped);
+        write_block(writer, &footer)?;
+    }
+    Ok(report)
+}
+pub const FENCE_CLOSE: &str = "```\n";
+/// SGR / erase-line sequences removed by the sanitizer.
+pub fn ansi_regex() -> Regex {
+    Regex::new(r"\x1B\[([0-9]{1,2}(;[0-9]{1,2})*)?[m|K]").unwrap()
+}
+/// Control characters the default sanitizer strips from a line (everything in
+/// Cc except Tab; combining marks and joiners are not Cc and are kept).
+/// --allow-control / --deny-control adjust this through `ControlSet`.
+pub fn is_stripped_control(c: char) -> bool {
+    c.is_control() && c != '\t'
+}
+/// A file argument of `-`: read stdin at that point of the bundle.
+pub fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+/// `-` may stand among the files once, and only when something is piped in.
+/// Empty stdin is an empty section, not an error: the files still count.
+pub fn check_stdin_placeholder(files: &[PathBuf]) -> Result<()> {
+    match files.iter().filter(|f| is_stdin(f)).count() {
+        0 => Ok(()),
+        1 => match input::input_source() {
+            input::InputSource::Terminal => {
+                anyhow::bail!("`-` reads stdin, but stdin is a terminal; pipe something in")
+            }
+            input::InputSource::Closed => anyhow::bail!("`-` reads stdin, but stdin is closed"),
+            _ => Ok(()),
+        },
+        n => anyhow::bail!("`-` (stdin) can be given only once, not {} times", n),
+    }
+}
+/// A FIFO or character device (`<(git diff)`, `/dev/stdin`): it can be
+/// read only once, so it is streamed as it comes, never sniffed first.
+pub fn is_stream(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| {
+        let kind = m.file_type();
+        kind.is_fifo() || kind.is_char_device()
+    })
+}
+/// Why a requested input is not streamed, or None if it is a readable file
+/// (with content, unless `keep_empty`) or a stream.
+pub fn skip_reason(path: &Path, opts: &TextOptions) -> Option<&'static str> {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return Some("not found");
+    };
+    let kind = meta.file_type();
+    if kind.is_fifo() || kind.is_char_device() {
+        // A pipe reports no size; whatever it produces is the content
+        None
+    } else if kind.is_socket() {
+        Some("socket")
+    } else if !kind.is_file() {
+        Some("not a regular file")
+    } else if !opts.keep_empty && meta.len() == 0 {
+        Some("empty")
     } else {
-        // Stdin Mode
-        log.debug("Reading from Stdin (Streaming)");
-        if atty::is(atty::Stream::Stdin) {
-            anyhow::bail!("No input provided. Pipe data or specify files.");
+        None
+    }
+}
+/// Files are emitted in the order given, each once (overlapping globs
+/// repeat paths, and `b.txt`, `./b.txt` and `$PWD/b.txt` are one file; the
+/// first spelling is the one shown). `--sort` reorders them. `--deterministic` sorts by name
+/// unless `--sort` says otherwise, and by name it means the displayed path,
+/// so the order matches what a reader sees in the headers. Ties keep the
+/// given order.
+pub fn order_files(files: &mut Vec<PathBuf>, opts: &TextOptions) {
+    let log = create_logger("text_processor");
+    let mut seen = std::collections::HashSet::new();
+    files.retain(|p| {
+        let first = seen.insert(dedup_key(p));
+        if !first {
+            log.debug(&format!("Dropped repeated path: {:?}", p));
+        }
+        first
+    });
+    let meta = |p: &PathBuf| std::fs::metadata(p).ok();
+    let sort = opts.sort.or(opts.deterministic.then_some(FileOrder::Name));
+    match sort {
+        None => {}
+        Some(FileOrder::Name) if opts.deterministic => {
+            files.sort_by_cached_key(|p| p.to_string_lossy().into_owned())
         }
-        let stdin = io::stdin();
-        let reader = stdin.lock();
-        for line_res in reader.lines() {
-            let line = line_res.context("Failed to read line from stdin")?;
-            write_line(writer, &line)?;
+        Some(FileOrder::Name) => files.sort(),
+        Some(FileOrder::Mtime) => {
+            files.sort_by_cached_key(|p| meta(p).and_then(|m| m.modified().ok()))
         }
+        Some(FileOrder::Size) => files.sort_by_cached_key(|p| meta(p).map(|m| m.len())),
     }
-    Ok(())
+}
+/// What makes two requested paths the same file: the resolved path when it
+/// exists, else the lexically cleaned absolute one.
+fn dedup_key(path: &Path) -> PathBuf {
+    dunce::canonicalize(path)
+        .or_else(|_| paths::lexical_absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+/// The state a `--snapshot` header shows. `--deterministic` drops the mtime,
+/// which changes whenever a file is recreated.
+pub fn header_state(state: &FileState, opts: &TextOptions) -> Option<FileState> {
+    opts.snapshot.then(|| FileState {
+        size: state.size,
+        mtime: state.mtime.filter(|_| !opts.deterministic),
+    })
+}
+/// Per-file header line (LF-terminated; callers convert for CRLF).
+pub fn file_header(path: &Path, timestamp: Option<&str>, snapshot: Option<&FileState>) -> String {
+    let mut header = match timestamp {
+        Some(ts) => format!("# FILE: {} READ: {}", path.display(), ts),
+        None => format!("# FILE: {}", path.display()),
+    };
+    if let Some(state) = snapshot {
+        header.push_str(&format!(" SNAPSHOT: {} bytes", state.size));
+        if state.mtime.is_some() {
+            header.push_str(&format!(", mtime {}", state.mtime_display()));
+        }
+    }
+    header.push('\n');
+    header
+}
+/// Header for the stdin section of a bundle (`-` among the files).
+pub fn stdin_header(timestamp: Option<&str>) -> String {
+    match timestamp {
+        Some(ts) => format!("# STDIN READ: {}\n", ts),
+        None => "# STDIN\n".to_string(),
+    }
+}
+/// Opening Markdown fence, tagged with the file extension.
+pub fn fence_open(path: &Path) -> String {
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    format!("```{}\n", ext)
+}
+/// Renders the end-of-files footer. Every detailed line carries a fixed
+/// `# ` prefix and keyword so the block stays parseable.
+pub fn build_footer(style: FooterStyle, sent: &[SentFile], skipped: &[SkippedFile]) -> String {
+    if style == FooterStyle::Compact {
+        let names: Vec<&str> = sent.iter().map(|f| f.display.as_str()).collect();
+        return format!("# End of FILES. SENT: {}\n", names.join(" "));
+    }
+    let mut out = String::from("# End of FILES.\n");
+    for f in sent {
+        out.push_str(&format!(
+            "# SENT: {} ({} lines, {} bytes)\n",
+            f.display, f.lines, f.bytes
+        ));
+    }
+    let lines: u64 = sent.iter().map(|f| f.lines).sum();
+    let bytes: u64 = sent.iter().map(|f| f.bytes).sum();
+    out.push_str(&format!(
+        "# TOTAL: {} files, {} lines, {} bytes\n",
+        sent.len(),
+        lines,
+        bytes
+    ));
+    for f in skipped {
+        out.push_str(&format!("# SKIPPED: {} ({})\n", f.display, f.reason));
+    }
+    out
 }
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Write;
+    use std::io::{self, Write};
     use tempfile::NamedTempFile;
     #[test]
     fn test_process_streaming() -> Result<()> {
@@ -156,6 +594,7 @@ mod tests {
             strip_ansi: false,
             use_markdown: false,
             use_crlf: false,
+            ..Default::default()
         };
         let mut buffer = Vec::new();
         process_input(Some(vec![path1]), &opts, &mut buffer)?;
@@ -165,6 +604,43 @@ mod tests {
         Ok(())
     }
     #[test]
+    fn test_utf16_files_are_decoded() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(&[0xFF, 0xFE])?;
+        for unit in "Grüße\r\nline 2\r\n".encode_utf16() {
+            file.write_all(&unit.to_le_bytes())?;
+        }
+        let opts = TextOptions {
+            no_header: true,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        let report = process_input(Some(vec![file.path().to_path_buf()]), &opts, &mut buffer)?;
+        assert_eq!(String::from_utf8(buffer)?, "Grüße\nline 2\n");
+        assert!(report.warnings.is_empty());
+        // Broken bytes are replaced, with a warning, instead of failing the copy
+        let mut buffer = Vec::new();
+        let report = process_bytes(b"caf\xE9\n", &opts, &mut buffer)?;
+        assert_eq!(String::from_utf8(buffer)?, "caf\u{FFFD}\n");
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].contains("not entirely valid UTF-8"));
+        Ok(())
+    }
+    #[test]
+    fn test_process_bytes_like_stdin() -> Result<()> {
+        let opts = TextOptions {
+            no_header: false,
+            strip_ansi: true,
+            use_crlf: true,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        process_bytes(b"\x1b[1mtitle\x1b[0m\nbody\n", &opts, &mut buffer)?;
+        // No file, so no `# FILE:` header
+        assert_eq!(String::from_utf8(buffer)?, "title\r\nbody\r\n");
+        Ok(())
+    }
+    #[test]
     fn test_safe_text_sanitization() -> Result<()> {
         let mut file = NamedTempFile::new()?;
         // Contains: ANSI color, Backspace (\x08), Bell (\x07), Tab (\t), and Text
@@ -175,6 +651,7 @@ mod tests {
             strip_ansi: true, // Should enable sanitization
             use_markdown: false,
             use_crlf: false,
+            ..Default::default()
         };
         let mut buffer = Vec::new();
         process_input(Some(vec![path]), &opts, &mut buffer)?;
@@ -188,7 +665,306 @@ mod tests {
         assert_eq!(output, "RedGood\tText\n");
         Ok(())
     }
+    #[test]
+    fn test_sanitizer_keeps_combining_and_joiners() -> Result<()> {
+        // Combining marks (Mn) and ZWJ (Cf) are not control characters and must
+        // survive stripping; removing them would tear graphemes apart
+        let text = "cafe\u{301} 👨\u{200d}👩\u{200d}👧 🇯🇵 한국어\x07";
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "{}", text)?;
+        let opts = TextOptions {
+            no_header: true,
+            strip_ansi: true,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        process_input(Some(vec![file.path().to_path_buf()]), &opts, &mut buffer)?;
+        assert_eq!(
+            String::from_utf8(buffer)?,
+            "cafe\u{301} 👨\u{200d}👩\u{200d}👧 🇯🇵 한국어\n"
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_footer_accounting() -> Result<()> {
+        let mut a = NamedTempFile::new()?;
+        write!(a, "one\ntwo\n")?;
+        let mut b = NamedTempFile::new()?;
+        write!(b, "three")?;
+        let missing = PathBuf::from("/nonexistent/wsl-clip-missing.txt");
+        let dir = tempfile::tempdir()?;
+        let mut files = vec![
+            a.path().to_path_buf(),
+            b.path().to_path_buf(),
+            missing.clone(),
+            dir.path().to_path_buf(),
+        ];
+        files.sort();
+        let opts = TextOptions {
+            use_crlf: true,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        process_input(Some(files), &opts, &mut buffer)?;
+        let output = String::from_utf8(buffer)?;
+        let footer: Vec<&str> = output
+            .split("\r\n")
+            .skip_while(|l| *l != "# End of FILES.")
+            .collect();
+        assert!(footer
+            .contains(&format!("# SENT: {} (2 lines, 10 bytes)", a.path().display()).as_str()));
+        assert!(
+            footer.contains(&format!("# SENT: {} (1 lines, 7 bytes)", b.path().display()).as_str())
+        );
+        assert!(footer.contains(&"# TOTAL: 2 files, 3 lines, 17 bytes"));
+        assert!(footer.contains(&format!("# SKIPPED: {} (not found)", missing.display()).as_str()));
+        assert!(footer.contains(
+            &format!("# SKIPPED: {} (not a regular file)", dir.path().display()).as_str()
+        ));
+        assert!(!output.replace("\r\n", "").contains('\n'));
+        Ok(())
+    }
+    #[test]
+    fn test_empty_files_skipped_unless_kept() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let keep = dir.path().join(".gitkeep");
+        std::fs::write(&keep, "")?;
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "hello\n")?;
+        let run = |keep_empty: bool| -> Result<(String, ProcessReport)> {
+            let opts = TextOptions {
+                keep_empty,
+                ..Default::default()
+            };
+            let mut buffer = Vec::new();
+            let report =
+                process_input(Some(vec![keep.clone(), notes.clone()]), &opts, &mut buffer)?;
+            Ok((String::from_utf8(buffer)?, report))
+        };
+        let (output, report) = run(false)?;
+        assert_eq!(report.sent.len(), 1);
+        assert!(!output.contains(&format!("# FILE: {}", keep.display())));
+        assert!(output.contains("# TOTAL: 1 files, 1 lines, 6 bytes"));
+        assert!(output.contains(&format!("# SKIPPED: {} (empty)", keep.display())));
+        let (output, report) = run(true)?;
+        assert_eq!(report.sent.len(), 2);
+        assert!(output.contains(&format!("# SENT: {} (0 lines, 0 bytes)", keep.display())));
+        assert!(output.contains("# TOTAL: 2 files, 1 lines, 6 bytes"));
+        Ok(())
+    }
+    #[test]
+    fn test_fifos_are_streamed_sockets_skipped() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let fifo = dir.path().join("diff");
+        let made = std::process::Command::new("mkfifo").arg(&fifo).status()?;
+        assert!(made.success());
+        let socket = dir.path().join("agent.sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket)?;
+        assert!(is_stream(&fifo) && !is_stream(&socket));
+        // Opening the write end blocks until the copy opens the read end
+        let writer = {
+            let fifo = fifo.clone();
+            std::thread::spawn(move || std::fs::write(fifo, "-old\n+new\n"))
+        };
+        let opts = TextOptions::default();
+        let mut buffer = Vec::new();
+        let report = process_input(Some(vec![fifo.clone(), socket.clone()]), &opts, &mut buffer)?;
+        writer.join().unwrap()?;
+        let output = String::from_utf8(buffer)?;
+        assert!(output.starts_with(&format!("# FILE: {} READ: ", fifo.display())));
+        assert!(output.contains("\n-old\n+new\n\n"));
+        assert_eq!(report.sent[0].lines, 2);
+        assert!(output.contains(&format!("# SKIPPED: {} (socket)", socket.display())));
+        assert_eq!(
+            report.warnings,
+            [format!("Skipped {} (socket)", socket.display())]
+        );
+        Ok(())
+    }
+    /// Writer that appends to `target` from another thread on the first write,
+    /// simulating a log writer racing the copy.
+    struct RacingWriter {
+        target: PathBuf,
+        fired: bool,
+        out: Vec<u8>,
+    }
+    impl Write for RacingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if !self.fired {
+                self.fired = true;
+                let target = self.target.clone();
+                std::thread::spawn(move || {
+                    let mut f = std::fs::OpenOptions::new().append(true).open(target)?;
+                    f.write_all(b"appended by writer\n")
+                })
+                .join()
+                .unwrap()?;
+            }
+            self.out.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    #[test]
+    fn test_concurrent_modification() -> Result<()> {
+        let original = "line 1\nline 2\n";
+        // Streaming: the change is detected and reported
+        let file = NamedTempFile::new()?;
+        std::fs::write(file.path(), original)?;
+        let mut writer = RacingWriter {
+            target: file.path().to_path_buf(),
+            fired: false,
+            out: Vec::new(),
+        };
+        let report = process_input(
+            Some(vec![file.path().to_path_buf()]),
+            &TextOptions::default(),
+            &mut writer,
+        )?;
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].contains("File changed during copy"));
+        // Snapshot: content and recorded size reflect the pre-write state
+        let file = NamedTempFile::new()?;
+        std::fs::write(file.path(), original)?;
+        let mut writer = RacingWriter {
+            target: file.path().to_path_buf(),
+            fired: false,
+            out: Vec::new(),
+        };
+        let opts = TextOptions {
+            snapshot: true,
+            ..Default::default()
+        };
+        let report = process_input(Some(vec![file.path().to_path_buf()]), &opts, &mut writer)?;
+        assert!(report.warnings.is_empty());
+        let output = String::from_utf8(writer.out)?;
+        assert!(output.contains(&format!("SNAPSHOT: {} bytes", original.len())));
+        assert!(output.ends_with(&format!("\n{}\n", original)));
+        assert_eq!(report.sent[0].bytes, original.len() as u64);
+        Ok(())
+    }
+    #[test]
+    fn test_single_line_and_paste_safe() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        write!(file, "cd /tmp\n\nls -la\n")?;
+        let path = file.path().to_path_buf();
+        let opts = TextOptions {
+            single_line: Some(TargetShell::Bash.default_separator().to_string()),
+            use_crlf: true,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        process_input(Some(vec![path.clone()]), &opts, &mut buffer)?;
+        assert_eq!(String::from_utf8(buffer)?, "cd /tmp && ls -la");
+        let opts = TextOptions {
+            no_header: true,
+            paste_safe: true,
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        let report = process_input(Some(vec![path]), &opts, &mut buffer)?;
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].contains("3 lines"));
+        Ok(())
+    }
+    #[test]
+    fn test_jsonl_pretty_with_grep() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, r#"{{"request_id":"abc","msg":"start"}}"#)?;
+        writeln!(file, "plain line request_id=abc")?;
+        writeln!(file, r#"{{"request_id":"zzz","msg":"other"}}"#)?;
+        writeln!(file, r#"{{"request_id":"abc", broken"#)?;
+        let path = file.path().to_path_buf();
+        let opts = TextOptions {
+            no_header: true,
+            jsonl_pretty: true,
+            grep: Some(Regex::new("request_id.*abc")?),
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        let report = process_input(Some(vec![path.clone()]), &opts, &mut buffer)?;
+        // The grep sees the one-line record; "abc" is on the same line as the
+        // key only before expansion. Invalid JSON passes through untouched.
+        assert_eq!(
+            String::from_utf8(buffer)?,
+            "{\n  \"request_id\": \"abc\",\n  \"msg\": \"start\"\n}\n---\n\
+             plain line request_id=abc\n\
+             {\"request_id\":\"abc\", broken\n"
+        );
+        assert_eq!(report.sent[0].lines, 7);
+        Ok(())
+    }
+    #[test]
+    fn test_footer_compact() {
+        let sent = vec![
+            SentFile {
+                display: "a.rs".to_string(),
+                lines: 1,
+                bytes: 2,
+            },
+            SentFile {
+                display: "b.rs".to_string(),
+                lines: 3,
+                bytes: 4,
+            },
+        ];
+        let skipped = vec![SkippedFile {
+            display: "c.rs".to_string(),
+            reason: "not found".to_string(),
+        }];
+        assert_eq!(
+            build_footer(FooterStyle::Compact, &sent, &skipped),
+            "# End of FILES. SENT: a.rs b.rs\n"
+        );
+    }
+    #[test]
+    fn test_header_timestamp_modes() -> Result<()> {
+        use crate::timestamp::TimestampMode;
+        use chrono::{FixedOffset, TimeZone, Utc};
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "x")?;
+        let path = file.path().to_path_buf();
+        let now = Utc.with_ymd_and_hms(2025, 11, 25, 2, 0, 0).unwrap();
+        let zone = FixedOffset::east_opt(3600).unwrap();
+        let render = |mode, format: Option<&str>| -> Result<String> {
+            let opts = TextOptions {
+                timestamp: Timestamp::with_clock(mode, format.map(String::from), now, zone),
+                ..Default::default()
+            };
+            let mut buffer = Vec::new();
+            process_input(Some(vec![path.clone()]), &opts, &mut buffer)?;
+            Ok(String::from_utf8(buffer)?
+                .lines()
+                .next()
+                .unwrap()
+                .to_string())
+        };
+        let shown = path.display();
+        assert_eq!(
+            render(TimestampMode::Utc, None)?,
+            format!("# FILE: {} READ: 2025-11-25T02:00:00Z", shown)
+        );
+        assert_eq!(
+            render(TimestampMode::Local, None)?,
+            format!("# FILE: {} READ: 2025-11-25T03:00:00+01:00", shown)
+        );
+        assert_eq!(
+            render(TimestampMode::Epoch, None)?,
+            format!("# FILE: {} READ: 1764036000", shown)
+        );
+        assert_eq!(
+            render(TimestampMode::None, None)?,
+            format!("# FILE: {}", shown)
+        );
+        assert_eq!(
+            render(TimestampMode::Local, Some("%d.%m.%Y %H:%M"))?,
+            format!("# FILE: {} READ: 25.11.2025 03:00", shown)
+        );
+        Ok(())
+    }
 }
 
-// <FILE>src/text_processor.rs</FILE> - <DESC>Streaming text processor with security sanitization</DESC>
-// <VERS>END OF VERSION: 2.2.0 - 2025-11-25T17:17:02Z</VERS>
+// <FILE>src/text_processor.rs</FILE> - <DESC>Text streaming pipeline</DESC>
+// <VERS>END OF VERSION: 2.25.0 - 2026-10-16T14:44:15Z</VERS>
@@ -0,0 +1,205 @@
+// <FILE>src/timestamp.rs</FILE> - <DESC>Header timestamp rendering</DESC>
+// <VERS>VERSION: 1.3.0 - 2026-10-16T14:42:52Z</VERS>
+// <WCTX>Reproducible bundles.</WCTX>
+// <CLOG>Validate --timestamp-format by trial render; render without panicking.</CLOG>
+
+use anyhow::Result;
+use chrono::format::{DelayedFormat, Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use std::fmt::Write;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampMode {
+    Utc,
+    Local,
+    None,
+    Epoch,
+}
+const UTC_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+const LOCAL_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+/// Renders the READ timestamp used by headers and footers.
+/// The clock and zone are captured once so every header in a run agrees.
+#[derive(Debug, Clone)]
+pub struct Timestamp {
+    mode: TimestampMode,
+    format: Option<String>,
+    now: DateTime<Utc>,
+    offset: FixedOffset,
+}
+impl Default for Timestamp {
+    fn default() -> Self {
+        Self::now(TimestampMode::Utc, None)
+    }
+}
+impl Timestamp {
+    /// Captures the current time and the local zone offset.
+    pub fn now(mode: TimestampMode, format: Option<String>) -> Self {
+        let offset = *Local::now().offset();
+        Self::with_clock(mode, format, Utc::now(), offset)
+    }
+    /// Injected clock/zone (used by tests and deterministic callers).
+    pub fn with_clock(
+        mode: TimestampMode,
+        format: Option<String>,
+        now: DateTime<Utc>,
+        offset: FixedOffset,
+    ) -> Self {
+        Timestamp {
+            mode,
+            format,
+            now,
+            offset,
+        }
+    }
+    /// A clock stopped at `at`; `local` renders in `at`'s own offset.
+    pub fn at(mode: TimestampMode, format: Option<String>, at: DateTime<FixedOffset>) -> Self {
+        Self::with_clock(mode, format, at.with_timezone(&Utc), *at.offset())
+    }
+    pub fn mode(&self) -> TimestampMode {
+        self.mode
+    }
+    /// Returns None when timestamps are disabled (`--timestamp none`).
+    pub fn render(&self) -> Option<String> {
+        match self.mode {
+            TimestampMode::None => None,
+            TimestampMode::Epoch => Some(self.now.timestamp().to_string()),
+            TimestampMode::Utc => {
+                let custom = self
+                    .format
+                    .as_deref()
+                    .and_then(|f| try_render(self.now.format(f)));
+                custom.or_else(|| try_render(self.now.format(UTC_FORMAT)))
+            }
+            TimestampMode::Local => {
+                let local = self.now.with_timezone(&self.offset);
+                let custom = self
+                    .format
+                    .as_deref()
+                    .and_then(|f| try_render(local.format(f)));
+                custom.or_else(|| try_render(local.format(LOCAL_FORMAT)))
+            }
+        }
+    }
+}
+/// Renders without panicking: chrono's `to_string()` panics on specifiers it
+/// parses but cannot render (e.g. `%#z`). A failed custom format falls back
+/// to the default one.
+fn try_render<'a, I>(formatted: DelayedFormat<I>) -> Option<String>
+where
+    I: Iterator<Item = Item<'a>> + Clone,
+{
+    let mut out = String::new();
+    write!(out, "{}", formatted).ok()?;
+    Some(out)
+}
+/// Rejects strftime strings chrono cannot parse (e.g. `%Q`) or cannot render
+/// (e.g. `%#z`), before any output is produced.
+pub fn validate_format(fmt: &str) -> Result<()> {
+    if StrftimeItems::new(fmt).any(|item| matches!(item, Item::Error))
+        || try_render(Utc::now().fixed_offset().format(fmt)).is_none()
+    {
+        anyhow::bail!(
+            "Invalid --timestamp-format '{}': unsupported strftime specifier",
+            fmt
+        );
+    }
+    Ok(())
+}
+/// Clap value parser for `--timestamp-value`.
+pub fn parse_rfc3339(raw: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(raw)
+        .map_err(|e| format!("expected RFC 3339 such as 2024-01-01T00:00:00Z ({})", e))
+}
+/// The instant headers are pinned to: `--timestamp-value`, else (under
+/// `--deterministic`) SOURCE_DATE_EPOCH, else the Unix epoch. None means
+/// the real clock.
+pub fn pinned(
+    value: Option<DateTime<FixedOffset>>,
+    deterministic: bool,
+    source_date_epoch: Option<&str>,
+) -> Result<Option<DateTime<FixedOffset>>> {
+    if value.is_some() || !deterministic {
+        return Ok(value);
+    }
+    let secs = match source_date_epoch.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(raw) => raw.parse::<i64>().map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid SOURCE_DATE_EPOCH '{}': expected whole seconds since 1970",
+                raw
+            )
+        })?,
+        None => 0,
+    };
+    let at = DateTime::from_timestamp(secs, 0)
+        .ok_or_else(|| anyhow::anyhow!("SOURCE_DATE_EPOCH {} is out of range", secs))?;
+    Ok(Some(at.fixed_offset()))
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    fn fixed(mode: TimestampMode, format: Option<&str>) -> Timestamp {
+        let now = Utc.with_ymd_and_hms(2025, 11, 25, 2, 0, 0).unwrap();
+        let zone = FixedOffset::west_opt(5 * 3600).unwrap();
+        Timestamp::with_clock(mode, format.map(String::from), now, zone)
+    }
+    #[test]
+    fn test_modes() {
+        assert_eq!(
+            fixed(TimestampMode::Utc, None).render().unwrap(),
+            "2025-11-25T02:00:00Z"
+        );
+        assert_eq!(
+            fixed(TimestampMode::Local, None).render().unwrap(),
+            "2025-11-24T21:00:00-05:00"
+        );
+        assert_eq!(
+            fixed(TimestampMode::Epoch, None).render().unwrap(),
+            "1764036000"
+        );
+        assert_eq!(fixed(TimestampMode::None, None).render(), None);
+    }
+    #[test]
+    fn test_custom_format() {
+        assert_eq!(
+            fixed(TimestampMode::Local, Some("%H:%M on %d/%m"))
+                .render()
+                .unwrap(),
+            "21:00 on 24/11"
+        );
+        assert_eq!(
+            fixed(TimestampMode::Utc, Some("%Y%m%d")).render().unwrap(),
+            "20251125"
+        );
+    }
+    #[test]
+    fn test_pinned_precedence() -> Result<()> {
+        let value = parse_rfc3339("2024-02-03T04:05:06+02:00").unwrap();
+        // An explicit value wins, with or without --deterministic
+        assert_eq!(pinned(Some(value), false, Some("1"))?, Some(value));
+        assert_eq!(pinned(Some(value), true, Some("1"))?, Some(value));
+        assert_eq!(pinned(None, false, Some("1"))?, None);
+        let sde = pinned(None, true, Some("1700000000"))?.unwrap();
+        assert_eq!(sde.to_rfc3339(), "2023-11-14T22:13:20+00:00");
+        assert_eq!(pinned(None, true, None)?.unwrap().timestamp(), 0);
+        assert!(pinned(None, true, Some("yesterday")).is_err());
+        assert!(parse_rfc3339("2024-02-03").is_err());
+        let ts = Timestamp::at(TimestampMode::Local, None, value);
+        assert_eq!(ts.render().unwrap(), "2024-02-03T04:05:06+02:00");
+        Ok(())
+    }
+    #[test]
+    fn test_validate_format() {
+        assert!(validate_format("%Y-%m-%d %H:%M").is_ok());
+        assert!(validate_format("%Q").is_err());
+        // Parses cleanly but fails at render time
+        assert!(validate_format("%#z").is_err());
+        assert_eq!(
+            fixed(TimestampMode::Utc, Some("%#z")).render().unwrap(),
+            "2025-11-25T02:00:00Z"
+        );
+    }
+}
+
+// <FILE>src/timestamp.rs</FILE> - <DESC>Header timestamp rendering</DESC>
+// <VERS>END OF VERSION: 1.3.0 - 2026-10-16T14:42:52Z</VERS>
@@ -0,0 +1,530 @@
+// <FILE>src/transcode.rs</FILE> - <DESC>Image formats GDI+ cannot read, converted to PNG</DESC>
+// <VERS>VERSION: 1.3.0 - 2026-10-16T12:27:51Z</VERS>
+// <WCTX>img renders SVG input</WCTX>
+// <CLOG>Render SVG files to pixels in img</CLOG>
+
+//! System.Drawing (GDI+) reads BMP, GIF, JPEG, PNG, TIFF and ICO. Other
+//! images are decoded here and handed to the image script as a PNG, and
+//! `img --max-dim` / `--scale` downscale here too, and `img` renders SVGs.
+use crate::staging::{StagedFile, StagingRoot};
+use anyhow::{Context, Result};
+use image::codecs::bmp::BmpEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+/// An image codec GDI+ lacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Webp,
+    Avif,
+    Heic,
+}
+impl Codec {
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Webp => "WebP",
+            Codec::Avif => "AVIF",
+            Codec::Heic => "HEIC",
+        }
+    }
+}
+/// The codec of `path` when it is an image GDI+ cannot read, by magic bytes.
+pub fn foreign_codec(path: &Path) -> Result<Option<Codec>> {
+    let mut head = Vec::new();
+    File::open(path)
+        .with_context(|| format!("Failed to open image: {:?}", path))?
+        .take(262)
+        .read_to_end(&mut head)
+        .with_context(|| format!("Failed to read image: {:?}", path))?;
+    Ok(match infer::get(&head).map(|kind| kind.mime_type()) {
+        Some("image/webp") => Some(Codec::Webp),
+        Some("image/avif") => Some(Codec::Avif),
+        Some("image/heif") => Some(Codec::Heic),
+        _ => None,
+    })
+}
+/// Decodes `path` and writes it as a PNG staged in `root`, alpha included.
+/// The source is only read. The PNG is removed when the returned file drops.
+pub fn to_png(path: &Path, codec: Codec, root: &StagingRoot) -> Result<StagedFile> {
+    let pixels = decode(path, codec)
+        .with_context(|| format!("Could not decode {} as {}", path.display(), codec.name()))?;
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let mut staged = StagedFile::create_in(root, &stem, "png")?;
+    pixels
+        .write_with_encoder(PngEncoder::new(&mut staged))
+        .with_context(|| format!("Failed to write PNG for {}", path.display()))?;
+    staged.flush()?;
+    Ok(staged)
+}
+fn decode(path: &Path, codec: Codec) -> Result<DynamicImage> {
+    match codec {
+        Codec::Webp => Ok(image::ImageReader::open(path)?
+            .with_guessed_format()?
+            .decode()?),
+        Codec::Avif | Codec::Heic => decode_heif(path, codec),
+    }
+}
+/// How far `img` shrinks a picture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resize {
+    /// `--max-dim`: neither side larger than this many pixels
+    MaxDim(u32),
+    /// `--scale`: this percentage of each side
+    Scale(u32),
+}
+impl Resize {
+    /// The size a `width` x `height` picture becomes, keeping its aspect
+    /// ratio, or None when it already fits. Sides never round to zero.
+    pub fn target(self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let factor = match self {
+            Resize::MaxDim(max) if width.max(height) > max => {
+                f64::from(max) / f64::from(width.max(height))
+            }
+            Resize::Scale(percent) if percent < 100 => f64::from(percent) / 100.0,
+            _ => return None,
+        };
+        let side = |n: u32| ((f64::from(n) * factor).round() as u32).max(1);
+        Some((side(width), side(height)))
+    }
+}
+/// A picture as `img --max-dim` / `--scale` left it.
+pub struct Resized {
+    /// The downscaled copy; None when the original already fit
+    pub file: Option<StagedFile>,
+    /// Width and height as displayed (after EXIF rotation)
+    pub from: (u32, u32),
+    pub to: (u32, u32),
+}
+/// `--jpeg-quality` default: visually lossless for photos, about half the
+/// size of quality 100.
+pub const DEFAULT_JPEG_QUALITY: u8 = 92;
+/// Decodes `path`, turns it upright by its EXIF orientation, and, if it
+/// exceeds `resize`, writes a downscaled copy staged in `root`. JPEG and BMP
+/// stay JPEG (at `quality`) and BMP; everything else becomes PNG, which
+/// keeps alpha. GIFs keep only their first frame. No metadata is written.
+pub fn resize(path: &Path, resize: Resize, quality: u8, root: &StagingRoot) -> Result<Resized> {
+    let (picture, format) = open_upright(path)?;
+    let from = (picture.width(), picture.height());
+    let Some(to) = resize.target(from.0, from.1) else {
+        return Ok(Resized {
+            file: None,
+            from,
+            to: from,
+        });
+    };
+    let small = picture.resize_exact(to.0, to.1, FilterType::Lanczos3);
+    let ext = match format {
+        Some(ImageFormat::Jpeg) => "jpg",
+        Some(ImageFormat::Bmp) => "bmp",
+        _ => "png",
+    };
+    let stem = format!("{}-{}x{}", stem(path), to.0, to.1);
+    let staged = write(&small, &stem, ext, quality, root)
+        .with_context(|| format!("Failed to write the resized {}", path.display()))?;
+    Ok(Resized {
+        file: Some(staged),
+        from,
+        to,
+    })
+}
+/// `img` width for an SVG given neither `--width` nor `--dpi`.
+pub const DEFAULT_SVG_WIDTH: u32 = 1024;
+/// Largest side an SVG is rendered at, whatever its own size or `--dpi`.
+const MAX_SVG_SIDE: u32 = 16 * 1024;
+/// How large `img` renders an SVG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvgSize {
+    /// `--width`: this many pixels wide, height to match
+    Width(u32),
+    /// `--dpi`: SVG units are 1/96 inch, so 96 is the drawing's own size
+    Dpi(u32),
+}
+/// Renders the SVG at `path` to a PNG staged in `root`, transparent where
+/// the drawing is. Text uses the system fonts; a missing font falls back
+/// to another, and with no fonts at all text is left out. Returns the file
+/// and its pixel size.
+pub fn render_svg(
+    path: &Path,
+    size: SvgSize,
+    root: &StagingRoot,
+) -> Result<(StagedFile, (u32, u32))> {
+    use resvg::{tiny_skia, usvg};
+    let data = std::fs::read(path).with_context(|| format!("Cannot read {:?}", path))?;
+    let mut options = usvg::Options {
+        // Relative <image href> paths resolve next to the SVG
+        resources_dir: dunce::canonicalize(path)
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf)),
+        ..usvg::Options::default()
+    };
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_data(&data, &options)
+        .map_err(|e| anyhow::anyhow!("{} is not a valid SVG: {}", path.display(), e))?;
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let factor = match size {
+        SvgSize::Width(w) => w as f32 / width,
+        SvgSize::Dpi(dpi) => dpi as f32 / 96.0,
+    };
+    let side = |n: f32| (n * factor).round().max(1.0) as u32;
+    let (w, h) = (side(width), side(height));
+    if w.max(h) > MAX_SVG_SIDE {
+        anyhow::bail!(
+            "{} would render at {}x{}; the limit is {} pixels a side (lower --width or --dpi)",
+            path.display(),
+            w,
+            h,
+            MAX_SVG_SIDE
+        );
+    }
+    let mut pixmap = tiny_skia::Pixmap::new(w, h).context("Failed to allocate the SVG canvas")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(factor, factor),
+        &mut pixmap.as_mut(),
+    );
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    let picture = image::RgbaImage::from_raw(w, h, rgba).context("SVG canvas size mismatch")?;
+    let staged = write(
+        &DynamicImage::ImageRgba8(picture),
+        &stem(path),
+        "png",
+        DEFAULT_JPEG_QUALITY,
+        root,
+    )
+    .with_context(|| format!("Failed to write the rendered {}", path.display()))?;
+    Ok((staged, (w, h)))
+}
+/// What `--strip-metadata` made of an image.
+pub enum Stripped {
+    /// A re-encoded copy without metadata
+    Rewritten(StagedFile),
+    /// BMP: the format has nowhere to keep metadata
+    NothingToStrip,
+    /// A format this cannot rewrite without losing something (GIF
+    /// animation, TIFF pages); the name is for the warning
+    Unsupported(&'static str),
+}
+/// Re-encodes `path` into `root` with no EXIF, XMP, ICC or text chunks,
+/// turned upright first since its EXIF orientation goes too. PNG stays
+/// lossless; JPEG is recompressed at `quality`; WebP, AVIF and HEIC become
+/// PNG.
+pub fn strip_metadata(path: &Path, quality: u8, root: &StagingRoot) -> Result<Stripped> {
+    let ext = match foreign_codec(path)? {
+        Some(_) => "png",
+        None => {
+            let format = image::ImageReader::open(path)
+                .with_context(|| format!("Failed to open image: {:?}", path))?
+                .with_guessed_format()?
+                .format();
+            match format {
+                Some(ImageFormat::Jpeg) => "jpg",
+                Some(ImageFormat::Png) => "png",
+                Some(ImageFormat::Bmp) => return Ok(Stripped::NothingToStrip),
+                Some(other) => return Ok(Stripped::Unsupported(other.extensions_str()[0])),
+                None => return Ok(Stripped::Unsupported("unrecognized")),
+            }
+        }
+    };
+    let (picture, _) = open_upright(path)?;
+    let staged = write(&picture, &stem(path), ext, quality, root)
+        .with_context(|| format!("Failed to rewrite {}", path.display()))?;
+    Ok(Stripped::Rewritten(staged))
+}
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+/// `picture` as a staged `<stem>.<ext>` ("jpg", "bmp" or "png"), with the
+/// pixels only.
+fn write(
+    picture: &DynamicImage,
+    stem: &str,
+    ext: &str,
+    quality: u8,
+    root: &StagingRoot,
+) -> Result<StagedFile> {
+    let mut staged = StagedFile::create_in(root, stem, ext)?;
+    match ext {
+        "jpg" => DynamicImage::ImageRgb8(picture.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut staged, quality))?,
+        "bmp" => DynamicImage::ImageRgba8(picture.to_rgba8())
+            .write_with_encoder(BmpEncoder::new(&mut staged))?,
+        _ => picture.write_with_encoder(PngEncoder::new(&mut staged))?,
+    }
+    staged.flush()?;
+    Ok(staged)
+}
+/// The picture with its EXIF orientation applied, and its format when the
+/// `image` crate read it (libheif applies HEIC/AVIF rotation itself).
+fn open_upright(path: &Path) -> Result<(DynamicImage, Option<ImageFormat>)> {
+    if let Some(codec @ (Codec::Avif | Codec::Heic)) = foreign_codec(path)? {
+        let picture = decode(path, codec)
+            .with_context(|| format!("Could not decode {} as {}", path.display(), codec.name()))?;
+        return Ok((picture, None));
+    }
+    let open = || -> Result<_> {
+        let reader = image::ImageReader::open(path)?.with_guessed_format()?;
+        let format = reader.format();
+        let mut decoder = reader.into_decoder()?;
+        let orientation = decoder.orientation()?;
+        let mut picture = DynamicImage::from_decoder(decoder)?;
+        picture.apply_orientation(orientation);
+        Ok((picture, format))
+    };
+    open().with_context(|| format!("Could not decode image {}", path.display()))
+}
+/// HEIC and AVIF both come through libheif (the `heif` feature).
+#[cfg(feature = "heif")]
+fn decode_heif(path: &Path, _codec: Codec) -> Result<DynamicImage> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+    let name = path.to_str().context("Path is not valid UTF-8")?;
+    let ctx = HeifContext::read_from_file(name)?;
+    let handle = ctx.primary_image_handle()?;
+    let decoded = LibHeif::new().decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)?;
+    let plane = decoded
+        .planes()
+        .interleaved
+        .context("libheif returned no RGBA plane")?;
+    let row = plane.width as usize * 4;
+    let mut rgba = Vec::with_capacity(row * plane.height as usize);
+    for y in 0..plane.height as usize {
+        rgba.extend_from_slice(&plane.data[y * plane.stride..][..row]);
+    }
+    let buffer = image::RgbaImage::from_raw(plane.width, plane.height, rgba)
+        .context("libheif returned a short RGBA plane")?;
+    Ok(DynamicImage::ImageRgba8(buffer))
+}
+#[cfg(not(feature = "heif"))]
+fn decode_heif(_path: &Path, codec: Codec) -> Result<DynamicImage> {
+    anyhow::bail!(
+        "this build has no {} decoder (rebuild with `--features heif`, which needs libheif)",
+        codec.name()
+    )
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+    fn root(dir: &tempfile::TempDir) -> StagingRoot {
+        StagingRoot {
+            dir: dir.path().join("staged"),
+            windows_visible: true,
+        }
+    }
+    #[test]
+    fn test_webp_becomes_png_with_alpha() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pixels = RgbaImage::new(3, 2);
+        pixels.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        pixels.put_pixel(2, 1, Rgba([0, 0, 255, 64]));
+        let mut webp = Cursor::new(Vec::new());
+        pixels.write_to(&mut webp, ImageFormat::WebP).unwrap();
+        let source = dir.path().join("photo.webp");
+        std::fs::write(&source, webp.get_ref()).unwrap();
+        assert_eq!(foreign_codec(&source).unwrap(), Some(Codec::Webp));
+        let png = to_png(&source, Codec::Webp, &root(&dir)).unwrap();
+        assert_eq!(png.path().file_name().unwrap(), "photo.png");
+        let back = image::open(png.path()).unwrap().to_rgba8();
+        assert_eq!(back, pixels);
+        // The source is untouched, and the PNG goes with its handle
+        assert_eq!(std::fs::read(&source).unwrap(), *webp.get_ref());
+        let path = png.path().to_path_buf();
+        drop(png);
+        assert!(!path.exists());
+    }
+    #[test]
+    fn test_gdi_formats_pass_through() {
+        let dir = tempfile::tempdir().unwrap();
+        let png = dir.path().join("shot.png");
+        std::fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        assert_eq!(foreign_codec(&png).unwrap(), None);
+        // AVIF by its ftyp box
+        let avif = dir.path().join("photo.avif");
+        std::fs::write(&avif, b"\0\0\0\x1cftypavif\0\0\0\0avifmif1miaf").unwrap();
+        assert_eq!(foreign_codec(&avif).unwrap(), Some(Codec::Avif));
+    }
+    #[test]
+    fn test_resize_targets() {
+        assert_eq!(Resize::MaxDim(1600).target(4000, 3000), Some((1600, 1200)));
+        assert_eq!(Resize::MaxDim(1600).target(3000, 4000), Some((1200, 1600)));
+        assert_eq!(Resize::MaxDim(1600).target(1600, 900), None);
+        assert_eq!(Resize::MaxDim(10).target(5000, 3), Some((10, 1)));
+        assert_eq!(Resize::Scale(50).target(801, 600), Some((401, 300)));
+        assert_eq!(Resize::Scale(100).target(801, 600), None);
+    }
+    /// A JPEG whose EXIF orientation is 6 (turn 90° clockwise to display).
+    fn rotated_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let exif = b"MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01\0\x06\0\0\0\0\0\0".to_vec();
+        let mut jpeg = Vec::new();
+        let mut encoder = JpegEncoder::new_with_quality(&mut jpeg, 90);
+        image::ImageEncoder::set_exif_metadata(&mut encoder, exif).unwrap();
+        DynamicImage::ImageRgb8(image::RgbImage::new(width, height))
+            .write_with_encoder(encoder)
+            .unwrap();
+        jpeg
+    }
+    #[test]
+    fn test_resize_turns_photos_upright_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("phone.jpg");
+        std::fs::write(&photo, rotated_jpeg(400, 300)).unwrap();
+        let before = std::fs::read(&photo).unwrap();
+        // Stored 400x300, displayed 300x400
+        let resized = resize(&photo, Resize::MaxDim(200), 90, &root(&dir)).unwrap();
+        assert_eq!((resized.from, resized.to), ((300, 400), (150, 200)));
+        let file = resized.file.unwrap();
+        assert_eq!(file.path().file_name().unwrap(), "phone-150x200.jpg");
+        let out = image::open(file.path()).unwrap();
+        assert_eq!((out.width(), out.height()), (150, 200));
+        assert_eq!(std::fs::read(&photo).unwrap(), before);
+        // Already small enough: nothing is written
+        let resized = resize(&photo, Resize::MaxDim(400), 90, &root(&dir)).unwrap();
+        assert!(resized.file.is_none());
+        assert_eq!(resized.to, (300, 400));
+    }
+    #[test]
+    fn test_resize_keeps_png_alpha() {
+        let dir = tempfile::tempdir().unwrap();
+        let logo = dir.path().join("logo.png");
+        RgbaImage::from_pixel(64, 32, Rgba([10, 20, 30, 0]))
+            .save(&logo)
+            .unwrap();
+        let resized = resize(&logo, Resize::Scale(25), 90, &root(&dir)).unwrap();
+        let file = resized.file.unwrap();
+        assert_eq!(file.path().file_name().unwrap(), "logo-16x8.png");
+        let out = image::open(file.path()).unwrap().to_rgba8();
+        assert_eq!(out.dimensions(), (16, 8));
+        assert_eq!(out.get_pixel(0, 0)[3], 0);
+        let Err(err) = resize(
+            &dir.path().join("missing.png"),
+            Resize::Scale(50),
+            90,
+            &root(&dir),
+        ) else {
+            panic!("missing file resized");
+        };
+        assert!(err.to_string().contains("missing.png"));
+    }
+    #[test]
+    fn test_strip_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("phone.jpg");
+        std::fs::write(&photo, rotated_jpeg(40, 30)).unwrap();
+        assert!(String::from_utf8_lossy(&std::fs::read(&photo).unwrap()).contains("Exif"));
+        let Stripped::Rewritten(clean) = strip_metadata(&photo, 92, &root(&dir)).unwrap() else {
+            panic!("JPEG not rewritten");
+        };
+        assert_eq!(clean.path().file_name().unwrap(), "phone.jpg");
+        let bytes = std::fs::read(clean.path()).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("Exif"));
+        // Upright, since the orientation tag is gone
+        let out = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((out.width(), out.height()), (30, 40));
+        // PNG: lossless, minus the eXIf chunk
+        let pixels = RgbaImage::from_pixel(4, 4, Rgba([9, 8, 7, 6]));
+        let mut png = Vec::new();
+        let mut encoder = PngEncoder::new(&mut png);
+        image::ImageEncoder::set_exif_metadata(&mut encoder, b"MM\0\x2a\0\0\0\x08\0\0".to_vec())
+            .unwrap();
+        DynamicImage::ImageRgba8(pixels.clone())
+            .write_with_encoder(encoder)
+            .unwrap();
+        assert!(png.windows(4).any(|w| w == b"eXIf"));
+        let logo = dir.path().join("logo.png");
+        std::fs::write(&logo, &png).unwrap();
+        let Stripped::Rewritten(clean) = strip_metadata(&logo, 92, &root(&dir)).unwrap() else {
+            panic!("PNG not rewritten");
+        };
+        let bytes = std::fs::read(clean.path()).unwrap();
+        assert!(!bytes.windows(4).any(|w| w == b"eXIf"));
+        assert_eq!(image::load_from_memory(&bytes).unwrap().to_rgba8(), pixels);
+        // BMP has no metadata; GIF would lose its animation
+        let bmp = dir.path().join("icon.bmp");
+        pixels.save(&bmp).unwrap();
+        assert!(matches!(
+            strip_metadata(&bmp, 92, &root(&dir)).unwrap(),
+            Stripped::NothingToStrip
+        ));
+        let gif = dir.path().join("anim.gif");
+        pixels.save(&gif).unwrap();
+        assert!(matches!(
+            strip_metadata(&gif, 92, &root(&dir)).unwrap(),
+            Stripped::Unsupported("gif")
+        ));
+    }
+    const SVG: &str = "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"200\" height=\"100\">\
+        <rect x=\"0\" y=\"0\" width=\"100\" height=\"100\" fill=\"#ff0000\"/>\
+        <text x=\"110\" y=\"50\" font-family=\"No Such Font\">hi</text></svg>";
+    #[test]
+    fn test_render_svg() {
+        let dir = tempfile::tempdir().unwrap();
+        let svg = dir.path().join("diagram.svg");
+        std::fs::write(&svg, SVG).unwrap();
+        let (png, size) = render_svg(&svg, SvgSize::Width(400), &root(&dir)).unwrap();
+        assert_eq!(size, (400, 200));
+        assert_eq!(png.path().file_name().unwrap(), "diagram.png");
+        let out = image::open(png.path()).unwrap().to_rgba8();
+        assert_eq!(out.dimensions(), (400, 200));
+        // Drawn where the drawing is, transparent elsewhere
+        assert_eq!(*out.get_pixel(10, 10), Rgba([255, 0, 0, 255]));
+        assert_eq!(out.get_pixel(390, 190)[3], 0);
+        drop(png);
+        let (_, size) = render_svg(&svg, SvgSize::Dpi(192), &root(&dir)).unwrap();
+        assert_eq!(size, (400, 200));
+        let Err(err) = render_svg(&svg, SvgSize::Width(100_000), &root(&dir)) else {
+            panic!("huge canvas allocated");
+        };
+        assert!(err.to_string().contains("the limit is 16384 pixels a side"));
+    }
+    #[test]
+    fn test_invalid_svg_shows_the_parser_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let svg = dir.path().join("broken.svg");
+        std::fs::write(&svg, "<svg xmlns=\"http://www.w3.org/2000/svg\"><rect").unwrap();
+        let Err(err) = render_svg(&svg, SvgSize::Width(100), &root(&dir)) else {
+            panic!("broken SVG rendered");
+        };
+        let msg = err.to_string();
+        assert!(msg.starts_with(&format!("{} is not a valid SVG: ", svg.display())));
+        assert!(msg.len() > format!("{} is not a valid SVG: ", svg.display()).len());
+    }
+    #[test]
+    fn test_failures_name_the_codec() {
+        let dir = tempfile::tempdir().unwrap();
+        let broken = dir.path().join("broken.webp");
+        std::fs::write(&broken, b"RIFF\x10\0\0\0WEBPVP8 \x04\0\0\0junk").unwrap();
+        assert_eq!(foreign_codec(&broken).unwrap(), Some(Codec::Webp));
+        let Err(err) = to_png(&broken, Codec::Webp, &root(&dir)) else {
+            panic!("broken WebP decoded");
+        };
+        assert!(err
+            .to_string()
+            .starts_with(&format!("Could not decode {} as WebP", broken.display())));
+        #[cfg(not(feature = "heif"))]
+        {
+            let Err(err) = to_png(&broken, Codec::Heic, &root(&dir)) else {
+                panic!("HEIC decoded without libheif");
+            };
+            assert!(format!("{:#}", err).contains("this build has no HEIC decoder"));
+        }
+    }
+}
+
+// <FILE>src/transcode.rs</FILE> - <DESC>Image formats GDI+ cannot read, converted to PNG</DESC>
+// <VERS>END OF VERSION: 1.3.0 - 2026-10-16T12:27:51Z</VERS>
@@ -0,0 +1,351 @@
+// <FILE>src/tree.rs</FILE> - <DESC>Recursive directory walks for `-r`</DESC>
+// <VERS>VERSION: 1.2.0 - 2026-10-16T13:58:57Z</VERS>
+// <WCTX>Bounded recursive walks.</WCTX>
+// <CLOG>Added --max-depth and --max-files (default 1000); the cap fails the walk before any clipboard write.</CLOG>
+
+use crate::classifier::{self, ClipboardStrategy};
+use crate::debug_logger::create_logger;
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+/// Files `-r` includes before it gives up, unless --max-files says otherwise.
+pub const DEFAULT_MAX_FILES: usize = 1000;
+/// Whether a walk honours .gitignore, .ignore and the global excludes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IgnoreRules {
+    /// Every file is a candidate (--no-gitignore)
+    #[default]
+    Off,
+    /// .gitignore only inside a git repository; .ignore anywhere (the -r default)
+    InRepo,
+    /// .gitignore even outside a repository (--gitignore)
+    Always,
+}
+/// Which files under a directory `-r` keeps. Globs match the path relative
+/// to the directory given (`*.rs` matches `src/a/b.rs` too).
+#[derive(Debug, Clone, Default)]
+pub struct TreeFilter {
+    /// When set, a file must match one of these
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Keep dotfiles and descend into dot-directories
+    hidden: bool,
+    ignore: IgnoreRules,
+    /// Levels below the directory given (1: its own files only)
+    pub max_depth: Option<usize>,
+    /// Files a walk may include before it fails; 0 for no limit
+    pub max_files: usize,
+}
+impl TreeFilter {
+    pub fn new(
+        include: &[String],
+        exclude: &[String],
+        hidden: bool,
+        ignore: IgnoreRules,
+    ) -> Result<Self> {
+        Ok(Self {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(glob_set(include, "--include")?)
+            },
+            exclude: glob_set(exclude, "--exclude")?,
+            hidden,
+            ignore,
+            max_depth: None,
+            max_files: DEFAULT_MAX_FILES,
+        })
+    }
+    fn keeps(&self, relative: &Path) -> bool {
+        !self.exclude.is_match(relative)
+            && self.include.as_ref().is_none_or(|i| i.is_match(relative))
+    }
+}
+fn glob_set(patterns: &[String], flag: &str) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .with_context(|| format!("{} {:?} is not a valid glob", flag, pattern))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+/// The files a walk found, and what it left out.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Walk {
+    /// Arguments in order, each directory replaced by its text files
+    pub files: Vec<PathBuf>,
+    /// Files under a directory the classifier did not call text
+    pub binary: Vec<PathBuf>,
+    /// Files under a directory the globs left out
+    pub excluded: usize,
+    /// Directories walked
+    pub roots: Vec<PathBuf>,
+}
+impl Walk {
+    /// `Walked src: 12 files matched, 1 skipped as binary, 30 excluded`
+    pub fn summary(&self) -> String {
+        let roots: Vec<String> = self.roots.iter().map(|r| r.display().to_string()).collect();
+        format!(
+            "Walked {}: {} files matched, {} skipped as binary, {} excluded",
+            roots.join(", "),
+            self.files.len(),
+            self.binary.len(),
+            self.excluded
+        )
+    }
+}
+/// Replaces each directory in `paths` with the text files under it, in
+/// file-name order; other arguments pass through untouched, so a file named
+/// on the command line is copied even when ignore rules cover it. Symlinked
+/// directories are not followed. Paths are the root as given joined with
+/// the relative path (`./` dropped), which is what the headers show.
+pub fn expand(paths: &[PathBuf], filter: &TreeFilter) -> Result<Walk> {
+    let log = create_logger("tree");
+    let mut walk = Walk::default();
+    for root in paths {
+        if !root.is_dir() {
+            walk.files.push(root.clone());
+            continue;
+        }
+        walk.roots.push(root.clone());
+        let mut each = |found: PathBuf| -> Result<()> {
+            let relative = found.strip_prefix(root).unwrap_or(&found);
+            if !filter.keeps(relative) {
+                walk.excluded += 1;
+                return Ok(());
+            }
+            let path = found.strip_prefix(".").unwrap_or(&found);
+            match classifier::inspect(path) {
+                Ok(ClipboardStrategy::Text) => walk.files.push(path.to_path_buf()),
+                Ok(strategy) => {
+                    log.debug(&format!("Skipped {:?} ({:?})", path, strategy));
+                    walk.binary.push(path.to_path_buf());
+                }
+                // Unreadable: the text pipeline reports it
+                Err(_) => walk.files.push(path.to_path_buf()),
+            }
+            check_cap(walk.files.len(), filter.max_files)
+        };
+        match filter.ignore {
+            IgnoreRules::Off => walk_all(root, filter, &mut each)?,
+            rules => walk_unignored(root, filter, rules == IgnoreRules::Always, &mut each)?,
+        }
+    }
+    check_cap(walk.files.len(), filter.max_files)?;
+    log.debug(&walk.summary());
+    Ok(walk)
+}
+/// Stops the walk as soon as one file too many is found, long before a
+/// stray `-r ~` has read the whole tree (or anything reached the clipboard).
+fn check_cap(found: usize, max_files: usize) -> Result<()> {
+    if max_files > 0 && found > max_files {
+        anyhow::bail!(
+            "-r stopped at {} files, over the --max-files cap of {} (the clipboard was left unchanged). \
+             Narrow the walk with --include, --exclude or --max-depth, or raise the cap with --max-files (0 for no limit)",
+            found,
+            max_files
+        );
+    }
+    Ok(())
+}
+/// Hands every file under `root` to `each`, dotfiles aside unless `--hidden`.
+fn walk_all(
+    root: &Path,
+    filter: &TreeFilter,
+    each: &mut dyn FnMut(PathBuf) -> Result<()>,
+) -> Result<()> {
+    let mut entries = walkdir::WalkDir::new(root).sort_by_file_name();
+    if let Some(depth) = filter.max_depth {
+        entries = entries.max_depth(depth);
+    }
+    let entries = entries
+        .into_iter()
+        .filter_entry(|e| filter.hidden || e.depth() == 0 || !is_hidden(e.file_name()));
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {}", root.display()))?;
+        if entry.path().is_file() {
+            each(entry.into_path())?;
+        }
+    }
+    Ok(())
+}
+/// Hands `each` the files under `root` that no .gitignore, .ignore or
+/// global exclude covers, nested files and `!` negations included. Ignored
+/// directories are not descended into, so what the rules dropped is counted
+/// afterwards, one entry per file or folder, from the listings of the
+/// folders walked.
+fn walk_unignored(
+    root: &Path,
+    filter: &TreeFilter,
+    outside_git: bool,
+    each: &mut dyn FnMut(PathBuf) -> Result<()>,
+) -> Result<()> {
+    let log = create_logger("tree");
+    let mut seen = HashSet::new();
+    let mut dirs = Vec::new();
+    // Git never tracks its own folder, so it stays out even with --hidden
+    let hidden = filter.hidden;
+    let walks = move |name: &std::ffi::OsStr| (hidden || !is_hidden(name)) && name != ".git";
+    let entries = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .require_git(!outside_git)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .max_depth(filter.max_depth)
+        .filter_entry(move |e| e.depth() == 0 || walks(e.file_name()))
+        .build();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {}", root.display()))?;
+        let listed = filter.max_depth.is_none_or(|max| entry.depth() < max);
+        let path = entry.into_path();
+        if path.is_dir() && listed {
+            dirs.push(path.clone());
+        } else if path.is_file() {
+            each(path.clone())?;
+        }
+        seen.insert(path);
+    }
+    let ignored: usize = dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .filter(|e| walks(&e.file_name()) && !seen.contains(&e.path()))
+        .count();
+    log.debug(&format!(
+        "Ignore rules left out {} entries under {}",
+        ignored,
+        root.display()
+    ));
+    Ok(())
+}
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_string_lossy().starts_with('.')
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn tree() -> Result<tempfile::TempDir> {
+        let dir = tempfile::tempdir()?;
+        for (name, data) in [
+            ("src/main.rs", &b"fn main() {}\n"[..]),
+            ("src/lib/b.rs", b"pub fn b() {}\n"),
+            ("src/lib/a.rs", b"pub fn a() {}\n"),
+            ("src/logo.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            ("src/.env", b"SECRET=1\n"),
+            ("src/.git/config", b"[core]\n"),
+            ("target/debug/out.txt", b"built\n"),
+            ("README.md", b"# hi\n"),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, data)?;
+        }
+        Ok(dir)
+    }
+    fn names(walk: &Walk, root: &Path) -> Vec<String> {
+        walk.files
+            .iter()
+            .map(|p| p.strip_prefix(root).unwrap().display().to_string())
+            .collect()
+    }
+    #[test]
+    fn test_walk_order_and_binaries() -> Result<()> {
+        let dir = tree()?;
+        let root = dir.path();
+        let walk = expand(&[root.join("src")], &TreeFilter::default())?;
+        assert_eq!(
+            names(&walk, root),
+            ["src/lib/a.rs", "src/lib/b.rs", "src/main.rs"]
+        );
+        assert_eq!(walk.binary, [root.join("src/logo.png")]);
+        assert_eq!(walk.excluded, 0);
+        // Hidden entries only on request
+        let walk = expand(
+            &[root.join("src")],
+            &TreeFilter::new(&[], &[], true, IgnoreRules::Off)?,
+        )?;
+        assert!(names(&walk, root).contains(&"src/.env".to_string()));
+        assert!(names(&walk, root).contains(&"src/.git/config".to_string()));
+        Ok(())
+    }
+    #[test]
+    fn test_include_and_exclude() -> Result<()> {
+        let dir = tree()?;
+        let root = dir.path();
+        let filter = TreeFilter::new(
+            &["*.rs".into(), "*.md".into()],
+            &["lib/**".into()],
+            false,
+            IgnoreRules::Off,
+        )?;
+        let walk = expand(&[root.join("src"), root.join("README.md")], &filter)?;
+        // Files given directly are not filtered
+        assert_eq!(names(&walk, root), ["src/main.rs", "README.md"]);
+        assert_eq!((walk.binary.len(), walk.excluded), (0, 3));
+        assert_eq!(walk.roots, [root.join("src")]);
+        let filter = TreeFilter::new(&[], &["target/**".into()], false, IgnoreRules::Off)?;
+        let walk = expand(&[root.to_path_buf()], &filter)?;
+        assert_eq!(walk.excluded, 1);
+        assert!(walk
+            .summary()
+            .ends_with(": 4 files matched, 1 skipped as binary, 1 excluded"));
+        assert!(TreeFilter::new(&["a[".into()], &[], false, IgnoreRules::Off).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_depth_and_file_cap() -> Result<()> {
+        let dir = tree()?;
+        let root = dir.path();
+        for ignore in [IgnoreRules::Off, IgnoreRules::Always] {
+            let mut filter = TreeFilter::new(&[], &[], false, ignore)?;
+            filter.max_depth = Some(2);
+            let walk = expand(&[root.to_path_buf()], &filter)?;
+            assert_eq!(names(&walk, root), ["README.md", "src/main.rs"]);
+            filter.max_depth = None;
+            filter.max_files = 3;
+            let err = expand(&[root.to_path_buf()], &filter).unwrap_err();
+            assert!(err
+                .to_string()
+                .starts_with("-r stopped at 4 files, over the --max-files cap of 3"));
+            filter.max_files = 0;
+            assert_eq!(expand(&[root.to_path_buf()], &filter)?.files.len(), 5);
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_gitignore_rules() -> Result<()> {
+        let dir = tree()?;
+        let root = dir.path();
+        std::fs::write(root.join(".gitignore"), "target/\n*.md\n!README.md\n")?;
+        std::fs::write(root.join("src/lib/.gitignore"), "b.rs\n")?;
+        std::fs::write(root.join("NOTES.md"), "ignored\n")?;
+        let rules = |ignore| TreeFilter::new(&[], &[], true, ignore);
+        // Not a repository yet: .gitignore only counts when asked for
+        let walk = expand(&[root.to_path_buf()], &rules(IgnoreRules::InRepo)?)?;
+        assert!(names(&walk, root).contains(&"NOTES.md".to_string()));
+        std::fs::create_dir(root.join(".git"))?;
+        let walk = expand(&[root.to_path_buf()], &rules(IgnoreRules::InRepo)?)?;
+        assert_eq!(
+            names(&walk, root),
+            [
+                ".gitignore",
+                "README.md",
+                "src/.env",
+                "src/lib/.gitignore",
+                "src/lib/a.rs",
+                "src/main.rs"
+            ]
+        );
+        let walk = expand(&[root.to_path_buf()], &rules(IgnoreRules::Always)?)?;
+        assert!(!names(&walk, root).contains(&"src/lib/b.rs".to_string()));
+        // Named on the command line, ignored files are copied anyway
+        let walk = expand(&[root.join("NOTES.md")], &rules(IgnoreRules::InRepo)?)?;
+        assert_eq!(names(&walk, root), ["NOTES.md"]);
+        Ok(())
+    }
+}
+
+// <FILE>src/tree.rs</FILE> - <DESC>Recursive directory walks for `-r`</DESC>
+// <VERS>END OF VERSION: 1.2.0 - 2026-10-16T13:58:57Z</VERS>
@@ -0,0 +1,189 @@
+// <FILE>src/watch.rs</FILE> - <DESC>Watch mode</DESC>
+// <VERS>VERSION: 1.0.0 - 2026-10-16T11:01:16Z</VERS>
+// <WCTX>--watch re-copies on change.</WCTX>
+// <CLOG>Initial creation with Target, touches and run.</CLOG>
+
+use crate::debug_logger::create_logger;
+// The crate, not this binary's desktop-notification module
+use ::notify::event::{AccessKind, AccessMode};
+use ::notify::{Event, EventKind, RecursiveMode, Watcher};
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+/// Quiet period after the last write before re-copying, so an editor's
+/// burst of writes (or a write-then-rename save) yields one copy.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+enum Signal {
+    Fs(::notify::Result<Event>),
+    Stop,
+}
+/// A watched file. Its directory is watched rather than the file itself, so
+/// a file that is deleted and recreated (atomic saves) keeps being seen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub dir: PathBuf,
+    pub name: OsString,
+}
+impl Target {
+    pub fn new(file: &Path) -> Result<Self> {
+        let abs = dunce::canonicalize(file)
+            .with_context(|| format!("Cannot watch {:?}: no such file", file))?;
+        match (abs.parent(), abs.file_name()) {
+            (Some(dir), Some(name)) => Ok(Target {
+                dir: dir.to_path_buf(),
+                name: name.to_os_string(),
+            }),
+            _ => anyhow::bail!("Cannot watch {:?}: not a file", file),
+        }
+    }
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(&self.name)
+    }
+}
+/// Whether `event` may have changed one of `targets`. Reads and opens are
+/// ignored (copying a file opens it, which must not trigger another copy);
+/// closing after a write counts.
+pub fn touches(event: &Event, targets: &[Target]) -> bool {
+    match event.kind {
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => {}
+        EventKind::Access(_) => return false,
+        _ => {}
+    }
+    event.paths.iter().any(|p| {
+        targets
+            .iter()
+            .any(|t| p.parent() == Some(t.dir.as_path()) && p.file_name() == Some(&t.name))
+    })
+}
+/// Runs `copy` once, then again after every (debounced) change to `files`,
+/// until Ctrl-C. A failed re-copy is reported and watching continues.
+pub fn run(files: &[PathBuf], mut copy: impl FnMut() -> Result<u64>) -> Result<()> {
+    let log = create_logger("watch");
+    let targets = files
+        .iter()
+        .map(|f| Target::new(f))
+        .collect::<Result<Vec<_>>>()?;
+    let (tx, rx) = mpsc::channel();
+    let fs_tx = tx.clone();
+    let mut watcher = ::notify::recommended_watcher(move |res| {
+        let _ = fs_tx.send(Signal::Fs(res));
+    })
+    .context("Failed to start the file watcher")?;
+    let dirs: BTreeSet<&Path> = targets.iter().map(|t| t.dir.as_path()).collect();
+    for dir in dirs {
+        log.debug(&format!("Watching directory {:?}", dir));
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {:?}", dir))?;
+    }
+    ctrlc::set_handler(move || {
+        let _ = tx.send(Signal::Stop);
+    })
+    .context("Failed to install the Ctrl-C handler")?;
+    let bytes = copy()?;
+    println!(
+        "[OK] Copied {} bytes; watching {} file(s) (Ctrl-C to stop)",
+        bytes,
+        targets.len()
+    );
+    let mut dirty = false;
+    loop {
+        let next = if dirty {
+            rx.recv_timeout(DEBOUNCE)
+        } else {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        };
+        match next {
+            Ok(Signal::Fs(Ok(event))) => {
+                if touches(&event, &targets) {
+                    log.debug(&format!("{:?} {:?}", event.kind, event.paths));
+                    dirty = true;
+                }
+            }
+            Ok(Signal::Fs(Err(e))) => eprintln!("[WARN] File watcher: {}", e),
+            Ok(Signal::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {
+                dirty = false;
+                // Mid atomic save: the next event brings the file back
+                if let Some(gone) = targets.iter().find(|t| !t.path().exists()) {
+                    log.debug(&format!("{:?} is missing; waiting", gone.path()));
+                    continue;
+                }
+                let at = chrono::Local::now().format("%H:%M:%S");
+                match copy() {
+                    Ok(bytes) => println!("[OK] Re-copied {} bytes at {}", bytes, at),
+                    Err(e) => eprintln!("[WARN] Re-copy failed at {}: {:#}", at, e),
+                }
+            }
+        }
+    }
+    println!("[OK] Stopped watching");
+    Ok(())
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::notify::event::{CreateKind, ModifyKind, RemoveKind, RenameMode};
+    fn event(kind: EventKind, path: &Path) -> Event {
+        Event::new(kind).add_path(path.to_path_buf())
+    }
+    #[test]
+    fn test_events_that_count() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("notes.md");
+        std::fs::write(&file, "x")?;
+        let targets = [Target::new(&file)?];
+        let file = targets[0].path();
+        let write = EventKind::Modify(ModifyKind::Any);
+        assert!(touches(&event(write, &file), &targets));
+        assert!(touches(
+            &event(EventKind::Create(CreateKind::File), &file),
+            &targets
+        ));
+        assert!(touches(
+            &event(
+                EventKind::Access(AccessKind::Close(AccessMode::Write)),
+                &file
+            ),
+            &targets
+        ));
+        // Our own read of the file must not loop
+        assert!(!touches(
+            &event(EventKind::Access(AccessKind::Open(AccessMode::Any)), &file),
+            &targets
+        ));
+        assert!(!touches(
+            &event(
+                EventKind::Access(AccessKind::Close(AccessMode::Read)),
+                &file
+            ),
+            &targets
+        ));
+        // Neighbours in the same directory are not watched files
+        assert!(!touches(
+            &event(write, &file.with_file_name("notes.md.swp")),
+            &targets
+        ));
+        // An editor's rename over the original is a change to it
+        let rename = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(file.with_file_name(".notes.md.tmp"))
+            .add_path(file.clone());
+        assert!(touches(&rename, &targets));
+        assert!(touches(
+            &event(EventKind::Remove(RemoveKind::File), &file),
+            &targets
+        ));
+        Ok(())
+    }
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let err = Target::new(Path::new("/nonexistent/file.md")).unwrap_err();
+        assert!(err.to_string().contains("no such file"));
+    }
+}
+
+// <FILE>src/watch.rs</FILE> - <DESC>Watch mode</DESC>
+// <VERS>END OF VERSION: 1.0.0 - 2026-10-16T11:01:16Z</VERS>
@@ -0,0 +1,290 @@
+// <FILE>tests/common/mod.rs</FILE> - <DESC>Fake WSL environment for e2e tests</DESC>
+// <VERS>VERSION: 1.18.0 - 2026-10-16T14:49:54Z</VERS>
+// <WCTX>Daemon socket ownership.</WCTX>
+// <CLOG>The fake XDG_RUNTIME_DIR is 0700.</CLOG>
+
+//! Fake WSL environment for end-to-end tests: stub Windows tools first on
+//! PATH, a fabricated /proc, and a scratch HOME. Nothing leaks in from the
+//! developer's environment (the command starts from `env_clear`).
+#![allow(dead_code)]
+use assert_cmd::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+pub const WSL2_KERNEL: &str = "5.15.153.1-microsoft-standard-WSL2";
+pub const WSL1_KERNEL: &str = "4.4.0-19041-Microsoft";
+pub const LINUX_KERNEL: &str = "6.8.0-45-generic";
+pub const BIN: &str = env!("CARGO_BIN_EXE_wsl-clip");
+pub struct FakeWsl {
+    dir: TempDir,
+    /// False for a plain Linux machine: no interop handler, no WSL_DISTRO_NAME
+    wsl: bool,
+    /// A private copy of the stub directory, once a test removes a tool from it
+    stubs: Option<PathBuf>,
+}
+impl FakeWsl {
+    pub fn wsl2() -> Self {
+        Self::with_kernel(WSL2_KERNEL)
+    }
+    pub fn wsl1() -> Self {
+        Self::with_kernel(WSL1_KERNEL)
+    }
+    /// A Linux laptop: the same scratch layout, but nothing says WSL.
+    pub fn linux() -> Self {
+        let mut env = Self::with_kernel(LINUX_KERNEL);
+        fs::remove_file(env.dir.path().join("proc/sys/fs/binfmt_misc/WSLInterop")).unwrap();
+        env.wsl = false;
+        env
+    }
+    fn with_kernel(release: &str) -> Self {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path();
+        for sub in [
+            "log",
+            "home",
+            "run",
+            "work",
+            "proc/sys/kernel",
+            "proc/sys/fs/binfmt_misc",
+            "proc/self",
+            "mnt/c/Temp",
+        ] {
+            fs::create_dir_all(root.join(sub)).unwrap();
+        }
+        fs::write(
+            root.join("proc/sys/kernel/osrelease"),
+            format!("{}\n", release),
+        )
+        .unwrap();
+        // Private, like the XDG_RUNTIME_DIR systemd creates
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(root.join("run"), fs::Permissions::from_mode(0o700)).unwrap();
+        fs::write(root.join("proc/sys/fs/binfmt_misc/WSLInterop"), "enabled\n").unwrap();
+        // The fake C: drive is a drvfs mount, as /mnt/c is on WSL2
+        fs::write(
+            root.join("proc/self/mounts"),
+            format!(
+                "/dev/sdc / ext4 rw,relatime 0 0\n\
+                 C:\\134 {} 9p rw,noatime,aname=drvfs;path=C:\\;uid=1000 0 0\n",
+                root.join("mnt/c").display()
+            ),
+        )
+        .unwrap();
+        FakeWsl {
+            dir,
+            wsl: true,
+            stubs: None,
+        }
+    }
+    fn stubs() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("stubs")
+    }
+    /// The private stub directory, created (as symlinks) on first use.
+    fn private_stubs(&mut self) -> PathBuf {
+        let dir = self.dir.path().join("stubs");
+        if self.stubs.is_none() {
+            fs::create_dir_all(&dir).unwrap();
+            for entry in fs::read_dir(Self::stubs()).unwrap() {
+                let entry = entry.unwrap();
+                if entry.path().is_file() {
+                    std::os::unix::fs::symlink(entry.path(), dir.join(entry.file_name())).unwrap();
+                }
+            }
+            self.stubs = Some(dir.clone());
+        }
+        dir
+    }
+    /// Takes a stub tool off PATH, as on a Windows image where it was removed.
+    pub fn remove_stub(&mut self, name: &str) {
+        fs::remove_file(self.private_stubs().join(name)).unwrap();
+    }
+    /// Puts a tool from tests/stubs/optional on PATH (e.g. pwsh.exe, which
+    /// most machines lack).
+    pub fn add_stub(&mut self, name: &str) {
+        let source = Self::stubs().join("optional").join(name);
+        std::os::unix::fs::symlink(source, self.private_stubs().join(name)).unwrap();
+    }
+    /// A Windows directory on a non-default drive, for `WSL_CLIP_WINDOWS_DIR`.
+    pub fn windows_dir(&self) -> PathBuf {
+        self.dir.path().join("mnt/d/Windows")
+    }
+    /// Moves a stub tool off PATH and into `windows_dir()/<sub>`, as with
+    /// `appendWindowsPath = false`.
+    pub fn move_stub_to_windows(&mut self, name: &str, sub: &str) {
+        self.remove_stub(name);
+        let dir = self.windows_dir().join(sub);
+        fs::create_dir_all(&dir).unwrap();
+        std::os::unix::fs::symlink(Self::stubs().join(name), dir.join(name)).unwrap();
+    }
+    /// A file in the stub log directory.
+    pub fn log_path(&self, name: &str) -> PathBuf {
+        self.dir.path().join("log").join(name)
+    }
+    /// The wsl-clip binary, run from the scratch work directory.
+    pub fn cmd(&self) -> Command {
+        Command::from_std(self.command(BIN))
+    }
+    /// Any program (e.g. a wrapper that execs `BIN`) in the same environment,
+    /// as a plain `std` command so stdin can be any `Stdio`.
+    pub fn command(&self, program: &str) -> std::process::Command {
+        let root = self.dir.path();
+        let stubs = self.stubs.clone().unwrap_or_else(Self::stubs);
+        let mut cmd = std::process::Command::new(program);
+        cmd.env_clear()
+            .env("PATH", format!("{}:/usr/bin:/bin", stubs.display()))
+            .env("HOME", root.join("home"))
+            .env("WSL_CLIP_PROC_ROOT", root.join("proc"))
+            .env("WSL_CLIP_CONFIG", root.join("home/config.toml"))
+            .env("STUB_LOG_DIR", root.join("log"))
+            .env("STUB_MNT", root.join("mnt"))
+            .env("WSL_CLIP_AUTOMOUNT_ROOT", root.join("mnt"))
+            .env("STUB_WIN_TEMP", "C:\\Temp")
+            // Each test gets its own daemon socket directory
+            .env("XDG_RUNTIME_DIR", root.join("run"))
+            // No terminal unless the test calls `attach_terminal`
+            .env("WSL_CLIP_TTY", root.join("log/tty"))
+            .current_dir(root.join("work"));
+        if self.wsl {
+            cmd.env("WSL_DISTRO_NAME", "Test");
+        }
+        cmd
+    }
+    /// The Linux side of the fake Windows %TEMP% (C:\Temp), where staged files land.
+    pub fn windows_temp(&self) -> PathBuf {
+        self.dir.path().join("mnt/c/Temp")
+    }
+    /// Where `wsl-clip daemon` listens in this environment.
+    pub fn daemon_socket(&self) -> PathBuf {
+        self.dir.path().join("run/wsl-clip.sock")
+    }
+    /// Gives wsl-clip a (fake) controlling terminal.
+    pub fn attach_terminal(&self) {
+        fs::write(self.log_path("tty"), "").unwrap();
+    }
+    /// Everything written to the terminal (OSC 52 sequences).
+    pub fn terminal(&self) -> String {
+        fs::read_to_string(self.log_path("tty")).unwrap_or_default()
+    }
+    /// Writes a file (parents created) into the work directory and returns its path.
+    pub fn file(&self, name: &str, content: &[u8]) -> PathBuf {
+        let path = self.dir.path().join("work").join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+    pub fn config(&self, toml: &str) {
+        fs::write(self.dir.path().join("home/config.toml"), toml).unwrap();
+    }
+    /// What the fake clip.exe committed, if anything.
+    pub fn clipboard(&self) -> Option<String> {
+        fs::read_to_string(self.log_path("clipboard")).ok()
+    }
+    /// The exact bytes the last committed clip.exe call read from stdin.
+    pub fn clip_stdin(&self) -> Vec<u8> {
+        fs::read(self.log_path("clip.exe.stdin")).unwrap_or_default()
+    }
+    /// Arguments of the last powershell.exe call.
+    pub fn powershell_args(&self) -> Vec<String> {
+        fs::read_to_string(self.log_path("powershell.args"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+    /// The last image/file copy as PowerShell sees it: the `-EncodedCommand`
+    /// script and the `-EncodedArguments` strings (its `$args`).
+    pub fn powershell_script(&self) -> (String, Vec<String>) {
+        decode_call(&self.powershell_args())
+    }
+    /// What the last encoded script call read from stdin.
+    pub fn powershell_stdin(&self) -> String {
+        fs::read_to_string(self.log_path("powershell.stdin")).unwrap_or_default()
+    }
+    /// Scripted stdout for the next powershell.exe calls.
+    pub fn powershell_responds(&self, stdout: &str) {
+        fs::write(self.log_path("powershell.stdout"), stdout).unwrap();
+    }
+    /// Makes the next powershell.exe calls fail with `stderr` and `code`.
+    pub fn powershell_fails(&self, stderr: &str, code: i32) {
+        fs::write(self.log_path("powershell.stderr"), stderr).unwrap();
+        fs::write(self.log_path("powershell.exit"), code.to_string()).unwrap();
+    }
+    /// Holds the clipboard open against the next `times` calls of `program`
+    /// (clip.exe or powershell.exe), as a clipboard manager or RDP can.
+    pub fn lock_clipboard(&self, program: &str, times: u32) {
+        fs::write(
+            self.log_path(&format!("{}.locked", program)),
+            times.to_string(),
+        )
+        .unwrap();
+    }
+    /// Every stub invocation, in order.
+    pub fn calls(&self) -> Vec<String> {
+        fs::read_to_string(self.log_path("calls"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+/// Decodes an `-EncodedCommand ... -EncodedArguments ...` command line.
+pub fn decode_call(args: &[String]) -> (String, Vec<String>) {
+    let value = |flag: &str| {
+        let at = args.iter().position(|a| a == flag).expect(flag);
+        utf16le(&args[at + 1])
+    };
+    let xml = value("-EncodedArguments");
+    let strings = xml
+        .split("<S>")
+        .skip(1)
+        .map(|item| clixml_text(item.split("</S>").next().unwrap()))
+        .collect();
+    (value("-EncodedCommand"), strings)
+}
+fn utf16le(encoded: &str) -> String {
+    use base64::Engine;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .unwrap();
+    let units: Vec<u16> = bytes
+        .chunks(2)
+        .map(|p| u16::from_le_bytes([p[0], p[1]]))
+        .collect();
+    String::from_utf16(&units).unwrap()
+}
+/// Undoes CLIXML string escaping: XML entities and `_xHHHH_` code units.
+fn clixml_text(text: &str) -> String {
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    let mut units = Vec::new();
+    let mut rest = text.as_str();
+    while let Some(at) = rest.find("_x") {
+        units.extend(rest[..at].encode_utf16());
+        let escape = rest[at + 2..]
+            .get(..5)
+            .filter(|e| e.ends_with('_'))
+            .and_then(|e| u16::from_str_radix(&e[..4], 16).ok());
+        match escape {
+            Some(unit) => {
+                units.push(unit);
+                rest = &rest[at + 7..];
+            }
+            None => {
+                units.extend("_x".encode_utf16());
+                rest = &rest[at + 2..];
+            }
+        }
+    }
+    units.extend(rest.encode_utf16());
+    String::from_utf16(&units).unwrap()
+}
+/// A minimal valid PNG header (enough for magic-byte detection).
+pub const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x02\0\0\0";
+pub const PDF: &[u8] = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n1 0 obj\n";
+
+// <FILE>tests/common/mod.rs</FILE> - <DESC>Fake WSL environment for e2e tests</DESC>
+// <VERS>END OF VERSION: 1.18.0 - 2026-10-16T14:49:54Z</VERS>
@@ -0,0 +1,3158 @@
+// <FILE>tests/e2e.rs</FILE> - <DESC>End-to-end tests</DESC>
+// <VERS>VERSION: 1.77.0 - 2026-10-16T14:50:15Z</VERS>
+// <WCTX>Daemon clients never block each other.</WCTX>
+// <CLOG>Connect at wait(); a thread per connection; buffer at most daemon::TEXT_LIMIT.</CLOG>
+
+//! End-to-end tests: the real binary against stub Windows tools (see tests/common).
+mod common;
+use common::{FakeWsl, BIN, PDF, PNG};
+fn stdout(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+fn stderr(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+#[test]
+fn smart_text_file_streams_to_clip() {
+    let env = FakeWsl::wsl2();
+    let path = env.file("notes.md", b"# Notes\n\x1b[31mred\x1b[0m line\n");
+    let out = env
+        .cmd()
+        .arg(&path)
+        .arg("--timestamp")
+        .arg("none")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Text"));
+    let clip = env.clipboard().expect("clipboard committed");
+    assert!(clip.contains("notes.md"));
+    assert!(clip.contains("# Notes\nred line\n"));
+    assert_eq!(env.calls(), vec!["clip.exe"]);
+}
+#[test]
+fn smart_unicode_text_files_copy_as_text() {
+    let env = FakeWsl::wsl2();
+    // PowerShell transcripts and Notepad's "Unicode" are UTF-16LE with a BOM
+    let mut transcript = vec![0xFF, 0xFE];
+    transcript.extend(
+        "PS C:\\> Get-Date\r\nDienstag\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes),
+    );
+    let path = env.file("transcript.txt", &transcript);
+    let out = env.cmd().args(["--no-header"]).arg(&path).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Text"));
+    assert_eq!(
+        env.clipboard().as_deref(),
+        Some("PS C:\\> Get-Date\nDienstag\n")
+    );
+    // Broken bytes are replaced, not fatal
+    let path = env.file("mixed.log", b"ok\nbad \xFF byte\n");
+    let out = env.cmd().args(["--no-header"]).arg(&path).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("is not entirely valid UTF-8"));
+    assert_eq!(env.clipboard().as_deref(), Some("ok\nbad \u{FFFD} byte\n"));
+}
+#[test]
+fn smart_single_image_uses_set_image() {
+    let env = FakeWsl::wsl2();
+    let path = env.file("shot.png", PNG);
+    let out = env.cmd().arg(&path).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Image to Clipboard"));
+    let args = env.powershell_args();
+    assert_eq!(
+        &args[..4],
+        ["-NoProfile", "-NonInteractive", "-WindowStyle", "Hidden"]
+    );
+    assert_eq!(args[4], "-EncodedCommand");
+    assert_eq!(args[6], "-EncodedArguments");
+    assert_eq!(args.len(), 8);
+    let (script, script_args) = env.powershell_script();
+    // CF_BITMAP for older programs, plus PNG and DIBV5 with alpha, all at once
+    assert!(script.contains("$data.SetImage($img)"));
+    assert!(script.contains("$data.SetData('PNG', "));
+    assert!(script.contains("GetFormat(17).Name, $dib"));
+    assert!(script.contains("[System.Windows.Forms.Clipboard]::SetDataObject($data, $true)"));
+    assert!(!script.contains("Clipboard]::SetImage"));
+    // The path travels as its own argument, never inside the script
+    let expected = format!(
+        "\\\\wsl.localhost\\Test{}",
+        path.display().to_string().replace('/', "\\")
+    );
+    assert_eq!(script_args, [expected]);
+    assert!(!script.contains("shot.png"));
+    assert!(env.clipboard().is_none());
+}
+#[test]
+fn webp_images_reach_powershell_as_png() {
+    let env = FakeWsl::wsl2();
+    let mut webp = std::io::Cursor::new(Vec::new());
+    image::RgbaImage::from_pixel(2, 2, image::Rgba([0, 128, 255, 100]))
+        .write_to(&mut webp, image::ImageFormat::WebP)
+        .unwrap();
+    let path = env.file("photo.webp", webp.get_ref());
+    let out = env
+        .cmd()
+        .args(["--debug", "img"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("(WebP) to PNG"));
+    let (_, script_args) = env.powershell_script();
+    assert_eq!(script_args, ["C:\\Temp\\wsl-clip\\photo.png"]);
+    // The PNG was only needed for the copy; the original is untouched
+    assert!(!env.windows_temp().join("wsl-clip/photo.png").exists());
+    assert_eq!(std::fs::read(&path).unwrap(), *webp.get_ref());
+    // An undecodable file names its codec, and PowerShell never runs
+    let broken = env.file("broken.webp", b"RIFF\x10\0\0\0WEBPVP8 \x04\0\0\0junk");
+    let calls = env.calls().len();
+    let out = env.cmd().arg("img").arg(&broken).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("as WebP"), "{}", stderr(&out));
+    assert!(!env.calls()[calls..].contains(&"powershell.exe".to_string()));
+}
+#[test]
+fn both_puts_pixels_and_the_file_in_one_copy() {
+    let env = FakeWsl::wsl2();
+    let mut webp = std::io::Cursor::new(Vec::new());
+    image::RgbaImage::from_pixel(2, 2, image::Rgba([0, 128, 255, 100]))
+        .write_to(&mut webp, image::ImageFormat::WebP)
+        .unwrap();
+    let path = env.file("photo.webp", webp.get_ref());
+    let out = env.cmd().arg("--both").arg(&path).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Image to Clipboard (pixels and file)"));
+    let (script, script_args) = env.powershell_script();
+    assert!(script.contains("$data.SetImage($img)"));
+    assert!(script.contains("[void]$files.Add($args[1]); $data.SetFileDropList($files)"));
+    assert_eq!(script.matches("SetDataObject").count(), 1);
+    // Pixels from a PNG GDI+ can read; the drop is the original file
+    assert_eq!(script_args[0], "C:\\Temp\\wsl-clip\\photo.png");
+    assert!(
+        script_args[1].ends_with("\\photo.webp"),
+        "{:?}",
+        script_args
+    );
+    let out = env
+        .cmd()
+        .args(["img", "--both", "--scale", "50"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("--both copies the file as it is"));
+}
+#[test]
+fn img_max_dim_copies_a_downscaled_png() {
+    let env = FakeWsl::wsl2();
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::RgbaImage::from_pixel(64, 48, image::Rgba([1, 2, 3, 4]))
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+    let path = env.file("big.png", png.get_ref());
+    let out = env
+        .cmd()
+        .args(["img", "--max-dim", "32"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Image to Clipboard (64x48 -> 32x24)"));
+    let (_, script_args) = env.powershell_script();
+    assert_eq!(script_args, ["C:\\Temp\\wsl-clip\\big-32x24.png"]);
+    assert!(!env.windows_temp().join("wsl-clip/big-32x24.png").exists());
+    // Small enough already: the original goes as is
+    let out = env
+        .cmd()
+        .args(["img", "--scale", "100"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(stdout(&out).contains("(64x48, already within the limit)"));
+    let (_, script_args) = env.powershell_script();
+    assert!(script_args[0].ends_with("\\big.png"));
+    let out = env
+        .cmd()
+        .args(["img", "--scale", "50", "--max-dim", "10"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("cannot be used with"));
+}
+#[test]
+fn strip_metadata_copies_a_clean_reencode() {
+    let env = FakeWsl::wsl2();
+    let pixels = image::RgbImage::from_pixel(8, 6, image::Rgb([200, 100, 50]));
+    let mut jpeg = Vec::new();
+    let mut encoder = image::codecs::jpeg::JpegEncoder::new(&mut jpeg);
+    // EXIF with a camera serial-like payload
+    image::ImageEncoder::set_exif_metadata(&mut encoder, b"MM\0\x2a\0\0\0\x08\0\0SERIAL".to_vec())
+        .unwrap();
+    image::DynamicImage::ImageRgb8(pixels.clone())
+        .write_with_encoder(encoder)
+        .unwrap();
+    let photo = env.file("photo.jpg", &jpeg);
+    // Smart mode and img alike
+    for args in [vec!["--strip-metadata"], vec!["img", "--strip-metadata"]] {
+        let out = env.cmd().args(&args).arg(&photo).output().unwrap();
+        assert!(out.status.success(), "{}", stderr(&out));
+        let (_, script_args) = env.powershell_script();
+        assert_eq!(script_args, ["C:\\Temp\\wsl-clip\\photo.jpg"]);
+    }
+    assert!(!env.windows_temp().join("wsl-clip/photo.jpg").exists());
+    assert_eq!(std::fs::read(&photo).unwrap(), jpeg);
+    // A GIF cannot be rewritten safely: a warning, and the original
+    let mut gif = std::io::Cursor::new(Vec::new());
+    pixels.write_to(&mut gif, image::ImageFormat::Gif).unwrap();
+    let anim = env.file("anim.gif", gif.get_ref());
+    let out = env
+        .cmd()
+        .arg("--strip-metadata")
+        .arg(&anim)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("[WARN] --strip-metadata cannot rewrite gif images"));
+    let (_, script_args) = env.powershell_script();
+    assert!(script_args[0].ends_with("\\anim.gif"));
+}
+#[test]
+fn img_refuses_non_images_before_powershell() {
+    let env = FakeWsl::wsl2();
+    let notes = env.file("notes.txt", b"hello\n");
+    let out = env.cmd().arg("img").arg(&notes).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("is not an image: detected text/plain"));
+    assert!(env.calls().is_empty());
+}
+#[test]
+fn img_renders_svg_files_to_png() {
+    let env = FakeWsl::wsl2();
+    let logo = env.file(
+        "logo.svg",
+        b"<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"20\">\
+          <rect width=\"40\" height=\"20\" fill=\"#00f\"/></svg>",
+    );
+    let out = env.cmd().arg("img").arg(&logo).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Image to Clipboard (SVG rendered at 1024x512)"));
+    let (script, script_args) = env.powershell_script();
+    assert!(script.contains("$data.SetImage($img)"));
+    assert_eq!(script_args, ["C:\\Temp\\wsl-clip\\logo.png"]);
+    assert!(!env.windows_temp().join("wsl-clip/logo.png").exists());
+    let out = env
+        .cmd()
+        .args(["img", "--dpi", "192", "--max-dim", "50"])
+        .arg(&logo)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("(SVG rendered at 80x40; 80x40 -> 50x25)"));
+    let (_, script_args) = env.powershell_script();
+    assert_eq!(script_args, ["C:\\Temp\\wsl-clip\\logo-50x25.png"]);
+    // Malformed SVGs show the parser's reason; size flags need an SVG
+    let calls = env.calls().len();
+    let broken = env.file(
+        "broken.svg",
+        b"<svg xmlns=\"http://www.w3.org/2000/svg\"><rect",
+    );
+    let out = env.cmd().arg("img").arg(&broken).output().unwrap();
+    assert!(!out.status.success());
+    assert!(
+        stderr(&out).contains("is not a valid SVG: "),
+        "{}",
+        stderr(&out)
+    );
+    let png = env.file("shot.png", PNG);
+    let out = env
+        .cmd()
+        .args(["img", "--width", "10"])
+        .arg(&png)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("--width and --dpi only apply to SVG files"));
+    assert!(!env.calls()[calls..].contains(&"powershell.exe".to_string()));
+}
+#[test]
+fn image_on_stdin_is_staged_copied_and_removed() {
+    let env = FakeWsl::wsl2();
+    let out = env.cmd().arg("--image").write_stdin(PNG).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains(&format!(
+        "[OK] Copied Image to Clipboard ({} bytes from stdin)",
+        PNG.len()
+    )));
+    let (script, script_args) = env.powershell_script();
+    assert!(script.contains("$data.SetImage($img)"));
+    assert_eq!(script_args, ["C:\\Temp\\wsl-clip\\stdin-image.png"]);
+    // Gone once PowerShell is done with it, and after a failed copy too
+    let staged = env.windows_temp().join("wsl-clip/stdin-image.png");
+    assert!(!staged.exists());
+    env.powershell_fails("wsl-clip: A generic error occurred in GDI+.\n", 5);
+    let out = env.cmd().arg("--image").write_stdin(PNG).output().unwrap();
+    assert!(!out.status.success());
+    assert!(!staged.exists());
+    // Anything else is refused before PowerShell runs
+    let calls = env.calls().len();
+    let out = env
+        .cmd()
+        .arg("--image")
+        .write_stdin("not a picture\n")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("stdin is not a PNG, JPEG, GIF or BMP image"));
+    assert!(!env.calls()[calls..].contains(&"powershell.exe".to_string()));
+    let out = env.cmd().args(["--image", "notes.txt"]).output().unwrap();
+    assert!(stderr(&out).contains("cannot be used with"));
+}
+#[test]
+fn stdin_as_file_is_spooled_for_the_paste() {
+    let env = FakeWsl::wsl2();
+    let out = env
+        .cmd()
+        .args(["--as-file", "--name", "report.pdf"])
+        .write_stdin(PDF)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains(&format!(
+        "[OK] Copied report.pdf as a File Object ({} bytes from stdin)",
+        PDF.len()
+    )));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("SetFileDropList"));
+    assert_eq!(args.len(), 1);
+    assert!(args[0].starts_with("C:\\Temp\\wsl-clip\\spool\\"));
+    assert!(args[0].ends_with("\\report.pdf"));
+    // Still there for the paste
+    let spooled = env
+        .windows_temp()
+        .join(args[0]["C:\\Temp\\".len()..].replace('\\', "/"));
+    assert_eq!(std::fs::read(spooled).unwrap(), PDF);
+    // Empty stdin copies nothing
+    let calls = env.calls().len();
+    let out = env.cmd().arg("--as-file").write_stdin("").output().unwrap();
+    assert_eq!(out.status.code(), Some(4));
+    assert_eq!(env.calls().len(), calls);
+}
+#[test]
+fn zip_bundles_files_and_folders_into_one_file_object() {
+    let env = FakeWsl::wsl2();
+    let notes = env.file("notes.txt", b"hi\n");
+    let logs = notes.with_file_name("logs");
+    std::fs::create_dir(&logs).unwrap();
+    std::fs::write(logs.join("app.log"), "started\n").unwrap();
+    let out = env
+        .cmd()
+        .arg("zip")
+        .arg(&logs)
+        .arg(&notes)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied bundle.zip to Clipboard (3 entries, "));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("SetFileDropList"));
+    assert!(args[0].starts_with("C:\\Temp\\wsl-clip\\spool\\"));
+    assert!(args[0].ends_with("\\bundle.zip"));
+    // Kept for the paste, like --as-file
+    let spooled = env
+        .windows_temp()
+        .join(args[0]["C:\\Temp\\".len()..].replace('\\', "/"));
+    let archive = zip::ZipArchive::new(std::fs::File::open(spooled).unwrap()).unwrap();
+    let mut names: Vec<&str> = archive.file_names().collect();
+    names.sort();
+    assert_eq!(names, ["logs/", "logs/app.log", "notes.txt"]);
+    // One argument names the archive; missing paths are all listed
+    let out = env.cmd().arg("zip").arg(&logs).output().unwrap();
+    assert!(stdout(&out).contains("[OK] Copied logs.zip to Clipboard (2 entries, "));
+    let gone = notes.with_file_name("gone.log");
+    let out = env
+        .cmd()
+        .arg("zip")
+        .arg(&notes)
+        .arg(&gone)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("gone.log (does not exist)"));
+}
+/// A one-thread HTTP server answering `GET <path>` from `routes`
+/// (status, content type, body); anything else is a 404.
+fn serve(routes: Vec<(&'static str, u16, &'static str, Vec<u8>)>) -> String {
+    use std::io::{BufRead, BufReader, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let path = request.split(' ').nth(1).unwrap_or("").to_string();
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+            let (status, kind, body) = routes
+                .iter()
+                .find(|r| r.0 == path)
+                .map(|r| (r.1, r.2, r.3.clone()))
+                .unwrap_or((404, "text/plain", b"missing".to_vec()));
+            let head = format!(
+                "HTTP/1.1 {} X\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                kind,
+                body.len()
+            );
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(&body);
+        }
+    });
+    base
+}
+#[test]
+fn url_copies_text_images_and_files_by_content() {
+    let env = FakeWsl::wsl2();
+    let base = serve(vec![
+        (
+            "/notes",
+            200,
+            "text/plain; charset=utf-8",
+            b"one\ntwo\n".to_vec(),
+        ),
+        ("/shot", 200, "image/png", PNG.to_vec()),
+        ("/docs/report.pdf", 200, "application/pdf", PDF.to_vec()),
+        ("/big", 200, "text/plain", vec![b'x'; 4096]),
+        ("/broken", 500, "text/plain", b"oops".to_vec()),
+    ]);
+    let out = env
+        .cmd()
+        .args(["url", &format!("{}/notes", base), "--crlf"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Text from "));
+    assert_eq!(env.clipboard().as_deref(), Some("one\r\ntwo\r\n"));
+    // Downloaded text lands in history like any other text copy
+    env.config("[history]\nenabled = true\n");
+    let out = env
+        .cmd()
+        .args(["url", &format!("{}/notes", base)])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let out = env.cmd().args(["history", "list"]).output().unwrap();
+    let list = stdout(&out);
+    let rows: Vec<&str> = list.lines().skip(1).collect();
+    assert_eq!(rows.len(), 1, "{}", list);
+    assert!(rows[0].contains("  one"), "{}", list);
+    let out = env
+        .cmd()
+        .args(["url", &format!("{}/shot", base)])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Image from "));
+    let (script, _) = env.powershell_script();
+    assert!(script.contains("SetImage"));
+    let out = env
+        .cmd()
+        .args(["url", &format!("{}/docs/report.pdf", base)])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied report.pdf to Clipboard"));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("SetFileDropList"));
+    assert!(args[0].starts_with("C:\\Temp\\wsl-clip\\spool\\"));
+    assert!(args[0].ends_with("\\report.pdf"));
+    // Refused by Content-Length; errors carry the status
+    let out = env
+        .cmd()
+        .args(["url", &format!("{}/big", base), "--max-size", "1K"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("is 4096 bytes, over the 1024 byte limit"));
+    for (path, status) in [("/broken", "HTTP 500"), ("/gone", "HTTP 404")] {
+        let out = env
+            .cmd()
+            .args(["url", &format!("{}{}", base, path)])
+            .output()
+            .unwrap();
+        assert!(!out.status.success());
+        assert!(stderr(&out).contains(status), "{}", stderr(&out));
+    }
+}
+#[test]
+fn smart_multiple_images_become_file_objects() {
+    let env = FakeWsl::wsl2();
+    let a = env.file("a.png", PNG);
+    let b = env.file("b b.png", PNG);
+    let out = env.cmd().arg(&a).arg(&b).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied 2 Images as Files"));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("SetFileDropList"));
+    assert_eq!(args.len(), 2);
+    assert!(args[1].ends_with("\\b b.png"));
+}
+#[test]
+fn smart_documents_and_assets_are_file_objects() {
+    let env = FakeWsl::wsl2();
+    let pdf = env.file("invoice.pdf", PDF);
+    let stl = env.file("part.stl", b"solid part\nendsolid part\n");
+    let out = env.cmd().arg(&pdf).arg(&stl).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied 2 Files"));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("SetFileDropList"));
+    assert!(args[0].ends_with("\\invoice.pdf") && args[1].ends_with("\\part.stl"));
+}
+#[test]
+fn a_broken_config_file_warns_instead_of_failing_the_copy() {
+    let env = FakeWsl::wsl2();
+    env.config("[metrics\nenabled = true\n");
+    let out = env.cmd().write_stdin("x").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("[WARN] Invalid config file: "));
+    assert!(stderr(&out).contains("config.toml"));
+    assert!(stderr(&out).contains("; using the defaults"));
+    assert_eq!(env.clipboard().as_deref(), Some("x\n"));
+    // Asking for a profile needs the file, so then it is an error
+    let out = env
+        .cmd()
+        .args(["--profile", "review"])
+        .write_stdin("y")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("Invalid config file: "));
+    assert_eq!(env.clipboard().as_deref(), Some("x\n"));
+}
+#[test]
+fn asset_extensions_come_from_config_and_env() {
+    let env = FakeWsl::wsl2();
+    env.config("[classifier]\nasset_extensions = [\"parquet\"]\ntext_extensions = [\"svg\"]\n");
+    // Printable bytes, but the extension decides first
+    let table = env.file("data.PARQUET", b"col_a,col_b\n1,2\n");
+    let out = env.cmd().arg("--debug").arg(&table).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied 1 Files"));
+    assert!(stderr(&out).contains("Asset extensions: 7z, ai, bin,"));
+    assert!(stderr(&out).contains("parquet") && !stderr(&out).contains(" svg,"));
+    // An SVG edited by hand is text once removed
+    let logo = env.file("logo.svg", b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>\n");
+    let out = env.cmd().arg("--no-header").arg(&logo).output().unwrap();
+    assert!(
+        stdout(&out).contains("[OK] Copied Text"),
+        "{}",
+        stderr(&out)
+    );
+    // The environment has the last word
+    let out = env
+        .cmd()
+        .env("WSL_CLIP_ASSET_EXTS", "-parquet")
+        .arg("--no-header")
+        .arg(&table)
+        .output()
+        .unwrap();
+    assert!(
+        stdout(&out).contains("[OK] Copied Text"),
+        "{}",
+        stderr(&out)
+    );
+    assert_eq!(env.clipboard().as_deref(), Some("col_a,col_b\n1,2\n"));
+}
+#[test]
+fn known_text_extensions_skip_sniffing_unless_asked() {
+    let env = FakeWsl::wsl2();
+    let shot = env.file("shot.md", PNG);
+    let out = env.cmd().arg("--debug").arg(&shot).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("Classified as Text (text extension) in "));
+    let out = env
+        .cmd()
+        .args(["--debug", "--no-fast-classify"])
+        .arg(&shot)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("Classified as Image (image magic) in "));
+    assert!(
+        stdout(&out).contains("[OK] Copied Image"),
+        "{}",
+        stdout(&out)
+    );
+}
+#[test]
+fn classify_explains_each_decision() {
+    let env = FakeWsl::wsl2();
+    let shot = env.file("shot.dat", PNG);
+    let doc = env.file("report.pdf", PDF);
+    let notes = env.file("notes.md", b"# Notes\n");
+    let out = env
+        .cmd()
+        .arg("classify")
+        .args([&shot, &doc, &notes])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let lines: Vec<String> = stdout(&out).lines().map(str::to_string).collect();
+    assert_eq!(
+        lines,
+        [
+            format!("{}: Image (image magic, image/png)", shot.display()),
+            format!("{}: File (asset extension, application/pdf)", doc.display()),
+            format!("{}: Text (text extension)", notes.display()),
+        ]
+    );
+    let gone = shot.with_file_name("gone.dat");
+    let out = env
+        .cmd()
+        .args(["classify", "--json"])
+        .args([&shot, &gone])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let json: serde_json::Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(json[0]["strategy"], "image");
+    assert_eq!(json[0]["rule"], "image magic");
+    assert_eq!(json[0]["mime"], "image/png");
+    assert!(json[1]["error"]
+        .as_str()
+        .unwrap()
+        .contains("Failed to open file for classification"));
+    assert!(stderr(&out).contains("1 of 2 files could not be classified"));
+    assert!(env.calls().is_empty());
+}
+#[test]
+fn deep_classify_asks_file_about_doubtful_text() {
+    let mut env = FakeWsl::wsl2();
+    env.add_stub("file");
+    let mut lotus = b"\0\0\x02\0\x06\x04".to_vec();
+    lotus.extend(b"sheet one, row one\n".repeat(60));
+    let sheet = env.file("budget.wk1", &lotus);
+    env.file("budget.wk1.mime", b"application/vnd.lotus-1-2-3\n");
+    // `file` cannot tell either: the built-in decision stands
+    let odd = env.file("odd.dat", &lotus);
+    let out = env.cmd().arg("classify").arg(&sheet).output().unwrap();
+    assert!(stdout(&out).contains("Text (default)"));
+    assert!(env.calls().is_empty());
+    let out = env
+        .cmd()
+        .args(["--deep-classify", "classify"])
+        .args([&sheet, &odd, &sheet])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let text = stdout(&out);
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].ends_with("budget.wk1: File (file command)"));
+    assert!(lines[1].ends_with("odd.dat: Text (default)"));
+    assert_eq!(lines[2], lines[0]);
+    // Asked once per path
+    let asked = std::fs::read_to_string(env.log_path("file.paths")).unwrap();
+    assert_eq!(asked, format!("{}\n{}\n", sheet.display(), odd.display()));
+    // Plain text is not doubtful, so `file` is not run for it
+    let notes = env.file("notes", b"just words\n");
+    env.config("[classifier]\ndeep = true\n");
+    let out = env.cmd().arg("classify").arg(&notes).output().unwrap();
+    assert!(stdout(&out).contains("notes: Text (default)"));
+    assert_eq!(env.calls(), vec!["file", "file"]);
+}
+#[test]
+fn empty_files_are_left_out_of_text_bundles() {
+    let env = FakeWsl::wsl2();
+    let a = env.file("src/a.rs", b"fn a() {}\n");
+    let keep = env.file("src/.gitkeep", b"");
+    let b = env.file("src/b.rs", b"fn b() {}\n");
+    let files = [&a, &keep, &b];
+    let out = env.cmd().args(files).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Text from 2 of 3 files"));
+    let clip = env.clipboard().unwrap();
+    assert!(!clip.contains(&format!("# FILE: {}", keep.display())));
+    assert!(clip.contains("# TOTAL: 2 files, 2 lines, 20 bytes"));
+    assert!(clip.contains(&format!("# SKIPPED: {} (empty)", keep.display())));
+    let out = env.cmd().arg("--keep-empty").args(files).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Text from 3 of 3 files"));
+    let clip = env.clipboard().unwrap();
+    assert!(clip.contains(&format!("# FILE: {}", keep.display())));
+    assert!(clip.contains("# TOTAL: 3 files, 2 lines, 20 bytes"));
+    // Nothing but empty files: the clipboard is left alone
+    let out = env.cmd().arg(&keep).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("Every file is empty; nothing to copy"));
+    assert_eq!(env.calls(), vec!["clip.exe", "clip.exe"]);
+}
+#[test]
+fn as_forces_the_strategy_for_every_file() {
+    let env = FakeWsl::wsl2();
+    // NUL padding makes this a file object in smart mode
+    let log = env.file("app.log", b"started\n\0\0\0\0\0\0\xFF done\n");
+    let out = env.cmd().args(["--as", "text"]).arg(&log).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Text (forced with --as text)"));
+    let clip = env.clipboard().unwrap();
+    assert!(clip.contains("started\n") && clip.contains("\u{FFFD} done\n"));
+    let readme = env.file("README.md", b"# Read me\n");
+    let out = env
+        .cmd()
+        .args(["--as", "file"])
+        .arg(&readme)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied 1 Files (forced with --as file)"));
+    assert_eq!(
+        env.calls().last().map(String::as_str),
+        Some("powershell.exe")
+    );
+    let shot = env.file("shot.dat", PNG);
+    let out = env
+        .cmd()
+        .args(["--as", "image"])
+        .arg(&shot)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Image to Clipboard (forced with --as image)"));
+    let calls = env.calls().len();
+    let out = env
+        .cmd()
+        .args(["--as", "image"])
+        .args([&shot, &shot])
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("--as image takes exactly one file (2 given)"));
+    let out = env
+        .cmd()
+        .args(["--as", "image"])
+        .arg(&readme)
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("is not an image"));
+    let out = env
+        .cmd()
+        .args(["--as", "text", "img"])
+        .arg(&shot)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr(&out).contains("'--as <STRATEGY>' cannot be used with the 'img' subcommand"));
+    assert_eq!(env.calls().len(), calls);
+}
+#[test]
+fn split_copies_each_group_in_turn() {
+    let env = FakeWsl::wsl2();
+    let txt = env.file("a.txt", b"text\n");
+    let png = env.file("b.png", PNG);
+    // Nothing to press Enter with: refuse before the first copy
+    let out = env
+        .cmd()
+        .arg("--split")
+        .args([&txt, &png])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("stdin is not a terminal; pass --yes"));
+    assert!(env.calls().is_empty());
+    let out = env
+        .cmd()
+        .args(["--split", "--yes"])
+        .args([&txt, &png])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let text = stdout(&out);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "Mixed content: copying 2 groups in turn (text from 1 file, then the image); each replaces the one before",
+            "[1/2] Copied text from 1 file",
+            "Replacing the clipboard with the image (--yes)",
+            "[2/2] Copied the image",
+            "[OK] Split copy done: the clipboard now holds only the last group (the image)",
+        ]
+    );
+    assert_eq!(env.calls().first().map(String::as_str), Some("clip.exe"));
+    assert_eq!(
+        env.calls().last().map(String::as_str),
+        Some("powershell.exe")
+    );
+}
+#[test]
+fn mixed_content_is_rejected_before_any_tool_runs() {
+    let env = FakeWsl::wsl2();
+    let txt = env.file("a.txt", b"text\n");
+    let png = env.file("b.png", PNG);
+    let out = env.cmd().arg(&txt).arg(&png).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("Mixed content detected! (1 images, 0 files/assets, 1 text)"));
+    assert!(env.calls().is_empty());
+}
+#[test]
+fn stdin_is_copied_byte_for_byte() {
+    let env = FakeWsl::wsl2();
+    let input = "tab\there\nünïcödé ✓ 日本語\n👨\u{200D}👩\u{200D}👧\nlast line\n";
+    let out = env.cmd().write_stdin(input).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some(input));
+    env.cmd()
+        .arg("--crlf")
+        .write_stdin("a\nb\n")
+        .assert()
+        .success();
+    assert_eq!(env.clipboard().as_deref(), Some("a\r\nb\r\n"));
+}
+#[test]
+fn path_command_copies_the_windows_path() {
+    let env = FakeWsl::wsl2();
+    let path = env.file("doc.pdf", PDF);
+    env.cmd().arg("path").arg(&path).assert().success();
+    let clip = env.clipboard().unwrap();
+    assert!(clip.starts_with("\\\\wsl.localhost\\Test\\") && clip.ends_with("\\doc.pdf"));
+    assert_eq!(env.calls(), vec!["wslpath", "clip.exe"]);
+    // Files under the automount root are converted without wslpath
+    let drive = env.windows_temp().join("report.pdf");
+    std::fs::write(&drive, PDF).unwrap();
+    env.cmd().arg("path").arg(&drive).assert().success();
+    assert_eq!(env.clipboard().as_deref(), Some("C:\\Temp\\report.pdf"));
+    assert_eq!(env.calls(), vec!["wslpath", "clip.exe", "clip.exe"]);
+    // Several paths: quoted where they have a space, or one per line
+    let spaced = env.file("my notes.txt", b"hi\n");
+    let out = env
+        .cmd()
+        .arg("path")
+        .arg(&spaced)
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(stdout(&out).contains("[OK] Copied 2 Paths to Clipboard"));
+    let clip = env.clipboard().unwrap();
+    assert!(clip.starts_with("\"\\\\wsl.localhost\\Test\\"));
+    assert!(clip.contains("\\my notes.txt\" \\\\wsl.localhost\\"));
+    env.cmd()
+        .args(["path", "--sep", "newline"])
+        .arg(&spaced)
+        .arg(&path)
+        .assert()
+        .success();
+    let clip = env.clipboard().unwrap();
+    assert_eq!(clip.lines().count(), 2);
+    assert!(clip.lines().next().unwrap().ends_with("\\my notes.txt"));
+    // --quote quotes every path for one shell, after the conversion
+    env.cmd()
+        .args(["path", "--quote", "powershell"])
+        .arg(&spaced)
+        .arg(&path)
+        .assert()
+        .success();
+    let clip = env.clipboard().unwrap();
+    assert!(clip.starts_with("'\\\\wsl.localhost\\Test\\"));
+    assert!(clip.contains("\\my notes.txt' '\\\\wsl.localhost"));
+    assert!(clip.ends_with("\\doc.pdf'"));
+    // --slash: forward slashes, UNC prefix included, before the quoting
+    env.cmd()
+        .args(["path", "--slash", "--quote", "bash"])
+        .arg(&spaced)
+        .assert()
+        .success();
+    let clip = env.clipboard().unwrap();
+    assert!(clip.starts_with("//wsl.localhost/Test/"), "{}", clip);
+    assert!(clip.ends_with("/my\\ notes.txt"));
+}
+#[test]
+fn path_defaults_to_the_current_directory() {
+    let env = FakeWsl::wsl2();
+    let work = env.file("probe", b"").parent().unwrap().to_path_buf();
+    let out = env.cmd().args(["path", "--slash"]).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let expected = format!("//wsl.localhost/Test{}", work.display());
+    assert_eq!(env.clipboard().as_deref(), Some(expected.as_str()));
+    assert!(stdout(&out).contains(&format!("[OK] Copied Path to Clipboard: {}", expected)));
+    // A working directory deleted underneath the shell
+    let out = env
+        .command("sh")
+        .arg("-c")
+        .arg(format!(
+            "mkdir gone && cd gone && rmdir ../gone && exec {} path",
+            BIN
+        ))
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(
+        stderr(&out).contains("The current directory no longer exists"),
+        "{}",
+        stderr(&out)
+    );
+}
+#[test]
+fn path_literal_converts_files_that_do_not_exist_yet() {
+    let env = FakeWsl::wsl2();
+    let work = env.file("probe", b"").parent().unwrap().to_path_buf();
+    let out = env
+        .cmd()
+        .args(["path", "./dist/../out/app.zip"])
+        .output()
+        .unwrap();
+    assert!(
+        !out.status.success(),
+        "missing files fail without --literal"
+    );
+    let out = env
+        .cmd()
+        .args(["path", "--literal", "./dist/../out/app.zip"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let expected = format!(
+        "\\\\wsl.localhost\\Test{}\\out\\app.zip",
+        work.to_string_lossy().replace('/', "\\")
+    );
+    assert_eq!(env.clipboard().as_deref(), Some(expected.as_str()));
+    assert!(std::fs::read_to_string(env.log_path("wslpath.flags"))
+        .unwrap()
+        .contains("-a"));
+    // Absolute input, and --wsl, stay lexical too
+    env.cmd()
+        .args(["path", "--literal", "--wsl", "/srv/a/../b/./c.txt"])
+        .assert()
+        .success();
+    assert_eq!(env.clipboard().as_deref(), Some("/srv/b/c.txt"));
+}
+#[test]
+fn path_unc_respells_the_wsl_share() {
+    let env = FakeWsl::wsl2();
+    let path = env.file("doc.pdf", PDF);
+    let tail = path.to_string_lossy().replace('/', "\\");
+    env.cmd()
+        .args(["path", "--unc", "wsl$"])
+        .arg(&path)
+        .assert()
+        .success();
+    assert_eq!(env.clipboard(), Some(format!("\\\\wsl$\\Test{}", tail)));
+    // A config default, which the flag still overrides; drive paths never change
+    env.config("[paths]\nunc = \"wsl$\"\n");
+    env.cmd().arg("path").arg(&path).assert().success();
+    assert_eq!(env.clipboard(), Some(format!("\\\\wsl$\\Test{}", tail)));
+    env.cmd()
+        .args(["path", "--unc", "localhost"])
+        .arg(&path)
+        .assert()
+        .success();
+    assert_eq!(
+        env.clipboard(),
+        Some(format!("\\\\wsl.localhost\\Test{}", tail))
+    );
+    let drive = env.windows_temp().join("notes.txt");
+    std::fs::write(&drive, "hi\n").unwrap();
+    env.cmd().arg("path").arg(&drive).assert().success();
+    assert_eq!(env.clipboard().as_deref(), Some("C:\\Temp\\notes.txt"));
+}
+#[test]
+fn missing_wslpath_only_fails_paths_that_need_it() {
+    let mut env = FakeWsl::linux();
+    env.remove_stub("wslpath");
+    let cmd = || {
+        let mut cmd = env.cmd();
+        cmd.env("WAYLAND_DISPLAY", "wayland-0");
+        cmd
+    };
+    // Text copies never needed a Windows path
+    cmd().write_stdin("laptop\n").assert().success();
+    assert_eq!(env.clipboard().as_deref(), Some("laptop\n"));
+    // Drive files convert in-process
+    let drive = env.windows_temp().join("notes.txt");
+    std::fs::create_dir_all(drive.parent().unwrap()).unwrap();
+    std::fs::write(&drive, "hi\n").unwrap();
+    cmd().arg("path").arg(&drive).assert().success();
+    assert_eq!(env.clipboard().as_deref(), Some("C:\\Temp\\notes.txt"));
+    // Anything else says why, instead of a bare spawn failure
+    let file = env.file("a.txt", b"x\n");
+    let out = cmd().args(["--debug", "path"]).arg(&file).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("wslpath: not found"));
+    assert!(
+        stderr(&out).contains("wslpath is not available: this is not WSL"),
+        "{}",
+        stderr(&out)
+    );
+    assert!(!env.calls().contains(&"wslpath".to_string()));
+}
+#[test]
+fn path_wsl_and_both_cover_both_filesystem_roots() {
+    let env = FakeWsl::wsl2();
+    let linux = env.file("doc.pdf", PDF);
+    let drive = env.windows_temp().join("report.txt");
+    std::fs::write(&drive, "hi\n").unwrap();
+    let canonical = |p: &std::path::Path| {
+        std::fs::canonicalize(p)
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    };
+    // --wsl never asks wslpath
+    let out = env
+        .cmd()
+        .args(["path", "--wsl"])
+        .arg(&drive)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied WSL Path to Clipboard"));
+    assert_eq!(env.clipboard().as_deref(), Some(canonical(&drive).as_str()));
+    assert!(canonical(&drive).ends_with("/mnt/c/Temp/report.txt"));
+    assert!(!env.calls().contains(&"wslpath".to_string()));
+    // --both: the Windows line, then the WSL line, per file
+    let out = env
+        .cmd()
+        .args(["path", "--both"])
+        .arg(&drive)
+        .arg(&linux)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied 2 Windows and WSL Paths to Clipboard"));
+    let clip = env.clipboard().unwrap();
+    let lines: Vec<&str> = clip.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "C:\\Temp\\report.txt");
+    assert_eq!(lines[1], canonical(&drive));
+    assert!(lines[2].starts_with("\\\\wsl.localhost\\Test\\") && lines[2].ends_with("\\doc.pdf"));
+    assert_eq!(lines[3], canonical(&linux));
+}
+#[test]
+fn no_follow_copies_the_link_instead_of_its_target() {
+    let env = FakeWsl::wsl2();
+    let real = env.file("real.pdf", PDF);
+    let link = real.with_file_name("link.pdf");
+    std::os::unix::fs::symlink("real.pdf", &link).unwrap();
+    let stale = real.with_file_name("stale.txt");
+    std::os::unix::fs::symlink("gone.txt", &stale).unwrap();
+    // --follow is the default
+    for flags in [&[][..], &["--follow"]] {
+        env.cmd()
+            .arg("path")
+            .args(flags)
+            .arg(&link)
+            .assert()
+            .success();
+        assert!(env.clipboard().unwrap().ends_with("\\real.pdf"));
+    }
+    env.cmd()
+        .args(["path", "--no-follow"])
+        .arg(&link)
+        .assert()
+        .success();
+    assert!(env.clipboard().unwrap().ends_with("\\link.pdf"));
+    env.cmd()
+        .args(["path", "--wsl", "--no-follow"])
+        .arg(&link)
+        .assert()
+        .success();
+    assert!(env.clipboard().unwrap().ends_with("/link.pdf"));
+    // A dangling link: a clear error when followed, its own path when not
+    let out = env.cmd().arg("path").arg(&stale).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("stale.txt is a symlink to gone.txt, which does not exist"));
+    env.cmd()
+        .args(["path", "--no-follow"])
+        .arg(&stale)
+        .assert()
+        .success();
+    assert!(env.clipboard().unwrap().ends_with("\\stale.txt"));
+    let out = env.cmd().arg(&stale).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains(
+        "stale.txt is a symlink to gone.txt, which does not exist (--no-follow copies the link itself as a file)"
+    ));
+    let out = env
+        .cmd()
+        .args(["--no-follow", "--as", "text"])
+        .arg(&stale)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("which does not exist; text mode has nothing to read"));
+    // Smart mode sends it as a file object under its own name
+    let out = env.cmd().arg("--no-follow").arg(&stale).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("SetFileDropList"));
+    assert!(args[0].ends_with("\\stale.txt"));
+    // zip takes the global flag too
+    env.cmd()
+        .args(["zip", "--no-follow"])
+        .arg(&stale)
+        .assert()
+        .success();
+}
+#[test]
+fn stub_failures_propagate() {
+    let env = FakeWsl::wsl2();
+    let txt = env.file("a.txt", b"text\n");
+    let png = env.file("b.png", PNG);
+    let out = env
+        .cmd()
+        .arg(&txt)
+        .env("STUB_FAIL", "clip.exe")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("clip.exe exited with error status"));
+    assert!(env.clipboard().is_none());
+    let out = env
+        .cmd()
+        .arg(&png)
+        .env("STUB_FAIL", "powershell.exe")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains(
+        "Failed to set image: powershell.exe: simulated failure (PowerShell exited with status 1)"
+    ));
+    let out = env
+        .cmd()
+        .arg(&png)
+        .env("STUB_FAIL", "wslpath")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("wslpath failed: wslpath: simulated failure"));
+    // No clip.exe on PATH at all: the interop hint is shown
+    let out = env
+        .cmd()
+        .arg(&txt)
+        .env("PATH", "/usr/bin:/bin")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("Windows interop looks disabled"));
+}
+#[test]
+fn wsl1_rejects_linux_filesystem_objects() {
+    let env = FakeWsl::wsl1();
+    let pdf = env.file("report.pdf", PDF);
+    let out = env.cmd().arg(&pdf).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("WSL1"));
+    assert!(env.calls().is_empty());
+}
+#[test]
+fn max_size_breach_commits_nothing() {
+    let env = FakeWsl::wsl2();
+    let big = "0123456789abcdef\n".repeat(64 * 1024);
+    let out = env
+        .cmd()
+        .args(["--max-size", "4K"])
+        .write_stdin(big.clone())
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(3));
+    assert!(stderr(&out).contains("--max-size 4096 bytes (4096 bytes accepted before the limit)"));
+    assert!(env.clipboard().is_none());
+    // Under the limit the copy goes through
+    env.cmd()
+        .args(["--max-size", "4M"])
+        .write_stdin(big.clone())
+        .assert()
+        .success();
+    assert_eq!(env.clipboard(), Some(big));
+}
+#[test]
+fn peek_reads_through_powershell() {
+    let env = FakeWsl::wsl2();
+    env.powershell_responds("TEXT\n{\"a\": 1}\n");
+    let out = env.cmd().arg("peek").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("{\"a\": 1}\n--- 9 bytes, 1 lines\nHints: looks like JSON"));
+    assert!(env.powershell_args().contains(&"-STA".to_string()));
+}
+#[test]
+fn paste_writes_clipboard_text_to_stdout() {
+    let env = FakeWsl::wsl2();
+    env.powershell_responds("TEXT\nfoo one\r\nbar\r\nfoo two\r\n");
+    let out = env.cmd().args(["paste", "--debug"]).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(stdout(&out), "foo one\r\nbar\r\nfoo two\r\n");
+    assert!(stderr(&out).contains("Clipboard format: text"));
+    assert!(stderr(&out).contains("Pasted 23 bytes"));
+    assert!(env.calls().iter().all(|c| c == "powershell.exe"));
+    env.powershell_responds("EMPTY");
+    let out = env.cmd().arg("paste").output().unwrap();
+    assert!(!out.status.success());
+    assert!(stdout(&out).is_empty());
+    assert!(stderr(&out).contains("Clipboard is empty; nothing to paste"));
+    env.powershell_responds("image (bitmap)");
+    let out = env.cmd().arg("paste").output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("non-text data: image (bitmap)"));
+}
+#[test]
+fn paste_lf_writes_a_normalized_file() {
+    let env = FakeWsl::wsl2();
+    env.powershell_responds("TEXT\n\u{feff}one\r\ntwo\rthree\r\nlast");
+    let out = env
+        .cmd()
+        .args(["paste", "--lf", "-o", "out/pasted.txt"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success(), "missing parent directory must fail");
+    env.file("out/.keep", b"");
+    let out = env
+        .cmd()
+        .args(["paste", "--lf", "-o", "out/pasted.txt"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let pasted = env.file("out/.keep", b"").with_file_name("pasted.txt");
+    assert_eq!(
+        std::fs::read_to_string(&pasted).unwrap(),
+        "one\ntwo\rthree\nlast"
+    );
+    assert!(stdout(&out).contains("[OK] Pasted 18 bytes to out/pasted.txt"));
+    // Created like any new file (0666 less the umask), not 0600
+    use std::os::unix::fs::PermissionsExt;
+    let mode = |p: &std::path::Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&pasted), mode(&pasted.with_file_name(".keep")));
+    // Replacing a file keeps its mode
+    std::fs::set_permissions(&pasted, std::fs::Permissions::from_mode(0o640)).unwrap();
+    env.powershell_responds("TEXT\nagain");
+    env.cmd()
+        .args(["paste", "-o", "out/pasted.txt"])
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(&pasted).unwrap(), "again");
+    assert_eq!(mode(&pasted), 0o640);
+    // A failed paste leaves the previous file alone
+    env.powershell_responds("EMPTY");
+    let out = env
+        .cmd()
+        .args(["paste", "-o", "out/pasted.txt"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert_eq!(std::fs::read_to_string(&pasted).unwrap(), "again");
+}
+#[test]
+fn paste_image_saves_the_screenshot_into_wsl() {
+    let env = FakeWsl::wsl2();
+    std::fs::write(env.windows_temp().join("wsl-clip-1.png"), PNG).unwrap();
+    env.powershell_responds("SAVED\nC:\\Temp\\wsl-clip-1.png");
+    let out = env
+        .cmd()
+        .args(["paste", "--image", "shot.png"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let shot = env.file("probe", b"").with_file_name("shot.png");
+    assert_eq!(std::fs::read(&shot).unwrap(), PNG);
+    assert!(!env.windows_temp().join("wsl-clip-1.png").exists());
+    assert!(stdout(&out).contains(&format!(
+        "[OK] Saved clipboard image to shot.png ({} bytes)",
+        PNG.len()
+    )));
+    assert!(env
+        .powershell_args()
+        .iter()
+        .any(|a| a.contains("ImageFormat]::Png")));
+    env.powershell_responds("NOIMAGE");
+    let out = env
+        .cmd()
+        .args(["paste", "--image", "b.jpg"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("Clipboard has no image"));
+    assert!(env
+        .powershell_args()
+        .iter()
+        .any(|a| a.contains("ImageFormat]::Jpeg")));
+    let calls = env.calls().len();
+    let out = env
+        .cmd()
+        .args(["paste", "--image", "c.gif"])
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("use a .png, .jpg or .jpeg file name"));
+    assert_eq!(
+        env.calls().len(),
+        calls,
+        "no Windows call for a bad extension"
+    );
+}
+#[test]
+fn shot_captures_to_the_clipboard_and_a_file() {
+    let env = FakeWsl::wsl2();
+    env.powershell_responds("SHOT\n1920x1080\n");
+    let out = env.cmd().arg("shot").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Screenshot to Clipboard (1920x1080)"));
+    let script = env.powershell_args().join("\n");
+    assert!(script.contains("PrimaryScreen.Bounds"));
+    assert!(script.contains("CopyFromScreen"));
+    // --out: Windows saves a copy, which moves into WSL
+    std::fs::write(env.windows_temp().join("wsl-clip-2.png"), PNG).unwrap();
+    env.powershell_responds("SHOT\n3840x1080\nC:\\Temp\\wsl-clip-2.png");
+    let out = env
+        .cmd()
+        .args(["shot", "--all-screens", "--out", "desk.png"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains(&format!(
+        "(3840x1080), saved to desk.png ({} bytes)",
+        PNG.len()
+    )));
+    let desk = env.file("probe", b"").with_file_name("desk.png");
+    assert_eq!(std::fs::read(&desk).unwrap(), PNG);
+    assert!(!env.windows_temp().join("wsl-clip-2.png").exists());
+    assert!(env.powershell_args().join("\n").contains("VirtualScreen"));
+    // --region waits for the snip, and says so when none comes
+    env.powershell_responds("TIMEOUT");
+    let out = env
+        .cmd()
+        .args(["shot", "--region", "--wait", "5", "--poll", "100ms"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("No snip reached the clipboard in time"));
+    let script = env.powershell_args().join("\n");
+    assert!(script.contains("ms-screenclip:"));
+    assert!(script.contains("AddMilliseconds(5000)"));
+}
+#[test]
+fn clear_now_and_after_a_delay() {
+    let env = FakeWsl::wsl2();
+    let out = env.cmd().arg("clear").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Clipboard cleared"));
+    assert!(env
+        .powershell_args()
+        .iter()
+        .any(|a| a.contains("Clipboard]::Clear()")));
+    std::fs::remove_file(env.log_path("powershell.args")).unwrap();
+    // The command returns at once; a background process clears later
+    let started = std::time::Instant::now();
+    let out = env.cmd().args(["clear", "--after", "2"]).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    assert!(stdout(&out).contains("[OK] Clipboard will be cleared in 2s (background pid "));
+    assert!(env.powershell_args().is_empty());
+    let deadline = started + std::time::Duration::from_secs(10);
+    while env.powershell_args().is_empty() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    assert!(started.elapsed() >= std::time::Duration::from_secs(2));
+    assert!(env
+        .powershell_args()
+        .iter()
+        .any(|a| a.contains("Clipboard]::Clear()")));
+}
+#[test]
+fn inspect_lists_clipboard_formats() {
+    let env = FakeWsl::wsl2();
+    env.powershell_responds(
+        "FORMAT\tHTML Format\r\nFORMAT\tUnicodeText\r\nFORMAT\tText\r\nTEXT\t11\r\n",
+    );
+    let out = env.cmd().arg("inspect").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let table = stdout(&out);
+    assert!(table.starts_with("Format       Description\n"));
+    assert!(table.contains("HTML Format  HTML (rich paste into Word, Teams, browsers)\n"));
+    assert!(table.contains("Text:  11 characters"));
+    env.powershell_responds("FORMAT\tPNG\nFORMAT\tBitmap\nIMAGE\t800x600\n");
+    let out = env.cmd().args(["inspect", "--json"]).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let json: serde_json::Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(json["formats"][0]["name"], "PNG");
+    assert_eq!(json["formats"][0]["description"], "PNG image");
+    assert_eq!(json["image"]["width"], 800);
+    assert_eq!(json["text_length"], serde_json::Value::Null);
+    env.powershell_responds("");
+    let out = env.cmd().arg("inspect").output().unwrap();
+    assert_eq!(stdout(&out), "Clipboard is empty\n");
+}
+#[test]
+fn copies_succeed_without_home() {
+    let env = FakeWsl::wsl2();
+    let out = env
+        .cmd()
+        .env_remove("HOME")
+        .env_remove("WSL_CLIP_CONFIG")
+        .arg("--debug")
+        .write_stdin("no home\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some("no home\n"));
+    assert!(stderr(&out).contains("No HOME or XDG config directory; using defaults"));
+}
+#[test]
+fn unwritable_data_dir_disables_history_only() {
+    let env = FakeWsl::wsl2();
+    env.config("[history]\nenabled = true\n");
+    let blocker = env.file("blocker", b"");
+    let out = env
+        .cmd()
+        .env("WSL_CLIP_DATA_DIR", blocker.join("data"))
+        .arg("--debug")
+        .write_stdin("still copied\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some("still copied\n"));
+    assert!(stderr(&out).contains("History disabled for this copy: No usable history directory"));
+    assert!(stderr(&out).contains("is not writable"));
+}
+#[test]
+fn history_lists_and_restores_recent_copies() {
+    let env = FakeWsl::wsl2();
+    env.config("[history]\nenabled = true\nmax_entries = 2\n");
+    let copy = |text: &str, extra: &[&str]| {
+        let out = env.cmd().args(extra).write_stdin(text).output().unwrap();
+        assert!(out.status.success(), "{}", stderr(&out));
+    };
+    copy("hunter2\n", &["--no-history"]);
+    for text in ["one\n", "two\n", "three\n"] {
+        copy(text, &[]);
+    }
+    let out = env.cmd().args(["history", "list"]).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let list = stdout(&out);
+    let rows: Vec<&str> = list.lines().skip(1).collect();
+    assert_eq!(rows.len(), 2, "{}", list);
+    assert!(rows[0].ends_with("  three") && rows[1].ends_with("  two"));
+    assert!(!list.contains("hunter2"));
+    let id = rows[1].split_whitespace().next().unwrap();
+    let out = env
+        .cmd()
+        .args(["history", "restore", &id[..id.len() - 2]])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some("two\n"));
+    assert!(stdout(&out).contains(&format!("[OK] Restored {} (4 bytes)", id)));
+    let out = env
+        .cmd()
+        .args(["history", "restore", "nope"])
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("No history entry 'nope'"));
+}
+#[test]
+fn registers_save_and_load() {
+    let env = FakeWsl::wsl2();
+    let out = env
+        .cmd()
+        .args(["save", "snippet"])
+        .write_stdin("kubectl get pods\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Saved 17 bytes to register 'snippet'"));
+    assert_eq!(env.clipboard(), None, "save does not touch the clipboard");
+    env.file("notes.txt", b"line\n");
+    let out = env
+        .cmd()
+        .args(["save", "notes", "notes.txt", "--no-header"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let out = env.cmd().args(["save", "--list"]).output().unwrap();
+    let list = stdout(&out);
+    assert!(list.starts_with("NAME   "), "{}", list);
+    assert!(list.contains("notes  ") && list.contains("snippet         17  "));
+    let out = env.cmd().args(["load", "snippet"]).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some("kubectl get pods\n"));
+    let out = env.cmd().args(["load", "missing"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr(&out).contains("No register named 'missing'"));
+    let out = env.cmd().args(["save", "../escape"]).output().unwrap();
+    assert!(stderr(&out).contains("invalid register name '../escape'"));
+}
+#[test]
+fn watch_recopies_on_change_and_stops_on_ctrl_c() {
+    let env = FakeWsl::wsl2();
+    let file = env.file("notes.md", b"v1\n");
+    let child = env
+        .command(BIN)
+        .args(["--watch", "-n", "notes.md"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let wait_for = |want: &str| {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while env.clipboard().as_deref() != Some(want) {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "never copied {:?}",
+                want
+            );
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    };
+    wait_for("v1\n");
+    std::fs::write(&file, "v2\n").unwrap();
+    wait_for("v2\n");
+    // Atomic save: write a temp file and rename it over the original
+    let tmp = file.with_file_name(".notes.md.tmp");
+    std::fs::write(&tmp, "v3\n").unwrap();
+    std::fs::rename(&tmp, &file).unwrap();
+    wait_for("v3\n");
+    std::fs::remove_file(&file).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    std::fs::write(&file, "v4\n").unwrap();
+    wait_for("v4\n");
+    let status = std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let printed = stdout(&out);
+    assert!(printed.contains("[OK] Copied 3 bytes; watching 1 file(s)"));
+    assert!(printed.contains("[OK] Re-copied 3 bytes at "));
+    assert!(printed.ends_with("[OK] Stopped watching\n"));
+    assert!(!stderr(&out).contains("WARN"), "{}", stderr(&out));
+    // Reading the file for a copy does not trigger another copy
+    let copies = env.calls().iter().filter(|c| *c == "clip.exe").count();
+    assert!((4..=6).contains(&copies), "{} copies", copies);
+}
+#[test]
+fn provider_copy_and_paste_round_trip_byte_for_byte() {
+    let env = FakeWsl::wsl2();
+    env.config("[defaults]\ncrlf = true\ncode = true\n");
+    for payload in [
+        "",
+        "word",
+        "line\n",
+        "two\nlines\n\n",
+        "\x1b[1mbold\x1b[0m\tno newline",
+    ] {
+        let out = env
+            .cmd()
+            .args(["--provider", "copy"])
+            .write_stdin(payload)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", stderr(&out));
+        assert!(stdout(&out).is_empty());
+        // No header, no stripping, no fences, no newline added or removed
+        assert_eq!(env.clipboard().as_deref(), Some(payload));
+        env.powershell_responds(&format!("TEXT\n{}", payload));
+        let out = env.cmd().args(["--provider", "paste"]).output().unwrap();
+        assert!(out.status.success(), "{}", stderr(&out));
+        assert_eq!(stdout(&out), payload);
+    }
+    env.cmd()
+        .args(["--provider", "copy", "--crlf"])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success();
+    assert_eq!(env.clipboard().as_deref(), Some("a\r\nb\r\n"));
+    env.powershell_responds("TEXT\na\r\nb\r\n");
+    let out = env.cmd().args(["--provider", "paste"]).output().unwrap();
+    assert_eq!(stdout(&out), "a\r\nb\r\n");
+    let out = env
+        .cmd()
+        .args(["--provider", "paste", "--lf"])
+        .output()
+        .unwrap();
+    assert_eq!(stdout(&out), "a\nb\n");
+    // Nothing to paste is an empty register, not an error
+    env.powershell_responds("EMPTY");
+    let out = env.cmd().args(["--provider", "paste"]).output().unwrap();
+    assert!(out.status.success() && out.stdout.is_empty());
+    let out = env
+        .cmd()
+        .args(["--provider", "paste", "--crlf"])
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("--crlf applies to --provider copy"));
+}
+#[test]
+fn osc52_backend_writes_text_to_the_terminal() {
+    use base64::Engine;
+    let decode = |seq: &str| {
+        let b64 = seq
+            .strip_prefix("\x1b]52;c;")
+            .and_then(|s| s.strip_suffix('\x07'))
+            .unwrap_or_else(|| panic!("not an OSC 52 sequence: {:?}", seq));
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(b64)
+            .unwrap();
+        String::from_utf8(bytes).unwrap()
+    };
+    let env = FakeWsl::wsl2();
+    env.attach_terminal();
+    env.cmd()
+        .args(["--backend", "osc52"])
+        .write_stdin("remote ✓\n")
+        .assert()
+        .success();
+    assert_eq!(decode(&env.terminal()), "remote ✓\n");
+    assert!(env.calls().is_empty());
+    // Auto: no clip.exe on PATH, but a terminal is attached
+    env.attach_terminal();
+    let out = env
+        .cmd()
+        .env("PATH", "/usr/bin:/bin")
+        .write_stdin("auto\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(decode(&env.terminal()), "auto\n");
+    // Past the terminal limit: a warning and a truncated copy
+    env.attach_terminal();
+    let out = env
+        .cmd()
+        .args(["--backend", "osc52"])
+        .write_stdin("z".repeat(79_999) + "\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("[WARN] OSC 52: 80000 bytes is over the ~100 KB terminal limit; copied the first 75000 bytes"));
+    assert_eq!(decode(&env.terminal()).len(), 75_000);
+    // Image, file and read operations need Windows
+    let png = env.file("shot.png", PNG);
+    let out = env
+        .cmd()
+        .args(["--backend", "osc52"])
+        .arg(&png)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out)
+        .contains("Copying files as Image/File objects needs clip.exe/PowerShell, but the osc52 backend is in use (selected with --backend)"));
+    let out = env
+        .cmd()
+        .args(["--backend", "osc52", "paste"])
+        .output()
+        .unwrap();
+    assert!(stderr(&out)
+        .contains("Reading the clipboard needs clip.exe/PowerShell, but the osc52 backend"));
+    assert!(env.calls().is_empty());
+}
+#[test]
+fn native_linux_backends_copy_text_and_images() {
+    let env = FakeWsl::linux();
+    let args =
+        |tool: &str| std::fs::read_to_string(env.log_path(&format!("{}.args", tool))).unwrap();
+    let out = env
+        .cmd()
+        .env("WAYLAND_DISPLAY", "wayland-0")
+        .env("DISPLAY", ":0")
+        .arg("--debug")
+        .write_stdin("laptop\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out)
+        .contains("Backend: wayland (not WSL; WAYLAND_DISPLAY is set and wl-copy is installed)"));
+    assert_eq!(env.clipboard().as_deref(), Some("laptop\n"));
+    assert_eq!(args("wl-copy"), "--type\ntext/plain;charset=utf-8\n");
+    let png = env.file("shot.png", PNG);
+    let out = env.cmd().env("DISPLAY", ":0").arg(&png).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(std::fs::read(env.log_path("clipboard")).unwrap(), PNG);
+    assert_eq!(args("xclip"), "-selection\nclipboard\n-t\nimage/png\n-in\n");
+    assert_eq!(env.calls(), vec!["wl-copy", "xclip"]);
+    // File objects are a Windows concept: the error names the backend and why
+    let pdf = env.file("a.pdf", PDF);
+    let out = env.cmd().env("DISPLAY", ":0").arg(&pdf).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("needs clip.exe/PowerShell, but the x11 backend is in use (not WSL; DISPLAY is set and xclip is installed)"), "{}", stderr(&out));
+    // --backend wins over detection
+    env.cmd()
+        .args(["--backend", "x11"])
+        .env("WAYLAND_DISPLAY", "wayland-0")
+        .write_stdin("forced\n")
+        .assert()
+        .success();
+    assert_eq!(env.calls().last().map(String::as_str), Some("xclip"));
+    // No display and no terminal: the hint names the missing tools
+    let out = env
+        .cmd()
+        .env("PATH", "/usr/bin:/bin")
+        .write_stdin("x\n")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(
+        stderr(&out).contains("install wl-clipboard or xclip"),
+        "{}",
+        stderr(&out)
+    );
+}
+/// `wsl-clip daemon`, once its socket is up.
+fn start_daemon(env: &FakeWsl) -> std::process::Child {
+    let daemon = env
+        .command(BIN)
+        .arg("daemon")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !env.daemon_socket().exists() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "daemon never listened"
+        );
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    daemon
+}
+#[test]
+fn daemon_routes_copies_and_pastes_through_one_warm_shell() {
+    let env = FakeWsl::wsl2();
+    let daemon = start_daemon(&env);
+    let out = env.cmd().arg("daemon").output().unwrap();
+    assert!(
+        stderr(&out).contains("already listening"),
+        "{}",
+        stderr(&out)
+    );
+    env.cmd().write_stdin("first\n").assert().success();
+    env.cmd().write_stdin("héllo\nworld\n").assert().success();
+    assert_eq!(env.clipboard().as_deref(), Some("héllo\nworld\n"));
+    let out = env.cmd().arg("paste").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(stdout(&out), "héllo\nworld\n");
+    let pdf = env.file("a.pdf", PDF);
+    let out = env.cmd().arg(&pdf).output().unwrap();
+    assert!(
+        stdout(&out).contains("[OK] Copied 1 Files"),
+        "{}",
+        stderr(&out)
+    );
+    let dropped = std::fs::read_to_string(env.log_path("clipboard.files")).unwrap();
+    assert!(dropped.trim_end().ends_with("\\a.pdf"), "{}", dropped);
+    // Two copies, a paste and a drop list, but only the one warm powershell.exe
+    assert_eq!(env.calls(), vec!["powershell.exe", "wslpath"]);
+    let out = env.cmd().args(["daemon", "stop"]).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let out = daemon.wait_with_output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).ends_with("[OK] Daemon stopped\n"));
+    assert!(!env.daemon_socket().exists());
+    // A stale socket file falls back to clip.exe without complaint
+    std::os::unix::net::UnixListener::bind(env.daemon_socket()).unwrap();
+    let out = env.cmd().write_stdin("direct\n").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).is_empty(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some("direct\n"));
+    assert_eq!(env.calls().last().map(String::as_str), Some("clip.exe"));
+    let out = env.cmd().args(["daemon", "stop"]).output().unwrap();
+    assert!(stderr(&out).contains("No daemon is listening"));
+}
+#[test]
+fn daemon_serves_a_copy_while_another_waits_on_its_producer() {
+    use std::io::Write;
+    let env = FakeWsl::wsl2();
+    let daemon = start_daemon(&env);
+    // `tail -f | wsl-clip`: input trickles in and stdin stays open
+    let mut slow = env
+        .command(BIN)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut producer = slow.stdin.take().unwrap();
+    // Past every sniffing read-ahead, so the copy is streaming
+    let head = "slow line\n".repeat(10_000);
+    producer.write_all(head.as_bytes()).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let started = std::time::Instant::now();
+    let out = env.cmd().write_stdin("fast\n").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(
+        started.elapsed() < std::time::Duration::from_secs(5),
+        "the second copy waited {:?}",
+        started.elapsed()
+    );
+    assert_eq!(env.clipboard().as_deref(), Some("fast\n"));
+    producer.write_all(b"producer\n").unwrap();
+    drop(producer);
+    let out = slow.wait_with_output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard(), Some(head + "producer\n"));
+    // Past the buffering limit the text streams to clip.exe instead
+    let big = "0123456789abcdef\n".repeat(300_000);
+    let out = env.cmd().write_stdin(big.clone()).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.calls(), vec!["powershell.exe", "clip.exe"]);
+    assert_eq!(env.clipboard().map(|c| c.len()), Some(big.len()));
+    env.cmd().args(["daemon", "stop"]).assert().success();
+    daemon.wait_with_output().unwrap();
+}
+#[test]
+fn daemon_socket_directory_must_be_private() {
+    use std::os::unix::fs::PermissionsExt;
+    let env = FakeWsl::wsl2();
+    let run = env.daemon_socket().parent().unwrap().to_path_buf();
+    let open = std::fs::Permissions::from_mode(0o755);
+    std::fs::set_permissions(&run, open.clone()).unwrap();
+    let out = env.cmd().arg("daemon").output().unwrap();
+    assert!(!out.status.success());
+    assert!(
+        stderr(&out).contains("is open to other users (mode 755)"),
+        "{}",
+        stderr(&out)
+    );
+    // A directory opened up after the daemon started is not trusted either
+    std::fs::set_permissions(&run, std::fs::Permissions::from_mode(0o700)).unwrap();
+    let daemon = start_daemon(&env);
+    std::fs::set_permissions(&run, open).unwrap();
+    let out = env.cmd().write_stdin("direct\n").output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("[WARN] Not using the wsl-clip daemon"));
+    assert_eq!(env.calls().last().map(String::as_str), Some("clip.exe"));
+    assert_eq!(env.clipboard().as_deref(), Some("direct\n"));
+    env.cmd().args(["daemon", "stop"]).assert().success();
+    daemon.wait_with_output().unwrap();
+}
+#[test]
+fn windows_access_check_passes_paths_as_arguments() {
+    let env = FakeWsl::wsl2();
+    let spaced = env.file("Jo Doe;$(calc).pdf", PDF);
+    let win = format!(
+        "\\\\wsl.localhost\\Test{}",
+        spaced.to_string_lossy().replace('/', "\\")
+    );
+    // More than a pipe holds: the list is read while PowerShell runs
+    let mut missing = format!("{}\n", win);
+    for i in 0..4000 {
+        missing.push_str(&format!(
+            "C:\\gone\\{:04}\\padding-to-fill-the-pipe.pdf\n",
+            i
+        ));
+    }
+    env.powershell_responds(&missing);
+    let out = env
+        .cmd()
+        .args(["file", "--verify-windows-access"])
+        .arg(&spaced)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(!stderr(&out).contains("timed out"), "{}", stderr(&out));
+    assert!(stderr(&out).contains(&format!("  {} (not visible to Windows)", win)));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("Test-Path -LiteralPath $_"));
+    assert_eq!(args, [win]);
+}
+#[test]
+fn file_objects_convert_with_one_wslpath_run_per_mount() {
+    let env = FakeWsl::wsl2();
+    let mut files: Vec<std::path::PathBuf> = (0..5)
+        .map(|i| env.file(&format!("src{}.rs", i), b"fn main() {}\n"))
+        .collect();
+    let drive = env.windows_temp().join("notes.txt");
+    std::fs::write(&drive, "hi\n").unwrap();
+    files.insert(2, drive);
+    // Windows cannot hold ':' in a name; wslpath maps it, so it gets its own run
+    files.push(env.file("a:b.txt", b"x\n"));
+    let out = env
+        .cmd()
+        .args(["--debug", "file"])
+        .args(&files)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    // The drive file is converted in-process; the Linux files share one run
+    assert!(stderr(&out).contains("Converted 7 paths with 2 wslpath runs"));
+    let runs = env.calls().iter().filter(|c| *c == "wslpath").count();
+    assert_eq!(runs, 2);
+    let (_, args) = env.powershell_script();
+    let unc = |p: &std::path::Path| {
+        format!(
+            "\\\\wsl.localhost\\Test{}",
+            p.to_string_lossy().replace('/', "\\")
+        )
+    };
+    let expected: Vec<String> = files
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match i {
+            2 => "C:\\Temp\\notes.txt".to_string(),
+            _ => unc(f),
+        })
+        .collect();
+    assert_eq!(args, expected);
+    // A failing wslpath still names the file
+    let out = env
+        .cmd()
+        .arg("file")
+        .args(&files[..2])
+        .env("STUB_FAIL", "wslpath")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains(&format!("Cannot convert {}", files[0].display())));
+    assert!(stderr(&out).contains("wslpath failed: wslpath: simulated failure"));
+}
+#[test]
+fn folders_join_the_drop_list() {
+    let env = FakeWsl::wsl2();
+    let a = env.file("a.pdf", PDF);
+    let project = a.parent().unwrap().join("project");
+    std::fs::create_dir(&project).unwrap();
+    std::fs::write(project.join("main.rs"), "fn main() {}\n").unwrap();
+    let out = env
+        .cmd()
+        .arg("file")
+        .arg(format!("{}/", project.display()))
+        .arg(&a)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied 2 File Object(s) to Clipboard (1 file, 1 folder)"));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("SetFileDropList"));
+    assert!(
+        args[0].ends_with("\\project") && args[1].ends_with("\\a.pdf"),
+        "{:?}",
+        args
+    );
+    // Smart mode sends a bare folder the same way
+    let out = env.cmd().arg(&project).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied 1 Files (1 folder)"));
+}
+#[test]
+fn skip_missing_copies_the_rest() {
+    let env = FakeWsl::wsl2();
+    let a = env.file("a.pdf", PDF);
+    let gone = a.with_file_name("gone.pdf");
+    let out = env.cmd().arg("file").arg(&a).arg(&gone).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("1 of 2 files cannot be copied:"));
+    assert!(stderr(&out).contains("gone.pdf (does not exist)"));
+    assert!(env.calls().is_empty());
+    let out = env
+        .cmd()
+        .args(["file", "--skip-missing"])
+        .arg(&a)
+        .arg(&gone)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains(&format!(
+        "[WARN] Skipped {} (does not exist)",
+        gone.display()
+    )));
+    assert!(stdout(&out).contains("[OK] Copied 1 File Object(s) to Clipboard"));
+    let (_, args) = env.powershell_script();
+    assert_eq!(args.len(), 1);
+}
+#[test]
+fn paths_past_max_path_are_prefixed_or_refused() {
+    let env = FakeWsl::wsl2();
+    let a = env.file("a.pdf", PDF);
+    let mut deep = a.parent().unwrap().to_path_buf();
+    for i in 0..12 {
+        deep.push(format!("nested-folder-{:06}", i));
+    }
+    std::fs::create_dir_all(&deep).unwrap();
+    let long = deep.join("render.pdf");
+    std::fs::write(&long, PDF).unwrap();
+    let out = env.cmd().arg("file").arg(&a).arg(&long).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("[WARN] 1 path(s) exceed the Windows MAX_PATH limit"));
+    assert!(stderr(&out).contains("render.pdf ("));
+    let (_, args) = env.powershell_script();
+    assert!(args[0].starts_with("\\\\wsl.localhost\\"), "{:?}", args);
+    assert!(
+        args[1].starts_with("\\\\?\\UNC\\wsl.localhost\\"),
+        "{:?}",
+        args
+    );
+    let calls = env.calls().len();
+    let out = env
+        .cmd()
+        .args(["file", "--strict-length"])
+        .arg(&long)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("1 path(s) exceed the Windows MAX_PATH limit of 259 characters"));
+    assert!(!env.calls()[calls..].contains(&"powershell.exe".to_string()));
+}
+#[test]
+fn move_marks_the_drop_list_as_cut() {
+    let env = FakeWsl::wsl2();
+    let a = env.file("a.pdf", PDF);
+    let out = env.cmd().args(["file", "--move"]).arg(&a).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out)
+        .contains("[OK] Copied 1 File Object(s) to Clipboard (cut: pasting moves them)"));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("$data.SetFileDropList($files)"));
+    assert!(script.contains(
+        "$data.SetData('Preferred DropEffect', (New-Object System.IO.MemoryStream(,[byte[]](2, 0, 0, 0))))"
+    ));
+    assert!(!script.contains("UnicodeText"));
+    assert_eq!(args.len(), 1);
+    assert!(args[0].ends_with("\\a.pdf"));
+    // With a manifest, its text still leads the arguments
+    let out = env
+        .cmd()
+        .args(["--move", "--manifest"])
+        .arg(&a)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("Select-Object -Skip 1") && script.contains("UnicodeText"));
+    assert!(script.contains("Preferred DropEffect"));
+    assert!(decode_base64(&args[0]).ends_with("  a.pdf\n"));
+    let out = env
+        .cmd()
+        .args(["img", "--move"])
+        .arg(env.file("shot.png", PNG))
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("--move only applies to file-object copies"));
+}
+#[test]
+fn manifest_joins_the_drop_list_and_text_format() {
+    let env = FakeWsl::wsl2();
+    let a = env.file("a.pdf", PDF);
+    let b = env.file("empty.stl", b"");
+    let out = env
+        .cmd()
+        .arg("--manifest")
+        .arg("file")
+        .arg(&a)
+        .arg(&b)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied 2 File Object(s) to Clipboard with MANIFEST.sha256"));
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("SetFileDropList") && script.contains("UnicodeText"));
+    // $args[0] is the manifest text, then the files, then the manifest itself
+    assert_eq!(args.len(), 4);
+    assert!(args[1].ends_with("\\a.pdf") && args[2].ends_with("\\empty.stl"));
+    assert!(args[3].starts_with("C:\\Temp\\wsl-clip\\manifest-"));
+    assert!(args[3].ends_with("\\MANIFEST.sha256"));
+    let staged = env
+        .windows_temp()
+        .join(args[3]["C:\\Temp\\".len()..].replace('\\', "/"));
+    let manifest = std::fs::read_to_string(staged).unwrap();
+    assert_eq!(
+        manifest.lines().nth(1),
+        Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  empty.stl")
+    );
+    assert!(manifest.starts_with(&format!("{}  a.pdf\n", sha256_hex(PDF))));
+    assert_eq!(decode_base64(&args[0]), manifest);
+    // Text copies have nothing to checksum
+    let out = env
+        .cmd()
+        .arg("--manifest")
+        .write_stdin("x\n")
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("--manifest only applies to file-object copies"));
+}
+#[test]
+fn custom_control_set_filters_stdin() {
+    let env = FakeWsl::wsl2();
+    env.cmd()
+        .args(["--allow-control", "ff,U+0007", "--deny-control", "tab"])
+        .write_stdin("a\tb\x0cc\x07d\x08e\n")
+        .assert()
+        .success();
+    assert_eq!(env.clipboard().as_deref(), Some("ab\x0cc\x07de\n"));
+    let out = env
+        .cmd()
+        .args(["--allow-control", "esc"])
+        .write_stdin("x\n")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("Refusing to allow ESC"));
+    // The guard trips before a second clip.exe starts
+    assert_eq!(env.calls(), vec!["clip.exe"]);
+    let out = env
+        .cmd()
+        .args(["--allow-control", "space"])
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("unknown control character 'space'"));
+}
+#[test]
+fn text_bundles_keep_the_command_line_order() {
+    let env = FakeWsl::wsl2();
+    env.file("intro.md", b"intro\n");
+    let details = env.file("details.md", b"details\n");
+    env.file("appendix.md", b"appendix\n");
+    let out = env
+        .cmd()
+        .args(["--debug", "--timestamp", "none"])
+        .args(["intro.md", "details.md", "appendix.md", "intro.md"])
+        // Other spellings of a file already listed
+        .args(["./details.md", "../work/appendix.md"])
+        .arg(&details)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("Dropped repeated path: \"intro.md\""));
+    let clip = env.clipboard().unwrap();
+    let headers: Vec<&str> = clip.lines().filter(|l| l.starts_with("# FILE: ")).collect();
+    assert_eq!(
+        headers,
+        [
+            "# FILE: intro.md",
+            "# FILE: details.md",
+            "# FILE: appendix.md"
+        ]
+    );
+    let sent: Vec<&str> = clip.lines().filter(|l| l.starts_with("# SENT: ")).collect();
+    assert_eq!(sent.len(), 3);
+    assert_eq!(sent[0], "# SENT: intro.md (1 lines, 6 bytes)");
+    assert_eq!(sent[2], "# SENT: appendix.md (1 lines, 9 bytes)");
+    // --sort puts them in name (or size) order
+    for (key, first) in [("--sort", "appendix.md"), ("--sort=size", "intro.md")] {
+        env.cmd()
+            .args(["--timestamp", "none", key])
+            .args(["details.md", "intro.md", "appendix.md"])
+            .assert()
+            .success();
+        let clip = env.clipboard().unwrap();
+        assert!(
+            clip.starts_with(&format!("# FILE: {}\n", first)),
+            "{}",
+            clip
+        );
+    }
+}
+#[test]
+fn recursive_copies_a_source_tree() {
+    let env = FakeWsl::wsl2();
+    env.file("src/main.rs", b"fn main() {}\n");
+    env.file("src/util/fmt.rs", b"pub fn fmt() {}\n");
+    env.file("src/gen/table.rs", b"// generated\n");
+    env.file("src/logo.png", PNG);
+    env.file("src/notes.txt", b"todo\n");
+    env.file("src/.secret.rs", b"const KEY: u8 = 1;\n");
+    let out = env
+        .cmd()
+        .args(["--timestamp", "none", "-r", "src"])
+        .args([
+            "--include",
+            "*.rs",
+            "--include",
+            "*.png",
+            "--exclude",
+            "gen/**",
+        ])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("Walked src: 2 files matched, 1 skipped as binary, 2 excluded"));
+    assert!(stderr(&out).contains("[WARN] Skipped binary file: src/logo.png"));
+    let clip = env.clipboard().unwrap();
+    let headers: Vec<&str> = clip.lines().filter(|l| l.starts_with("# FILE: ")).collect();
+    assert_eq!(headers, ["# FILE: src/main.rs", "# FILE: src/util/fmt.rs"]);
+    // Globs without -r are refused; a tree with nothing left copies nothing
+    env.cmd()
+        .args(["--include", "*.rs", "src"])
+        .assert()
+        .failure();
+    let out = env
+        .cmd()
+        .args(["-r", "src", "--include", "*.go"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(
+        stderr(&out).contains("No text files matched"),
+        "{}",
+        stderr(&out)
+    );
+}
+#[test]
+fn recursive_copies_honour_gitignore_in_a_repository() {
+    let env = FakeWsl::wsl2();
+    env.file(".git/HEAD", b"ref: refs/heads/main\n");
+    env.file(".gitignore", b"node_modules/\n*.log\n");
+    env.file("app.js", b"run()\n");
+    env.file("debug.log", b"noise\n");
+    env.file("node_modules/dep/index.js", b"dep()\n");
+    let headers = |args: &[&str]| {
+        let out = env
+            .cmd()
+            .args(["--debug", "--timestamp", "none", "-r", "."])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", stderr(&out));
+        let clip = env.clipboard().unwrap();
+        let headers: Vec<String> = clip
+            .lines()
+            .filter_map(|l| l.strip_prefix("# FILE: "))
+            .map(String::from)
+            .collect();
+        (headers, stderr(&out))
+    };
+    let (files, log) = headers(&[]);
+    assert_eq!(files, ["app.js"]);
+    assert!(
+        log.contains("Ignore rules left out 2 entries under ."),
+        "{}",
+        log
+    );
+    // Naming an ignored file copies it; --no-gitignore walks everything
+    let (files, _) = headers(&["debug.log"]);
+    assert_eq!(files, ["app.js", "debug.log"]);
+    let (files, _) = headers(&["--no-gitignore"]);
+    assert_eq!(files, ["app.js", "debug.log", "node_modules/dep/index.js"]);
+}
+#[test]
+fn recursive_walks_are_capped_before_the_clipboard_is_touched() {
+    let env = FakeWsl::wsl2();
+    for name in ["logs/a.txt", "logs/b.txt", "logs/old/c.txt"] {
+        env.file(name, b"line\n");
+    }
+    let out = env
+        .cmd()
+        .args(["-r", "logs", "--max-files", "2"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(
+        stderr(&out).contains("-r stopped at 3 files, over the --max-files cap of 2"),
+        "{}",
+        stderr(&out)
+    );
+    assert!(stderr(&out).contains("--include"));
+    assert_eq!(env.clipboard(), None);
+    assert!(env.calls().is_empty(), "{:?}", env.calls());
+    // A shallower walk fits under the cap
+    let out = env
+        .cmd()
+        .args(["--timestamp", "none", "-r", "logs", "--max-files", "2"])
+        .args(["--max-depth", "1"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("Walked logs: 2 files matched"));
+    env.cmd()
+        .args(["-r", "logs", "--max-files", "0"])
+        .assert()
+        .success();
+}
+#[test]
+fn files_from_reads_the_list_like_arguments() {
+    let env = FakeWsl::wsl2();
+    env.file("a.md", b"alpha\n");
+    env.file("b.md", b"beta\n");
+    env.file("odd\nname.txt", b"newline in the name\n");
+    let headers = || -> Vec<String> {
+        let clip = env.clipboard().unwrap();
+        clip.lines()
+            .filter_map(|l| l.strip_prefix("# FILE: "))
+            .map(String::from)
+            .collect()
+    };
+    // Positional files first, then the list; comments and blank lines skipped
+    env.cmd()
+        .args(["--timestamp", "none", "a.md", "--files-from", "-"])
+        .write_stdin("# from git ls-files\n\nb.md  \n")
+        .assert()
+        .success();
+    assert_eq!(headers(), ["a.md", "b.md"]);
+    // -0 keeps the newline that would otherwise split the name
+    env.file("list", b"odd\nname.txt\0a.md\0");
+    env.cmd()
+        .args(["--timestamp", "none", "--files-from", "list", "-0"])
+        .assert()
+        .success();
+    let clip = env.clipboard().unwrap();
+    assert!(
+        clip.starts_with("# FILE: odd\nname.txt\nnewline in the name\n"),
+        "{}",
+        clip
+    );
+    assert_eq!(headers().last().unwrap(), "a.md");
+    // A listed file that is missing fails like a missing argument
+    let out = env
+        .cmd()
+        .args(["--files-from", "-"])
+        .write_stdin("a.md\nmissing.md\n")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("\"missing.md\""), "{}", stderr(&out));
+    let out = env
+        .cmd()
+        .args(["--files-from", "-"])
+        .write_stdin("# nothing\n")
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("--files-from: stdin lists no files"));
+}
+#[test]
+fn deterministic_bundles_are_byte_identical() {
+    const GOLDEN: &str = "# FILE: a.b READ: 1970-01-01T00:00:00Z SNAPSHOT: 4 bytes\n\
+                          dot\n\
+                          \n\
+                          # FILE: a/c READ: 1970-01-01T00:00:00Z SNAPSHOT: 6 bytes\n\
+                          slash\n\
+                          \n\
+                          # End of FILES.\n\
+                          # SENT: a.b (1 lines, 4 bytes)\n\
+                          # SENT: a/c (1 lines, 6 bytes)\n\
+                          # TOTAL: 2 files, 2 lines, 10 bytes\n";
+    let run = |extra: &[(&str, &str)]| {
+        // A fresh environment each time: new temp dirs, new mtimes
+        let env = FakeWsl::wsl2();
+        env.file("a.b", b"dot\n");
+        env.file("a/c", b"slash\n");
+        // Path order would put a/c first; display order puts a.b first
+        env.cmd()
+            .args(["--deterministic", "--snapshot", "a/c", "a.b"])
+            .envs(extra.iter().copied())
+            .assert()
+            .success();
+        env.clipboard().unwrap()
+    };
+    let first = run(&[]);
+    assert_eq!(first, GOLDEN);
+    assert_eq!(run(&[]), first);
+    let pinned = run(&[("SOURCE_DATE_EPOCH", "1700000000")]);
+    assert_eq!(
+        pinned,
+        GOLDEN.replace("1970-01-01T00:00:00Z", "2023-11-14T22:13:20Z")
+    );
+    // Modification times are not reproducible, so neither is their order
+    let env = FakeWsl::wsl2();
+    env.file("a.b", b"dot\n");
+    let out = env
+        .cmd()
+        .args(["--deterministic", "--sort=mtime", "a.b"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("--sort=mtime cannot be combined with --deterministic"));
+    assert_eq!(env.clipboard(), None);
+}
+#[test]
+fn state_moves_between_machines() {
+    let old = FakeWsl::wsl2();
+    old.config("[profile.review]\ncode = true\n");
+    let bundle = old.file("state.tar.gz", b"");
+    old.cmd()
+        .arg("export-state")
+        .arg(&bundle)
+        .assert()
+        .success();
+    let new = FakeWsl::wsl2();
+    new.config("[profile.mine]\ncrlf = true\n");
+    let out = new.cmd().arg("import-state").arg(&bundle).output().unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("Import would change 1 existing file(s)"));
+    let out = new
+        .cmd()
+        .args(["import-state", "--merge"])
+        .arg(&bundle)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("Added profile 'review'"));
+    let out = new
+        .cmd()
+        .args(["--profile", "review", "--print-config"])
+        .output()
+        .unwrap();
+    assert!(stdout(&out).contains("code = true  # profile 'review'"));
+}
+#[test]
+fn cite_links_the_emitted_lines_to_the_commit() {
+    let env = FakeWsl::wsl2();
+    env.config("cite.url = \"https://example.com/{commit}/{path}#L{start}-L{end}\"\n");
+    let file = env.file(
+        "src/foo.rs",
+        b"fn a() {}\nfn b() {}\n// c\nfn d() {}\nfn e() {}\n",
+    );
+    let work = file.parent().unwrap().parent().unwrap();
+    let git = |args: &[&str]| {
+        let out = std::process::Command::new("git")
+            .current_dir(work)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", stderr(&out));
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    };
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-qm", "init"]);
+    let commit = git(&["rev-parse", "HEAD"]);
+    env.cmd()
+        .args(["--cite", "--lines", "2:4", "src/foo.rs"])
+        .assert()
+        .success();
+    assert_eq!(
+        env.clipboard().unwrap(),
+        format!(
+            "```rs\nfn b() {{}}\n// c\nfn d() {{}}\n```\n\
+             from [src/foo.rs lines 2-4](https://example.com/{}/src/foo.rs#L2-L4) at commit {}\n",
+            commit,
+            &commit[..7]
+        )
+    );
+    // --grep narrows the cited range to what was actually emitted
+    env.cmd()
+        .args([
+            "--cite",
+            "--lines",
+            "1:4",
+            "--grep",
+            "fn [bd]",
+            "src/foo.rs",
+        ])
+        .assert()
+        .success();
+    assert!(env.clipboard().unwrap().ends_with(&format!(
+        "from [src/foo.rs lines 2-4](https://example.com/{}/src/foo.rs#L2-L4) \
+         (2 of 4 lines matching --grep) at commit {}\n",
+        commit,
+        &commit[..7]
+    )));
+    let out = env.cmd().arg("--cite").write_stdin("x\n").output().unwrap();
+    assert!(stderr(&out).contains("--cite needs file arguments"));
+}
+#[test]
+fn stdin_sources_are_told_apart() {
+    let env = FakeWsl::wsl2();
+    let empty = "nothing to copy (the clipboard was left unchanged)";
+    // /dev/null and an immediately-EOF pipe never reach clip.exe
+    let out = env
+        .command(BIN)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(4));
+    assert!(stderr(&out).contains(&format!("stdin is /dev/null (or was closed); {}", empty)));
+    let out = env.cmd().write_stdin("").output().unwrap();
+    assert_eq!(out.status.code(), Some(4));
+    assert!(stderr(&out).contains(&format!("stdin was empty; {}", empty)));
+    // A closed fd 0 is reopened on /dev/null by the runtime
+    let out = env
+        .command("sh")
+        .args(["-c", "exec \"$0\" <&-", BIN])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(4));
+    assert!(stderr(&out).contains("(or was closed)"));
+    assert!(env.calls().is_empty());
+    // A non-terminal character device is read like a pipe (capped: random
+    // bytes decode lossily, so nothing else would end the copy)
+    let out = env
+        .command(BIN)
+        .args(["--max-size", "64K", "--no-drain"])
+        .stdin(std::fs::File::open("/dev/urandom").unwrap())
+        .output()
+        .unwrap();
+    assert!(!stderr(&out).contains("No input provided") && !stderr(&out).contains(empty));
+    assert_eq!(env.calls(), vec!["clip.exe"]);
+}
+#[test]
+fn piped_images_and_binaries_are_not_copied_as_text() {
+    let env = FakeWsl::wsl2();
+    let out = env.cmd().write_stdin(PNG).output().unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr(&out).contains(
+        "stdin looks like a PNG image; use --image to copy it as an image, or --as-file"
+    ));
+    let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+    elf.extend([0u8; 200]);
+    let out = env.cmd().write_stdin(elf).output().unwrap();
+    assert!(stderr(&out).contains("stdin looks like binary data (elf magic); use --as-file"));
+    assert!(env.calls().is_empty());
+    // Text longer than the sniffed prefix arrives whole
+    let text = "line of text\n".repeat(1000);
+    let out = env.cmd().write_stdin(text.as_str()).output().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some(text.as_str()));
+}
+#[test]
+fn terminal_stdin_asks_for_input() {
+    let env = FakeWsl::wsl2();
+    // util-linux `script` gives the child a pseudo-terminal; skip where it is missing
+    let Ok(out) = env
+        .command("script")
+        .args(["-eqc", BIN, "/dev/null"])
+        .stdin(std::process::Stdio::null())
+        .output()
+    else {
+        return;
+    };
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout(&out).contains("No input provided. Pipe data or specify files."));
+    assert!(env.calls().is_empty());
+}
+#[test]
+fn dash_reads_stdin_between_files() {
+    let env = FakeWsl::wsl2();
+    env.file("header.md", b"# Report\n");
+    env.file("footer.md", b"-- end\n");
+    let out = env
+        .cmd()
+        .args(["--timestamp", "none", "header.md", "-", "footer.md"])
+        .write_stdin("summary \x1b[31mred\x1b[0m\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("Copied Text from 3 of 3 files"));
+    let clip = env.clipboard().unwrap();
+    assert!(
+        clip.starts_with(
+            "# FILE: header.md\n# Report\n\n# STDIN\nsummary red\n\n# FILE: footer.md\n-- end\n\n"
+        ),
+        "{}",
+        clip
+    );
+    assert!(clip.contains("# SENT: stdin (1 lines, 12 bytes)\n"));
+    // Fences and CRLF apply to the stdin section too; -n drops its header
+    env.cmd()
+        .args(["--timestamp", "none", "--code", "--crlf", "header.md", "-"])
+        .write_stdin("piped\n")
+        .assert()
+        .success();
+    let clip = env.clipboard().unwrap();
+    assert!(
+        clip.contains("# STDIN\r\n```\r\npiped\r\n```\r\n"),
+        "{:?}",
+        clip
+    );
+    env.cmd()
+        .args(["-n", "-", "footer.md"])
+        .write_stdin("piped\n")
+        .assert()
+        .success();
+    assert_eq!(env.clipboard().as_deref(), Some("piped\n-- end\n"));
+    let out = env
+        .cmd()
+        .args(["-", "header.md", "-"])
+        .write_stdin("x\n")
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("`-` (stdin) can be given only once, not 2 times"));
+    // From a terminal there is nothing to read
+    let Ok(out) = env
+        .command("script")
+        .args(["-eqc", &format!("{} header.md -", BIN), "/dev/null"])
+        .stdin(std::process::Stdio::null())
+        .output()
+    else {
+        return;
+    };
+    assert!(
+        stdout(&out).contains("`-` reads stdin, but stdin is a terminal"),
+        "{}",
+        stdout(&out)
+    );
+}
+#[test]
+fn fifos_and_process_substitution_are_copied_as_text() {
+    let env = FakeWsl::wsl2();
+    let fifo = env.file("notes.md", b"notes\n").with_file_name("live.log");
+    let made = std::process::Command::new("mkfifo")
+        .arg(&fifo)
+        .status()
+        .unwrap();
+    assert!(made.success());
+    let writer = std::thread::spawn(move || std::fs::write(fifo, "step 1\nstep 2\n"));
+    // --debug runs the size estimate too, which must not read the pipe
+    let out = env
+        .cmd()
+        .args(["--debug", "--timestamp", "none", "live.log", "notes.md"])
+        .output()
+        .unwrap();
+    writer.join().unwrap().unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let clip = env.clipboard().unwrap();
+    assert!(
+        clip.starts_with("# FILE: live.log\nstep 1\nstep 2\n\n# FILE: notes.md\nnotes\n"),
+        "{}",
+        clip
+    );
+    assert!(
+        stderr(&out).contains(", Unknown) from 6 input bytes"),
+        "{}",
+        stderr(&out)
+    );
+    // bash's <(...) hands over a /dev/fd path
+    let out = env
+        .command("bash")
+        .args([
+            "-c",
+            &format!("{} --timestamp none notes.md <(printf 'a\\nb\\n')", BIN),
+        ])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let clip = env.clipboard().unwrap();
+    assert!(clip.contains("\n\n# FILE: /dev/fd/"), "{}", clip);
+    assert!(clip.contains("\na\nb\n\n# End of FILES."), "{}", clip);
+}
+#[test]
+fn set_clipboard_stands_in_for_a_missing_clip_exe() {
+    let mut env = FakeWsl::wsl2();
+    // Forced, with clip.exe right there: only PowerShell is used
+    let out = env
+        .cmd()
+        .args(["--backend", "powershell", "-n", "--debug"])
+        .write_stdin("caf\u{e9}\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some("caf\u{e9}\n"));
+    assert_eq!(env.calls(), vec!["powershell.exe"]);
+    assert!(env
+        .powershell_args()
+        .iter()
+        .any(|a| a.contains("Set-Clipboard")));
+    assert!(stderr(&out).contains("Spawning powershell.exe Set-Clipboard"));
+    // clip.exe gone: auto still picks Windows and falls back, saying so
+    env.remove_stub("clip.exe");
+    let out = env
+        .cmd()
+        .args(["-n", "--debug"])
+        .write_stdin("locked down\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some("locked down\n"));
+    assert!(stderr(&out)
+        .contains("Backend: windows (WSL without clip.exe; text goes through Set-Clipboard)"));
+    assert!(stderr(&out).contains("falling back to Set-Clipboard"));
+    // Neither tool: the original error, with the interop hint
+    env.remove_stub("powershell.exe");
+    let out = env.cmd().arg("-n").write_stdin("x\n").output().unwrap();
+    assert!(!out.status.success());
+    assert!(
+        stderr(&out).contains("Failed to spawn clip.exe (and the powershell.exe fallback)"),
+        "{}",
+        stderr(&out)
+    );
+}
+#[test]
+fn interop_tools_are_found_off_path() {
+    let mut env = FakeWsl::wsl2();
+    env.move_stub_to_windows("clip.exe", "System32");
+    env.move_stub_to_windows("powershell.exe", "System32/WindowsPowerShell/v1.0");
+    let windows = env.windows_dir();
+    // Nothing on PATH and no override: every location tried is named
+    let out = env.cmd().arg("-n").write_stdin("x\n").output().unwrap();
+    assert!(!out.status.success());
+    let err = stderr(&out);
+    for tried in [
+        "/mnt/c/Windows/System32/clip.exe",
+        "/mnt/c/Windows/System32/WindowsPowerShell/v1.0/powershell.exe",
+        "WSL_CLIP_WINDOWS_DIR",
+    ] {
+        assert!(err.contains(tried), "{}", err);
+    }
+    // With the override, text and file objects both work
+    let out = env
+        .cmd()
+        .env("WSL_CLIP_WINDOWS_DIR", &windows)
+        .args(["-n", "--debug"])
+        .write_stdin("found\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(env.clipboard().as_deref(), Some("found\n"));
+    assert!(stderr(&out).contains("clip.exe: not on PATH, using"));
+    let pdf = env.file("a.pdf", PDF);
+    env.cmd()
+        .env("WSL_CLIP_WINDOWS_DIR", &windows)
+        .args(["--no-preflight", "file"])
+        .arg(&pdf)
+        .assert()
+        .success();
+    assert!(env.powershell_script().0.contains("SetFileDropList"));
+}
+#[test]
+fn file_copies_prefer_pwsh_when_installed() {
+    let mut env = FakeWsl::wsl2();
+    env.add_stub("pwsh.exe");
+    let pdf = env.file("a.pdf", PDF);
+    let out = env
+        .cmd()
+        .args(["--no-preflight", "--debug", "file"])
+        .arg(&pdf)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stderr(&out).contains("Shell: pwsh.exe (auto)"));
+    assert_eq!(env.calls(), vec!["wslpath", "pwsh.exe"]);
+    let args = std::fs::read_to_string(env.log_path("pwsh.args")).unwrap();
+    assert!(
+        args.starts_with(
+            "-NoProfile\n-NonInteractive\n-WindowStyle\nHidden\n-NoLogo\n-Sta\n-EncodedCommand\n"
+        ),
+        "{}",
+        args
+    );
+    let args: Vec<String> = args.lines().map(str::to_string).collect();
+    assert!(common::decode_call(&args).0.contains("SetFileDropList"));
+    // --shell powershell sticks to Windows PowerShell 5.1
+    env.cmd()
+        .args(["--no-preflight", "--shell", "powershell", "file"])
+        .arg(&pdf)
+        .assert()
+        .success();
+    assert_eq!(
+        env.calls().last().map(String::as_str),
+        Some("powershell.exe")
+    );
+    assert_eq!(
+        env.powershell_args()[..5],
+        [
+            "-NoProfile",
+            "-NonInteractive",
+            "-WindowStyle",
+            "Hidden",
+            "-EncodedCommand"
+        ]
+    );
+}
+#[test]
+fn hung_clipboard_processes_time_out() {
+    let env = FakeWsl::wsl2();
+    let pdf = env.file("a.pdf", PDF);
+    let started = std::time::Instant::now();
+    let out = env
+        .cmd()
+        .env("STUB_HANG", "powershell.exe")
+        .args(["--timeout", "1", "--no-preflight", "file"])
+        .arg(&pdf)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    assert!(
+        stderr(&out).contains(
+            "Timed out after 1s setting the clipboard (File mode); powershell.exe was killed"
+        ),
+        "{}",
+        stderr(&out)
+    );
+    // clip.exe too, with the limit from the environment; nothing is committed
+    let out = env
+        .cmd()
+        .env("STUB_HANG", "clip.exe")
+        .env("WSL_CLIP_TIMEOUT", "1s")
+        .write_stdin("stuck\n")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("(Text mode); clip.exe was killed"));
+    assert_eq!(env.clipboard(), None);
+}
+#[test]
+fn powershell_errors_reach_the_user() {
+    let env = FakeWsl::wsl2();
+    let png = env.file("shot.png", PNG);
+    env.powershell_fails("wsl-clip: A generic error occurred in GDI+.\n", 5);
+    let out = env
+        .cmd()
+        .args(["--no-preflight", "--no-retry", "img"])
+        .arg(&png)
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    let err = stderr(&out);
+    assert!(
+        err.contains("Failed to set image: A generic error occurred in GDI+. (the clipboard is locked by another program, or GDI+ failed; try again)"),
+        "{}",
+        err
+    );
+    assert!(err.contains("file: \\\\wsl.localhost\\Test"), "{}", err);
+    // An untrapped error record (e.g. Add-Type failing) is parsed too
+    env.powershell_fails(
+        "Add-Type : Cannot add type. The assembly 'System.Windows.Forms' could not be found.\r\n\
+         At line:1 char:1\r\n+ Add-Type -AssemblyName System.Windows.Forms\r\n",
+        1,
+    );
+    let pdf = env.file("a.pdf", PDF);
+    let out = env
+        .cmd()
+        .args(["--no-preflight", "file"])
+        .arg(&pdf)
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("Failed to set file list: Add-Type : Cannot add type. The assembly 'System.Windows.Forms' could not be found. (PowerShell exited with status 1)"), "{}", stderr(&out));
+}
+#[test]
+fn locked_clipboards_are_retried() {
+    let env = FakeWsl::wsl2();
+    // clip.exe read the text before failing; the retry sends all of it again
+    env.lock_clipboard("clip.exe", 2);
+    env.cmd()
+        .args(["--retry-delay", "10ms"])
+        .write_stdin("once\n")
+        .assert()
+        .success();
+    assert_eq!(env.clipboard().as_deref(), Some("once\n"));
+    assert_eq!(env.calls(), vec!["clip.exe"; 3]);
+    // A locked image copy re-runs the script
+    let png = env.file("shot.png", PNG);
+    env.lock_clipboard("powershell.exe", 1);
+    env.cmd()
+        .env("WSL_CLIP_RETRY_DELAY", "10")
+        .args(["--no-preflight", "img"])
+        .arg(&png)
+        .assert()
+        .success();
+    assert_eq!(
+        env.calls()
+            .iter()
+            .filter(|c| c.as_str() == "powershell.exe")
+            .count(),
+        2
+    );
+    // --no-retry gives up at once
+    env.lock_clipboard("clip.exe", 1);
+    let out = env
+        .cmd()
+        .arg("--no-retry")
+        .write_stdin("twice\n")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("clip.exe exited with error status"));
+    assert_eq!(env.clipboard().as_deref(), Some("once\n"));
+}
+#[test]
+fn hostile_file_names_stay_arguments() {
+    let env = FakeWsl::wsl2();
+    let names = [
+        "$(calc).pdf",
+        "a\"; rm -rf \"b.pdf",
+        "`whoami`'x.pdf",
+        "two\nlines.pdf",
+        "_x0041_ & <tag> 🎉.pdf",
+    ];
+    let files: Vec<_> = names.iter().map(|n| env.file(n, PDF)).collect();
+    env.cmd()
+        .args(["--no-preflight", "file"])
+        .args(&files)
+        .assert()
+        .success();
+    // Window hidden, no prompts, and nothing but flags and base64 on the command line
+    let args = env.powershell_args();
+    assert!(args
+        .join(" ")
+        .starts_with("-NoProfile -NonInteractive -WindowStyle Hidden -EncodedCommand "));
+    assert_eq!(args.len(), 8);
+    // The script is fixed text; each name comes back whole as its own $args entry
+    let (script, script_args) = env.powershell_script();
+    assert!(names.iter().all(|n| !script.contains(n)));
+    assert_eq!(script_args.len(), names.len());
+    for (arg, name) in script_args.iter().zip(names) {
+        assert!(arg.ends_with(&format!("\\work\\{}", name)), "{:?}", arg);
+    }
+    // The same for an image
+    let png = env.file("$(calc).png", PNG);
+    env.cmd()
+        .args(["--no-preflight", "img"])
+        .arg(&png)
+        .assert()
+        .success();
+    let (script, script_args) = env.powershell_script();
+    assert!(script.contains("SetImage") && !script.contains("$(calc)"));
+    assert_eq!(script_args.len(), 1);
+    assert!(script_args[0].ends_with("\\$(calc).png"));
+}
+#[test]
+fn html_copies_carry_cf_html_and_text() {
+    let env = FakeWsl::wsl2();
+    let out = env
+        .cmd()
+        .args(["--no-retry", "--html"])
+        .write_stdin("<h1>Caf\u{e9}</h1><p>a &amp; b</p>")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied HTML"));
+    assert_eq!(env.calls(), vec!["powershell.exe"]);
+    let (script, args) = env.powershell_script();
+    assert!(script.contains("TextDataFormat]$args[$i]"));
+    assert_eq!(args, ["UnicodeText", "Html"]);
+    // stdin: one Base64 line per format, in the order of $args
+    let stdin = env.powershell_stdin();
+    let lines: Vec<&str> = stdin.lines().collect();
+    assert_eq!(decode_base64(lines[0]), "Caf\u{e9}\na & b\n\n");
+    let cf_html = decode_base64(lines[1]);
+    let start: usize = cf_html[cf_html.find("StartFragment:").unwrap() + 14..][..10]
+        .parse()
+        .unwrap();
+    let end: usize = cf_html[cf_html.find("EndFragment:").unwrap() + 12..][..10]
+        .parse()
+        .unwrap();
+    assert_eq!(
+        &cf_html.as_bytes()[start..end],
+        "<h1>Caf\u{e9}</h1><p>a &amp; b</p>\n".as_bytes()
+    );
+    // Text only, and Windows only
+    let png = env.file("shot.png", PNG);
+    let out = env.cmd().arg("--html").arg(&png).output().unwrap();
+    assert!(stderr(&out).contains("--html only applies to text copies"));
+    let out = env
+        .cmd()
+        .args(["--html", "--backend", "osc52"])
+        .write_stdin("<b>x</b>")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("HTML copies need the Windows clipboard"));
+}
+#[test]
+fn rtf_copies_carry_the_document_and_text() {
+    let env = FakeWsl::wsl2();
+    let src = env.file(
+        "main.rs",
+        "fn main() { println!(\"\u{e9}\"); }\n".as_bytes(),
+    );
+    let out = env
+        .cmd()
+        .args(["--rtf", "--code", "--no-header"])
+        .arg(&src)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied RTF and plain text"));
+    let (_, args) = env.powershell_script();
+    assert_eq!(args, ["UnicodeText", "Rtf"]);
+    let stdin = env.powershell_stdin();
+    let lines: Vec<&str> = stdin.lines().collect();
+    let rtf = decode_base64(lines[1]);
+    assert!(rtf.starts_with("{\\rtf1\\ansi"));
+    assert!(rtf.contains("\\f1\\fs20 fn main() \\{ println!(\"\\u233?\"); \\}\\par"));
+    // The plain text is the processed text, fences included
+    assert_eq!(
+        decode_base64(lines[0]),
+        "```rs\nfn main() { println!(\"\u{e9}\"); }\n```\n"
+    );
+}
+#[test]
+fn html_and_rtf_go_in_one_clipboard_operation() {
+    let env = FakeWsl::wsl2();
+    let out = env
+        .cmd()
+        .args(["--html", "--rtf"])
+        .write_stdin("<p>a &amp; b</p>")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied HTML+RTF and plain text"));
+    assert_eq!(env.calls(), vec!["powershell.exe"]);
+    let (script, args) = env.powershell_script();
+    assert_eq!(script.matches("SetDataObject").count(), 1);
+    assert_eq!(args, ["UnicodeText", "Html", "Rtf"]);
+    let stdin = env.powershell_stdin();
+    let lines: Vec<&str> = stdin.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(decode_base64(lines[0]), "a & b\n\n");
+    assert!(decode_base64(lines[1]).contains("<!--StartFragment--><p>a &amp; b</p>"));
+    assert!(decode_base64(lines[2]).contains("a & b\\par"));
+}
+#[test]
+fn render_md_copies_html_and_keeps_the_markdown() {
+    let env = FakeWsl::wsl2();
+    let out = env
+        .cmd()
+        .arg("--render-md")
+        .write_stdin("Ship **it**\n\n```sh\nmake <all>\n```\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let (_, args) = env.powershell_script();
+    assert_eq!(args, ["UnicodeText", "Html"]);
+    let stdin = env.powershell_stdin();
+    let lines: Vec<&str> = stdin.lines().collect();
+    assert_eq!(
+        decode_base64(lines[0]),
+        "Ship **it**\n\n```sh\nmake <all>\n```\n"
+    );
+    let cf_html = decode_base64(lines[1]);
+    assert!(cf_html.contains("<p>Ship <strong>it</strong></p>"));
+    assert!(cf_html.contains("<pre><code class=\"language-sh\">make &lt;all&gt;\n</code></pre>"));
+    let out = env
+        .cmd()
+        .args(["--render-md", "--html"])
+        .write_stdin("x")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+}
+#[test]
+fn highlight_colors_files_and_keeps_stdin_plain() {
+    let env = FakeWsl::wsl2();
+    let src = env.file("lib.py", b"def f():\n    return 1\n");
+    let out = env
+        .cmd()
+        .args(["--highlight", "--no-header"])
+        .arg(&src)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let stdin = env.powershell_stdin();
+    let lines: Vec<&str> = stdin.lines().collect();
+    assert_eq!(decode_base64(lines[0]), "def f():\n    return 1\n");
+    let cf_html = decode_base64(lines[1]);
+    assert!(cf_html.contains("<pre style=\"font-family: Consolas"));
+    assert!(cf_html.contains("<span style=\"color:#"));
+    let out = env
+        .cmd()
+        .args(["--highlight", "--theme", "Solarized (light)"])
+        .write_stdin("a <b>\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    let stdin = env.powershell_stdin();
+    let cf_html = decode_base64(stdin.lines().nth(1).unwrap());
+    assert!(cf_html.contains(">a &lt;b&gt;\n</pre>"));
+    assert!(!cf_html.contains("<span"));
+}
+#[test]
+fn clip_exe_gets_utf16_with_a_bom() {
+    let env = FakeWsl::wsl2();
+    let utf16 = |text: &str| -> Vec<u8> {
+        [0xFF, 0xFE]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect()
+    };
+    for sample in [
+        "plain ascii\n",
+        "na\u{ef}ve caf\u{e9} \u{1F980}\n",
+        "\u{4E2D}\u{6587}\u{65E5}\u{672C}\u{8A9E}\n",
+    ] {
+        let out = env.cmd().write_stdin(sample).output().unwrap();
+        assert!(out.status.success(), "{}", stderr(&out));
+        assert_eq!(env.clip_stdin(), utf16(sample), "{:?}", sample);
+        assert_eq!(env.clipboard().unwrap(), sample);
+    }
+    // Exact bytes for the crab: a surrogate pair, low byte first
+    env.cmd().write_stdin("\u{1F980}").assert().success();
+    assert_eq!(
+        env.clip_stdin(),
+        [0xFF, 0xFE, 0x3E, 0xD8, 0x80, 0xDD, b'\n', 0]
+    );
+    // A replay after a locked clipboard is encoded the same way
+    env.lock_clipboard("clip.exe", 1);
+    env.cmd()
+        .args(["--retry-delay", "1ms"])
+        .write_stdin("\u{e9}")
+        .assert()
+        .success();
+    assert_eq!(env.clip_stdin(), utf16("\u{e9}\n"));
+}
+#[test]
+fn encoding_picks_the_bytes_clip_exe_reads() {
+    let env = FakeWsl::wsl2();
+    let out = env
+        .cmd()
+        .args(["--encoding", "windows-1252"])
+        .write_stdin("caf\u{e9} \u{20ac}5 \u{2713}")
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("[OK] Copied Text (Windows-1252)"));
+    // Narrowed to Windows-1252, but clip.exe still reads it as Unicode
+    assert_eq!(env.clipboard().as_deref(), Some("caf\u{e9} \u{20ac}5 ?\n"));
+    assert_eq!(&env.clip_stdin()[..2], [0xFF, 0xFE]);
+    // Strict: refused, and nothing reaches the clipboard
+    let out = env
+        .cmd()
+        .args(["--encoding", "windows-1252", "--strict-encoding"])
+        .write_stdin("ok \u{2713}")
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+    assert!(stderr(&out).contains("'\u{2713}' (U+2713) has no Windows-1252 encoding"));
+    assert_eq!(env.clipboard().as_deref(), Some("caf\u{e9} \u{20ac}5 ?\n"));
+    // UTF-8 and UTF-16LE paste the same text through clip.exe
+    for encoding in ["utf8", "utf16le"] {
+        env.cmd()
+            .args(["--encoding", encoding])
+            .write_stdin("\u{e9}\u{1F980}")
+            .assert()
+            .success();
+        assert_eq!(env.clipboard().as_deref(), Some("\u{e9}\u{1F980}\n"));
+    }
+    assert_eq!(&env.clip_stdin()[..4], [0xFF, 0xFE, 0xE9, 0]);
+    // Set-Clipboard is told how to read it
+    env.cmd()
+        .args(["--backend", "powershell", "--encoding", "windows-1252"])
+        .write_stdin("x")
+        .assert()
+        .success();
+    assert!(env.powershell_args().iter().any(
+        |a| a.contains("[Console]::InputEncoding = [System.Text.Encoding]::GetEncoding(1252)")
+    ));
+    let out = env
+        .cmd()
+        .args(["--backend", "osc52", "--encoding", "windows-1252"])
+        .write_stdin("x")
+        .output()
+        .unwrap();
+    assert!(stderr(&out).contains("--encoding Windows-1252 needs clip.exe/PowerShell"));
+}
+/// Digest through the system `sha256sum`, so the manifest is checked against
+/// an independent implementation.
+fn sha256_hex(data: &[u8]) -> String {
+    use std::io::Write;
+    let mut child = std::process::Command::new("sha256sum")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(data).unwrap();
+    let out = child.wait_with_output().unwrap();
+    String::from_utf8_lossy(&out.stdout)[..64].to_string()
+}
+fn decode_base64(encoded: &str) -> String {
+    let out = std::process::Command::new("base64")
+        .arg("-d")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child.stdin.take().unwrap().write_all(encoded.as_bytes())?;
+            child.wait_with_output()
+        })
+        .unwrap();
+    String::from_utf8(out.stdout).unwrap()
+}
+
+// <FILE>tests/e2e.rs</FILE> - <DESC>End-to-end tests</DESC>
+// <VERS>END OF VERSION: 1.77.0 - 2026-10-16T14:50:15Z</VERS>